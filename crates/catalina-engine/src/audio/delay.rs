@@ -0,0 +1,123 @@
+//! A fixed-capacity delay line with fractional taps.
+//!
+//! Delay lines are the memory behind echoes, chorus and comb filters, and the
+//! strings and tubes of [physical models](super::physical). Samples are
+//! written one at a time and read back from any delay up to the capacity,
+//! either whole samples or interpolated between them.
+
+/// A delay line holding the last `N` samples written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DelayLine<const N: usize> {
+    buffer: [f32; N],
+    /// Where the next sample is written.
+    position: usize,
+}
+
+impl<const N: usize> DelayLine<N> {
+    /// Builds a silent delay line.
+    pub const fn new() -> Self {
+        core::assert!(N > 0);
+
+        Self {
+            buffer: [0.0; N],
+            position: 0,
+        }
+    }
+
+    /// Returns the longest delay that can be read.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Writes the next sample into the delay line.
+    #[inline]
+    pub fn push(&mut self, sample: f32) {
+        self.buffer[self.position] = sample;
+        self.position += 1;
+        if self.position == N {
+            self.position = 0;
+        }
+    }
+
+    #[inline]
+    fn index(&self, delay: usize) -> usize {
+        debug_assert!(delay >= 1 && delay <= N);
+
+        if delay <= self.position {
+            self.position - delay
+        } else {
+            self.position + N - delay
+        }
+    }
+
+    /// Reads the sample written `delay` samples ago, where a delay of 1 is
+    /// the last sample written.
+    ///
+    /// **Panics** if the delay is 0 or longer than the capacity.
+    #[inline]
+    pub fn tap(&self, delay: usize) -> f32 {
+        assert!(delay >= 1 && delay <= N);
+        self.buffer[self.index(delay)]
+    }
+
+    /// Borrows the sample written `delay` samples ago, such as to shape an excitation in place.
+    ///
+    /// **Panics** if the delay is 0 or longer than the capacity.
+    #[inline]
+    pub fn tap_mut(&mut self, delay: usize) -> &mut f32 {
+        assert!(delay >= 1 && delay <= N);
+        let index = self.index(delay);
+        &mut self.buffer[index]
+    }
+
+    /// Reads between samples, linearly interpolating a fractional delay.
+    ///
+    /// The delay is clamped to between 1 and the capacity.
+    #[inline]
+    pub fn tap_linear(&self, delay: f32) -> f32 {
+        let delay = delay.clamp(1.0, N as f32);
+        let whole = delay as usize;
+        let fraction = delay - whole as f32;
+
+        if whole >= N {
+            return self.tap(N);
+        }
+
+        let a = self.tap(whole);
+        let b = self.tap(whole + 1);
+        a + (b - a) * fraction
+    }
+
+    /// Silences the delay line.
+    pub fn clear(&mut self) {
+        self.buffer = [0.0; N];
+    }
+}
+
+impl<const N: usize> Default for DelayLine<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taps() {
+        let mut line = DelayLine::<4>::new();
+        for i in 1..=6 {
+            line.push(i as f32);
+        }
+
+        assert_eq!(line.tap(1), 6.0);
+        assert_eq!(line.tap(4), 3.0);
+        assert_eq!(line.tap_linear(2.25), 4.75);
+        assert_eq!(line.tap_linear(8.0), 3.0);
+
+        *line.tap_mut(2) = 0.0;
+        assert_eq!(line.tap(2), 0.0);
+    }
+}
@@ -0,0 +1,294 @@
+//! Dither and noise shaping for reducing the bit depth of audio.
+//!
+//! Converting float samples to integers with [`FromSample`] truncates, and the
+//! resulting quantization error follows the signal, which is heard as
+//! distortion on quiet material and fade outs. Adding a little noise before
+//! rounding, *dither*, decorrelates the error from the signal so it becomes a
+//! constant, benign noise floor instead.
+//!
+//! [`Dither`] adds rectangular or triangular dither, and can optionally shape
+//! the quantization noise with error feedback, pushing it towards high
+//! frequencies where the ear is less sensitive. Use [`dither_slice`] to convert
+//! blocks of frames, or [`SignalDither`](crate::audio::signal::dither::SignalDither)
+//! to convert a **Signal**.
+
+use crate::audio::frame::Frame;
+use crate::audio::sample::{FromSample, Sample, ToSample};
+use crate::core::rng::Rng;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The finest resolution, in bits, that dither is applied at.
+///
+/// Float and 32-bit integer outputs are dithered at 24 bits, the precision of
+/// an `f32` mantissa, below which dithering has no effect.
+pub const MAX_BITS: u32 = 24;
+
+/// The probability distribution of the noise added before quantizing.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum DitherKind {
+    /// No dither, samples are rounded to the nearest step.
+    None,
+    /// Rectangular (RPDF) dither of +/-0.5 LSB.
+    ///
+    /// Removes distortion, but the noise level still varies with the signal.
+    Rectangular,
+    /// Triangular (TPDF) dither of +/-1 LSB.
+    ///
+    /// Makes the noise independent of the signal, the usual choice for mastering.
+    #[default]
+    Triangular,
+}
+
+/// How the quantization error is spectrally shaped.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum NoiseShaping {
+    /// The error is left white.
+    #[default]
+    None,
+    /// First-order error feedback, `1 - z^-1`, tilting the noise up 6dB per octave.
+    FirstOrder,
+    /// Second-order error feedback, `(1 - z^-1)^2`, tilting the noise up 12dB per octave.
+    SecondOrder,
+}
+
+/// Returns the resolution of a sample type, in bits, capped at [`MAX_BITS`].
+///
+/// This is the number of bits including the sign, so 16 for `i16` and `u16`.
+pub fn sample_bits<S>() -> u32
+where
+    S: Sample + FromSample<f32> + ToSample<f32>,
+{
+    // Count the powers of two that survive a round trip, the
+    // smallest of them is one step.
+    let mut bits = 1;
+    while bits < MAX_BITS {
+        let step = libm::ldexpf(1.0, -(bits as i32));
+        if S::from_sample(step).to_sample::<f32>() != step {
+            break;
+        }
+        bits += 1;
+    }
+    bits
+}
+
+/// Dithers and optionally noise shapes `CHANNELS` channels of audio down to
+/// a lower resolution.
+///
+/// The generator is seeded, so the same input and seed always give the same output.
+#[derive(Debug, Clone)]
+pub struct Dither<const CHANNELS: usize = 1> {
+    kind: DitherKind,
+    shaping: NoiseShaping,
+    rng: Rng,
+    /// The last two quantization errors of each channel, newest first.
+    errors: [[f32; 2]; CHANNELS],
+}
+
+impl<const CHANNELS: usize> Dither<CHANNELS> {
+    /// Builds a ditherer, seeding the noise generator with `seed`.
+    pub fn new(kind: DitherKind, shaping: NoiseShaping, seed: u64) -> Self {
+        Self {
+            kind,
+            shaping,
+            rng: Rng::new(seed),
+            errors: [[0.0; 2]; CHANNELS],
+        }
+    }
+
+    #[inline]
+    pub const fn kind(&self) -> DitherKind {
+        self.kind
+    }
+
+    pub fn set_kind(&mut self, kind: DitherKind) {
+        self.kind = kind;
+    }
+
+    #[inline]
+    pub const fn shaping(&self) -> NoiseShaping {
+        self.shaping
+    }
+
+    pub fn set_shaping(&mut self, shaping: NoiseShaping) {
+        self.shaping = shaping;
+    }
+
+    /// Clears the noise shaping error history.
+    pub fn reset(&mut self) {
+        self.errors = [[0.0; 2]; CHANNELS];
+    }
+
+    /// Quantizes a sample of a channel to `bits` of resolution.
+    ///
+    /// The result is exactly on a step of the target resolution, and clamped
+    /// into `-1.0..1.0`, so converting it with [`FromSample`] is lossless.
+    #[inline]
+    pub fn quantize(&mut self, channel: usize, sample: f32, bits: u32) -> f32 {
+        let step = libm::ldexpf(1.0, 1 - bits as i32);
+        let [e1, e2] = self.errors[channel];
+
+        let shaped = match self.shaping {
+            NoiseShaping::None => sample,
+            NoiseShaping::FirstOrder => sample - e1,
+            NoiseShaping::SecondOrder => sample - 2.0 * e1 + e2,
+        };
+
+        let noise = match self.kind {
+            DitherKind::None => 0.0,
+            DitherKind::Rectangular => self.rng.next_f32() - 0.5,
+            DitherKind::Triangular => self.rng.next_f32() - self.rng.next_f32(),
+        };
+
+        let quantized = (libm::floorf(shaped / step + noise + 0.5) * step).clamp(-1.0, 1.0 - step);
+
+        // Clipping makes the error huge, limit it so the feedback
+        // loop doesn't ring after an overload.
+        let error = (quantized - shaped).clamp(-2.0 * step, 2.0 * step);
+        self.errors[channel] = [error, e1];
+
+        quantized
+    }
+
+    /// Dithers a frame to a frame of a lower resolution sample type.
+    #[inline]
+    pub fn frame<FI, FO>(&mut self, frame: FI, bits: u32) -> FO
+    where
+        FI: Frame,
+        FI::Sample: ToSample<f32>,
+        FO: Frame<NumChannels = FI::NumChannels>,
+        FO::Sample: FromSample<f32>,
+    {
+        let mut channel = 0;
+        frame.map(|sample| {
+            let quantized = self.quantize(channel, sample.to_sample(), bits);
+            channel += 1;
+            FO::Sample::from_sample(quantized)
+        })
+    }
+}
+
+/// Dithers a slice of frames into a slice of frames of a lower resolution sample type.
+///
+/// **Panics** if the slice lengths differ, or the frames have more than `CHANNELS` channels.
+pub fn dither_slice<FI, FO, const CHANNELS: usize>(
+    dither: &mut Dither<CHANNELS>,
+    input: &[FI],
+    output: &mut [FO],
+) where
+    FI: Frame,
+    FI::Sample: ToSample<f32>,
+    FO: Frame<NumChannels = FI::NumChannels>,
+    FO::Sample: FromSample<f32> + ToSample<f32>,
+{
+    assert_eq!(input.len(), output.len());
+    assert!(FI::CHANNELS <= CHANNELS);
+
+    let bits = sample_bits::<FO::Sample>();
+    for (input, output) in input.iter().zip(output.iter_mut()) {
+        *output = dither.frame(*input, bits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::sample::{I24, Q15};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_sample_bits() {
+        assert_eq!(sample_bits::<i8>(), 8);
+        assert_eq!(sample_bits::<i16>(), 16);
+        assert_eq!(sample_bits::<u16>(), 16);
+        assert_eq!(sample_bits::<Q15>(), 16);
+        assert_eq!(sample_bits::<I24>(), 24);
+        assert_eq!(sample_bits::<i32>(), MAX_BITS);
+        assert_eq!(sample_bits::<f32>(), MAX_BITS);
+    }
+
+    #[test]
+    fn test_rounds_without_dither() {
+        let mut dither = Dither::<1>::new(DitherKind::None, NoiseShaping::None, 0);
+        let input = [0.0, 0.9 / 32_768.0, -0.6 / 32_768.0, 1.0, -1.0];
+        let mut output = [0i16; 5];
+        dither_slice(&mut dither, &input, &mut output);
+
+        assert_eq!(output, [0, 1, -1, i16::MAX, i16::MIN]);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let input: [[f32; 2]; 64] = core::array::from_fn(|i| [i as f32 / 64.0, -0.5]);
+
+        let mut a = Dither::<2>::new(DitherKind::Triangular, NoiseShaping::SecondOrder, 1);
+        let mut b = Dither::<2>::new(DitherKind::Triangular, NoiseShaping::SecondOrder, 1);
+        let mut out_a = [[0i16; 2]; 64];
+        let mut out_b = [[0i16; 2]; 64];
+        dither_slice(&mut a, &input, &mut out_a);
+        dither_slice(&mut b, &input, &mut out_b);
+
+        assert_eq!(out_a, out_b);
+    }
+
+    /// Quantizes a quiet sine to 8 bits, returning the total error power and
+    /// the error correlation with the signal.
+    fn measure(kind: DitherKind, shaping: NoiseShaping) -> (f32, f32, f32) {
+        let mut dither = Dither::<1>::new(kind, shaping, 3);
+        let mut input = [0.0f32; 4_096];
+        for (i, sample) in input.iter_mut().enumerate() {
+            *sample = 0.01 * libm::sinf(2.0 * PI * i as f32 / 64.0);
+        }
+        let mut output = [0i8; 4_096];
+        dither_slice(&mut dither, &input, &mut output);
+
+        let mut power = 0.0;
+        let mut correlation = 0.0;
+        let mut difference = 0.0;
+        let mut last = 0.0;
+        for (x, y) in input.iter().zip(output.iter()) {
+            let error = y.to_sample::<f32>() - x;
+            power += error * error;
+            correlation += error * x;
+            difference += (error - last) * (error - last);
+            last = error;
+        }
+
+        (power, correlation, difference)
+    }
+
+    #[test]
+    fn test_dither_decorrelates() {
+        // A sine under two steps of 8 bits is badly distorted by plain
+        // rounding, dither trades that for noise uncorrelated with the signal.
+        let (_, rounded, _) = measure(DitherKind::None, NoiseShaping::None);
+        let (_, triangular, _) = measure(DitherKind::Triangular, NoiseShaping::None);
+        let (_, rectangular, _) = measure(DitherKind::Rectangular, NoiseShaping::None);
+
+        assert!(triangular.abs() < rounded.abs() / 4.0);
+        assert!(rectangular.abs() < rounded.abs() / 4.0);
+    }
+
+    #[test]
+    fn test_noise_shaping_tilts_spectrum() {
+        // Shaped noise has more energy between adjacent samples relative to
+        // its total power, meaning it sits at higher frequencies.
+        let (white_power, _, white_difference) =
+            measure(DitherKind::Triangular, NoiseShaping::None);
+        let (first_power, _, first_difference) =
+            measure(DitherKind::Triangular, NoiseShaping::FirstOrder);
+        let (second_power, _, second_difference) =
+            measure(DitherKind::Triangular, NoiseShaping::SecondOrder);
+
+        let white = white_difference / white_power;
+        let first = first_difference / first_power;
+        let second = second_difference / second_power;
+        assert!(first > white * 1.3, "{white} {first}");
+        assert!(second > first, "{first} {second}");
+    }
+}
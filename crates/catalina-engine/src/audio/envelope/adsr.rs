@@ -153,6 +153,17 @@ impl Envelope {
         }
     }
 
+    /// Restarts the attack stage from the current level, such as when a
+    /// new note is played while the gate is still held.
+    pub fn retrigger(&mut self) {
+        self.stage = EnvelopeStage::Attack;
+    }
+
+    /// Returns true while the envelope is producing a level above silence.
+    pub fn is_active(&self) -> bool {
+        self.stage != EnvelopeStage::Init
+    }
+
     /// Processes a single sample from the envelope.
     ///
     /// The returned float is a percentage of the current level of the envelope.
@@ -171,6 +182,9 @@ impl Envelope {
             // should trigger the release stage.
             self.stage = EnvelopeStage::Release;
         }
+        // Remember the gate so the edges are only seen once, otherwise a held
+        // gate re-attacks on every sample and never reaches the sustain level.
+        self.gate = gate;
 
         // Determine which coefficiant to use depending
         // on the current stage of the envelope.
@@ -218,3 +232,46 @@ impl Envelope {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gate_edges() {
+        let mut envelope = Envelope::new(1000);
+        envelope.set_sustain_level(0.5);
+
+        // Holding the gate settles on the sustain level rather than re-attacking.
+        let mut level = 0.0;
+        for _ in 0..2000 {
+            level = envelope.process(true);
+        }
+        assert!((level - 0.5).abs() < 0.01);
+
+        // Dropping the gate releases down to silence.
+        for _ in 0..2000 {
+            level = envelope.process(false);
+        }
+        assert_eq!(level, 0.0);
+        assert!(!envelope.is_active());
+    }
+
+    #[test]
+    fn test_retrigger() {
+        let mut envelope = Envelope::new(1000);
+        envelope.set_sustain_level(0.5);
+        assert!(!envelope.is_active());
+
+        let mut level = 0.0;
+        for _ in 0..2000 {
+            level = envelope.process(true);
+        }
+        assert!(envelope.is_active());
+
+        // Retriggering with the gate held attacks again from the sustain level.
+        envelope.retrigger();
+        let attacked = envelope.process(true);
+        assert!(attacked > level);
+    }
+}
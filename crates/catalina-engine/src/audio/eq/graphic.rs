@@ -0,0 +1,251 @@
+//! A ten band graphic equalizer with fixed octave-spaced bands.
+
+use crate::{
+    audio::{
+        eq::EqError,
+        filter::{Biquad, Coefficients, FrequencyResponse},
+        processor::AudioProcessor,
+    },
+    core::Hertz,
+};
+
+/// The amount of bands in a [`GraphicEq`].
+pub const GRAPHIC_EQ_BANDS: usize = 10;
+
+/// The ISO standard octave band center frequencies.
+pub const GRAPHIC_EQ_FREQUENCIES: [Hertz; GRAPHIC_EQ_BANDS] = [
+    Hertz(31.5),
+    Hertz(63.0),
+    Hertz(125.0),
+    Hertz(250.0),
+    Hertz(500.0),
+    Hertz(1_000.0),
+    Hertz(2_000.0),
+    Hertz(4_000.0),
+    Hertz(8_000.0),
+    Hertz(16_000.0),
+];
+
+/// The Q of a band one octave wide.
+const OCTAVE_Q: f32 = 1.414;
+
+/// The maximum boost or cut of a band in decibels.
+pub const GRAPHIC_EQ_RANGE_DB: f32 = 12.0;
+
+/// Designs the filter for a band, which is left flat with no gain.
+fn band(sample_rate: f32, frequency: Hertz, gain_db: f32) -> Coefficients {
+    if gain_db == 0.0 {
+        Coefficients::IDENTITY
+    } else {
+        Coefficients::peaking(sample_rate, frequency, OCTAVE_Q, gain_db)
+    }
+}
+
+/// A ten band graphic EQ, like the sliders on a mixing desk or hi-fi.
+///
+/// Each band is a peaking filter one octave wide centered on the ISO octave
+/// frequencies, with a gain of ±12dB.
+#[derive(Debug, Clone)]
+pub struct GraphicEq {
+    sample_rate: f32,
+
+    gains: [f32; GRAPHIC_EQ_BANDS],
+    filters: [Biquad; GRAPHIC_EQ_BANDS],
+}
+
+impl GraphicEq {
+    /// Construct a flat graphic EQ running at the provided sample rate.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            gains: [0.0; GRAPHIC_EQ_BANDS],
+            filters: core::array::from_fn(|_| Biquad::new(sample_rate, Coefficients::IDENTITY)),
+        }
+    }
+
+    /// Returns the center frequency of a band.
+    pub fn frequency(&self, index: usize) -> Result<Hertz, EqError> {
+        GRAPHIC_EQ_FREQUENCIES
+            .get(index)
+            .copied()
+            .ok_or(EqError::InvalidBand {
+                index,
+                bands: GRAPHIC_EQ_BANDS,
+            })
+    }
+
+    /// Returns the gain of a band in decibels.
+    pub fn gain(&self, index: usize) -> Result<f32, EqError> {
+        self.gains.get(index).copied().ok_or(EqError::InvalidBand {
+            index,
+            bands: GRAPHIC_EQ_BANDS,
+        })
+    }
+
+    /// Sets the gain of a band in decibels, clamped to ±12dB.
+    pub fn set_gain(&mut self, index: usize, gain_db: f32) -> Result<(), EqError> {
+        let frequency = self.frequency(index)?;
+        let gain_db = gain_db.clamp(-GRAPHIC_EQ_RANGE_DB, GRAPHIC_EQ_RANGE_DB);
+
+        self.gains[index] = gain_db;
+        self.filters[index].set_coefficients(band(self.sample_rate, frequency, gain_db));
+
+        Ok(())
+    }
+
+    /// Sets the gain of every band at once, each clamped to ±12dB.
+    pub fn set_gains(&mut self, gains: [f32; GRAPHIC_EQ_BANDS]) {
+        for (((filter, frequency), gain), gain_db) in self
+            .filters
+            .iter_mut()
+            .zip(GRAPHIC_EQ_FREQUENCIES)
+            .zip(self.gains.iter_mut())
+            .zip(gains)
+        {
+            *gain = gain_db.clamp(-GRAPHIC_EQ_RANGE_DB, GRAPHIC_EQ_RANGE_DB);
+            filter.set_coefficients(band(self.sample_rate, frequency, *gain));
+        }
+    }
+
+    /// Sets how long band changes take to settle, in seconds.
+    pub fn set_smoothing_time(&mut self, seconds: f32) {
+        for filter in self.filters.iter_mut() {
+            filter.set_smoothing_time(seconds);
+        }
+    }
+
+    /// Clears the filter state of all the bands.
+    pub fn reset(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.reset();
+        }
+    }
+
+    /// Changes the sample rate, redesigning every band for the new rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+
+        for ((filter, frequency), gain_db) in self
+            .filters
+            .iter_mut()
+            .zip(GRAPHIC_EQ_FREQUENCIES)
+            .zip(self.gains)
+        {
+            filter.set_sample_rate(sample_rate);
+            filter.set_coefficients_immediate(band(sample_rate, frequency, gain_db));
+        }
+    }
+
+    /// Equalizes a single sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let mut output = input;
+        for filter in self.filters.iter_mut() {
+            output = filter.process(output);
+        }
+
+        output
+    }
+
+    /// Equalizes a buffer of samples in place.
+    pub fn process_in_place(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+impl AudioProcessor for GraphicEq {
+    type Frame = f32;
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.set_sample_rate(sample_rate);
+    }
+
+    fn reset(&mut self) {
+        GraphicEq::reset(self);
+    }
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [f32]) {
+        GraphicEq::process_in_place(self, buffer);
+    }
+}
+
+impl FrequencyResponse for GraphicEq {
+    fn response_db(&self, frequency: Hertz) -> f32 {
+        self.filters
+            .iter()
+            .map(|filter| filter.response_db(frequency))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    #[test]
+    fn test_flat_by_default() {
+        let eq = GraphicEq::new(SAMPLE_RATE);
+
+        for frequency in [20.0, 100.0, 1_000.0, 10_000.0, 20_000.0] {
+            assert!(eq.response_db(Hertz(frequency)).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_band_response() {
+        let mut eq = GraphicEq::new(SAMPLE_RATE);
+        eq.set_gain(5, 6.0).unwrap();
+
+        // The band peaks at its center, and is back near flat two octaves away.
+        assert!((eq.response_db(Hertz(1_000.0)) - 6.0).abs() < 0.05);
+        assert!(eq.response_db(Hertz(250.0)).abs() < 1.0);
+        assert!(eq.response_db(Hertz(4_000.0)).abs() < 1.0);
+
+        // Gains are limited to the slider range.
+        eq.set_gain(0, -20.0).unwrap();
+        assert_eq!(eq.gain(0), Ok(-GRAPHIC_EQ_RANGE_DB));
+        assert!((eq.response_db(Hertz(31.5)) + GRAPHIC_EQ_RANGE_DB).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_set_gains() {
+        let mut eq = GraphicEq::new(SAMPLE_RATE);
+        let mut gains = [0.0; GRAPHIC_EQ_BANDS];
+        gains[2] = -3.0;
+        gains[8] = 15.0;
+        eq.set_gains(gains);
+
+        assert_eq!(eq.gain(2), Ok(-3.0));
+        assert_eq!(eq.gain(8), Ok(GRAPHIC_EQ_RANGE_DB));
+        assert!((eq.response_db(Hertz(125.0)) + 3.0).abs() < 0.1);
+        assert!((eq.response_db(Hertz(8_000.0)) - GRAPHIC_EQ_RANGE_DB).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_invalid_band() {
+        let mut eq = GraphicEq::new(SAMPLE_RATE);
+
+        assert_eq!(
+            eq.set_gain(GRAPHIC_EQ_BANDS, 1.0),
+            Err(EqError::InvalidBand {
+                index: GRAPHIC_EQ_BANDS,
+                bands: GRAPHIC_EQ_BANDS,
+            })
+        );
+        assert!(eq.gain(GRAPHIC_EQ_BANDS).is_err());
+    }
+
+    #[test]
+    fn test_prepare_redesigns() {
+        let mut eq = GraphicEq::new(SAMPLE_RATE);
+        eq.set_gain(7, 6.0).unwrap();
+        eq.prepare(96_000.0, 256);
+
+        assert!((eq.response_db(Hertz(4_000.0)) - 6.0).abs() < 0.05);
+    }
+}
@@ -0,0 +1,54 @@
+//! Equalizers built on the [`Biquad`](crate::audio::filter::Biquad) filter core.
+//!
+//! - [`ParametricEq`] has a low shelf, a high shelf, and a fixed number of
+//!   peaking bands with adjustable frequency, gain and Q.
+//! - [`GraphicEq`] has ten fixed octave bands with adjustable gain.
+//!
+//! Both implement [`FrequencyResponse`](crate::audio::filter::FrequencyResponse)
+//! so the resulting EQ curve can be drawn on a device display or plotted by tools.
+
+use crate::core::Hertz;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod graphic;
+pub use graphic::GraphicEq;
+
+pub mod parametric;
+pub use parametric::ParametricEq;
+
+/// An error returned when configuring an equalizer.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum EqError {
+    /// The band index is larger than the amount of bands in the EQ.
+    InvalidBand { index: usize, bands: usize },
+}
+
+/// The settings for a single band of an equalizer.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EqBand {
+    /// The center or corner frequency of the band.
+    pub frequency: Hertz,
+    /// The boost (positive) or cut (negative) of the band in decibels.
+    pub gain_db: f32,
+    /// The bandwidth of the band, higher values are narrower.
+    pub q: f32,
+    /// Disabled bands are bypassed and have a flat response.
+    pub enabled: bool,
+}
+
+impl EqBand {
+    /// Construct an enabled band.
+    pub const fn new(frequency: Hertz, gain_db: f32, q: f32) -> Self {
+        Self {
+            frequency,
+            gain_db,
+            q,
+            enabled: true,
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! A parametric equalizer with shelves and a fixed number of peaking bands.
+
+use crate::{
+    audio::{
+        eq::{EqBand, EqError},
+        filter::{Biquad, Coefficients, FilterKind, FrequencyResponse},
+        processor::AudioProcessor,
+    },
+    core::Hertz,
+};
+
+/// The Q used for shelves, giving the steepest slope without overshoot.
+const SHELF_Q: f32 = 0.707;
+
+/// A single band of the EQ with its filter.
+#[derive(Debug, Clone)]
+struct Stage {
+    kind: FilterKind,
+    band: EqBand,
+    filter: Biquad,
+}
+
+impl Stage {
+    fn new(kind: FilterKind, sample_rate: f32, band: EqBand) -> Self {
+        let mut stage = Self {
+            kind,
+            band,
+            filter: Biquad::new(sample_rate, Coefficients::IDENTITY),
+        };
+        stage
+            .filter
+            .set_coefficients_immediate(stage.coefficients());
+
+        stage
+    }
+
+    /// Designs the filter coefficients for the band settings.
+    fn coefficients(&self) -> Coefficients {
+        if !self.band.enabled || self.band.gain_db == 0.0 {
+            return Coefficients::IDENTITY;
+        }
+
+        Coefficients::new(
+            self.kind,
+            self.filter.sample_rate(),
+            self.band.frequency,
+            self.band.q,
+            self.band.gain_db,
+        )
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.filter.set_sample_rate(sample_rate);
+        self.filter.set_coefficients_immediate(self.coefficients());
+    }
+
+    fn set_band(&mut self, band: EqBand) {
+        // A skipped stage's state is stale, so clear it before it's heard again.
+        if self.is_bypassed() {
+            self.filter.reset();
+        }
+
+        self.band = band;
+        self.filter.set_coefficients(self.coefficients());
+    }
+
+    /// Returns true once the stage has smoothed out to flat, so it can be skipped.
+    #[inline]
+    fn is_bypassed(&self) -> bool {
+        !self.filter.is_smoothing() && self.filter.coefficients() == Coefficients::IDENTITY
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32) -> f32 {
+        if self.is_bypassed() {
+            return input;
+        }
+
+        self.filter.process(input)
+    }
+
+    fn process_in_place(&mut self, buffer: &mut [f32]) {
+        if !self.is_bypassed() {
+            self.filter.process_in_place(buffer);
+        }
+    }
+
+    fn response_db(&self, frequency: Hertz) -> f32 {
+        if !self.band.enabled {
+            return 0.0;
+        }
+
+        self.filter.response_db(frequency)
+    }
+}
+
+/// A parametric EQ with a low shelf, a high shelf and `BANDS` peaking bands.
+///
+/// The stages are run in series; a band with 0dB gain or that is disabled is
+/// skipped once it has smoothed out to flat, so unused bands cost nothing.
+/// Band changes are smoothed by the
+/// underlying [`Biquad`] filters, so bands can be swept while audio plays.
+#[derive(Debug, Clone)]
+pub struct ParametricEq<const BANDS: usize> {
+    low_shelf: Stage,
+    high_shelf: Stage,
+    bands: [Stage; BANDS],
+}
+
+impl<const BANDS: usize> ParametricEq<BANDS> {
+    /// Construct a flat EQ running at the provided sample rate.
+    ///
+    /// The peaking bands start spread logarithmically between 100Hz and 10kHz.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            low_shelf: Stage::new(
+                FilterKind::LowShelf,
+                sample_rate,
+                EqBand::new(Hertz(100.0), 0.0, SHELF_Q),
+            ),
+            high_shelf: Stage::new(
+                FilterKind::HighShelf,
+                sample_rate,
+                EqBand::new(Hertz(10_000.0), 0.0, SHELF_Q),
+            ),
+            bands: core::array::from_fn(|index| {
+                let position = (index as f32 + 1.0) / (BANDS as f32 + 1.0);
+                let frequency = 100.0 * libm::powf(100.0, position);
+
+                Stage::new(
+                    FilterKind::Peaking,
+                    sample_rate,
+                    EqBand::new(Hertz(frequency), 0.0, 1.0),
+                )
+            }),
+        }
+    }
+
+    /// Returns the amount of peaking bands, not including the shelves.
+    #[inline]
+    pub const fn bands(&self) -> usize {
+        BANDS
+    }
+
+    /// Returns the settings of the low shelf.
+    #[inline]
+    pub const fn low_shelf(&self) -> EqBand {
+        self.low_shelf.band
+    }
+
+    /// Configures the low shelf.
+    pub fn set_low_shelf(&mut self, band: EqBand) {
+        self.low_shelf.set_band(band);
+    }
+
+    /// Returns the settings of the high shelf.
+    #[inline]
+    pub const fn high_shelf(&self) -> EqBand {
+        self.high_shelf.band
+    }
+
+    /// Configures the high shelf.
+    pub fn set_high_shelf(&mut self, band: EqBand) {
+        self.high_shelf.set_band(band);
+    }
+
+    /// Returns the settings of a peaking band.
+    pub fn band(&self, index: usize) -> Result<EqBand, EqError> {
+        self.bands
+            .get(index)
+            .map(|stage| stage.band)
+            .ok_or(EqError::InvalidBand {
+                index,
+                bands: BANDS,
+            })
+    }
+
+    /// Configures a peaking band.
+    pub fn set_band(&mut self, index: usize, band: EqBand) -> Result<(), EqError> {
+        let stage = self.bands.get_mut(index).ok_or(EqError::InvalidBand {
+            index,
+            bands: BANDS,
+        })?;
+
+        stage.set_band(band);
+
+        Ok(())
+    }
+
+    /// Sets how long band changes take to settle, in seconds.
+    pub fn set_smoothing_time(&mut self, seconds: f32) {
+        self.low_shelf.filter.set_smoothing_time(seconds);
+        self.high_shelf.filter.set_smoothing_time(seconds);
+        for stage in self.bands.iter_mut() {
+            stage.filter.set_smoothing_time(seconds);
+        }
+    }
+
+    /// Clears the filter state of all the bands.
+    pub fn reset(&mut self) {
+        self.low_shelf.filter.reset();
+        self.high_shelf.filter.reset();
+        for stage in self.bands.iter_mut() {
+            stage.filter.reset();
+        }
+    }
+
+    /// Changes the sample rate, redesigning every band for the new rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.low_shelf.set_sample_rate(sample_rate);
+        self.high_shelf.set_sample_rate(sample_rate);
+        for stage in self.bands.iter_mut() {
+            stage.set_sample_rate(sample_rate);
+        }
+    }
+
+    /// Equalizes a single sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let mut output = self.low_shelf.process(input);
+        for stage in self.bands.iter_mut() {
+            output = stage.process(output);
+        }
+
+        self.high_shelf.process(output)
+    }
+
+    /// Equalizes a buffer of samples in place.
+    ///
+    /// The stages are in series, so each runs over the whole buffer in turn.
+    pub fn process_in_place(&mut self, buffer: &mut [f32]) {
+        self.low_shelf.process_in_place(buffer);
+        for stage in self.bands.iter_mut() {
+            stage.process_in_place(buffer);
+        }
+        self.high_shelf.process_in_place(buffer);
+    }
+}
+
+impl<const BANDS: usize> AudioProcessor for ParametricEq<BANDS> {
+    type Frame = f32;
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.set_sample_rate(sample_rate);
+    }
+
+    fn reset(&mut self) {
+        ParametricEq::reset(self);
+    }
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [f32]) {
+        ParametricEq::process_in_place(self, buffer);
+    }
+}
+
+impl<const BANDS: usize> FrequencyResponse for ParametricEq<BANDS> {
+    /// Returns the combined response of every band at the frequency.
+    ///
+    /// This reflects the configured band settings, not the smoothed
+    /// coefficients the filters are currently moving through.
+    fn response_db(&self, frequency: Hertz) -> f32 {
+        let mut db = self.low_shelf.response_db(frequency) + self.high_shelf.response_db(frequency);
+        for stage in self.bands.iter() {
+            db += stage.response_db(frequency);
+        }
+
+        db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    #[test]
+    fn test_flat_by_default() {
+        let eq = ParametricEq::<4>::new(SAMPLE_RATE);
+
+        for frequency in [20.0, 100.0, 1_000.0, 10_000.0, 20_000.0] {
+            assert!(eq.response_db(Hertz(frequency)).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn test_band_response() {
+        let mut eq = ParametricEq::<4>::new(SAMPLE_RATE);
+        eq.set_band(1, EqBand::new(Hertz(2_000.0), -6.0, 2.0))
+            .unwrap();
+        eq.set_low_shelf(EqBand::new(Hertz(80.0), 3.0, SHELF_Q));
+
+        assert!((eq.response_db(Hertz(2_000.0)) + 6.0).abs() < 0.05);
+        assert!((eq.response_db(Hertz(10.0)) - 3.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_invalid_band() {
+        let mut eq = ParametricEq::<2>::new(SAMPLE_RATE);
+
+        assert_eq!(
+            eq.set_band(2, EqBand::new(Hertz(1_000.0), 1.0, 1.0)),
+            Err(EqError::InvalidBand { index: 2, bands: 2 })
+        );
+    }
+
+    #[test]
+    fn test_prepare_redesigns() {
+        let mut eq = ParametricEq::<4>::new(SAMPLE_RATE);
+        eq.set_band(1, EqBand::new(Hertz(2_000.0), -6.0, 2.0))
+            .unwrap();
+        eq.prepare(96_000.0, 256);
+
+        assert!((eq.response_db(Hertz(2_000.0)) + 6.0).abs() < 0.05);
+        assert_eq!(eq.band(1).unwrap().frequency.hertz(), 2_000.0);
+    }
+
+    #[test]
+    fn test_bypass() {
+        let input: [f32; 64] = core::array::from_fn(|i| libm::sinf(i as f32 * 0.3));
+
+        // A flat EQ skips every stage, leaving the signal untouched.
+        let mut eq = ParametricEq::<4>::new(SAMPLE_RATE);
+        let mut buffer = input;
+        eq.process_in_place(&mut buffer);
+        assert_eq!(buffer, input);
+
+        // A band that's switched on is processed, and sample by sample
+        // processing matches the block.
+        let band = EqBand::new(Hertz(2_000.0), 6.0, 1.0);
+        let mut block = ParametricEq::<4>::new(SAMPLE_RATE);
+        let mut single = ParametricEq::<4>::new(SAMPLE_RATE);
+        block.set_band(2, band).unwrap();
+        single.set_band(2, band).unwrap();
+
+        let mut buffer = input;
+        block.process_in_place(&mut buffer);
+        assert_ne!(buffer, input);
+        for (sample, expected) in input.iter().zip(buffer) {
+            assert_eq!(single.process(*sample), expected);
+        }
+    }
+}
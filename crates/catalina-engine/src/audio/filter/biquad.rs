@@ -0,0 +1,543 @@
+//! A second-order IIR filter, commonly called a "biquad".
+//!
+//! Biquads are the building block for most tone-shaping in a synth or effect
+//! chain: low/high pass filters, shelves and the peaking bands of an EQ are
+//! all a single biquad with different coefficients.
+//!
+//! The coefficient formulas are from Robert Bristow-Johnson's
+//! [Audio EQ Cookbook](https://www.w3.org/TR/audio-eq-cookbook/), and the
+//! filter itself runs in transposed direct form II which behaves well with
+//! the limited precision of f32.
+
+use crate::{
+    audio::{filter::FrequencyResponse, processor::AudioProcessor},
+    core::Hertz,
+    prelude::*,
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The response shapes that [`Coefficients::new`] can design.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum FilterKind {
+    /// Passes frequencies below the cutoff.
+    LowPass,
+    /// Passes frequencies above the cutoff.
+    HighPass,
+    /// Passes a band around the center frequency with 0dB peak gain.
+    BandPass,
+    /// Rejects a band around the center frequency.
+    Notch,
+    /// Passes all frequencies, shifting the phase around the center frequency.
+    AllPass,
+    /// Boosts or cuts a band around the center frequency by the gain.
+    Peaking,
+    /// Boosts or cuts frequencies below the corner frequency by the gain.
+    LowShelf,
+    /// Boosts or cuts frequencies above the corner frequency by the gain.
+    HighShelf,
+}
+
+/// Normalized biquad coefficients, with `a0` divided out.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Coefficients {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl Coefficients {
+    /// Coefficients that pass the signal through unchanged.
+    pub const IDENTITY: Coefficients = Coefficients {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: 0.0,
+    };
+
+    /// Designs the coefficients for a filter of the given kind.
+    ///
+    /// `q` controls the bandwidth (or resonance for the pass filters), and
+    /// `gain_db` is only used by the peaking and shelving filters.
+    ///
+    /// The frequency is clamped just below Nyquist so that modulating the
+    /// cutoff past the sample rate can't produce an unstable filter.
+    pub fn new(kind: FilterKind, sample_rate: f32, frequency: Hertz, q: f32, gain_db: f32) -> Self {
+        let frequency = frequency.hertz().clamp(1.0, sample_rate * 0.499);
+        let q = libm::fmaxf(q, 0.001);
+
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        let cos_w0 = libm::cosf(w0);
+        let alpha = libm::sinf(w0) / (2.0 * q);
+
+        // Amplitude for the peaking and shelving filters.
+        let a = libm::powf(10.0, gain_db / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) = match kind {
+            FilterKind::LowPass => (
+                (1.0 - cos_w0) / 2.0,
+                1.0 - cos_w0,
+                (1.0 - cos_w0) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            FilterKind::HighPass => (
+                (1.0 + cos_w0) / 2.0,
+                -(1.0 + cos_w0),
+                (1.0 + cos_w0) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            FilterKind::BandPass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha),
+            FilterKind::Notch => (
+                1.0,
+                -2.0 * cos_w0,
+                1.0,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            FilterKind::AllPass => (
+                1.0 - alpha,
+                -2.0 * cos_w0,
+                1.0 + alpha,
+                1.0 + alpha,
+                -2.0 * cos_w0,
+                1.0 - alpha,
+            ),
+            FilterKind::Peaking => (
+                1.0 + alpha * a,
+                -2.0 * cos_w0,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos_w0,
+                1.0 - alpha / a,
+            ),
+            FilterKind::LowShelf => {
+                let sqrt_a_alpha = 2.0 * libm::sqrtf(a) * alpha;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+                    a * ((a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha),
+                    (a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+                    (a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha,
+                )
+            }
+            FilterKind::HighShelf => {
+                let sqrt_a_alpha = 2.0 * libm::sqrtf(a) * alpha;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+                    a * ((a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha),
+                    (a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+                    (a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha,
+                )
+            }
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+
+    /// Designs a low pass filter.
+    pub fn low_pass(sample_rate: f32, cutoff: Hertz, q: f32) -> Self {
+        Self::new(FilterKind::LowPass, sample_rate, cutoff, q, 0.0)
+    }
+
+    /// Designs a high pass filter.
+    pub fn high_pass(sample_rate: f32, cutoff: Hertz, q: f32) -> Self {
+        Self::new(FilterKind::HighPass, sample_rate, cutoff, q, 0.0)
+    }
+
+    /// Designs a peaking filter that boosts or cuts around the center frequency.
+    pub fn peaking(sample_rate: f32, center: Hertz, q: f32, gain_db: f32) -> Self {
+        Self::new(FilterKind::Peaking, sample_rate, center, q, gain_db)
+    }
+
+    /// Designs a shelf that boosts or cuts below the corner frequency.
+    pub fn low_shelf(sample_rate: f32, corner: Hertz, q: f32, gain_db: f32) -> Self {
+        Self::new(FilterKind::LowShelf, sample_rate, corner, q, gain_db)
+    }
+
+    /// Designs a shelf that boosts or cuts above the corner frequency.
+    pub fn high_shelf(sample_rate: f32, corner: Hertz, q: f32, gain_db: f32) -> Self {
+        Self::new(FilterKind::HighShelf, sample_rate, corner, q, gain_db)
+    }
+
+    /// Returns the linear magnitude of the filter at the given frequency.
+    pub fn magnitude(&self, sample_rate: f32, frequency: Hertz) -> f32 {
+        // This uses the sin^2(w/2) form of |H(e^jw)|^2 from the cookbook,
+        // evaluated in f64. The more obvious cos(w) expansion suffers from
+        // cancellation at low frequencies where the poles sit close to 1.
+        let w = core::f64::consts::PI * 2.0 * frequency.hertz() as f64 / sample_rate as f64;
+        let phi = libm::pow(libm::sin(w / 2.0), 2.0);
+
+        let (b0, b1, b2) = (self.b0 as f64, self.b1 as f64, self.b2 as f64);
+        let (a1, a2) = (self.a1 as f64, self.a2 as f64);
+
+        let numerator = libm::pow(b0 + b1 + b2, 2.0)
+            - 4.0 * (b0 * b1 + 4.0 * b0 * b2 + b1 * b2) * phi
+            + 16.0 * b0 * b2 * phi * phi;
+        let denominator = libm::pow(1.0 + a1 + a2, 2.0) - 4.0 * (a1 + 4.0 * a2 + a1 * a2) * phi
+            + 16.0 * a2 * phi * phi;
+
+        libm::sqrt(libm::fmax(numerator, 0.0) / libm::fmax(denominator, f64::MIN_POSITIVE)) as f32
+    }
+
+    /// Adds the per-sample ramp step to each coefficient.
+    #[inline]
+    fn offset(&mut self, step: &Coefficients) {
+        self.b0 += step.b0;
+        self.b1 += step.b1;
+        self.b2 += step.b2;
+        self.a1 += step.a1;
+        self.a2 += step.a2;
+    }
+}
+
+impl Default for Coefficients {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// The settings a filter is designed from, kept so a [`Biquad`] can be
+/// redesigned when the sample rate changes.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Design {
+    pub kind: FilterKind,
+    pub frequency: Hertz,
+    pub q: f32,
+    pub gain_db: f32,
+}
+
+impl Design {
+    pub const fn new(kind: FilterKind, frequency: Hertz, q: f32, gain_db: f32) -> Self {
+        Self {
+            kind,
+            frequency,
+            q,
+            gain_db,
+        }
+    }
+
+    /// Designs the coefficients for the sample rate, see [`Coefficients::new`].
+    pub fn coefficients(&self, sample_rate: f32) -> Coefficients {
+        Coefficients::new(self.kind, sample_rate, self.frequency, self.q, self.gain_db)
+    }
+}
+
+/// A biquad filter with smoothed coefficient updates.
+///
+/// Changing the coefficients of an IIR filter instantly while audio is running
+/// causes clicks and zipper noise, especially when a knob is sweeping the
+/// cutoff or gain. Coefficients set with [`Biquad::set_coefficients`] are
+/// instead ramped to over the configured smoothing time.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone)]
+pub struct Biquad {
+    /// The sample rate the filter is running at.
+    sample_rate: f32,
+
+    /// The coefficients used for the current sample.
+    current: Coefficients,
+    /// The coefficients the filter is being smoothed towards.
+    target: Coefficients,
+    /// How many samples a coefficient change is ramped over.
+    ramp_frames: u32,
+    /// The per-sample change applied to the coefficients while ramping.
+    step: Coefficients,
+    /// How many samples are left until the current coefficients reach the target.
+    remaining: u32,
+
+    /// The settings the target coefficients were designed from, if any.
+    design: Option<Design>,
+
+    // Transposed direct form II state.
+    z1: f32,
+    z2: f32,
+}
+
+impl Biquad {
+    /// Construct a new filter running at the sample rate with the given coefficients.
+    pub fn new(sample_rate: f32, coefficients: Coefficients) -> Self {
+        let mut filter = Self {
+            sample_rate,
+            current: coefficients,
+            target: coefficients,
+            ramp_frames: 0,
+            step: Coefficients::IDENTITY,
+            remaining: 0,
+            design: None,
+            z1: 0.0,
+            z2: 0.0,
+        };
+
+        // A short default smoothing time is enough to hide zipper noise
+        // from knob movements without audibly lagging behind them.
+        filter.set_smoothing_time(0.005);
+
+        filter
+    }
+
+    /// Returns the sample rate the filter was configured for.
+    #[inline]
+    pub const fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Changes the sample rate the filter runs at, keeping the smoothing time.
+    ///
+    /// The coefficients are left as they are, so they need redesigning for the new rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.ramp_frames = (self.ramp_frames as f32 * sample_rate / self.sample_rate) as u32;
+        self.sample_rate = sample_rate;
+    }
+
+    /// Sets how long a coefficient change takes to settle, in seconds.
+    ///
+    /// A time of zero makes coefficient changes apply immediately.
+    pub fn set_smoothing_time(&mut self, seconds: f32) {
+        self.ramp_frames = libm::fmaxf(seconds * self.sample_rate, 0.0) as u32;
+    }
+
+    /// Designs the coefficients the filter smoothly moves towards.
+    ///
+    /// Unlike coefficients set directly, the design is kept and the filter is
+    /// redesigned for the new rate when it's prepared as an [`AudioProcessor`].
+    pub fn set_design(&mut self, design: Design) {
+        self.set_coefficients(design.coefficients(self.sample_rate));
+        self.design = Some(design);
+    }
+
+    /// Returns the settings the coefficients were designed from, or `None`
+    /// if they were set directly.
+    #[inline]
+    pub const fn design(&self) -> Option<Design> {
+        self.design
+    }
+
+    /// Sets the coefficients the filter smoothly moves towards.
+    ///
+    /// The coefficients are ramped linearly from their current values, so a
+    /// change made part way through a previous ramp continues smoothly.
+    pub fn set_coefficients(&mut self, coefficients: Coefficients) {
+        self.target = coefficients;
+        self.design = None;
+
+        if self.ramp_frames == 0 {
+            self.current = coefficients;
+            self.remaining = 0;
+            return;
+        }
+
+        let frames = self.ramp_frames as f32;
+        self.step = Coefficients {
+            b0: (coefficients.b0 - self.current.b0) / frames,
+            b1: (coefficients.b1 - self.current.b1) / frames,
+            b2: (coefficients.b2 - self.current.b2) / frames,
+            a1: (coefficients.a1 - self.current.a1) / frames,
+            a2: (coefficients.a2 - self.current.a2) / frames,
+        };
+        self.remaining = self.ramp_frames;
+    }
+
+    /// Sets the coefficients without any smoothing.
+    ///
+    /// Use this when the filter isn't producing audio yet, such as when loading a preset.
+    pub fn set_coefficients_immediate(&mut self, coefficients: Coefficients) {
+        self.target = coefficients;
+        self.design = None;
+        self.current = coefficients;
+        self.remaining = 0;
+    }
+
+    /// Returns the coefficients the filter is smoothing towards.
+    #[inline]
+    pub const fn coefficients(&self) -> Coefficients {
+        self.target
+    }
+
+    /// Clears the filter state, leaving the coefficients in place.
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+        self.current = self.target;
+        self.remaining = 0;
+    }
+
+    /// Returns true while the coefficients are ramping towards a new target.
+    #[inline]
+    pub const fn is_smoothing(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Filters a single sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+
+            // Snap to the target at the end of the ramp so that
+            // rounding errors from the steps don't accumulate.
+            if self.remaining == 0 {
+                self.current = self.target;
+            } else {
+                self.current.offset(&self.step);
+            }
+        }
+
+        let c = &self.current;
+        let output = c.b0 * input + self.z1;
+        self.z1 = c.b1 * input - c.a1 * output + self.z2;
+        self.z2 = c.b2 * input - c.a2 * output;
+
+        output
+    }
+
+    /// Filters a buffer of samples in place.
+    pub fn process_in_place(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+impl AudioProcessor for Biquad {
+    type Frame = f32;
+
+    /// Moves the filter to the sample rate, redesigning it if it was set
+    /// with [`Biquad::set_design`]. Coefficients set directly are kept.
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.set_sample_rate(sample_rate);
+
+        if let Some(design) = self.design {
+            self.set_coefficients_immediate(design.coefficients(sample_rate));
+            self.design = Some(design);
+        }
+    }
+
+    fn reset(&mut self) {
+        Biquad::reset(self);
+    }
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [f32]) {
+        Biquad::process_in_place(self, buffer);
+    }
+}
+
+impl FrequencyResponse for Biquad {
+    fn response_db(&self, frequency: Hertz) -> f32 {
+        gain_to_db(self.target.magnitude(self.sample_rate, frequency))
+    }
+}
+
+/// Converts a linear gain into decibels, flooring silence at -120dB.
+#[inline]
+pub fn gain_to_db(gain: f32) -> f32 {
+    20.0 * libm::log10f(libm::fmaxf(gain, 1.0e-6))
+}
+
+/// Converts decibels into a linear gain.
+#[inline]
+pub fn db_to_gain(db: f32) -> f32 {
+    libm::powf(10.0, db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    #[test]
+    fn test_peaking_response() {
+        let coefficients = Coefficients::peaking(SAMPLE_RATE, Hertz(1_000.0), 1.0, 6.0);
+        let filter = Biquad::new(SAMPLE_RATE, coefficients);
+
+        assert!((filter.response_db(Hertz(1_000.0)) - 6.0).abs() < 0.01);
+        assert!(filter.response_db(Hertz(20.0)).abs() < 0.1);
+        assert!(filter.response_db(Hertz(20_000.0)).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_low_pass_attenuates() {
+        let coefficients = Coefficients::low_pass(SAMPLE_RATE, Hertz(1_000.0), 0.707);
+        let filter = Biquad::new(SAMPLE_RATE, coefficients);
+
+        assert!(filter.response_db(Hertz(100.0)).abs() < 0.1);
+        assert!((filter.response_db(Hertz(1_000.0)) + 3.0).abs() < 0.1);
+        assert!(filter.response_db(Hertz(10_000.0)) < -35.0);
+    }
+
+    #[test]
+    fn test_dc_gain_of_low_shelf() {
+        let coefficients = Coefficients::low_shelf(SAMPLE_RATE, Hertz(200.0), 0.707, -9.0);
+        let mut filter = Biquad::new(SAMPLE_RATE, coefficients);
+
+        let mut output = 0.0;
+        for _ in 0..48_000 {
+            output = filter.process(1.0);
+        }
+
+        assert!((gain_to_db(output) + 9.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_smoothing_settles() {
+        let mut filter = Biquad::new(SAMPLE_RATE, Coefficients::IDENTITY);
+        let target = Coefficients::low_pass(SAMPLE_RATE, Hertz(500.0), 0.707);
+        filter.set_coefficients(target);
+
+        // The first sample should only have moved part way to the target.
+        filter.process(0.0);
+        assert!(filter.is_smoothing());
+        assert!(filter.current != target);
+
+        for _ in 0..48_000 {
+            filter.process(0.0);
+        }
+        assert!(!filter.is_smoothing());
+        assert_eq!(filter.current, target);
+    }
+
+    #[test]
+    fn test_prepare_redesigns() {
+        let design = Design::new(FilterKind::LowPass, Hertz(1_000.0), 0.707, 0.0);
+        let mut filter = Biquad::new(SAMPLE_RATE, Coefficients::IDENTITY);
+        filter.set_design(design);
+
+        // The cutoff stays put at the new rate.
+        filter.prepare(96_000.0, 256);
+        assert_eq!(filter.coefficients(), design.coefficients(96_000.0));
+        assert!((filter.response_db(Hertz(1_000.0)) + 3.0).abs() < 0.1);
+        assert!(!filter.is_smoothing());
+
+        // Coefficients set directly have nothing to be redesigned from.
+        let coefficients = Coefficients::low_pass(96_000.0, Hertz(500.0), 0.707);
+        filter.set_coefficients_immediate(coefficients);
+        assert_eq!(filter.design(), None);
+        filter.prepare(SAMPLE_RATE, 256);
+        assert_eq!(filter.coefficients(), coefficients);
+    }
+}
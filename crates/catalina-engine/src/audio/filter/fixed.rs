@@ -0,0 +1,312 @@
+//! Fixed-point biquad filters for parts without an FPU.
+//!
+//! [`BiquadQ15`] and [`BiquadQ31`] run the same filters as [`Biquad`](super::Biquad) using only
+//! integer arithmetic. They follow the direct form I structure and coefficient layout of
+//! CMSIS-DSP's `arm_biquad_cascade_df1_q15` and `arm_biquad_cascade_df1_q31`, so coefficients
+//! can be shared with CMSIS-DSP, but the output isn't bit exact with it. Both filters here
+//! saturate the output, where `arm_biquad_cascade_df1_q31` lets it wrap.
+//!
+//! Filter coefficients are often larger than one, which the fractional formats can't
+//! represent, so they're scaled down by a power of two, the *post shift*, and the
+//! accumulator is shifted back up before the output is saturated.
+
+use super::Coefficients;
+use crate::audio::processor::AudioProcessor;
+use crate::audio::sample::{Q15, Q31};
+
+/// The largest post shift, which fits coefficients up to 256 in magnitude.
+pub const MAX_POST_SHIFT: u32 = 8;
+
+/// An error returned when coefficients can't be quantized for a fixed-point filter.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FixedBiquadError {
+    /// A coefficient is infinite or NaN.
+    NonFinite,
+    /// A coefficient, or the post shift, is larger than [`MAX_POST_SHIFT`] allows.
+    OutOfRange,
+}
+
+/// The post shift needed to bring the largest coefficient into `-1.0 <= c < 1.0`.
+fn post_shift(coefficients: &Coefficients) -> Result<u32, FixedBiquadError> {
+    let coefficients = [
+        coefficients.b0,
+        coefficients.b1,
+        coefficients.b2,
+        coefficients.a1,
+        coefficients.a2,
+    ];
+    if coefficients.iter().any(|c| !c.is_finite()) {
+        return Err(FixedBiquadError::NonFinite);
+    }
+
+    let largest = coefficients.iter().fold(0.0f32, |max, c| max.max(c.abs()));
+    (0..=MAX_POST_SHIFT)
+        .find(|&shift| largest < (1u32 << shift) as f32)
+        .ok_or(FixedBiquadError::OutOfRange)
+}
+
+macro_rules! fixed_biquad {
+    ($Biquad:ident, $Q:ident, $Rep:ident, $Acc:ident, $bits:expr, $cmsis:expr) => {
+        #[doc = concat!(
+            "A biquad filter processing [`", stringify!($Q), "`] samples, structured like CMSIS-DSP's `",
+            $cmsis, "`."
+        )]
+        ///
+        /// Unlike the floating point filter, coefficient changes are applied immediately.
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[derive(Debug, Clone)]
+        pub struct $Biquad {
+            /// `b0, b1, b2, a1, a2` scaled down by the post shift, with the feedback
+            /// coefficients negated so every term is accumulated.
+            coefficients: [$Rep; 5],
+            post_shift: u32,
+
+            // Direct form I state.
+            x1: $Rep,
+            x2: $Rep,
+            y1: $Rep,
+            y2: $Rep,
+        }
+
+        impl $Biquad {
+            /// Construct a new filter with the given coefficients.
+            pub fn new(coefficients: Coefficients) -> Result<Self, FixedBiquadError> {
+                let mut filter = Self {
+                    coefficients: [0; 5],
+                    post_shift: 0,
+                    x1: 0,
+                    x2: 0,
+                    y1: 0,
+                    y2: 0,
+                };
+                filter.set_coefficients(coefficients)?;
+
+                Ok(filter)
+            }
+
+            /// Quantizes new coefficients, choosing the smallest post shift that fits them.
+            ///
+            /// The coefficients are left unchanged if any are infinite or NaN, or
+            /// too large for [`MAX_POST_SHIFT`].
+            pub fn set_coefficients(
+                &mut self,
+                coefficients: Coefficients,
+            ) -> Result<(), FixedBiquadError> {
+                let shift = post_shift(&coefficients)?;
+                let scale = (1u64 << ($bits - shift)) as f64;
+                let quantize = |c: f32| {
+                    libm::round(c as f64 * scale).clamp($Rep::MIN as f64, $Rep::MAX as f64) as $Rep
+                };
+
+                self.coefficients = [
+                    quantize(coefficients.b0),
+                    quantize(coefficients.b1),
+                    quantize(coefficients.b2),
+                    quantize(-coefficients.a1),
+                    quantize(-coefficients.a2),
+                ];
+                self.post_shift = shift;
+
+                Ok(())
+            }
+
+            /// Sets pre-quantized coefficients, as used by CMSIS-DSP.
+            ///
+            /// `coefficients` are `b0, b1, b2, -a1, -a2`, each scaled by `2^-post_shift`,
+            /// and the post shift can be at most [`MAX_POST_SHIFT`].
+            pub fn set_coefficients_raw(
+                &mut self,
+                coefficients: [$Rep; 5],
+                post_shift: u32,
+            ) -> Result<(), FixedBiquadError> {
+                if post_shift > MAX_POST_SHIFT {
+                    return Err(FixedBiquadError::OutOfRange);
+                }
+
+                self.coefficients = coefficients;
+                self.post_shift = post_shift;
+
+                Ok(())
+            }
+
+            /// Returns the post shift applied to the coefficients.
+            #[inline]
+            pub const fn post_shift(&self) -> u32 {
+                self.post_shift
+            }
+
+            /// Clears the filter state, leaving the coefficients in place.
+            pub fn reset(&mut self) {
+                self.x1 = 0;
+                self.x2 = 0;
+                self.y1 = 0;
+                self.y2 = 0;
+            }
+
+            /// Filters a single sample.
+            #[inline]
+            pub fn process(&mut self, input: $Q) -> $Q {
+                let [b0, b1, b2, a1, a2] = self.coefficients;
+                let x = input.to_bits();
+
+                // The accumulator wraps rather than saturating, and only the
+                // output is saturated.
+                let acc = (b0 as $Acc * x as $Acc)
+                    .wrapping_add(b1 as $Acc * self.x1 as $Acc)
+                    .wrapping_add(b2 as $Acc * self.x2 as $Acc)
+                    .wrapping_add(a1 as $Acc * self.y1 as $Acc)
+                    .wrapping_add(a2 as $Acc * self.y2 as $Acc);
+
+                let shifted = acc >> ($bits - self.post_shift);
+                let output = shifted.clamp($Rep::MIN as $Acc, $Rep::MAX as $Acc) as $Rep;
+
+                self.x2 = self.x1;
+                self.x1 = x;
+                self.y2 = self.y1;
+                self.y1 = output;
+
+                $Q::from_bits(output)
+            }
+
+            /// Filters a buffer of samples in place.
+            pub fn process_in_place(&mut self, buffer: &mut [$Q]) {
+                for sample in buffer.iter_mut() {
+                    *sample = self.process(*sample);
+                }
+            }
+        }
+
+        impl AudioProcessor for $Biquad {
+            type Frame = $Q;
+
+            fn reset(&mut self) {
+                $Biquad::reset(self);
+            }
+
+            #[inline]
+            fn process_in_place(&mut self, buffer: &mut [$Q]) {
+                $Biquad::process_in_place(self, buffer);
+            }
+        }
+    };
+}
+
+fixed_biquad!(BiquadQ15, Q15, i16, i64, 15, "arm_biquad_cascade_df1_q15");
+fixed_biquad!(BiquadQ31, Q31, i32, i64, 31, "arm_biquad_cascade_df1_q31");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::filter::Biquad;
+    use crate::core::Hertz;
+    use crate::prelude::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn compare(coefficients: Coefficients, frequency: f32, q15_tolerance: f32, q31_tolerance: f32) {
+        let mut float = Biquad::new(SAMPLE_RATE, coefficients);
+        let mut q15 = BiquadQ15::new(coefficients).unwrap();
+        let mut q31 = BiquadQ31::new(coefficients).unwrap();
+
+        for i in 0..4_800 {
+            let x = 0.5 * libm::sinf(2.0 * PI * frequency * i as f32 / SAMPLE_RATE);
+            let expected = float.process(x);
+
+            let q15_out = q15.process(Q15::from_f32(x)).to_f32();
+            let q31_out = q31.process(Q31::from_f32(x)).to_f32();
+            assert!(
+                (q15_out - expected).abs() < q15_tolerance,
+                "{i}: {q15_out} {expected}"
+            );
+            assert!(
+                (q31_out - expected).abs() < q31_tolerance,
+                "{i}: {q31_out} {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_float() {
+        compare(
+            Coefficients::low_pass(SAMPLE_RATE, Hertz(2_000.0), 0.707),
+            500.0,
+            2e-3,
+            1e-5,
+        );
+        compare(
+            Coefficients::peaking(SAMPLE_RATE, Hertz(1_000.0), 1.0, 6.0),
+            1_000.0,
+            2e-3,
+            1e-5,
+        );
+    }
+
+    #[test]
+    fn test_post_shift() {
+        // A low pass has |a1| close to 2, so needs a shift of one.
+        let filter =
+            BiquadQ15::new(Coefficients::low_pass(SAMPLE_RATE, Hertz(1_000.0), 0.707)).unwrap();
+        assert_eq!(filter.post_shift(), 1);
+
+        let filter = BiquadQ31::new(Coefficients::IDENTITY).unwrap();
+        assert_eq!(filter.post_shift(), 1);
+    }
+
+    #[test]
+    fn test_invalid_coefficients() {
+        let infinite = Coefficients {
+            a1: f32::INFINITY,
+            ..Coefficients::IDENTITY
+        };
+        let nan = Coefficients {
+            b2: f32::NAN,
+            ..Coefficients::IDENTITY
+        };
+        let huge = Coefficients {
+            b0: 1.0e9,
+            ..Coefficients::IDENTITY
+        };
+        assert_eq!(
+            BiquadQ15::new(infinite).unwrap_err(),
+            FixedBiquadError::NonFinite
+        );
+        assert_eq!(
+            BiquadQ31::new(nan).unwrap_err(),
+            FixedBiquadError::NonFinite
+        );
+        assert_eq!(
+            BiquadQ15::new(huge).unwrap_err(),
+            FixedBiquadError::OutOfRange
+        );
+
+        // A failed update keeps the filter as it was.
+        let mut filter = BiquadQ31::new(Coefficients::IDENTITY).unwrap();
+        assert!(filter.set_coefficients(huge).is_err());
+        assert!(
+            filter
+                .set_coefficients_raw([0; 5], MAX_POST_SHIFT + 1)
+                .is_err()
+        );
+        assert_eq!(filter.post_shift(), 1);
+        assert_eq!(filter.process(Q31::HALF), Q31::HALF);
+    }
+
+    #[test]
+    fn test_output_saturates() {
+        // A +12dB gain on a half scale input clips rather than wrapping.
+        let gain = Coefficients {
+            b0: 4.0,
+            ..Coefficients::IDENTITY
+        };
+        let mut q15 = BiquadQ15::new(gain).unwrap();
+        let mut q31 = BiquadQ31::new(gain).unwrap();
+        assert_eq!(q15.post_shift(), 3);
+
+        assert_eq!(q15.process(Q15::HALF), Q15::MAX);
+        assert_eq!(q15.process(-Q15::HALF), Q15::MIN);
+        assert_eq!(q15.process(Q15::from_f32(0.125)), Q15::HALF);
+        assert_eq!(q31.process(Q31::HALF), Q31::MAX);
+        assert_eq!(q31.process(-Q31::HALF), Q31::MIN);
+    }
+}
@@ -0,0 +1,36 @@
+//! Filters for shaping the frequency content of audio signals.
+//!
+//! The [`biquad`] module provides the second-order filter core that the
+//! equalizers in [`crate::audio::eq`] and other tone-shaping blocks are built on,
+//! and [`fixed`] runs the same filters on [`Q15`](crate::audio::sample::Q15) and
+//! [`Q31`](crate::audio::sample::Q31) samples. The [`svf`] module provides a
+//! state variable filter for synth filters modulated every sample.
+
+use crate::core::Hertz;
+
+pub mod biquad;
+pub use biquad::{Biquad, Coefficients, Design, FilterKind, db_to_gain, gain_to_db};
+
+pub mod fixed;
+pub use fixed::{BiquadQ15, BiquadQ31, FixedBiquadError};
+
+pub mod svf;
+pub use svf::{Svf, SvfMode, SvfOutput};
+
+/// Types that can report their magnitude response, such as filters and
+/// equalizers, so that devices and tools can draw their curves.
+pub trait FrequencyResponse {
+    /// Returns the gain in decibels applied at the given frequency.
+    fn response_db(&self, frequency: Hertz) -> f32;
+
+    /// Evaluates the response at each frequency, writing the decibels to `output`.
+    ///
+    /// **Panics** if the slice lengths differ.
+    fn response_db_into(&self, frequencies: &[Hertz], output: &mut [f32]) {
+        assert_eq!(frequencies.len(), output.len());
+
+        for (frequency, db) in frequencies.iter().zip(output.iter_mut()) {
+            *db = self.response_db(*frequency);
+        }
+    }
+}
@@ -0,0 +1,256 @@
+//! A state variable filter, for synth filters swept by envelopes and LFOs.
+//!
+//! A [`Biquad`](super::Biquad) takes several trig calls to redesign, so it
+//! suits filters that are set by a knob and left alone. The state variable
+//! filter maps its cutoff and resonance onto its coefficients with a single
+//! `tan`, so they can be modulated every sample, and it produces its lowpass,
+//! bandpass and highpass outputs at the same time.
+//!
+//! This is the trapezoidal integrated form from Andrew Simper's
+//! [Cytomic technical papers](https://cytomic.com/technical-papers/), which
+//! stays stable and in tune right up to Nyquist, even while being swept.
+
+use crate::{audio::processor::AudioProcessor, core::Hertz, prelude::*};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Which of the filter's responses [`Svf::process`] returns.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Default)]
+pub enum SvfMode {
+    /// Passes frequencies below the cutoff.
+    #[default]
+    LowPass,
+    /// Passes a band around the cutoff.
+    BandPass,
+    /// Passes frequencies above the cutoff.
+    HighPass,
+    /// Rejects a band around the cutoff.
+    Notch,
+}
+
+/// Every response of the filter for one sample.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SvfOutput {
+    pub low: f32,
+    pub band: f32,
+    pub high: f32,
+}
+
+impl SvfOutput {
+    /// Returns the response for the mode.
+    #[inline]
+    pub fn get(&self, mode: SvfMode) -> f32 {
+        match mode {
+            SvfMode::LowPass => self.low,
+            SvfMode::BandPass => self.band,
+            SvfMode::HighPass => self.high,
+            SvfMode::Notch => self.low + self.high,
+        }
+    }
+}
+
+/// A resonant state variable filter.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone)]
+pub struct Svf {
+    sample_rate: f32,
+    mode: SvfMode,
+
+    cutoff: f32,
+    resonance: f32,
+
+    // The damping and the integrator gains derived from it and the cutoff.
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+
+    // The integrator states.
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Svf {
+    /// Construct a new lowpass filter with a 1kHz cutoff and no resonance.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut filter = Self {
+            sample_rate,
+            mode: SvfMode::LowPass,
+
+            cutoff: 1_000.0,
+            resonance: 0.0,
+
+            k: 2.0,
+            a1: 0.0,
+            a2: 0.0,
+            a3: 0.0,
+
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        };
+        filter.update();
+        filter
+    }
+
+    /// Changes the sample rate the filter runs at, keeping the cutoff as
+    /// long as it's still under Nyquist.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.set_cutoff(Hertz(self.cutoff));
+    }
+
+    /// Sets which response [`Svf::process`] returns.
+    pub fn set_mode(&mut self, mode: SvfMode) {
+        self.mode = mode;
+    }
+
+    #[inline]
+    pub const fn mode(&self) -> SvfMode {
+        self.mode
+    }
+
+    /// Sets the cutoff, which is limited to just under Nyquist.
+    #[inline]
+    pub fn set_cutoff(&mut self, cutoff: Hertz) {
+        self.cutoff = cutoff.0.clamp(1.0, self.sample_rate * 0.49);
+        self.update();
+    }
+
+    #[inline]
+    pub fn cutoff(&self) -> Hertz {
+        Hertz(self.cutoff)
+    }
+
+    /// Sets the resonance from 0 for none to 1 for the edge of self-oscillation.
+    #[inline]
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.resonance = resonance.clamp(0.0, 1.0);
+        self.k = 2.0 - 1.98 * self.resonance;
+        self.update();
+    }
+
+    #[inline]
+    pub const fn resonance(&self) -> f32 {
+        self.resonance
+    }
+
+    /// Clears the filter state, leaving the cutoff and resonance in place.
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+
+    /// Filters a single sample, returning every response.
+    #[inline]
+    pub fn process_all(&mut self, input: f32) -> SvfOutput {
+        let v3 = input - self.ic2eq;
+        let v1 = self.a1 * self.ic1eq + self.a2 * v3;
+        let v2 = self.ic2eq + self.a2 * self.ic1eq + self.a3 * v3;
+
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        SvfOutput {
+            low: v2,
+            band: v1,
+            high: input - self.k * v1 - v2,
+        }
+    }
+
+    /// Filters a single sample, returning the response for the mode.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.process_all(input).get(self.mode)
+    }
+
+    /// Filters a buffer of samples in place.
+    pub fn process_in_place(&mut self, buffer: &mut [f32]) {
+        for sample in buffer.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+
+    fn update(&mut self) {
+        let g = libm::tanf(PI * self.cutoff / self.sample_rate);
+
+        self.a1 = 1.0 / (1.0 + g * (g + self.k));
+        self.a2 = g * self.a1;
+        self.a3 = g * self.a2;
+    }
+}
+
+impl AudioProcessor for Svf {
+    type Frame = f32;
+
+    fn prepare(&mut self, sample_rate: f32, _max_block: usize) {
+        self.set_sample_rate(sample_rate);
+    }
+
+    fn reset(&mut self) {
+        Svf::reset(self);
+    }
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [f32]) {
+        Svf::process_in_place(self, buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::audio::oscillator::sample_sine;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    /// Returns the peak level of a sine at the frequency through the filter.
+    fn peak(filter: &mut Svf, frequency: f32) -> f32 {
+        let sample_rate = filter.sample_rate;
+        filter.reset();
+        (0..4_800)
+            .map(|i| filter.process(sample_sine(i, sample_rate, Hertz(frequency))))
+            .skip(2_400)
+            .fold(0.0, |peak: f32, frame| peak.max(frame.abs()))
+    }
+
+    #[test]
+    fn test_responses() {
+        let mut filter = Svf::new(SAMPLE_RATE);
+        filter.set_cutoff(Hertz(1_000.0));
+
+        assert!(peak(&mut filter, 100.0) > 0.95);
+        assert!(peak(&mut filter, 10_000.0) < 0.02);
+
+        filter.set_mode(SvfMode::HighPass);
+        assert!(peak(&mut filter, 100.0) < 0.02);
+        assert!(peak(&mut filter, 10_000.0) > 0.95);
+
+        // Resonance boosts the cutoff frequency.
+        filter.set_mode(SvfMode::LowPass);
+        filter.set_resonance(0.9);
+        assert!(peak(&mut filter, 1_000.0) > 4.0);
+    }
+
+    #[test]
+    fn test_prepare_redesigns() {
+        let mut filter = Svf::new(SAMPLE_RATE);
+        filter.set_cutoff(Hertz(1_000.0));
+        let before = peak(&mut filter, 1_000.0);
+
+        // The cutoff stays put at the new rate.
+        filter.prepare(96_000.0, 256);
+        assert_eq!(filter.sample_rate, 96_000.0);
+        assert_eq!(filter.cutoff(), Hertz(1_000.0));
+        assert!((peak(&mut filter, 1_000.0) - before).abs() < 0.01);
+
+        // A cutoff past the new Nyquist is pulled back under it.
+        filter.set_cutoff(Hertz(40_000.0));
+        filter.prepare(SAMPLE_RATE, 256);
+        assert!(filter.cutoff().0 < SAMPLE_RATE / 2.0);
+    }
+}
@@ -570,6 +570,8 @@ impl_frame_for_sample! {
     crate::audio::sample::types::I48
     crate::audio::sample::types::U24
     crate::audio::sample::types::U48
+    crate::audio::sample::fixed::Q15
+    crate::audio::sample::fixed::Q31
 }
 
 impl<F> Iterator for Channels<F>
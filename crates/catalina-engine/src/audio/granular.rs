@@ -0,0 +1,482 @@
+//! A granular synthesis engine over a buffer of samples.
+//!
+//! Granular synthesis chops a recording into short, overlapping grains and
+//! plays them back with their own position, pitch and pan. Playing grains
+//! from one spot freezes a sound into a drone, moving the position slowly
+//! stretches it without changing the pitch, and randomizing everything
+//! scatters it into a cloud.
+//!
+//! The [`Granular`] engine reads from any slice of samples, such as a sample
+//! loaded into SDRAM, and plays up to `GRAINS` grains at once without
+//! allocating. Each grain is shaped by a window looked up from a table that's
+//! built when the [`GrainWindow`] changes.
+//!
+//! # Example
+//!
+//! ```
+//! use catalina_engine::audio::granular::Granular;
+//! use catalina_engine::audio::signal::Signal;
+//!
+//! let source: [i16; 4800] = core::array::from_fn(|i| ((i % 100) as i16 - 50) * 300);
+//!
+//! let mut granular = Granular::<_, 16>::new(48_000.0, &source, 7);
+//! granular.set_grain_size(0.05);
+//! granular.set_density(40.0);
+//! granular.set_position(0.5);
+//! granular.set_position_jitter(0.1);
+//!
+//! let [left, right] = granular.next();
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::audio::sample::{Sample, ToSample};
+use crate::audio::signal::Signal;
+use crate::audio::window::{Hann, Window};
+use crate::audio::{AudioSource, Stereo};
+use crate::core::rng::Rng;
+use crate::prelude::*;
+
+/// The number of steps in the window table, which holds one extra point so
+/// lookups can always interpolate.
+const WINDOW_STEPS: usize = 256;
+
+/// The shape grains fade in and out with.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum GrainWindow {
+    /// A raised cosine, smooth grains that blend into a continuous texture.
+    #[default]
+    Hann,
+    /// A linear fade in and out.
+    Triangle,
+    /// Flat with Hann fades taking up the given fraction of the grain, from 0
+    /// to 1, for grains that keep more of the source's transients.
+    Tukey(f32),
+    /// No fades, for hard, clicky grains.
+    Rectangle,
+}
+
+impl GrainWindow {
+    /// Returns the level of the window at a phase from 0 to 1.
+    fn level(self, phase: f32) -> f32 {
+        match self {
+            Self::Hann => <Hann as Window<f32>>::window(phase),
+            Self::Triangle => 1.0 - (2.0 * phase - 1.0).abs(),
+            Self::Tukey(fade) => {
+                let fade = fade.clamp(0.0, 1.0);
+                let edge = phase.min(1.0 - phase);
+                if edge < fade * 0.5 {
+                    <Hann as Window<f32>>::window(edge / fade)
+                } else {
+                    1.0
+                }
+            }
+            Self::Rectangle => 1.0,
+        }
+    }
+}
+
+/// A single grain being played.
+#[derive(Debug, Copy, Clone, Default)]
+struct Grain {
+    active: bool,
+    /// The position in the source, in samples.
+    position: f32,
+    /// How far the position moves each sample.
+    rate: f32,
+    /// How far through its window the grain is, from 0 to 1.
+    phase: f32,
+    /// How far the phase moves each sample.
+    step: f32,
+    /// The samples left to play.
+    remaining: u32,
+    /// The gain of the grain in each channel.
+    gains: [f32; 2],
+}
+
+/// A granular engine reading grains from a slice of `S` samples, playing up
+/// to `GRAINS` grains at once into a stereo output.
+pub struct Granular<'a, S, const GRAINS: usize> {
+    sample_rate: f32,
+    source: &'a [S],
+    rng: Rng,
+
+    grains: [Grain; GRAINS],
+    window: GrainWindow,
+    /// The window shape, sampled at evenly spaced phases.
+    table: [f32; WINDOW_STEPS + 1],
+
+    /// The length of each grain, in seconds.
+    size: f32,
+    /// How many grains start each second.
+    density: f32,
+    /// How randomly the grains are spaced out, from 0 to 1.
+    timing_jitter: f32,
+    /// Where grains start in the source, from 0 to 1.
+    position: f32,
+    /// How far grains randomly start from the position, as a fraction of the source.
+    position_jitter: f32,
+    /// The playback rate of the grains.
+    pitch: f32,
+    /// How far the pitch of each grain is randomly moved, in semitones.
+    pitch_jitter: f32,
+    /// Where the grains are placed, from -1 for left to 1 for right.
+    pan: f32,
+    /// How far grains are randomly spread around the pan, from 0 to 1.
+    pan_jitter: f32,
+    gain: f32,
+
+    /// The samples until the next grain starts.
+    countdown: f32,
+}
+
+impl<'a, S, const GRAINS: usize> Granular<'a, S, GRAINS>
+where
+    S: Sample + ToSample<f32>,
+{
+    /// Builds an engine reading grains from the source, with the grains'
+    /// randomness seeded by `seed`.
+    pub fn new(sample_rate: f32, source: &'a [S], seed: u64) -> Self {
+        let mut granular = Self {
+            sample_rate,
+            source,
+            rng: Rng::new(seed),
+
+            grains: [Grain::default(); GRAINS],
+            window: GrainWindow::Hann,
+            table: [0.0; WINDOW_STEPS + 1],
+
+            size: 0.1,
+            density: 20.0,
+            timing_jitter: 0.0,
+            position: 0.0,
+            position_jitter: 0.0,
+            pitch: 1.0,
+            pitch_jitter: 0.0,
+            pan: 0.0,
+            pan_jitter: 0.0,
+            gain: 1.0,
+
+            countdown: 0.0,
+        };
+        granular.set_window(GrainWindow::Hann);
+        granular
+    }
+
+    /// Changes the buffer grains are read from, stopping any playing grains.
+    pub fn set_source(&mut self, source: &'a [S]) {
+        self.source = source;
+        self.reset();
+    }
+
+    #[inline]
+    pub fn source(&self) -> &'a [S] {
+        self.source
+    }
+
+    /// Changes the sample rate, keeping the grain size and density in seconds.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    #[inline]
+    pub fn window(&self) -> GrainWindow {
+        self.window
+    }
+
+    /// Changes the shape grains fade in and out with.
+    pub fn set_window(&mut self, window: GrainWindow) {
+        self.window = window;
+        for (i, level) in self.table.iter_mut().enumerate() {
+            *level = window.level(i as f32 / WINDOW_STEPS as f32);
+        }
+    }
+
+    #[inline]
+    pub fn grain_size(&self) -> f32 {
+        self.size
+    }
+
+    /// Sets the length of each grain, in seconds.
+    pub fn set_grain_size(&mut self, seconds: f32) {
+        self.size = seconds.max(0.0);
+    }
+
+    #[inline]
+    pub fn density(&self) -> f32 {
+        self.density
+    }
+
+    /// Sets how many grains start each second.
+    pub fn set_density(&mut self, grains_per_second: f32) {
+        self.density = grains_per_second.max(0.0);
+    }
+
+    /// Sets how randomly grains are spaced out, from 0 for evenly to 1 for
+    /// anywhere up to twice the average spacing.
+    pub fn set_timing_jitter(&mut self, jitter: f32) {
+        self.timing_jitter = jitter.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Sets where grains start in the source, from 0 at the start to 1 at the end.
+    pub fn set_position(&mut self, position: f32) {
+        self.position = position.clamp(0.0, 1.0);
+    }
+
+    /// Sets how far grains randomly start from the position, as a fraction of the source.
+    pub fn set_position_jitter(&mut self, jitter: f32) {
+        self.position_jitter = jitter.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    /// Sets the playback rate of new grains, where 2 is an octave up.
+    pub fn set_pitch(&mut self, rate: f32) {
+        self.pitch = rate.max(0.0);
+    }
+
+    /// Sets the pitch of new grains in semitones.
+    pub fn set_pitch_semitones(&mut self, semitones: f32) {
+        self.pitch = libm::exp2f(semitones / 12.0);
+    }
+
+    /// Sets how far the pitch of each grain is randomly moved, in semitones.
+    pub fn set_pitch_jitter(&mut self, semitones: f32) {
+        self.pitch_jitter = semitones.max(0.0);
+    }
+
+    #[inline]
+    pub fn pan(&self) -> f32 {
+        self.pan
+    }
+
+    /// Sets where grains are placed, from -1 for left to 1 for right.
+    pub fn set_pan(&mut self, pan: f32) {
+        self.pan = pan.clamp(-1.0, 1.0);
+    }
+
+    /// Sets how far grains are randomly spread around the pan, from 0 to 1.
+    pub fn set_pan_jitter(&mut self, jitter: f32) {
+        self.pan_jitter = jitter.clamp(0.0, 1.0);
+    }
+
+    /// Sets the level of each grain, to make room for overlapping grains.
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain;
+    }
+
+    /// Returns how many grains are playing.
+    pub fn active_grains(&self) -> usize {
+        self.grains.iter().filter(|grain| grain.active).count()
+    }
+
+    /// Stops every grain and restarts the grain timing.
+    pub fn reset(&mut self) {
+        for grain in &mut self.grains {
+            grain.active = false;
+        }
+        self.countdown = 0.0;
+    }
+
+    /// Starts a grain in a free slot, dropping it if every slot is playing.
+    fn spawn(&mut self) {
+        let length = libm::roundf(self.size * self.sample_rate);
+        if self.source.is_empty() || length < 1.0 {
+            return;
+        }
+
+        let Some(grain) = self.grains.iter_mut().find(|grain| !grain.active) else {
+            return;
+        };
+
+        let last = (self.source.len() - 1) as f32;
+        let position = self.position + self.position_jitter * self.rng.next_bipolar();
+        let semitones = self.pitch_jitter * self.rng.next_bipolar();
+        let pan = (self.pan + self.pan_jitter * self.rng.next_bipolar()).clamp(-1.0, 1.0);
+
+        // Constant power panning.
+        let angle = (pan + 1.0) * PI * 0.25;
+
+        *grain = Grain {
+            active: true,
+            position: position.clamp(0.0, 1.0) * last,
+            rate: self.pitch * libm::exp2f(semitones / 12.0),
+            phase: 0.0,
+            step: 1.0 / length,
+            remaining: length as u32,
+            gains: [libm::cosf(angle) * self.gain, libm::sinf(angle) * self.gain],
+        };
+    }
+
+    /// Reads the source between samples.
+    #[inline]
+    fn read(&self, position: f32) -> f32 {
+        let index = position as usize;
+        let fraction = position - index as f32;
+
+        let a = self.source[index].to_sample::<f32>();
+        let b = match self.source.get(index + 1) {
+            Some(b) => b.to_sample::<f32>(),
+            None => a,
+        };
+        a + (b - a) * fraction
+    }
+
+    /// Looks up the window between its steps.
+    #[inline]
+    fn window_level(&self, phase: f32) -> f32 {
+        let position = phase * WINDOW_STEPS as f32;
+        let index = (position as usize).min(WINDOW_STEPS - 1);
+        let fraction = position - index as f32;
+
+        let (a, b) = (self.table[index], self.table[index + 1]);
+        a + (b - a) * fraction
+    }
+}
+
+impl<S, const GRAINS: usize> Signal for Granular<'_, S, GRAINS>
+where
+    S: Sample + ToSample<f32>,
+{
+    type Frame = Stereo<f32>;
+
+    fn next(&mut self) -> Self::Frame {
+        if self.density > 0.0 {
+            self.countdown -= 1.0;
+            if self.countdown <= 0.0 {
+                self.spawn();
+
+                let spacing = self.sample_rate / self.density;
+                let jitter = 1.0 + self.timing_jitter * self.rng.next_bipolar();
+                self.countdown += spacing * jitter;
+            }
+        }
+
+        let last = self.source.len() as f32 - 1.0;
+        let mut output = [0.0; 2];
+        for i in 0..GRAINS {
+            let grain = self.grains[i];
+            if !grain.active {
+                continue;
+            }
+
+            let sample = self.read(grain.position) * self.window_level(grain.phase);
+            output[0] += sample * grain.gains[0];
+            output[1] += sample * grain.gains[1];
+
+            let grain = &mut self.grains[i];
+            grain.position += grain.rate;
+            grain.phase += grain.step;
+            grain.remaining -= 1;
+            if grain.remaining == 0 || grain.position > last {
+                grain.active = false;
+            }
+        }
+
+        output
+    }
+}
+
+impl<S, const GRAINS: usize> AudioSource for Granular<'_, S, GRAINS>
+where
+    S: Sample + ToSample<f32>,
+{
+    type Frame = Stereo<f32>;
+
+    fn render(&mut self, buffer: &mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = Signal::next(self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    /// A ramp rising by one step per sample.
+    fn ramp() -> [f32; 1000] {
+        core::array::from_fn(|i| i as f32 / 1000.0)
+    }
+
+    #[test]
+    fn test_single_grain() {
+        let source = ramp();
+        let mut granular = Granular::<_, 4>::new(1_000.0, &source, 1);
+        granular.set_window(GrainWindow::Rectangle);
+        granular.set_grain_size(0.1);
+        granular.set_density(1.0);
+        granular.set_position(0.5);
+
+        let gain = core::f32::consts::FRAC_1_SQRT_2;
+        for i in 0..100 {
+            let [left, right] = granular.next();
+            let expected = (499.5 + i as f32) / 1000.0 * gain;
+            assert_float_eq!(left, expected, abs <= 1e-5);
+            assert_float_eq!(right, expected, abs <= 1e-5);
+        }
+        assert_eq!(granular.active_grains(), 0);
+        assert_eq!(granular.next(), [0.0; 2]);
+    }
+
+    #[test]
+    fn test_pitch_and_pan() {
+        let source = ramp();
+        let mut granular = Granular::<_, 4>::new(1_000.0, &source, 1);
+        granular.set_window(GrainWindow::Rectangle);
+        granular.set_density(1.0);
+        granular.set_pitch_semitones(12.0);
+        granular.set_pan(-1.0);
+
+        let first = granular.next();
+        let second = granular.next();
+        assert_float_eq!(second[0] - first[0], 0.002, abs <= 1e-5);
+        assert_float_eq!(second[1], 0.0, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_grain_limit() {
+        let source = [0.5f32; 4_800];
+        let mut granular = Granular::<_, 8>::new(48_000.0, &source, 3);
+        granular.set_grain_size(0.05);
+        granular.set_density(1_000.0);
+        granular.set_timing_jitter(1.0);
+        granular.set_position_jitter(1.0);
+        granular.set_pitch_jitter(12.0);
+
+        let mut peak = 0;
+        for _ in 0..4_800 {
+            let frame = granular.next();
+            assert!(frame.iter().all(|x| x.is_finite()));
+            peak = peak.max(granular.active_grains());
+        }
+        assert_eq!(peak, 8);
+    }
+
+    #[test]
+    fn test_windows() {
+        for window in [
+            GrainWindow::Hann,
+            GrainWindow::Triangle,
+            GrainWindow::Tukey(0.5),
+        ] {
+            assert_float_eq!(window.level(0.0), 0.0, abs <= 1e-6);
+            assert_float_eq!(window.level(0.5), 1.0, abs <= 1e-6);
+        }
+        assert_eq!(GrainWindow::Tukey(0.5).level(0.3), 1.0);
+        assert_eq!(GrainWindow::Rectangle.level(0.0), 1.0);
+    }
+}
@@ -0,0 +1,519 @@
+//! A fixed-capacity audio processing graph.
+//!
+//! Chaining **Signal** adaptors builds a tree that's fixed at compile time. A
+//! [`Graph`] instead holds up to `NODES` [`AudioProcessor`] nodes connected by
+//! up to `EDGES` weighted edges, so instruments, effects, sends and mixers can
+//! be wired together at runtime, such as when loading a preset.
+//!
+//! After the graph is wired, [`Graph::compile`] sorts the nodes so every node
+//! runs after the nodes feeding it, and rejects cycles. Nodes are also grouped
+//! into levels, where no node depends on another node of the same level, so a
+//! host with more than one core can run each level in parallel.
+//!
+//! Every node has its own output buffer of `BLOCK` frames, allocated inline
+//! with the graph, so processing never allocates. Each block, the outputs of
+//! a node's incoming edges are scaled by their gain and summed into its input.
+//!
+//! # Example
+//!
+//! ```
+//! use catalina_engine::audio::graph::Graph;
+//! use catalina_engine::audio::processor::FnProcessor;
+//!
+//! let mut graph = Graph::<f32, FnProcessor<f32, fn(f32) -> f32>, 4, 4, 32>::new();
+//! let source = graph.add_node(FnProcessor::new((|_| 0.5) as fn(f32) -> f32)).unwrap();
+//! let mixer = graph.add_node(FnProcessor::new((|x| x) as fn(f32) -> f32)).unwrap();
+//!
+//! graph.connect(source, mixer, 1.0).unwrap();
+//! graph.connect(source, mixer, 0.5).unwrap();
+//! graph.set_output(mixer).unwrap();
+//! graph.compile().unwrap();
+//!
+//! let mut buffer = [0.0; 64];
+//! graph.process(&mut buffer);
+//! assert_eq!(buffer, [0.75; 64]);
+//! ```
+
+use heapless::Vec;
+
+use crate::audio::frame::Frame;
+use crate::audio::processor::AudioProcessor;
+use crate::audio::sample::{FromSample, Sample};
+
+/// Identifies a node within a [`Graph`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u16);
+
+impl NodeId {
+    /// Returns the index of the node in the order it was added.
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// A weighted connection from the output of one node to the input of another.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Edge {
+    pub from: NodeId,
+    pub to: NodeId,
+    /// The gain applied to the signal along the edge, such as a send level.
+    pub gain: f32,
+}
+
+/// An error returned when building or running a [`Graph`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The graph already holds its maximum amount of nodes.
+    TooManyNodes { capacity: usize },
+    /// The graph already holds its maximum amount of edges.
+    TooManyEdges { capacity: usize },
+    /// The node isn't part of this graph.
+    InvalidNode(NodeId),
+    /// The edges form a cycle, so there's no order to run the nodes in.
+    Cycle,
+    /// There's no edge between the nodes.
+    NotConnected { from: NodeId, to: NodeId },
+}
+
+/// A directed acyclic graph of audio processors.
+///
+/// - `F` is the frame type flowing through the graph.
+/// - `N` is the node type, usually an enum of every kind of node a device
+///   supports, or `&mut dyn AudioProcessor<Frame = F>`.
+/// - `NODES` and `EDGES` are the capacities of the graph.
+/// - `BLOCK` is the most frames processed at once, longer buffers are split.
+pub struct Graph<F, N, const NODES: usize, const EDGES: usize, const BLOCK: usize>
+where
+    F: Frame,
+    N: AudioProcessor<Frame = F>,
+{
+    nodes: Vec<N, NODES>,
+    edges: Vec<Edge, EDGES>,
+    output: Option<NodeId>,
+
+    /// The nodes in the order they run, sorted by level.
+    order: Vec<NodeId, NODES>,
+    /// The level of each node, nodes on the same level don't depend on each other.
+    levels: [u16; NODES],
+    /// Whether the order matches the current edges.
+    compiled: bool,
+
+    /// The output of each node for the current block.
+    buffers: [[F; BLOCK]; NODES],
+    /// The summed inputs of the node being run.
+    input: [F; BLOCK],
+}
+
+impl<F, N, const NODES: usize, const EDGES: usize, const BLOCK: usize>
+    Graph<F, N, NODES, EDGES, BLOCK>
+where
+    F: Frame,
+    N: AudioProcessor<Frame = F>,
+{
+    /// Builds an empty graph with all of its buffers allocated.
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            output: None,
+            order: Vec::new(),
+            levels: [0; NODES],
+            compiled: false,
+            buffers: [[F::EQUILIBRIUM; BLOCK]; NODES],
+            input: [F::EQUILIBRIUM; BLOCK],
+        }
+    }
+
+    /// Adds a node to the graph, returning its id.
+    pub fn add_node(&mut self, node: N) -> Result<NodeId, GraphError> {
+        let id = NodeId(self.nodes.len() as u16);
+        self.nodes
+            .push(node)
+            .map_err(|_| GraphError::TooManyNodes { capacity: NODES })?;
+        self.compiled = false;
+
+        Ok(id)
+    }
+
+    /// Borrows a node, such as to change its parameters.
+    pub fn node(&self, id: NodeId) -> Result<&N, GraphError> {
+        self.nodes
+            .get(id.index())
+            .ok_or(GraphError::InvalidNode(id))
+    }
+
+    /// Mutably borrows a node, such as to change its parameters.
+    pub fn node_mut(&mut self, id: NodeId) -> Result<&mut N, GraphError> {
+        self.nodes
+            .get_mut(id.index())
+            .ok_or(GraphError::InvalidNode(id))
+    }
+
+    /// Returns the amount of nodes in the graph.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the edges of the graph.
+    #[inline]
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Connects the output of `from` into the input of `to`, scaled by `gain`.
+    ///
+    /// Connecting the same nodes twice adds a second edge, which sums with the first.
+    pub fn connect(&mut self, from: NodeId, to: NodeId, gain: f32) -> Result<(), GraphError> {
+        self.check(from)?;
+        self.check(to)?;
+
+        self.edges
+            .push(Edge { from, to, gain })
+            .map_err(|_| GraphError::TooManyEdges { capacity: EDGES })?;
+        self.compiled = false;
+
+        Ok(())
+    }
+
+    /// Changes the gain of every edge from `from` to `to`, without recompiling.
+    pub fn set_gain(&mut self, from: NodeId, to: NodeId, gain: f32) -> Result<(), GraphError> {
+        let mut found = false;
+        for edge in self
+            .edges
+            .iter_mut()
+            .filter(|edge| edge.from == from && edge.to == to)
+        {
+            edge.gain = gain;
+            found = true;
+        }
+
+        found
+            .then_some(())
+            .ok_or(GraphError::NotConnected { from, to })
+    }
+
+    /// Removes every edge from `from` to `to`.
+    pub fn disconnect(&mut self, from: NodeId, to: NodeId) -> Result<(), GraphError> {
+        let before = self.edges.len();
+        self.edges.retain(|edge| edge.from != from || edge.to != to);
+        if self.edges.len() == before {
+            return Err(GraphError::NotConnected { from, to });
+        }
+        self.compiled = false;
+
+        Ok(())
+    }
+
+    /// Sets the node whose output [`Graph::process`] returns.
+    pub fn set_output(&mut self, id: NodeId) -> Result<(), GraphError> {
+        self.check(id)?;
+        self.output = Some(id);
+
+        Ok(())
+    }
+
+    /// Sorts the nodes into the order they run in.
+    ///
+    /// Must be called after changing the nodes or edges and before processing,
+    /// outside of the audio thread.
+    pub fn compile(&mut self) -> Result<(), GraphError> {
+        let count = self.nodes.len();
+        let mut incoming = [0u16; NODES];
+        for edge in self.edges.iter() {
+            incoming[edge.to.index()] += 1;
+        }
+
+        // Kahn's algorithm, one level at a time so each level holds only
+        // nodes whose inputs are all on earlier levels.
+        self.order.clear();
+        self.levels = [0; NODES];
+        for (index, incoming) in incoming.iter().enumerate().take(count) {
+            if *incoming == 0 {
+                // Can't overflow, there are never more than NODES nodes.
+                let _ = self.order.push(NodeId(index as u16));
+            }
+        }
+
+        let mut start = 0;
+        let mut level = 0;
+        while start < self.order.len() {
+            let end = self.order.len();
+            for position in start..end {
+                let node = self.order[position];
+                self.levels[node.index()] = level;
+
+                for edge in self.edges.iter().filter(|edge| edge.from == node) {
+                    incoming[edge.to.index()] -= 1;
+                    if incoming[edge.to.index()] == 0 {
+                        let _ = self.order.push(edge.to);
+                    }
+                }
+            }
+
+            start = end;
+            level += 1;
+        }
+
+        if self.order.len() != count {
+            self.order.clear();
+            self.compiled = false;
+            return Err(GraphError::Cycle);
+        }
+
+        self.compiled = true;
+        Ok(())
+    }
+
+    /// Returns true once the graph has been compiled since its last change.
+    #[inline]
+    pub const fn is_compiled(&self) -> bool {
+        self.compiled
+    }
+
+    /// Returns the nodes in the order they run.
+    #[inline]
+    pub fn order(&self) -> &[NodeId] {
+        &self.order
+    }
+
+    /// Returns the level of a node in the compiled graph.
+    ///
+    /// Nodes on the same level don't depend on each other, so can run in parallel.
+    pub fn level(&self, id: NodeId) -> Result<usize, GraphError> {
+        self.check(id)?;
+
+        Ok(self.levels[id.index()] as usize)
+    }
+
+    /// Returns the amount of levels in the compiled graph.
+    pub fn level_count(&self) -> usize {
+        self.order
+            .last()
+            .map_or(0, |last| self.levels[last.index()] as usize + 1)
+    }
+
+    /// Returns the latency added by a node itself, in frames.
+    pub fn node_latency(&self, id: NodeId) -> Result<usize, GraphError> {
+        Ok(self.node(id)?.latency())
+    }
+
+    /// Returns the latency of the output of a node, in frames.
+    ///
+    /// This is the node's own latency plus the longest latency of any path into it.
+    pub fn latency(&self, id: NodeId) -> Result<usize, GraphError> {
+        self.check(id)?;
+
+        let mut latencies = [0usize; NODES];
+        for node in self.order.iter() {
+            let upstream = self
+                .edges
+                .iter()
+                .filter(|edge| edge.to == *node)
+                .map(|edge| latencies[edge.from.index()])
+                .max()
+                .unwrap_or(0);
+            latencies[node.index()] = upstream + self.nodes[node.index()].latency();
+        }
+
+        Ok(latencies[id.index()])
+    }
+
+    /// Prepares every node to run at the sample rate, with blocks of `BLOCK` frames.
+    pub fn prepare(&mut self, sample_rate: f32) {
+        for node in self.nodes.iter_mut() {
+            node.prepare(sample_rate, BLOCK);
+        }
+    }
+
+    /// Resets every node and clears the buffers.
+    pub fn reset(&mut self) {
+        for node in self.nodes.iter_mut() {
+            node.reset();
+        }
+        self.buffers = [[F::EQUILIBRIUM; BLOCK]; NODES];
+    }
+
+    /// Runs the graph, writing the output node into `output`.
+    ///
+    /// Buffers longer than `BLOCK` frames are processed in several blocks.
+    /// Writes silence when the graph isn't compiled or has no output node.
+    pub fn process(&mut self, output: &mut [F]) {
+        let Some(out) = self.output.filter(|_| self.compiled) else {
+            output.fill(F::EQUILIBRIUM);
+            return;
+        };
+
+        for chunk in output.chunks_mut(BLOCK) {
+            self.run_block(chunk.len());
+            chunk.copy_from_slice(&self.buffers[out.index()][..chunk.len()]);
+        }
+    }
+
+    /// Returns the output of a node from the last processed block.
+    pub fn buffer(&self, id: NodeId) -> Result<&[F; BLOCK], GraphError> {
+        self.check(id)?;
+
+        Ok(&self.buffers[id.index()])
+    }
+
+    /// Runs every node once over `frames` frames.
+    fn run_block(&mut self, frames: usize) {
+        for node in self.order.iter() {
+            let input = &mut self.input[..frames];
+            input.fill(F::EQUILIBRIUM);
+
+            for edge in self.edges.iter().filter(|edge| edge.to == *node) {
+                let gain =
+                    <<F::Sample as Sample>::Float as FromSample<f32>>::from_sample_(edge.gain);
+                let source = &self.buffers[edge.from.index()][..frames];
+                for (sum, frame) in input.iter_mut().zip(source.iter()) {
+                    *sum = sum.add_amp(frame.scale_amp(gain).to_signed_frame());
+                }
+            }
+
+            let output = &mut self.buffers[node.index()][..frames];
+            self.nodes[node.index()].process(input, output);
+        }
+    }
+
+    fn check(&self, id: NodeId) -> Result<(), GraphError> {
+        if id.index() < self.nodes.len() {
+            Ok(())
+        } else {
+            Err(GraphError::InvalidNode(id))
+        }
+    }
+}
+
+impl<F, N, const NODES: usize, const EDGES: usize, const BLOCK: usize> Default
+    for Graph<F, N, NODES, EDGES, BLOCK>
+where
+    F: Frame,
+    N: AudioProcessor<Frame = F>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A node that outputs a constant plus its input, with a fixed latency.
+    struct Test {
+        value: f32,
+        latency: usize,
+    }
+
+    impl Test {
+        fn new(value: f32, latency: usize) -> Self {
+            Self { value, latency }
+        }
+    }
+
+    impl AudioProcessor for Test {
+        type Frame = f32;
+
+        fn process_in_place(&mut self, buffer: &mut [f32]) {
+            for sample in buffer.iter_mut() {
+                *sample += self.value;
+            }
+        }
+
+        fn latency(&self) -> usize {
+            self.latency
+        }
+    }
+
+    type TestGraph = Graph<f32, Test, 8, 8, 16>;
+
+    #[test]
+    fn test_diamond() {
+        // Two sources, a send through an effect, and a mixer.
+        let mut graph = TestGraph::new();
+        let synth = graph.add_node(Test::new(0.25, 0)).unwrap();
+        let drums = graph.add_node(Test::new(0.5, 0)).unwrap();
+        let reverb = graph.add_node(Test::new(0.0, 64)).unwrap();
+        let mixer = graph.add_node(Test::new(0.0, 0)).unwrap();
+
+        // Added out of order, the sort puts them right.
+        graph.connect(reverb, mixer, 1.0).unwrap();
+        graph.connect(synth, mixer, 1.0).unwrap();
+        graph.connect(drums, mixer, 1.0).unwrap();
+        graph.connect(synth, reverb, 0.5).unwrap();
+        graph.set_output(mixer).unwrap();
+        graph.compile().unwrap();
+
+        assert_eq!(graph.order(), &[synth, drums, reverb, mixer]);
+        assert_eq!(graph.level(synth), Ok(0));
+        assert_eq!(graph.level(drums), Ok(0));
+        assert_eq!(graph.level(reverb), Ok(1));
+        assert_eq!(graph.level(mixer), Ok(2));
+        assert_eq!(graph.level_count(), 3);
+
+        let mut buffer = [0.0; 40];
+        graph.process(&mut buffer);
+        assert_eq!(buffer, [0.25 + 0.5 + 0.125; 40]);
+
+        assert_eq!(graph.node_latency(reverb), Ok(64));
+        assert_eq!(graph.latency(mixer), Ok(64));
+        assert_eq!(graph.latency(drums), Ok(0));
+
+        graph.set_gain(synth, reverb, 0.0).unwrap();
+        graph.process(&mut buffer);
+        assert_eq!(buffer, [0.75; 40]);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut graph = TestGraph::new();
+        let a = graph.add_node(Test::new(0.0, 0)).unwrap();
+        let b = graph.add_node(Test::new(0.0, 0)).unwrap();
+        graph.connect(a, b, 1.0).unwrap();
+        graph.connect(b, a, 1.0).unwrap();
+        graph.set_output(b).unwrap();
+
+        assert_eq!(graph.compile(), Err(GraphError::Cycle));
+
+        let mut buffer = [1.0; 4];
+        graph.process(&mut buffer);
+        assert_eq!(buffer, [0.0; 4]);
+
+        graph.disconnect(b, a).unwrap();
+        assert_eq!(graph.compile(), Ok(()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut graph = Graph::<f32, Test, 1, 1, 4>::new();
+        let a = graph.add_node(Test::new(0.0, 0)).unwrap();
+
+        assert_eq!(
+            graph.add_node(Test::new(0.0, 0)).unwrap_err(),
+            GraphError::TooManyNodes { capacity: 1 }
+        );
+        assert_eq!(
+            graph.connect(a, NodeId(3), 1.0),
+            Err(GraphError::InvalidNode(NodeId(3)))
+        );
+
+        graph.connect(a, a, 1.0).unwrap();
+        assert_eq!(
+            graph.connect(a, a, 1.0),
+            Err(GraphError::TooManyEdges { capacity: 1 })
+        );
+        assert_eq!(graph.compile(), Err(GraphError::Cycle));
+        assert_eq!(graph.set_gain(a, NodeId(0), 1.0), Ok(()));
+    }
+}
@@ -0,0 +1,182 @@
+//! Interleaved sample formats used by codecs on serial audio buses like I2S.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::audio::frame::Frame;
+use crate::audio::sample::{FromSample, Sample, ToSample};
+
+/// The layout of the samples in a DMA buffer of 16-bit words, matching the
+/// data and channel lengths a codec is configured for.
+///
+/// Samples longer than 16 bits take two words, most significant word first,
+/// and the channels of each frame are interleaved.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum CodecFormat {
+    /// 16-bit samples in 16-bit channels.
+    Data16Channel16,
+    /// 16-bit samples padded into 32-bit channels by the peripheral.
+    #[default]
+    Data16Channel32,
+    /// 24-bit samples, left aligned in 32-bit channels.
+    Data24Channel32,
+    /// 32-bit samples in 32-bit channels.
+    Data32Channel32,
+}
+
+impl CodecFormat {
+    /// Returns the resolution of the samples, in bits.
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        match self {
+            Self::Data16Channel16 | Self::Data16Channel32 => 16,
+            Self::Data24Channel32 => 24,
+            Self::Data32Channel32 => 32,
+        }
+    }
+
+    /// Returns how many words of the DMA buffer each sample takes.
+    #[inline]
+    pub const fn words_per_sample(self) -> usize {
+        match self {
+            Self::Data16Channel16 | Self::Data16Channel32 => 1,
+            Self::Data24Channel32 | Self::Data32Channel32 => 2,
+        }
+    }
+
+    /// Returns how many words of the DMA buffer a frame of `channels` samples takes.
+    #[inline]
+    pub const fn words_per_frame(self, channels: usize) -> usize {
+        self.words_per_sample() * channels
+    }
+
+    /// Writes a sample into its words of the DMA buffer.
+    #[inline]
+    fn encode_sample(self, sample: i32, words: &mut [u16]) {
+        match self {
+            Self::Data16Channel16 | Self::Data16Channel32 => words[0] = (sample >> 16) as u16,
+            Self::Data24Channel32 => {
+                words[0] = (sample >> 16) as u16;
+                words[1] = sample as u16 & 0xFF00;
+            }
+            Self::Data32Channel32 => {
+                words[0] = (sample >> 16) as u16;
+                words[1] = sample as u16;
+            }
+        }
+    }
+
+    /// Reads a sample from its words of the DMA buffer.
+    #[inline]
+    fn decode_sample(self, words: &[u16]) -> i32 {
+        match self {
+            Self::Data16Channel16 | Self::Data16Channel32 => (words[0] as i16 as i32) << 16,
+            Self::Data24Channel32 => ((words[0] as u32) << 16 | (words[1] & 0xFF00) as u32) as i32,
+            Self::Data32Channel32 => ((words[0] as u32) << 16 | words[1] as u32) as i32,
+        }
+    }
+
+    /// Interleaves frames into words of the DMA buffer, returning how many
+    /// frames were written.
+    ///
+    /// Writes as many frames as fit in `words`.
+    pub fn encode<F>(self, frames: &[F], words: &mut [u16]) -> usize
+    where
+        F: Frame,
+        F::Sample: ToSample<i32>,
+    {
+        let sample_words = self.words_per_sample();
+        let frame_words = self.words_per_frame(F::CHANNELS);
+
+        let mut count = 0;
+        for (frame, words) in frames.iter().zip(words.chunks_exact_mut(frame_words)) {
+            for (sample, words) in frame.channels().zip(words.chunks_exact_mut(sample_words)) {
+                self.encode_sample(sample.to_sample::<i32>(), words);
+            }
+            count += 1;
+        }
+
+        count
+    }
+
+    /// De-interleaves words of the DMA buffer into frames, returning how many
+    /// frames were read.
+    ///
+    /// Reads as many frames as `words` holds.
+    pub fn decode<F>(self, words: &[u16], frames: &mut [F]) -> usize
+    where
+        F: Frame,
+        F::Sample: FromSample<i32>,
+    {
+        let sample_words = self.words_per_sample();
+        let frame_words = self.words_per_frame(F::CHANNELS);
+
+        let mut count = 0;
+        for (frame, words) in frames.iter_mut().zip(words.chunks_exact(frame_words)) {
+            let mut samples = words.chunks_exact(sample_words);
+            *frame = F::from_fn(|_| {
+                let words = samples.next().unwrap();
+                F::Sample::from_sample(self.decode_sample(words))
+            });
+            count += 1;
+        }
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [CodecFormat; 4] = [
+        CodecFormat::Data16Channel16,
+        CodecFormat::Data16Channel32,
+        CodecFormat::Data24Channel32,
+        CodecFormat::Data32Channel32,
+    ];
+
+    #[test]
+    fn test_layout() {
+        let frames = [[0x1234_5678i32, -2]];
+        let mut words = [0u16; 4];
+
+        CodecFormat::Data16Channel32.encode(&frames, &mut words);
+        assert_eq!(words[..2], [0x1234, 0xFFFF]);
+
+        CodecFormat::Data24Channel32.encode(&frames, &mut words);
+        assert_eq!(words, [0x1234, 0x5600, 0xFFFF, 0xFF00]);
+
+        CodecFormat::Data32Channel32.encode(&frames, &mut words);
+        assert_eq!(words, [0x1234, 0x5678, 0xFFFF, 0xFFFE]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let frames: [[i16; 2]; 8] =
+            core::array::from_fn(|i| [i as i16 * 4_000, -(i as i16) * 3_000]);
+
+        for format in FORMATS {
+            let mut words = [0u16; 32];
+            assert_eq!(format.encode(&frames, &mut words), 8);
+
+            let mut decoded = [[0i16; 2]; 8];
+            let used = format.words_per_frame(2) * 8;
+            assert_eq!(format.decode(&words[..used], &mut decoded), 8);
+            assert_eq!(decoded, frames, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_float_frames() {
+        let frames = [[0.5f32, -0.25]];
+        let mut words = [0u16; 4];
+        CodecFormat::Data24Channel32.encode(&frames, &mut words);
+
+        let mut decoded = [[0.0f32; 2]];
+        CodecFormat::Data24Channel32.decode(&words, &mut decoded);
+        assert_eq!(decoded, frames);
+    }
+}
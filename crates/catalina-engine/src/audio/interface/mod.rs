@@ -0,0 +1,391 @@
+//! A hardware-agnostic driver layer between a codec's DMA and the engine.
+//!
+//! Codecs are usually fed by a DMA channel running in circular mode over a
+//! buffer split into two halves. While the DMA transfers one half to or from
+//! the codec, the other half is refilled, and the DMA raises an interrupt
+//! each time it crosses into the other half.
+//!
+//! An [`AudioInterface`] owns those ping-pong buffers. The board support code
+//! hands [`AudioInterface::tx_buffer_mut`] and [`AudioInterface::rx_buffer_mut`]
+//! to its DMA channels, forwards the half and complete transfer interrupts to
+//! [`AudioInterface::on_half_transfer`] and [`AudioInterface::on_transfer_complete`],
+//! and then calls [`AudioInterface::render`] with an [`AudioSource`] or
+//! [`AudioInterface::process`] with an [`AudioProcessor`] to refill the freed
+//! halves.
+//!
+//! # Sharing with interrupts
+//!
+//! Every method takes `&mut self` and the half flags are plain `bool`s, so only
+//! one context may use the interface at a time. Either:
+//!
+//! - refill the freed half straight from the DMA interrupt, calling `render`
+//!   or `process` right after `on_half_transfer` or `on_transfer_complete`,
+//! - or keep the interface behind a critical section, such as a
+//!   `critical_section::Mutex<RefCell<_>>`, and hold it for each call from the
+//!   interrupts and from the main loop.
+//!
+//! The DMA itself never touches the half being refilled, so neither needs
+//! to stop it while refilling.
+//!
+//! The interface converts between the interleaved [`CodecFormat`] in the
+//! buffers and engine frames, and counts underruns, where the DMA came back
+//! around to a half that wasn't refilled in time.
+//!
+//! [`SimulatedDma`] clocks the buffers like a DMA channel would, so the whole
+//! path can be tested on a host.
+//!
+//! # Example
+//!
+//! ```
+//! use catalina_engine::audio::AudioSource;
+//! use catalina_engine::audio::interface::{AudioInterface, CodecFormat, SimulatedDma};
+//!
+//! struct Silence;
+//!
+//! impl AudioSource for Silence {
+//!     type Frame = [f32; 2];
+//!
+//!     fn render(&mut self, buffer: &mut [[f32; 2]]) {
+//!         buffer.fill([0.0; 2]);
+//!     }
+//! }
+//!
+//! let mut interface = AudioInterface::<[f32; 2], 64, 16>::new(CodecFormat::Data16Channel32);
+//! let mut dma = SimulatedDma::new();
+//!
+//! // Prime both halves before starting the DMA.
+//! interface.render(&mut Silence);
+//!
+//! for _ in 0..8 {
+//!     dma.transfer(&mut interface, 64, |word| word);
+//!     interface.render(&mut Silence);
+//! }
+//! assert_eq!(interface.underruns(), 0);
+//! ```
+
+mod format;
+pub use format::CodecFormat;
+
+mod sim;
+pub use sim::SimulatedDma;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioSource;
+use crate::audio::frame::Frame;
+use crate::audio::processor::AudioProcessor;
+use crate::audio::sample::{FromSample, ToSample};
+
+/// One of the two halves of a ping-pong buffer.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DmaHalf {
+    First,
+    Second,
+}
+
+impl DmaHalf {
+    #[inline]
+    const fn index(self) -> usize {
+        match self {
+            Self::First => 0,
+            Self::Second => 1,
+        }
+    }
+}
+
+/// Double-buffered transmit and receive buffers for a codec, with the
+/// conversion to and from engine frames.
+///
+/// - `F` is the frame type the engine renders or processes.
+/// - `WORDS` is the length of each half of the DMA buffers, in 16-bit words.
+/// - `BLOCK` is the most frames handed to a source or processor at once,
+///   longer halves are split.
+pub struct AudioInterface<F, const WORDS: usize, const BLOCK: usize>
+where
+    F: Frame,
+{
+    format: CodecFormat,
+
+    /// The buffer the DMA sends to the codec.
+    tx: [[u16; WORDS]; 2],
+    /// The buffer the DMA receives from the codec.
+    rx: [[u16; WORDS]; 2],
+
+    /// The halves the DMA is done with, waiting to be refilled.
+    ///
+    /// These aren't atomic, see the module docs on sharing the interface
+    /// with the DMA interrupts.
+    pending: [bool; 2],
+    /// How many times the DMA was done with a half that was still waiting to be refilled.
+    underruns: u32,
+
+    /// The frames being rendered or processed.
+    scratch: [F; BLOCK],
+}
+
+impl<F, const WORDS: usize, const BLOCK: usize> AudioInterface<F, WORDS, BLOCK>
+where
+    F: Frame,
+    F::Sample: ToSample<i32> + FromSample<i32>,
+{
+    /// Builds an interface with silent buffers, usable in a `static`.
+    ///
+    /// Both halves start out waiting to be filled, so the first render or
+    /// process primes the buffers before the DMA is started.
+    ///
+    /// **Panics** if a half doesn't hold a whole number of frames.
+    pub const fn new(format: CodecFormat) -> Self {
+        let frame_words = format.words_per_frame(F::CHANNELS);
+        core::assert!(BLOCK > 0 && WORDS >= frame_words && WORDS.is_multiple_of(frame_words));
+
+        Self {
+            format,
+            tx: [[0; WORDS]; 2],
+            rx: [[0; WORDS]; 2],
+            pending: [true; 2],
+            underruns: 0,
+            scratch: [F::EQUILIBRIUM; BLOCK],
+        }
+    }
+
+    #[inline]
+    pub const fn format(&self) -> CodecFormat {
+        self.format
+    }
+
+    /// Returns how many frames each half of the buffers holds.
+    #[inline]
+    pub const fn frames_per_half(&self) -> usize {
+        WORDS / self.format.words_per_frame(F::CHANNELS)
+    }
+
+    /// Borrows the whole transmit buffer, to hand to the DMA channel feeding the codec.
+    #[inline]
+    pub fn tx_buffer_mut(&mut self) -> &mut [u16] {
+        self.tx.as_flattened_mut()
+    }
+
+    #[inline]
+    pub fn tx_buffer(&self) -> &[u16] {
+        self.tx.as_flattened()
+    }
+
+    /// Borrows the whole receive buffer, to hand to the DMA channel reading the codec.
+    #[inline]
+    pub fn rx_buffer_mut(&mut self) -> &mut [u16] {
+        self.rx.as_flattened_mut()
+    }
+
+    #[inline]
+    pub fn rx_buffer(&self) -> &[u16] {
+        self.rx.as_flattened()
+    }
+
+    /// Call from the DMA half transfer interrupt, once the first half is done.
+    #[inline]
+    pub fn on_half_transfer(&mut self) {
+        self.release(DmaHalf::First);
+    }
+
+    /// Call from the DMA transfer complete interrupt, once the second half is done.
+    #[inline]
+    pub fn on_transfer_complete(&mut self) {
+        self.release(DmaHalf::Second);
+    }
+
+    /// Marks a half as done by the DMA, so it can be refilled.
+    pub fn release(&mut self, half: DmaHalf) {
+        let pending = &mut self.pending[half.index()];
+        if *pending {
+            self.underruns = self.underruns.saturating_add(1);
+        }
+        *pending = true;
+    }
+
+    /// Returns whether the half is waiting to be refilled.
+    #[inline]
+    pub fn is_pending(&self, half: DmaHalf) -> bool {
+        self.pending[half.index()]
+    }
+
+    /// Returns how many times the DMA came back around to a half before it was refilled.
+    #[inline]
+    pub fn underruns(&self) -> u32 {
+        self.underruns
+    }
+
+    #[inline]
+    pub fn clear_underruns(&mut self) {
+        self.underruns = 0;
+    }
+
+    /// Silences the buffers and marks both halves as waiting to be filled,
+    /// such as before restarting the DMA.
+    pub fn reset(&mut self) {
+        self.tx = [[0; WORDS]; 2];
+        self.rx = [[0; WORDS]; 2];
+        self.pending = [true; 2];
+        self.underruns = 0;
+    }
+
+    /// Renders audio from the source into every half waiting to be refilled,
+    /// returning how many halves were filled.
+    pub fn render<S>(&mut self, source: &mut S) -> usize
+    where
+        S: AudioSource<Frame = F>,
+    {
+        self.refill(|format, _, tx, scratch| {
+            source.render(scratch);
+            format.encode(scratch, tx);
+        })
+    }
+
+    /// Runs the audio received from the codec through the processor and into
+    /// every half waiting to be refilled, returning how many halves were filled.
+    ///
+    /// The processor should be prepared with a `max_block` of `BLOCK` frames.
+    pub fn process<P>(&mut self, processor: &mut P) -> usize
+    where
+        P: AudioProcessor<Frame = F>,
+    {
+        self.refill(|format, rx, tx, scratch| {
+            format.decode(rx, scratch);
+            processor.process_in_place(scratch);
+            format.encode(scratch, tx);
+        })
+    }
+
+    /// Calls `block` with the receive and transmit words of each block of the
+    /// pending halves, and the matching slice of frames.
+    fn refill<B>(&mut self, mut block: B) -> usize
+    where
+        B: FnMut(CodecFormat, &[u16], &mut [u16], &mut [F]),
+    {
+        let format = self.format;
+        let frame_words = format.words_per_frame(F::CHANNELS);
+
+        let mut filled = 0;
+        for half in [DmaHalf::First, DmaHalf::Second] {
+            if !self.pending[half.index()] {
+                continue;
+            }
+
+            let rx = self.rx[half.index()].chunks(BLOCK * frame_words);
+            let tx = self.tx[half.index()].chunks_mut(BLOCK * frame_words);
+            for (rx, tx) in rx.zip(tx) {
+                let frames = tx.len() / frame_words;
+                block(format, rx, tx, &mut self.scratch[..frames]);
+            }
+
+            self.pending[half.index()] = false;
+            filled += 1;
+        }
+
+        filled
+    }
+}
+
+impl<F, const WORDS: usize, const BLOCK: usize> Default for AudioInterface<F, WORDS, BLOCK>
+where
+    F: Frame,
+    F::Sample: ToSample<i32> + FromSample<i32>,
+{
+    fn default() -> Self {
+        Self::new(CodecFormat::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::processor::FnProcessor;
+
+    /// Renders an increasing count, one step per frame.
+    struct Counter(i16);
+
+    impl AudioSource for Counter {
+        type Frame = [i16; 2];
+
+        fn render(&mut self, buffer: &mut [[i16; 2]]) {
+            for frame in buffer {
+                *frame = [self.0, -self.0];
+                self.0 += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_is_continuous() {
+        let mut interface = AudioInterface::<[i16; 2], 24, 5>::new(CodecFormat::Data16Channel32);
+        assert_eq!(interface.frames_per_half(), 12);
+
+        let mut dma = SimulatedDma::new();
+        let mut counter = Counter(0);
+        assert_eq!(interface.render(&mut counter), 2);
+
+        let mut sent = [0u16; 24 * 6];
+        let mut position = 0;
+        for _ in 0..6 {
+            dma.transfer(&mut interface, 24, |word| {
+                sent[position] = word;
+                position += 1;
+                0
+            });
+            assert_eq!(interface.render(&mut counter), 1);
+        }
+        assert_eq!(interface.underruns(), 0);
+
+        let mut frames = [[0i16; 2]; 72];
+        CodecFormat::Data16Channel32.decode(&sent, &mut frames);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(*frame, [i as i16, -(i as i16)]);
+        }
+    }
+
+    #[test]
+    fn test_underruns() {
+        let mut interface = AudioInterface::<[i16; 2], 16, 8>::new(CodecFormat::Data24Channel32);
+        let mut dma = SimulatedDma::new();
+        let mut counter = Counter(0);
+        interface.render(&mut counter);
+
+        // The DMA comes back around to the first half without it being refilled.
+        dma.transfer(&mut interface, 48, |word| word);
+        assert_eq!(interface.underruns(), 1);
+        assert!(interface.is_pending(DmaHalf::First));
+        assert!(interface.is_pending(DmaHalf::Second));
+
+        assert_eq!(interface.render(&mut counter), 2);
+        dma.transfer(&mut interface, 16, |word| word);
+        assert_eq!(interface.underruns(), 1);
+
+        interface.reset();
+        assert_eq!(interface.underruns(), 0);
+    }
+
+    #[test]
+    fn test_process_loopback() {
+        let mut interface = AudioInterface::<[f32; 2], 32, 4>::new(CodecFormat::Data32Channel32);
+        let mut dma = SimulatedDma::new();
+        let mut gain = FnProcessor::new(|frame: [f32; 2]| [frame[0] * 0.5, frame[1] * 0.5]);
+        interface.process(&mut gain);
+
+        // The codec receives a constant level on both channels.
+        let mut input = [0u16; 4];
+        CodecFormat::Data32Channel32.encode(&[[0.5f32, -0.5]], &mut input);
+
+        let mut position = 0;
+        dma.transfer(&mut interface, 32, |_| {
+            position += 1;
+            input[(position - 1) % 4]
+        });
+        assert_eq!(interface.process(&mut gain), 1);
+
+        let mut frames = [[0.0f32; 2]; 8];
+        CodecFormat::Data32Channel32.decode(&interface.tx_buffer()[..32], &mut frames);
+        assert_eq!(frames, [[0.25, -0.25]; 8]);
+    }
+}
@@ -0,0 +1,60 @@
+//! A simulated DMA channel for testing an [`AudioInterface`] without hardware.
+
+use super::AudioInterface;
+use crate::audio::frame::Frame;
+use crate::audio::sample::{FromSample, ToSample};
+
+/// Clocks the buffers of an [`AudioInterface`] the way a circular DMA
+/// channel in full duplex would, raising the half and complete transfer
+/// callbacks as it crosses each half.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct SimulatedDma {
+    /// The word of the buffers transferred next.
+    position: usize,
+}
+
+impl SimulatedDma {
+    pub const fn new() -> Self {
+        Self { position: 0 }
+    }
+
+    /// Returns the word of the buffers transferred next.
+    #[inline]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves back to the start of the buffers, like restarting the channel.
+    pub fn reset(&mut self) {
+        self.position = 0;
+    }
+
+    /// Transfers `count` words, as if clocked by the codec.
+    ///
+    /// Each word sent from the transmit buffer is passed to `codec`, and the
+    /// word it returns is received into the same position of the receive buffer.
+    pub fn transfer<F, C, const WORDS: usize, const BLOCK: usize>(
+        &mut self,
+        interface: &mut AudioInterface<F, WORDS, BLOCK>,
+        count: usize,
+        mut codec: C,
+    ) where
+        F: Frame,
+        F::Sample: ToSample<i32> + FromSample<i32>,
+        C: FnMut(u16) -> u16,
+    {
+        for _ in 0..count {
+            let word = interface.tx_buffer()[self.position];
+            interface.rx_buffer_mut()[self.position] = codec(word);
+
+            self.position += 1;
+            if self.position == WORDS {
+                interface.on_half_transfer();
+            } else if self.position == 2 * WORDS {
+                interface.on_transfer_complete();
+                self.position = 0;
+            }
+        }
+    }
+}
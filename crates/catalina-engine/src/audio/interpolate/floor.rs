@@ -46,6 +46,10 @@ where
         self.left = source_frame;
     }
 
+    fn buffered_frames(&self) -> usize {
+        1
+    }
+
     fn reset(&mut self) {
         self.left = Self::Frame::EQUILIBRIUM;
     }
@@ -60,6 +60,10 @@ where
         self.right = source_frame;
     }
 
+    fn buffered_frames(&self) -> usize {
+        2
+    }
+
     fn reset(&mut self) {
         self.left = Self::Frame::EQUILIBRIUM;
         self.right = Self::Frame::EQUILIBRIUM;
@@ -32,6 +32,12 @@ pub trait Interpolator {
     /// To be called whenever the Interpolator value steps passed 1.0.
     fn next_source_frame(&mut self, source_frame: Self::Frame);
 
+    /// The number of source frames the interpolator holds.
+    ///
+    /// A source frame is still heard until this many further frames have been
+    /// passed to [`Interpolator::next_source_frame`].
+    fn buffered_frames(&self) -> usize;
+
     /// Resets the state of the interpolator.
     ///
     /// Call this when there's a break in the continuity of the input data stream.
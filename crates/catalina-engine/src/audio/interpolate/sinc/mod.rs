@@ -122,6 +122,10 @@ where
         }
     }
 
+    fn buffered_frames(&self) -> usize {
+        self.frames.len()
+    }
+
     fn reset(&mut self) {
         self.idx = 0;
         self.frames.set_first(0);
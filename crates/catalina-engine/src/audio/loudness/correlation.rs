@@ -0,0 +1,129 @@
+//! Stereo phase correlation and balance.
+
+/// The default averaging time of the meter, in seconds.
+pub const DEFAULT_INTEGRATION_TIME: f32 = 0.3;
+
+/// Measures how related the two channels of a stereo signal are, and how
+/// the level is balanced between them.
+///
+/// A correlation of 1 is mono, 0 is two unrelated channels, and -1 means
+/// the channels cancel when summed to mono.
+#[derive(Debug, Clone)]
+pub struct CorrelationMeter {
+    sample_rate: f32,
+    coefficient: f32,
+    left_right: f32,
+    left: f32,
+    right: f32,
+}
+
+impl CorrelationMeter {
+    /// Builds a meter for audio at the given sample rate.
+    pub fn new(sample_rate: usize) -> Self {
+        let mut meter = Self {
+            sample_rate: sample_rate as f32,
+            coefficient: 0.0,
+            left_right: 0.0,
+            left: 0.0,
+            right: 0.0,
+        };
+        meter.set_integration_time(DEFAULT_INTEGRATION_TIME);
+
+        meter
+    }
+
+    /// Sets how long the meter averages over, in seconds.
+    pub fn set_integration_time(&mut self, seconds: f32) {
+        self.coefficient = 1.0 - libm::expf(-1.0 / (seconds * self.sample_rate).max(1.0));
+    }
+
+    /// Measures a block of stereo frames.
+    pub fn process(&mut self, frames: &[[f32; 2]]) {
+        let a = self.coefficient;
+
+        for [left, right] in frames {
+            self.left_right += a * (left * right - self.left_right);
+            self.left += a * (left * left - self.left);
+            self.right += a * (right * right - self.right);
+        }
+    }
+
+    /// Returns the phase correlation, from -1 to 1.
+    ///
+    /// Silence reads as 0.
+    pub fn correlation(&self) -> f32 {
+        let power = libm::sqrtf(self.left * self.right);
+        if power < 1e-12 {
+            return 0.0;
+        }
+
+        (self.left_right / power).clamp(-1.0, 1.0)
+    }
+
+    /// Returns the balance between the channels, from -1 for hard left to 1
+    /// for hard right.
+    ///
+    /// Silence reads as 0.
+    pub fn balance(&self) -> f32 {
+        let left = libm::sqrtf(self.left);
+        let right = libm::sqrtf(self.right);
+        if left + right < 1e-6 {
+            return 0.0;
+        }
+
+        (right - left) / (right + left)
+    }
+
+    /// Clears the averages.
+    pub fn reset(&mut self) {
+        self.left_right = 0.0;
+        self.left = 0.0;
+        self.right = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    use float_eq::assert_float_eq;
+
+    fn measure(left: impl Fn(f32) -> f32, right: impl Fn(f32) -> f32) -> CorrelationMeter {
+        let mut frames = [[0.0; 2]; 4_800];
+        for (i, frame) in frames.iter_mut().enumerate() {
+            let phase = 2.0 * PI * 440.0 * i as f32 / 48_000.0;
+            *frame = [left(phase), right(phase)];
+        }
+
+        let mut meter = CorrelationMeter::new(48_000);
+        for _ in 0..10 {
+            meter.process(&frames);
+        }
+        meter
+    }
+
+    #[test]
+    fn test_correlation() {
+        let mono = measure(libm::sinf, libm::sinf);
+        assert_float_eq!(mono.correlation(), 1.0, abs <= 0.01);
+        assert_float_eq!(mono.balance(), 0.0, abs <= 0.01);
+
+        let inverted = measure(libm::sinf, |p| -libm::sinf(p));
+        assert_float_eq!(inverted.correlation(), -1.0, abs <= 0.01);
+
+        let quadrature = measure(libm::sinf, libm::cosf);
+        assert_float_eq!(quadrature.correlation(), 0.0, abs <= 0.05);
+    }
+
+    #[test]
+    fn test_balance() {
+        let left = measure(libm::sinf, |p| 0.0 * p);
+        assert_float_eq!(left.balance(), -1.0, abs <= 0.01);
+
+        let right_heavy = measure(|p| 0.5 * libm::sinf(p), libm::sinf);
+        assert_float_eq!(right_heavy.balance(), 1.0 / 3.0, abs <= 0.01);
+
+        assert_eq!(CorrelationMeter::new(48_000).correlation(), 0.0);
+    }
+}
@@ -0,0 +1,94 @@
+//! The K-weighting pre-filter from ITU-R BS.1770.
+//!
+//! The standard only lists coefficients for 48kHz, so the two stages are
+//! designed from their analog prototypes for any sample rate, matching the
+//! approach taken by libebur128 and pyloudnorm.
+
+use crate::audio::filter::{Biquad, Coefficients};
+
+/// The K-weighting filter: a high shelf modelling the acoustic effect of the
+/// head, followed by a high pass (the "RLB" curve) that removes the lowest bass.
+#[derive(Debug, Clone)]
+pub struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    /// Builds the filter for audio at the given sample rate.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            shelf: Biquad::new(sample_rate, Self::shelf(sample_rate)),
+            high_pass: Biquad::new(sample_rate, Self::high_pass(sample_rate)),
+        }
+    }
+
+    /// Filters a single sample.
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.high_pass.process(self.shelf.process(input))
+    }
+
+    /// Clears the filter state.
+    pub fn reset(&mut self) {
+        self.shelf.reset();
+        self.high_pass.reset();
+    }
+
+    fn shelf(sample_rate: f32) -> Coefficients {
+        let f0 = 1_681.974_450_955_533_f64;
+        let gain = 3.999_843_853_973_347_f64;
+        let q = 0.707_175_236_955_419_6_f64;
+
+        let k = libm::tan(core::f64::consts::PI * f0 / sample_rate as f64);
+        let vh = libm::pow(10.0, gain / 20.0);
+        let vb = libm::pow(vh, 0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+
+        Coefficients {
+            b0: ((vh + vb * k / q + k * k) / a0) as f32,
+            b1: (2.0 * (k * k - vh) / a0) as f32,
+            b2: ((vh - vb * k / q + k * k) / a0) as f32,
+            a1: (2.0 * (k * k - 1.0) / a0) as f32,
+            a2: ((1.0 - k / q + k * k) / a0) as f32,
+        }
+    }
+
+    fn high_pass(sample_rate: f32) -> Coefficients {
+        let f0 = 38.135_470_876_024_44_f64;
+        let q = 0.500_327_037_323_877_3_f64;
+
+        let k = libm::tan(core::f64::consts::PI * f0 / sample_rate as f64);
+        let a0 = 1.0 + k / q + k * k;
+
+        Coefficients {
+            b0: 1.0,
+            b1: -2.0,
+            b2: 1.0,
+            a1: (2.0 * (k * k - 1.0) / a0) as f32,
+            a2: ((1.0 - k / q + k * k) / a0) as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_48k_coefficients() {
+        // The coefficients listed in BS.1770-4 for 48kHz.
+        let shelf = KWeighting::shelf(48_000.0);
+        assert_float_eq!(shelf.b0, 1.535_124_9, abs <= 1e-5);
+        assert_float_eq!(shelf.b1, -2.691_696_2, abs <= 1e-5);
+        assert_float_eq!(shelf.b2, 1.198_392_8, abs <= 1e-5);
+        assert_float_eq!(shelf.a1, -1.690_659_3, abs <= 1e-5);
+        assert_float_eq!(shelf.a2, 0.732_480_77, abs <= 1e-5);
+
+        let high_pass = KWeighting::high_pass(48_000.0);
+        assert_float_eq!(high_pass.a1, -1.990_047_5, abs <= 1e-5);
+        assert_float_eq!(high_pass.a2, 0.990_072_25, abs <= 1e-5);
+    }
+}
@@ -0,0 +1,242 @@
+//! Momentary, short-term and integrated loudness from ITU-R BS.1770.
+
+use super::{KWeighting, mean_square_to_lufs};
+
+/// The length of each gating sub-block, in seconds.
+const BLOCK_SECONDS: f32 = 0.1;
+
+/// The momentary window is 400ms of sub-blocks.
+const MOMENTARY_BLOCKS: usize = 4;
+
+/// The short-term window is 3s of sub-blocks.
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Blocks quieter than this are never part of the integrated loudness.
+const ABSOLUTE_GATE: f32 = -70.0;
+
+/// Blocks more than this far below the ungated loudness are dropped.
+const RELATIVE_GATE: f32 = -10.0;
+
+/// The resolution of the integrated loudness histogram, in LU.
+const HISTOGRAM_STEP: f32 = 0.1;
+
+/// Histogram bins from the absolute gate up to +5 LUFS.
+const HISTOGRAM_BINS: usize = 750;
+
+/// Measures the loudness of `CHANNELS` channels of audio.
+///
+/// Integrated loudness uses a histogram of the gating blocks rather than
+/// keeping every block, so the meter can run indefinitely in a fixed amount
+/// of memory with the result accurate to within 0.1 LU.
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter<const CHANNELS: usize> {
+    filters: [KWeighting; CHANNELS],
+    weights: [f32; CHANNELS],
+    block_frames: usize,
+    /// Frames and weighted sum of squares gathered for the current sub-block.
+    frames: usize,
+    sum: f64,
+    /// Mean square of the most recent sub-blocks, as a circular buffer.
+    blocks: [f64; SHORT_TERM_BLOCKS],
+    block_index: usize,
+    blocks_filled: usize,
+    histogram: [u32; HISTOGRAM_BINS],
+}
+
+impl<const CHANNELS: usize> LoudnessMeter<CHANNELS> {
+    /// Builds a meter for audio at the given sample rate.
+    ///
+    /// All channels are weighted equally, as for mono, stereo and the front
+    /// channels of a surround mix.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            filters: core::array::from_fn(|_| KWeighting::new(sample_rate as f32)),
+            weights: [1.0; CHANNELS],
+            block_frames: ((sample_rate as f32 * BLOCK_SECONDS) as usize).max(1),
+            frames: 0,
+            sum: 0.0,
+            blocks: [0.0; SHORT_TERM_BLOCKS],
+            block_index: 0,
+            blocks_filled: 0,
+            histogram: [0; HISTOGRAM_BINS],
+        }
+    }
+
+    /// Sets how much a channel contributes, BS.1770 uses 1.41 for surround channels.
+    pub fn set_channel_weight(&mut self, channel: usize, weight: f32) {
+        self.weights[channel] = weight;
+    }
+
+    /// Measures a block of frames.
+    pub fn process(&mut self, frames: &[[f32; CHANNELS]]) {
+        for frame in frames {
+            let mut sum = 0.0;
+            for ((sample, filter), weight) in frame
+                .iter()
+                .zip(self.filters.iter_mut())
+                .zip(self.weights.iter())
+            {
+                let weighted = filter.process(*sample);
+                sum += weight * weighted * weighted;
+            }
+            self.sum += sum as f64;
+
+            self.frames += 1;
+            if self.frames == self.block_frames {
+                self.finish_block();
+            }
+        }
+    }
+
+    /// Returns the loudness over the last 400ms, in LUFS.
+    pub fn momentary(&self) -> f32 {
+        self.window(MOMENTARY_BLOCKS)
+    }
+
+    /// Returns the loudness over the last 3s, in LUFS.
+    pub fn short_term(&self) -> f32 {
+        self.window(SHORT_TERM_BLOCKS)
+    }
+
+    /// Returns the gated loudness since the meter was built or reset, in LUFS.
+    ///
+    /// Returns negative infinity until there is a block above the absolute gate.
+    pub fn integrated(&self) -> f32 {
+        let Some(ungated) = self.gated_mean(0) else {
+            return f32::NEG_INFINITY;
+        };
+
+        let relative = mean_square_to_lufs(ungated) + RELATIVE_GATE;
+        let first = libm::ceilf((relative - ABSOLUTE_GATE) / HISTOGRAM_STEP).max(0.0) as usize;
+
+        self.gated_mean(first)
+            .map_or(f32::NEG_INFINITY, mean_square_to_lufs)
+    }
+
+    /// Clears the measurement, including the integrated loudness.
+    pub fn reset(&mut self) {
+        for filter in self.filters.iter_mut() {
+            filter.reset();
+        }
+
+        self.frames = 0;
+        self.sum = 0.0;
+        self.blocks = [0.0; SHORT_TERM_BLOCKS];
+        self.block_index = 0;
+        self.blocks_filled = 0;
+        self.reset_integrated();
+    }
+
+    /// Clears the integrated loudness, keeping the momentary and short-term windows.
+    pub fn reset_integrated(&mut self) {
+        self.histogram = [0; HISTOGRAM_BINS];
+    }
+
+    fn finish_block(&mut self) {
+        self.blocks[self.block_index] = self.sum / self.frames as f64;
+        self.block_index = (self.block_index + 1) % SHORT_TERM_BLOCKS;
+        self.blocks_filled = (self.blocks_filled + 1).min(SHORT_TERM_BLOCKS);
+        self.frames = 0;
+        self.sum = 0.0;
+
+        // Every sub-block completes a new 400ms gating block, overlapping the
+        // last by 75%.
+        if self.blocks_filled >= MOMENTARY_BLOCKS {
+            let loudness = self.momentary();
+            if loudness > ABSOLUTE_GATE {
+                let bin = ((loudness - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize;
+                self.histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+            }
+        }
+    }
+
+    /// Returns the loudness of the mean of the last `count` sub-blocks.
+    fn window(&self, count: usize) -> f32 {
+        if self.blocks_filled == 0 {
+            return f32::NEG_INFINITY;
+        }
+
+        let count = count.min(self.blocks_filled);
+        let mut sum = 0.0;
+        for i in 1..=count {
+            sum += self.blocks[(self.block_index + SHORT_TERM_BLOCKS - i) % SHORT_TERM_BLOCKS];
+        }
+
+        mean_square_to_lufs(sum / count as f64)
+    }
+
+    /// Returns the mean square of the gating blocks from histogram bin `first` up.
+    fn gated_mean(&self, first: usize) -> Option<f64> {
+        let mut count = 0u64;
+        let mut sum = 0.0;
+
+        for (bin, blocks) in self.histogram.iter().enumerate().skip(first) {
+            if *blocks > 0 {
+                let loudness = ABSOLUTE_GATE + (bin as f32 + 0.5) * HISTOGRAM_STEP;
+                count += *blocks as u64;
+                sum += *blocks as f64 * libm::pow(10.0, (loudness as f64 + 0.691) / 10.0);
+            }
+        }
+
+        (count > 0).then(|| sum / count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    use float_eq::assert_float_eq;
+
+    fn sine(frames: &mut [[f32; 2]], frequency: f32, amplitude: f32, sample_rate: f32) {
+        for (i, frame) in frames.iter_mut().enumerate() {
+            let x = amplitude * libm::sinf(2.0 * PI * frequency * i as f32 / sample_rate);
+            *frame = [x, x];
+        }
+    }
+
+    #[test]
+    fn test_reference_tone() {
+        // A 0dBFS 1kHz sine in one channel reads -3.01 LUFS, so a -20dBFS
+        // sine in both channels of a stereo signal reads -20 LUFS.
+        let mut meter = LoudnessMeter::<2>::new(48_000);
+        let mut frames = [[0.0; 2]; 48_000];
+        sine(&mut frames, 1000.0, 0.1, 48_000.0);
+
+        for _ in 0..4 {
+            meter.process(&frames);
+        }
+
+        assert_float_eq!(meter.momentary(), -20.0, abs <= 0.1);
+        assert_float_eq!(meter.short_term(), -20.0, abs <= 0.1);
+        assert_float_eq!(meter.integrated(), -20.0, abs <= 0.1);
+    }
+
+    #[test]
+    fn test_gating() {
+        let mut meter = LoudnessMeter::<2>::new(48_000);
+        let mut loud = [[0.0; 2]; 48_000];
+        sine(&mut loud, 1000.0, 0.1, 48_000.0);
+        let mut quiet = [[0.0; 2]; 48_000];
+        sine(&mut quiet, 1000.0, 0.001, 48_000.0);
+
+        // Quiet passages more than 10 LU down are gated out of the integrated
+        // loudness, and silence is below the absolute gate.
+        for _ in 0..2 {
+            for _ in 0..10 {
+                meter.process(&loud);
+            }
+            for _ in 0..5 {
+                meter.process(&quiet);
+                meter.process(&[[0.0; 2]; 48_000]);
+            }
+        }
+
+        assert_float_eq!(meter.integrated(), -20.0, abs <= 0.2);
+        assert!(meter.momentary() < ABSOLUTE_GATE);
+
+        meter.reset();
+        assert_eq!(meter.integrated(), f32::NEG_INFINITY);
+    }
+}
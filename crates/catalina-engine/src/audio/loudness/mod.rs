@@ -0,0 +1,203 @@
+//! Loudness, true peak and stereo correlation metering.
+//!
+//! Where [`crate::audio::rms`] and [`crate::audio::peak`] give raw signal
+//! levels, these meters follow the broadcast standards used to level-match
+//! material:
+//!
+//! - [`LoudnessMeter`] measures momentary, short-term and integrated loudness
+//!   in LUFS with the K-weighting and gating from ITU-R BS.1770.
+//! - [`TruePeakMeter`] oversamples to catch peaks between samples.
+//! - [`CorrelationMeter`] reads the phase correlation and balance of a
+//!   stereo signal.
+//!
+//! [`StereoMeter`] bundles all three for a master bus. The meters are updated
+//! block by block on the audio thread, then [`StereoMeter::publish`] stores
+//! the readings into a [`MeterReadings`], which a UI thread can read at any
+//! time without locking.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod correlation;
+pub use correlation::CorrelationMeter;
+
+pub mod kweighting;
+pub use kweighting::KWeighting;
+
+pub mod lufs;
+pub use lufs::LoudnessMeter;
+
+pub mod true_peak;
+pub use true_peak::TruePeakMeter;
+
+/// Converts a K-weighted mean square to LUFS.
+fn mean_square_to_lufs(mean_square: f64) -> f32 {
+    if mean_square <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+
+    (-0.691 + 10.0 * libm::log10(mean_square)) as f32
+}
+
+/// A copy of all the readings of a [`StereoMeter`] at one point in time.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct MeterSnapshot {
+    /// Loudness over the last 400ms, in LUFS.
+    pub momentary: f32,
+    /// Loudness over the last 3s, in LUFS.
+    pub short_term: f32,
+    /// Gated loudness since the last reset, in LUFS.
+    pub integrated: f32,
+    /// Highest true peak since the last reset, in dBTP.
+    pub true_peak: f32,
+    /// Stereo phase correlation, from -1 to 1.
+    pub correlation: f32,
+    /// Stereo balance, from -1 for hard left to 1 for hard right.
+    pub balance: f32,
+}
+
+/// Meter readings that can be shared between the audio thread and a UI thread.
+///
+/// Each reading is stored as the bits of an `f32` in an atomic, so reads and
+/// writes never block. Readings are stored one at a time, so a snapshot can
+/// mix values from two consecutive blocks, which is invisible on a meter.
+#[derive(Debug, Default)]
+pub struct MeterReadings {
+    momentary: AtomicU32,
+    short_term: AtomicU32,
+    integrated: AtomicU32,
+    true_peak: AtomicU32,
+    correlation: AtomicU32,
+    balance: AtomicU32,
+}
+
+impl MeterReadings {
+    /// Builds readings that are all 0, usable in a `static`.
+    pub const fn new() -> Self {
+        Self {
+            momentary: AtomicU32::new(0),
+            short_term: AtomicU32::new(0),
+            integrated: AtomicU32::new(0),
+            true_peak: AtomicU32::new(0),
+            correlation: AtomicU32::new(0),
+            balance: AtomicU32::new(0),
+        }
+    }
+
+    /// Stores a new set of readings.
+    pub fn store(&self, snapshot: MeterSnapshot) {
+        Self::set(&self.momentary, snapshot.momentary);
+        Self::set(&self.short_term, snapshot.short_term);
+        Self::set(&self.integrated, snapshot.integrated);
+        Self::set(&self.true_peak, snapshot.true_peak);
+        Self::set(&self.correlation, snapshot.correlation);
+        Self::set(&self.balance, snapshot.balance);
+    }
+
+    /// Loads the latest readings.
+    pub fn load(&self) -> MeterSnapshot {
+        MeterSnapshot {
+            momentary: Self::get(&self.momentary),
+            short_term: Self::get(&self.short_term),
+            integrated: Self::get(&self.integrated),
+            true_peak: Self::get(&self.true_peak),
+            correlation: Self::get(&self.correlation),
+            balance: Self::get(&self.balance),
+        }
+    }
+
+    #[inline]
+    fn set(atomic: &AtomicU32, value: f32) {
+        atomic.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    #[inline]
+    fn get(atomic: &AtomicU32) -> f32 {
+        f32::from_bits(atomic.load(Ordering::Relaxed))
+    }
+}
+
+/// Loudness, true peak and correlation metering for a stereo master bus.
+#[derive(Debug, Clone)]
+pub struct StereoMeter {
+    pub loudness: LoudnessMeter<2>,
+    pub true_peak: TruePeakMeter<2>,
+    pub correlation: CorrelationMeter,
+}
+
+impl StereoMeter {
+    /// Builds a meter for audio at the given sample rate.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            loudness: LoudnessMeter::new(sample_rate),
+            true_peak: TruePeakMeter::new(),
+            correlation: CorrelationMeter::new(sample_rate),
+        }
+    }
+
+    /// Measures a block of stereo frames.
+    pub fn process(&mut self, frames: &[[f32; 2]]) {
+        self.loudness.process(frames);
+        self.true_peak.process(frames);
+        self.correlation.process(frames);
+    }
+
+    /// Returns the current readings.
+    pub fn snapshot(&self) -> MeterSnapshot {
+        MeterSnapshot {
+            momentary: self.loudness.momentary(),
+            short_term: self.loudness.short_term(),
+            integrated: self.loudness.integrated(),
+            true_peak: self.true_peak.max_db(),
+            correlation: self.correlation.correlation(),
+            balance: self.correlation.balance(),
+        }
+    }
+
+    /// Stores the current readings for other threads to read.
+    pub fn publish(&self, readings: &MeterReadings) {
+        readings.store(self.snapshot());
+    }
+
+    /// Clears all the measurements.
+    pub fn reset(&mut self) {
+        self.loudness.reset();
+        self.true_peak.reset();
+        self.correlation.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    use float_eq::assert_float_eq;
+
+    static READINGS: MeterReadings = MeterReadings::new();
+
+    #[test]
+    fn test_publish() {
+        let mut meter = StereoMeter::new(48_000);
+        let mut frames = [[0.0; 2]; 4_800];
+        for (i, frame) in frames.iter_mut().enumerate() {
+            let x = 0.1 * libm::sinf(2.0 * PI * 1000.0 * i as f32 / 48_000.0);
+            *frame = [x, x];
+        }
+        for _ in 0..10 {
+            meter.process(&frames);
+        }
+
+        meter.publish(&READINGS);
+        let snapshot = READINGS.load();
+
+        assert_eq!(snapshot, meter.snapshot());
+        assert_float_eq!(snapshot.momentary, -20.0, abs <= 0.1);
+        assert_float_eq!(snapshot.true_peak, -20.0, abs <= 0.1);
+        assert_float_eq!(snapshot.correlation, 1.0, abs <= 0.01);
+    }
+}
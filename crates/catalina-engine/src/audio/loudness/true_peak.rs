@@ -0,0 +1,159 @@
+//! True peak measurement from ITU-R BS.1770 Annex 2.
+//!
+//! A sample peak meter can read several decibels low when the real waveform
+//! peaks between two samples, which is exactly what clips a DAC or a lossy
+//! encoder. Oversampling 4x and measuring the interpolated signal catches
+//! those inter-sample peaks.
+
+/// The oversampling factor.
+const OVERSAMPLING: usize = 4;
+
+/// Taps of the interpolation filter in each polyphase branch.
+const TAPS_PER_PHASE: usize = 12;
+
+/// Measures the true peak level of `CHANNELS` channels of audio.
+///
+/// The meter holds the highest peak seen on each channel until it's reset.
+#[derive(Debug, Clone)]
+pub struct TruePeakMeter<const CHANNELS: usize> {
+    /// The interpolation filter, split into one branch per output phase.
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLING],
+    /// The last samples of each channel, as a circular buffer.
+    history: [[f32; TAPS_PER_PHASE]; CHANNELS],
+    index: usize,
+    peaks: [f32; CHANNELS],
+}
+
+impl<const CHANNELS: usize> TruePeakMeter<CHANNELS> {
+    pub fn new() -> Self {
+        // A Hann windowed sinc with its cutoff at the original Nyquist
+        // frequency. It's centered on a tap, so the first phase passes the
+        // original samples straight through.
+        let length = OVERSAMPLING * TAPS_PER_PHASE;
+        let center = (length / 2) as f64;
+
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLING];
+        for (p, phase) in phases.iter_mut().enumerate() {
+            for (k, tap) in phase.iter_mut().enumerate() {
+                let n = (k * OVERSAMPLING + p) as f64;
+                let x = (n - center) / OVERSAMPLING as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    libm::sin(core::f64::consts::PI * x) / (core::f64::consts::PI * x)
+                };
+                let window = 0.5 - 0.5 * libm::cos(2.0 * core::f64::consts::PI * n / length as f64);
+                *tap = (sinc * window) as f32;
+            }
+
+            // Normalize each branch so a DC signal passes at unity gain.
+            let sum: f32 = phase.iter().sum();
+            for tap in phase.iter_mut() {
+                *tap /= sum;
+            }
+        }
+
+        Self {
+            phases,
+            history: [[0.0; TAPS_PER_PHASE]; CHANNELS],
+            index: 0,
+            peaks: [0.0; CHANNELS],
+        }
+    }
+
+    /// Measures a block of frames.
+    pub fn process(&mut self, frames: &[[f32; CHANNELS]]) {
+        for frame in frames {
+            for (channel, sample) in frame.iter().enumerate() {
+                self.history[channel][self.index] = *sample;
+
+                for phase in self.phases.iter() {
+                    let mut sum = 0.0;
+                    for (k, tap) in phase.iter().enumerate() {
+                        let at = (self.index + TAPS_PER_PHASE - k) % TAPS_PER_PHASE;
+                        sum += tap * self.history[channel][at];
+                    }
+
+                    self.peaks[channel] = self.peaks[channel].max(sum.abs());
+                }
+            }
+
+            self.index = (self.index + 1) % TAPS_PER_PHASE;
+        }
+    }
+
+    /// Returns the highest true peak of a channel as a linear amplitude.
+    pub fn peak(&self, channel: usize) -> f32 {
+        self.peaks[channel]
+    }
+
+    /// Returns the highest true peak of a channel in dBTP.
+    pub fn peak_db(&self, channel: usize) -> f32 {
+        20.0 * libm::log10f(self.peaks[channel])
+    }
+
+    /// Returns the highest true peak across all channels in dBTP.
+    pub fn max_db(&self) -> f32 {
+        let peak = self.peaks.iter().fold(0.0f32, |max, peak| max.max(*peak));
+        20.0 * libm::log10f(peak)
+    }
+
+    /// Clears the held peaks, keeping the filter history.
+    pub fn reset_peaks(&mut self) {
+        self.peaks = [0.0; CHANNELS];
+    }
+
+    /// Clears the held peaks and the filter history.
+    pub fn reset(&mut self) {
+        self.history = [[0.0; TAPS_PER_PHASE]; CHANNELS];
+        self.index = 0;
+        self.reset_peaks();
+    }
+}
+
+impl<const CHANNELS: usize> Default for TruePeakMeter<CHANNELS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_inter_sample_peak() {
+        // A sine at a quarter of the sample rate, offset by 45 degrees, has
+        // every sample at +/-0.707 while the waveform peaks at 1.0.
+        let mut frames = [[0.0f32; 1]; 512];
+        for (i, frame) in frames.iter_mut().enumerate() {
+            frame[0] = libm::sinf(PI / 2.0 * i as f32 + PI / 4.0);
+        }
+
+        let sample_peak = frames.iter().fold(0.0f32, |max, f| max.max(f[0].abs()));
+        assert_float_eq!(20.0 * libm::log10f(sample_peak), -3.01, abs <= 0.01);
+
+        let mut meter = TruePeakMeter::<1>::new();
+        meter.process(&frames);
+        assert_float_eq!(meter.peak_db(0), 0.0, abs <= 0.2);
+
+        meter.reset();
+        assert_eq!(meter.peak(0), 0.0);
+    }
+
+    #[test]
+    fn test_passes_samples() {
+        // The first phase reproduces the input, so sample peaks are never missed.
+        let mut meter = TruePeakMeter::<2>::new();
+        let mut frames = [[0.0f32; 2]; 32];
+        frames[10] = [0.5, -0.25];
+        meter.process(&frames);
+
+        assert!(meter.peak(0) >= 0.5);
+        assert!(meter.peak(1) >= 0.25);
+        assert_float_eq!(meter.max_db(), meter.peak_db(0), abs <= 1e-6);
+    }
+}
@@ -15,6 +15,9 @@ pub use sample::{FromSample, Sample};
 pub mod frame;
 pub use frame::{Frame, Mono, Stereo};
 
+// Dither and noise shaping for converting to lower resolution samples.
+pub mod dither;
+
 // Traits and functions for working with slices of samples and frames.
 // Ported from dasp.
 pub mod slice;
@@ -41,6 +44,43 @@ pub mod oscillator;
 
 pub mod envelope;
 
+// Delay lines for echoes, combs and physical models.
+pub mod delay;
+
+// Physical models of plucked, bowed and struck instruments.
+pub mod physical;
+
+// Granular synthesis over sample buffers.
+pub mod granular;
+
+// Biquad and other filters for tone shaping.
+pub mod filter;
+
+// Parametric and graphic equalizers.
+pub mod eq;
+
+// FFT and spectral analysis.
+pub mod spectrum;
+
+// Pitch detection for tuners and pitch tracking.
+pub mod pitch;
+
+// Onset and transient detection.
+pub mod onset;
+
+// Loudness, true peak and correlation metering.
+pub mod loudness;
+
+// Block based effects that process an input into an output.
+pub mod processor;
+pub use processor::AudioProcessor;
+
+// Runtime wiring of processors into a graph.
+pub mod graph;
+
+// Double-buffered DMA drivers for codecs.
+pub mod interface;
+
 pub trait AudioSource {
     type Frame: Frame;
 
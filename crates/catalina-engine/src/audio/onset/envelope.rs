@@ -0,0 +1,196 @@
+//! Onset detection from the level envelope of a signal.
+
+use super::{AdaptiveThreshold, DEFAULT_MIN_INTERVAL, Onset, OnsetDetector};
+use crate::audio::{envelope::detect::Detector, envelope::detect::Peak, peak::FullWave};
+
+/// How many detection function values the threshold averages over.
+const HISTORY: usize = 32;
+
+/// How many samples are folded into each value of the threshold history.
+const DECIMATION: usize = 64;
+
+/// Detects onsets by comparing a fast and a slow peak envelope.
+///
+/// The detection function is how many decibels the fast envelope is above
+/// the slow one. A sudden rise in level pulls the fast envelope up right
+/// away while the slow one lags behind, so the gap between them spikes at
+/// each transient.
+#[derive(Debug, Clone)]
+pub struct EnvelopeOnset {
+    sample_rate: f32,
+    fast: Detector<f32, Peak<FullWave>>,
+    slow: Detector<f32, Peak<FullWave>>,
+    threshold: AdaptiveThreshold<HISTORY>,
+    /// Levels below this are ignored, in linear amplitude.
+    floor: f32,
+    /// The shortest gap between onsets, in samples.
+    min_interval: u64,
+    /// The sample count since the detector was built or reset.
+    position: u64,
+    last_onset: Option<u64>,
+    /// Set once the detection function drops back below the threshold.
+    armed: bool,
+    decimated_max: f32,
+    decimated_count: usize,
+}
+
+impl EnvelopeOnset {
+    /// Builds a detector for audio at the given sample rate.
+    pub fn new(sample_rate: usize) -> Self {
+        let sample_rate = sample_rate as f32;
+
+        Self {
+            sample_rate,
+            // Instant attack so the fast envelope lands on the first sample
+            // of a transient, then a short release to ride over each cycle.
+            fast: Detector::peak(0.0, 0.005 * sample_rate),
+            slow: Detector::peak(0.01 * sample_rate, 0.1 * sample_rate),
+            threshold: AdaptiveThreshold::new(1.5, 6.0),
+            floor: libm::powf(10.0, -50.0 / 20.0),
+            min_interval: (DEFAULT_MIN_INTERVAL * sample_rate) as u64,
+            position: 0,
+            last_onset: None,
+            armed: true,
+            decimated_max: 0.0,
+            decimated_count: 0,
+        }
+    }
+
+    /// Sets how many decibels the fast envelope must jump over the slow one,
+    /// on top of the adaptive part of the threshold.
+    pub fn set_sensitivity(&mut self, db: f32) {
+        self.threshold.delta = db;
+    }
+
+    /// Sets how strongly the threshold follows the recent detection function.
+    pub fn set_adaptivity(&mut self, multiplier: f32) {
+        self.threshold.multiplier = multiplier;
+    }
+
+    /// Sets the level in decibels that the input has to exceed to trigger.
+    pub fn set_floor(&mut self, db: f32) {
+        self.floor = libm::powf(10.0, db / 20.0);
+    }
+
+    /// Sets the shortest time between two onsets, in seconds.
+    pub fn set_min_interval(&mut self, seconds: f32) {
+        self.min_interval = (seconds * self.sample_rate) as u64;
+    }
+
+    /// Processes a single sample, returning an onset if one starts on it.
+    pub fn next(&mut self, sample: f32) -> Option<Onset> {
+        let fast = self.fast.next(sample);
+        let slow = self.slow.next(sample);
+        let odf = 20.0 * libm::log10f((fast + 1e-6) / (slow + 1e-6));
+
+        let threshold = self.threshold.threshold();
+        let position = self.position;
+        self.position += 1;
+
+        self.decimated_max = self.decimated_max.max(odf);
+        self.decimated_count += 1;
+        if self.decimated_count == DECIMATION {
+            self.threshold.push(self.decimated_max.max(0.0));
+            self.decimated_max = 0.0;
+            self.decimated_count = 0;
+        }
+
+        if odf < 0.5 * threshold {
+            self.armed = true;
+        }
+
+        let held = self
+            .last_onset
+            .is_some_and(|last| position - last < self.min_interval);
+
+        if self.armed && !held && odf > threshold && fast > self.floor {
+            self.armed = false;
+            self.last_onset = Some(position);
+
+            return Some(Onset {
+                position,
+                strength: odf - threshold,
+            });
+        }
+
+        None
+    }
+}
+
+impl OnsetDetector for EnvelopeOnset {
+    fn process<C>(&mut self, samples: &[f32], mut on_onset: C)
+    where
+        C: FnMut(Onset),
+    {
+        for sample in samples {
+            if let Some(onset) = self.next(*sample) {
+                on_onset(onset);
+            }
+        }
+    }
+
+    fn latency(&self) -> usize {
+        0
+    }
+
+    fn reset(&mut self) {
+        self.fast = Detector::peak(0.0, 0.005 * self.sample_rate);
+        self.slow = Detector::peak(0.01 * self.sample_rate, 0.1 * self.sample_rate);
+        self.threshold.reset();
+        self.position = 0;
+        self.last_onset = None;
+        self.armed = true;
+        self.decimated_max = 0.0;
+        self.decimated_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::onset::tests::bursts;
+
+    #[test]
+    fn test_detects_bursts() {
+        let mut buffer = [0.0; 48_000];
+        let positions = [4_800, 17_000, 29_123, 40_000];
+        bursts(&mut buffer, &positions, 48_000.0);
+
+        let mut detector = EnvelopeOnset::new(48_000);
+        let mut found = [0u64; 8];
+        let mut count = 0;
+
+        // Process in blocks to check onsets are placed across block boundaries.
+        for block in buffer.chunks(64) {
+            detector.process(block, |onset| {
+                found[count] = onset.position;
+                count += 1;
+            });
+        }
+
+        assert_eq!(count, positions.len());
+        for (found, expected) in found.iter().zip(positions) {
+            assert!(
+                found.abs_diff(expected as u64) < 16,
+                "{found} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reset_restarts_count() {
+        let mut buffer = [0.0; 4_800];
+        bursts(&mut buffer, &[1_000], 48_000.0);
+
+        let mut detector = EnvelopeOnset::new(48_000);
+        let mut first = None;
+        detector.process(&buffer, |onset| first = Some(onset.position));
+
+        detector.reset();
+        let mut second = None;
+        detector.process(&buffer, |onset| second = Some(onset.position));
+
+        assert!(first.is_some());
+        assert_eq!(first, second);
+    }
+}
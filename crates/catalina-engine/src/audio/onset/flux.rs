@@ -0,0 +1,271 @@
+//! Onset detection from the spectral flux of a signal.
+
+use super::{AdaptiveThreshold, DEFAULT_MIN_INTERVAL, Onset, OnsetDetector};
+use crate::audio::{
+    envelope::detect::{Detector, Peak},
+    peak::FullWave,
+    spectrum::{RealFft, bin},
+    window::{Hann, Window},
+};
+
+/// How many flux values the threshold averages over.
+const HISTORY: usize = 16;
+
+/// Compression applied to magnitudes before differencing, so quiet partials
+/// still contribute to the flux.
+const COMPRESSION: f32 = 100.0;
+
+/// Detects onsets from the spectral flux between overlapping FFT frames.
+///
+/// Every `hop` samples the last `N` samples are windowed and transformed,
+/// and the flux is the sum of how much each bin's log magnitude grew since
+/// the previous frame. Peaks in the flux above the [`AdaptiveThreshold`] are
+/// onsets.
+///
+/// The flux only says which hop an onset landed in, so a fast peak envelope
+/// runs alongside and the onset is placed on the sharpest rise in level
+/// within the analysis window.
+#[derive(Debug, Clone)]
+pub struct SpectralFlux<const N: usize> {
+    sample_rate: f32,
+    fft: RealFft<N>,
+    window: [f32; N],
+    /// The last `N` samples, as a circular buffer starting at `write`.
+    input: [f32; N],
+    write: usize,
+    /// Compressed magnitudes of the previous frame.
+    previous: [f32; N],
+    hop: usize,
+    since_hop: usize,
+    threshold: AdaptiveThreshold<HISTORY>,
+    /// The last two flux values, for peak picking.
+    flux: [f32; 2],
+    envelope: Detector<f32, Peak<FullWave>>,
+    last_envelope: f32,
+    /// The position and size of the sharpest rise in level within the window.
+    rise: Option<(u64, f32)>,
+    /// The shortest gap between onsets, in samples.
+    min_interval: u64,
+    position: u64,
+    last_onset: Option<u64>,
+}
+
+impl<const N: usize> SpectralFlux<N> {
+    /// Builds a detector for audio at the given sample rate, with a hop of
+    /// a quarter of the frame size.
+    pub fn new(sample_rate: usize) -> Self {
+        let sample_rate = sample_rate as f32;
+
+        let mut window = [0.0; N];
+        for (i, w) in window.iter_mut().enumerate() {
+            *w = <Hann as Window<f32>>::window(i as f32 / N as f32);
+        }
+
+        Self {
+            sample_rate,
+            fft: RealFft::new(),
+            window,
+            input: [0.0; N],
+            write: 0,
+            previous: [0.0; N],
+            hop: N / 4,
+            since_hop: 0,
+            threshold: AdaptiveThreshold::new(1.5, 0.02),
+            flux: [0.0; 2],
+            envelope: Detector::peak(0.0, 0.01 * sample_rate),
+            last_envelope: 0.0,
+            rise: None,
+            min_interval: (DEFAULT_MIN_INTERVAL * sample_rate) as u64,
+            position: 0,
+            last_onset: None,
+        }
+    }
+
+    /// Sets the number of samples between analysis frames.
+    ///
+    /// **Panics** if the hop is 0 or larger than the frame size.
+    pub fn set_hop(&mut self, hop: usize) {
+        assert!(hop > 0 && hop <= N, "hop must be within the frame size");
+        self.hop = hop;
+    }
+
+    /// Sets the flux the detector needs to see, on top of the adaptive part
+    /// of the threshold.
+    pub fn set_sensitivity(&mut self, delta: f32) {
+        self.threshold.delta = delta;
+    }
+
+    /// Sets how strongly the threshold follows the recent flux.
+    pub fn set_adaptivity(&mut self, multiplier: f32) {
+        self.threshold.multiplier = multiplier;
+    }
+
+    /// Sets the shortest time between two onsets, in seconds.
+    pub fn set_min_interval(&mut self, seconds: f32) {
+        self.min_interval = (seconds * self.sample_rate) as u64;
+    }
+
+    /// Processes a single sample, returning an onset if one was confirmed.
+    pub fn next(&mut self, sample: f32) -> Option<Onset> {
+        let position = self.position;
+        self.position += 1;
+
+        self.input[self.write] = sample;
+        self.write = (self.write + 1) % N;
+
+        // Track where the level jumped the most within the analysis window.
+        let level = self.envelope.next(sample);
+        let jump = level - self.last_envelope;
+        self.last_envelope = level;
+        let stale = self
+            .rise
+            .is_none_or(|(at, size)| position - at >= N as u64 || jump > size);
+        if stale && jump > 0.0 {
+            self.rise = Some((position, jump));
+        }
+
+        self.since_hop += 1;
+        if self.since_hop < self.hop {
+            return None;
+        }
+        self.since_hop = 0;
+
+        let flux = self.analyze();
+        let threshold = self.threshold.threshold();
+        self.threshold.push(flux);
+
+        // The previous frame is a peak if it's above both of its neighbours.
+        let [before, peak] = self.flux;
+        self.flux = [peak, flux];
+        if peak <= before || peak < flux || peak <= threshold {
+            return None;
+        }
+
+        // Without a rise in level, fall back to the middle of the peak frame.
+        let at = match self.rise.take() {
+            Some((at, _)) => at,
+            None => position.saturating_sub((self.hop + N / 2) as u64),
+        };
+        if self
+            .last_onset
+            .is_some_and(|last| at.saturating_sub(last) < self.min_interval)
+        {
+            return None;
+        }
+
+        self.last_onset = Some(at);
+        Some(Onset {
+            position: at,
+            strength: peak - threshold,
+        })
+    }
+
+    /// Transforms the current window and returns its flux over the last one.
+    fn analyze(&mut self) -> f32 {
+        let mut buffer = [0.0; N];
+        for (i, sample) in buffer.iter_mut().enumerate() {
+            *sample = self.input[(self.write + i) % N] * self.window[i];
+        }
+
+        self.fft.forward(&mut buffer);
+
+        let bins = N / 2 + 1;
+        let scale = 2.0 / N as f32;
+        let mut flux = 0.0;
+        for k in 0..bins {
+            let magnitude = libm::log1pf(COMPRESSION * bin(&buffer, k).norm() * scale);
+            flux += (magnitude - self.previous[k]).max(0.0);
+            self.previous[k] = magnitude;
+        }
+
+        flux / bins as f32
+    }
+}
+
+impl<const N: usize> OnsetDetector for SpectralFlux<N> {
+    fn process<C>(&mut self, samples: &[f32], mut on_onset: C)
+    where
+        C: FnMut(Onset),
+    {
+        for sample in samples {
+            if let Some(onset) = self.next(*sample) {
+                on_onset(onset);
+            }
+        }
+    }
+
+    /// An onset is confirmed one hop after the frame it peaked in, and can
+    /// sit anywhere in that frame.
+    fn latency(&self) -> usize {
+        N + self.hop
+    }
+
+    fn reset(&mut self) {
+        self.input = [0.0; N];
+        self.write = 0;
+        self.previous = [0.0; N];
+        self.since_hop = 0;
+        self.threshold.reset();
+        self.flux = [0.0; 2];
+        self.envelope = Detector::peak(0.0, 0.01 * self.sample_rate);
+        self.last_envelope = 0.0;
+        self.rise = None;
+        self.position = 0;
+        self.last_onset = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::onset::tests::bursts;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_detects_bursts() {
+        let mut buffer = [0.0; 48_000];
+        let positions = [4_800, 17_000, 29_123, 40_000];
+        bursts(&mut buffer, &positions, 48_000.0);
+
+        let mut detector = SpectralFlux::<512>::new(48_000);
+        let mut found = [0u64; 8];
+        let mut count = 0;
+
+        for block in buffer.chunks(48) {
+            detector.process(block, |onset| {
+                found[count] = onset.position;
+                count += 1;
+            });
+        }
+
+        assert_eq!(count, positions.len());
+        for (found, expected) in found.iter().zip(positions) {
+            assert!(
+                found.abs_diff(expected as u64) < 16,
+                "{found} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_detects_new_note() {
+        // A steady tone that changes pitch without changing level.
+        let mut buffer = [0.0; 24_000];
+        for (i, x) in buffer.iter_mut().enumerate() {
+            let frequency = if i < 12_000 { 440.0 } else { 660.0 };
+            *x = 0.5 * libm::sinf(2.0 * PI * frequency * i as f32 / 48_000.0);
+        }
+
+        let mut detector = SpectralFlux::<1024>::new(48_000);
+        let mut found = [0u64; 4];
+        let mut count = 0;
+        detector.process(&buffer, |onset| {
+            found[count] = onset.position;
+            count += 1;
+        });
+
+        // The tone starting, then the change in pitch.
+        assert_eq!(count, 2);
+        assert!(found[1].abs_diff(12_000) < 1024);
+    }
+}
@@ -0,0 +1,154 @@
+//! Onset and transient detection for triggering from live audio.
+//!
+//! Two detectors are provided, both reporting sample-accurate [`Onset`]s
+//! through a callback as blocks of mono samples are processed:
+//!
+//! - [`SpectralFlux`] compares the spectrum of each analysis frame to the
+//!   last and fires on sudden increases in energy at any frequency. It picks
+//!   up soft onsets like a new note on a sustained pad, at the cost of an FFT
+//!   per hop.
+//! - [`EnvelopeOnset`] compares a fast and slow peak envelope from
+//!   [`envelope::detect`](crate::audio::envelope::detect). It only catches
+//!   changes in level, but is cheap enough for the smallest targets and is
+//!   a good fit for drums and other percussive input.
+//!
+//! Both use an [`AdaptiveThreshold`] so that they follow the level of the
+//! material instead of needing a fixed trigger level. [`TempoTracker`] turns
+//! a stream of onsets into a tempo for tap-tempo from audio.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod envelope;
+pub use envelope::EnvelopeOnset;
+
+pub mod flux;
+pub use flux::SpectralFlux;
+
+pub mod tempo;
+pub use tempo::TempoTracker;
+
+/// The default shortest time between two onsets, in seconds.
+pub const DEFAULT_MIN_INTERVAL: f32 = 0.05;
+
+/// A detected onset.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Onset {
+    /// The sample the onset starts at, counted from the first sample
+    /// processed after the detector was built or reset.
+    ///
+    /// Detection needs to see some of the audio after an onset to confirm it,
+    /// so this can point at a sample in an earlier block than the one being
+    /// processed, see [`OnsetDetector::latency`].
+    pub position: u64,
+    /// How far the detection function rose above the threshold, useful as a
+    /// velocity for drum replacement.
+    pub strength: f32,
+}
+
+/// Types that detect onsets in a stream of mono samples.
+pub trait OnsetDetector {
+    /// Processes a block of samples, calling `on_onset` for each onset found.
+    fn process<C>(&mut self, samples: &[f32], on_onset: C)
+    where
+        C: FnMut(Onset);
+
+    /// Returns the most samples an onset can be reported after it happened.
+    fn latency(&self) -> usize;
+
+    /// Clears the detector's history and restarts the sample count.
+    fn reset(&mut self);
+}
+
+/// A threshold that follows the recent average of a detection function.
+///
+/// The threshold is `delta` above `multiplier` times the mean of the last
+/// `HISTORY` values, so quiet passages still trigger on small changes while
+/// dense, loud passages need a larger jump.
+#[derive(Debug, Clone)]
+pub struct AdaptiveThreshold<const HISTORY: usize> {
+    history: [f32; HISTORY],
+    index: usize,
+    sum: f32,
+    /// Scales the average of the history.
+    pub multiplier: f32,
+    /// Fixed offset added on top, the threshold for a silent history.
+    pub delta: f32,
+}
+
+impl<const HISTORY: usize> AdaptiveThreshold<HISTORY> {
+    pub const fn new(multiplier: f32, delta: f32) -> Self {
+        Self {
+            history: [0.0; HISTORY],
+            index: 0,
+            sum: 0.0,
+            multiplier,
+            delta,
+        }
+    }
+
+    /// Returns the current threshold.
+    pub fn threshold(&self) -> f32 {
+        self.multiplier * self.sum / HISTORY as f32 + self.delta
+    }
+
+    /// Adds the next value of the detection function to the history.
+    pub fn push(&mut self, value: f32) {
+        self.sum += value - self.history[self.index];
+        self.history[self.index] = value;
+        self.index = (self.index + 1) % HISTORY;
+    }
+
+    /// Clears the history.
+    pub fn reset(&mut self) {
+        self.history = [0.0; HISTORY];
+        self.index = 0;
+        self.sum = 0.0;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Fills the buffer with quiet noise and decaying bursts at the given positions.
+    pub(crate) fn bursts(buffer: &mut [f32], positions: &[usize], sample_rate: f32) {
+        let mut seed = 0x1234_5678u32;
+        let mut noise = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+        };
+
+        for x in buffer.iter_mut() {
+            *x = 0.001 * noise();
+        }
+
+        for &start in positions {
+            for (i, x) in buffer[start..].iter_mut().enumerate() {
+                let t = i as f32 / sample_rate;
+                *x += 0.8 * libm::expf(-t * 30.0) * noise();
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive_threshold() {
+        let mut threshold = AdaptiveThreshold::<4>::new(2.0, 0.1);
+        assert_eq!(threshold.threshold(), 0.1);
+
+        for _ in 0..4 {
+            threshold.push(1.0);
+        }
+        assert_eq!(threshold.threshold(), 2.1);
+
+        threshold.push(3.0);
+        assert_eq!(threshold.threshold(), 3.1);
+
+        threshold.reset();
+        assert_eq!(threshold.threshold(), 0.1);
+    }
+}
@@ -0,0 +1,122 @@
+//! Tap tempo from a stream of onsets.
+
+use super::Onset;
+
+/// How many intervals between onsets are averaged.
+const INTERVALS: usize = 4;
+
+/// Estimates a tempo from the spacing of onsets, like tapping a tempo button.
+///
+/// Intervals shorter than the fastest tempo are ignored as flams or double
+/// triggers, while a gap longer than the slowest tempo starts a new run of
+/// taps.
+#[derive(Debug, Clone)]
+pub struct TempoTracker {
+    sample_rate: f32,
+    min_bpm: f32,
+    max_bpm: f32,
+    intervals: [u64; INTERVALS],
+    count: usize,
+    index: usize,
+    last: Option<u64>,
+}
+
+impl TempoTracker {
+    /// Builds a tracker for onsets from audio at the given sample rate,
+    /// following tempos from 40 to 300 BPM.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            min_bpm: 40.0,
+            max_bpm: 300.0,
+            intervals: [0; INTERVALS],
+            count: 0,
+            index: 0,
+            last: None,
+        }
+    }
+
+    /// Sets the range of tempos to follow, in beats per minute.
+    pub fn set_range(&mut self, min_bpm: f32, max_bpm: f32) {
+        self.min_bpm = min_bpm;
+        self.max_bpm = max_bpm;
+    }
+
+    /// Adds an onset, returning the updated tempo once there are at least two taps.
+    pub fn push(&mut self, onset: Onset) -> Option<f32> {
+        let shortest = (60.0 * self.sample_rate / self.max_bpm) as u64;
+        let longest = (60.0 * self.sample_rate / self.min_bpm) as u64;
+
+        if let Some(last) = self.last {
+            let interval = onset.position.saturating_sub(last);
+
+            if interval < shortest {
+                return self.bpm();
+            }
+
+            if interval > longest {
+                self.count = 0;
+                self.index = 0;
+            } else {
+                self.intervals[self.index] = interval;
+                self.index = (self.index + 1) % INTERVALS;
+                self.count = (self.count + 1).min(INTERVALS);
+            }
+        }
+
+        self.last = Some(onset.position);
+        self.bpm()
+    }
+
+    /// Returns the current tempo in beats per minute, if there is one.
+    pub fn bpm(&self) -> Option<f32> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let total: u64 = self.intervals[..self.count].iter().sum();
+        let average = total as f32 / self.count as f32;
+
+        Some(60.0 * self.sample_rate / average)
+    }
+
+    /// Forgets all the taps.
+    pub fn reset(&mut self) {
+        self.count = 0;
+        self.index = 0;
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    fn onset(position: u64) -> Onset {
+        Onset {
+            position,
+            strength: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_tempo() {
+        let mut tracker = TempoTracker::new(48_000);
+        assert_eq!(tracker.push(onset(0)), None);
+
+        // 120 BPM is a beat every 24000 samples.
+        tracker.push(onset(24_000));
+        // A flam right after the beat is ignored.
+        tracker.push(onset(24_100));
+        tracker.push(onset(48_000));
+        let bpm = tracker.push(onset(72_000)).unwrap();
+        assert_float_eq!(bpm, 120.0, abs <= 0.01);
+
+        // A long pause starts over.
+        assert_eq!(tracker.push(onset(1_000_000)), None);
+        let bpm = tracker.push(onset(1_032_000)).unwrap();
+        assert_float_eq!(bpm, 90.0, abs <= 0.01);
+    }
+}
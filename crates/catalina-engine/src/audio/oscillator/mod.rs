@@ -138,6 +138,7 @@ pub fn sample_square<S: Sample + FromSample<f32>>(
 ///
 /// Needs future work to allow a larger range of square wave cycles.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 pub enum DutyCycle {
     /// A duty cycle of 12.5%.
@@ -0,0 +1,33 @@
+//! Physical models that synthesize instruments from the way they vibrate,
+//! rather than from stored samples or oscillators.
+//!
+//! - [`PluckedString`] is an extended Karplus-Strong string, a delay line
+//!   the length of one period fed back through a lowpass filter, for guitars,
+//!   harps and plucked basses.
+//! - [`BowedString`] is a digital waveguide driven by the stick-slip friction
+//!   of a bow, for sustained violin and cello like tones.
+//! - [`ModalBank`] is a bank of decaying resonators, one per partial, for
+//!   struck bars, bells and membranes.
+//!
+//! Every model is a mono **Signal**, and holds its delay lines inline, so the
+//! longest period, and so the lowest note, is set by the `N` parameter.
+
+pub mod string;
+pub use string::PluckedString;
+
+pub mod waveguide;
+pub use waveguide::BowedString;
+
+pub mod modal;
+pub use modal::{ModalBank, Mode};
+
+/// Returns the per-sample gain that decays a signal by 60dB over `seconds`
+/// when applied `rate` times a second.
+#[inline]
+fn decay_gain(seconds: f32, rate: f32) -> f32 {
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+
+    libm::powf(0.001, 1.0 / (seconds * rate))
+}
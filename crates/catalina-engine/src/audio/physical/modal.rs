@@ -0,0 +1,240 @@
+//! A bank of modal resonators for struck and plucked objects.
+//!
+//! Anything struck, from a marimba bar to a bell, rings at a set of partials
+//! that each decay at their own rate. Modal synthesis runs one decaying
+//! resonator per partial and excites them all at once, so the timbre comes
+//! from the ratios, levels and decay times of the [`Mode`]s.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::audio::physical::decay_gain;
+use crate::audio::signal::Signal;
+use crate::core::Hertz;
+use crate::prelude::*;
+
+/// Partials above this fraction of the sample rate are muted instead of aliasing.
+const MAX_FREQUENCY: f32 = 0.49;
+
+/// Below this level a mode counts as silent.
+const SILENCE: f32 = 1e-4;
+
+/// A single partial of a [`ModalBank`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mode {
+    /// The frequency of the partial, relative to the fundamental.
+    pub ratio: f32,
+    /// The level of the partial.
+    pub gain: f32,
+    /// The time for the partial to decay by 60dB, in seconds.
+    pub decay: f32,
+}
+
+impl Mode {
+    pub const fn new(ratio: f32, gain: f32, decay: f32) -> Self {
+        Self { ratio, gain, decay }
+    }
+
+    /// A silent mode, for unused partials.
+    pub const SILENT: Mode = Mode::new(1.0, 0.0, 0.0);
+}
+
+/// A bank of `MODES` resonators tuned relative to a fundamental.
+#[derive(Debug, Clone)]
+pub struct ModalBank<const MODES: usize> {
+    sample_rate: f32,
+    frequency: Hertz,
+    modes: [Mode; MODES],
+    /// The longest any mode rings for while damped, in seconds.
+    damping: Option<f32>,
+
+    /// Twice the radius times the cosine of each mode's frequency.
+    a1: [f32; MODES],
+    /// The squared radius of each mode.
+    a2: [f32; MODES],
+    /// Scales the excitation so each mode peaks at its gain.
+    input: [f32; MODES],
+
+    y1: [f32; MODES],
+    y2: [f32; MODES],
+    /// The strike waiting to be fed into the resonators.
+    excitation: f32,
+}
+
+impl<const MODES: usize> ModalBank<MODES> {
+    pub fn new(sample_rate: f32, frequency: Hertz, modes: [Mode; MODES]) -> Self {
+        let mut bank = Self {
+            sample_rate,
+            frequency,
+            modes,
+            damping: None,
+            a1: [0.0; MODES],
+            a2: [0.0; MODES],
+            input: [0.0; MODES],
+            y1: [0.0; MODES],
+            y2: [0.0; MODES],
+            excitation: 0.0,
+        };
+        bank.update();
+        bank
+    }
+
+    #[inline]
+    pub fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Retunes every mode relative to a new fundamental, without interrupting them.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        self.frequency = frequency;
+        self.update();
+    }
+
+    #[inline]
+    pub fn modes(&self) -> &[Mode; MODES] {
+        &self.modes
+    }
+
+    /// Changes a single partial.
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn set_mode(&mut self, index: usize, mode: Mode) {
+        self.modes[index] = mode;
+        self.update();
+    }
+
+    /// Replaces every partial, such as when switching between presets.
+    pub fn set_modes(&mut self, modes: [Mode; MODES]) {
+        self.modes = modes;
+        self.update();
+    }
+
+    /// Changes the sample rate, retuning the modes.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    /// Recalculates the resonators for the current settings.
+    fn update(&mut self) {
+        for (i, mode) in self.modes.iter().enumerate() {
+            let frequency = self.frequency.hertz() * mode.ratio;
+            if frequency <= 0.0 || frequency >= self.sample_rate * MAX_FREQUENCY {
+                self.a1[i] = 0.0;
+                self.a2[i] = 0.0;
+                self.input[i] = 0.0;
+                continue;
+            }
+
+            let decay = match self.damping {
+                Some(damping) => mode.decay.min(damping),
+                None => mode.decay,
+            };
+            let radius = decay_gain(decay, self.sample_rate);
+            let omega = 2.0 * PI * frequency / self.sample_rate;
+
+            self.a1[i] = 2.0 * radius * libm::cosf(omega);
+            self.a2[i] = radius * radius;
+            // The impulse response of the resonator peaks at 1 / sin(omega).
+            self.input[i] = mode.gain * libm::sinf(omega);
+        }
+    }
+
+    /// Strikes the object, at a velocity from 0 to 1, letting any previous strike ring on.
+    pub fn strike(&mut self, velocity: f32) {
+        self.excitation += velocity.clamp(0.0, 1.0);
+        if self.damping.take().is_some() {
+            self.update();
+        }
+    }
+
+    /// Damps the modes so none rings for longer than `decay` seconds, like
+    /// a hand on a bell. Lasts until the next strike.
+    pub fn damp(&mut self, decay: f32) {
+        self.damping = Some(decay.max(0.0));
+        self.update();
+    }
+
+    /// Returns whether any mode is still ringing.
+    pub fn is_active(&self) -> bool {
+        self.excitation != 0.0
+            || self
+                .y1
+                .iter()
+                .chain(self.y2.iter())
+                .any(|y| y.abs() > SILENCE)
+    }
+
+    /// Silences every mode immediately.
+    pub fn reset(&mut self) {
+        self.y1 = [0.0; MODES];
+        self.y2 = [0.0; MODES];
+        self.excitation = 0.0;
+    }
+}
+
+impl<const MODES: usize> Signal for ModalBank<MODES> {
+    type Frame = f32;
+
+    #[inline]
+    fn next(&mut self) -> f32 {
+        let excitation = core::mem::take(&mut self.excitation);
+
+        let mut output = 0.0;
+        for i in 0..MODES {
+            let y = self.a1[i] * self.y1[i] - self.a2[i] * self.y2[i] + self.input[i] * excitation;
+            self.y2[i] = self.y1[i];
+            self.y1[i] = y;
+            output += y;
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::{PitchDetector, Yin};
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_single_mode() {
+        let mut bank = ModalBank::new(48_000.0, Hertz(440.0), [Mode::new(1.0, 0.5, 1.0)]);
+        bank.strike(1.0);
+
+        let mut buffer = [0.0; 2048];
+        buffer.iter_mut().for_each(|x| *x = bank.next());
+        let peak = buffer.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+        assert_float_eq!(peak, 0.5, abs <= 0.01);
+
+        let estimate = Yin::<2048>::new(48_000).detect(&buffer).unwrap();
+        assert_float_eq!(estimate.frequency.hertz(), 440.0, rmax <= 0.002);
+    }
+
+    #[test]
+    fn test_damp_and_nyquist() {
+        let modes = [Mode::new(1.0, 1.0, 2.0), Mode::new(200.0, 1.0, 2.0)];
+        let mut bank = ModalBank::new(48_000.0, Hertz(220.0), modes);
+
+        // The second mode would be at 44kHz.
+        assert_eq!(bank.input[1], 0.0);
+
+        bank.strike(1.0);
+        bank.damp(0.05);
+        for _ in 0..4_800 {
+            bank.next();
+        }
+        assert!(!bank.is_active());
+
+        bank.strike(1.0);
+        assert_eq!(bank.damping, None);
+        for _ in 0..4_800 {
+            bank.next();
+        }
+        assert!(bank.is_active());
+    }
+}
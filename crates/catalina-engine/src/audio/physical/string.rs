@@ -0,0 +1,292 @@
+//! An extended Karplus-Strong plucked string.
+//!
+//! The classic Karplus-Strong algorithm fills a delay line with a burst of
+//! noise and feeds it back through an averaging filter, so the noise settles
+//! into a decaying tone at the period of the delay. This implements the
+//! extensions from Jaffe and Smith's "Extensions of the Karplus-Strong
+//! Plucked-String Algorithm":
+//!
+//! - An allpass filter in the loop adds the fractional part of the period, so
+//!   high notes are in tune instead of snapping to whole sample periods.
+//! - The loss filter is adjustable, from a bright, ringing string to the
+//!   damped thud of a palm muted one.
+//! - The decay time is set in seconds, independently of the pitch.
+//! - A comb filter on the excitation removes the harmonics that have a node
+//!   at the pick position, like plucking near the bridge or the neck.
+
+use crate::audio::delay::DelayLine;
+use crate::audio::physical::decay_gain;
+use crate::audio::signal::Signal;
+use crate::core::Hertz;
+use crate::core::rng::Rng;
+
+/// The smallest fractional delay kept for the allpass, which becomes
+/// unstable to tune as its delay approaches zero.
+const MIN_FRACTION: f32 = 0.1;
+
+/// A plucked string, with a period of at most `N` samples.
+#[derive(Debug, Clone)]
+pub struct PluckedString<const N: usize> {
+    sample_rate: f32,
+    line: DelayLine<N>,
+    rng: Rng,
+
+    frequency: Hertz,
+    /// How much high frequencies are damped, from 0 to 1.
+    damping: f32,
+    /// The time for the string to decay by 60dB, in seconds.
+    decay: f32,
+    /// Where the string is plucked, from 0 at the bridge to 1 at the nut.
+    pick_position: f32,
+
+    /// The whole samples of the period.
+    length: usize,
+    /// The weight of the previous sample in the loss filter.
+    loss: f32,
+    /// The allpass coefficient for the fractional period.
+    allpass: f32,
+    /// The gain applied each time around the loop.
+    gain: f32,
+
+    loss_x1: f32,
+    allpass_x1: f32,
+    allpass_y1: f32,
+}
+
+impl<const N: usize> PluckedString<N> {
+    /// Builds a silent string, with the noise bursts seeded by `seed`.
+    pub fn new(sample_rate: f32, seed: u64) -> Self {
+        let mut string = Self {
+            sample_rate,
+            line: DelayLine::new(),
+            rng: Rng::new(seed),
+
+            frequency: Hertz(110.0),
+            damping: 0.5,
+            decay: 4.0,
+            pick_position: 0.2,
+
+            length: 1,
+            loss: 0.0,
+            allpass: 0.0,
+            gain: 0.0,
+
+            loss_x1: 0.0,
+            allpass_x1: 0.0,
+            allpass_y1: 0.0,
+        };
+        string.update();
+        string
+    }
+
+    #[inline]
+    pub fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Tunes the string, taking effect immediately so it can be bent while ringing.
+    ///
+    /// Frequencies with a period longer than `N` samples are played at `N` samples,
+    /// and frequencies that aren't finite are ignored.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        if !frequency.hertz().is_finite() {
+            return;
+        }
+        self.frequency = frequency;
+        self.update();
+    }
+
+    #[inline]
+    pub fn damping(&self) -> f32 {
+        self.damping
+    }
+
+    /// Sets how much high frequencies are damped each time around the
+    /// string, from 0 for a bright string to 1 for a dull one.
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.clamp(0.0, 1.0);
+        self.update();
+    }
+
+    #[inline]
+    pub fn decay(&self) -> f32 {
+        self.decay
+    }
+
+    /// Sets the time for the string to decay by 60dB, in seconds.
+    ///
+    /// Shortening the decay while the string rings mutes it, such as on note off.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds.max(0.0);
+        self.update();
+    }
+
+    #[inline]
+    pub fn pick_position(&self) -> f32 {
+        self.pick_position
+    }
+
+    /// Sets where the next pluck happens along the string, from 0 at the
+    /// bridge to 1 at the nut. Plucking at 0.5 removes the even harmonics.
+    pub fn set_pick_position(&mut self, position: f32) {
+        self.pick_position = position.clamp(0.0, 1.0);
+    }
+
+    /// Changes the sample rate, retuning the string.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    /// Recalculates the loop for the current settings.
+    fn update(&mut self) {
+        let period = self.sample_rate / self.frequency.hertz();
+
+        // The one-zero loss filter delays low frequencies by its weight, so
+        // that's taken off the period along with the allpass's fraction.
+        self.loss = 0.5 * self.damping;
+        let remaining = period - self.loss;
+        // `max` before `min` so a period that isn't a number still gives a
+        // length the delay line can be tapped at.
+        let length = libm::floorf(remaining - MIN_FRACTION)
+            .max(1.0)
+            .min(N as f32);
+        let fraction = (remaining - length).clamp(MIN_FRACTION, 1.0 + MIN_FRACTION);
+
+        self.length = length as usize;
+        self.allpass = (1.0 - fraction) / (1.0 + fraction);
+        self.gain = decay_gain(self.decay, self.frequency.hertz());
+    }
+
+    /// Plucks the string with a burst of noise, at a velocity from 0 to 1.
+    pub fn pluck(&mut self, velocity: f32) {
+        let velocity = velocity.clamp(0.0, 1.0);
+        let length = self.length;
+        let comb = libm::roundf(self.pick_position * length as f32) as usize % length;
+
+        // Comb filter the burst so the harmonics with a node at the pick
+        // position cancel out. The burst is one period of the string, so the
+        // comb wraps around it, reading the delayed noise from copies of the
+        // generator rather than a second buffer.
+        let mut wrapped = self.rng.clone();
+        let mut delayed = wrapped.clone();
+        for _ in 0..length - comb {
+            delayed.next_bipolar();
+        }
+
+        for i in 0..length {
+            let noise = self.rng.next_bipolar();
+            let comb = match comb {
+                0 => 0.0,
+                _ if i < comb => delayed.next_bipolar(),
+                _ => wrapped.next_bipolar(),
+            };
+            self.line.push((noise - comb) * velocity * 0.5);
+        }
+
+        self.loss_x1 = 0.0;
+        self.allpass_x1 = 0.0;
+        self.allpass_y1 = 0.0;
+    }
+
+    /// Silences the string immediately.
+    pub fn mute(&mut self) {
+        self.line.clear();
+        self.loss_x1 = 0.0;
+        self.allpass_x1 = 0.0;
+        self.allpass_y1 = 0.0;
+    }
+}
+
+impl<const N: usize> Signal for PluckedString<N> {
+    type Frame = f32;
+
+    #[inline]
+    fn next(&mut self) -> f32 {
+        let output = self.line.tap(self.length);
+
+        let lowpassed = (1.0 - self.loss) * output + self.loss * self.loss_x1;
+        self.loss_x1 = output;
+
+        let tuned = self.allpass * lowpassed + self.allpass_x1 - self.allpass * self.allpass_y1;
+        self.allpass_x1 = lowpassed;
+        self.allpass_y1 = tuned;
+
+        self.line.push(tuned * self.gain);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::{PitchDetector, Yin};
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_tuning() {
+        let mut yin = Yin::<2048>::new(48_000);
+        let mut string = PluckedString::<1024>::new(48_000.0, 3);
+
+        // High notes have periods far from whole samples.
+        for frequency in [82.41, 196.0, 659.26, 1318.5] {
+            string.set_frequency(Hertz(frequency));
+            string.pluck(1.0);
+
+            let mut buffer = [0.0; 2048];
+            buffer.iter_mut().for_each(|x| *x = string.next());
+            let estimate = yin.detect(&buffer).unwrap();
+            assert_float_eq!(estimate.frequency.hertz(), frequency, rmax <= 0.003);
+        }
+    }
+
+    #[test]
+    fn test_decay() {
+        let mut string = PluckedString::<512>::new(48_000.0, 1);
+        string.set_frequency(Hertz(220.0));
+        string.set_decay(0.1);
+        string.pluck(1.0);
+
+        let peak = |string: &mut PluckedString<512>| {
+            (0..4_800).map(|_| string.next().abs()).fold(0.0, f32::max)
+        };
+        let start = peak(&mut string);
+        let end = peak(&mut string);
+        assert!(start > 0.1);
+        assert!(end < start * 0.05);
+
+        string.mute();
+        assert_eq!(peak(&mut string), 0.0);
+    }
+
+    #[test]
+    fn test_pick_position() {
+        // Plucking at the middle leaves the excitation antisymmetric over the
+        // period, so it has no even harmonics.
+        let mut string = PluckedString::<256>::new(48_000.0, 5);
+        string.set_frequency(Hertz(48_000.0 / 100.5));
+        string.set_pick_position(0.5);
+        string.pluck(1.0);
+
+        let half = string.length / 2;
+        for delay in 1..=half {
+            let a = string.line.tap(delay);
+            let b = string.line.tap(delay + half);
+            assert_float_eq!(a, -b, abs <= 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_non_finite_tuning() {
+        let mut string = PluckedString::<256>::new(48_000.0, 7);
+        string.set_frequency(Hertz(f32::NAN));
+        assert_eq!(string.frequency(), Hertz(110.0));
+
+        // A sample rate that isn't a number still leaves a delay to tap.
+        string.set_sample_rate(f32::NAN);
+        assert_eq!(string.length, 1);
+        string.pluck(1.0);
+        string.next();
+    }
+}
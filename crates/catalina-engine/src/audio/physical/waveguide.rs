@@ -0,0 +1,233 @@
+//! A bowed string digital waveguide.
+//!
+//! A digital waveguide models the waves travelling along a string as a pair
+//! of delay lines, one on each side of the point where the string is driven.
+//! Here that point is a bow: while the string moves with the bow they stick
+//! together, and when the difference in velocity grows too large the string
+//! slips, which pumps energy into the string for as long as it's bowed.
+//!
+//! This follows the bowed string from Perry Cook and Gary Scavone's
+//! Synthesis ToolKit, with a hyperbolic friction curve at the bow and a
+//! lowpass loss filter at the bridge.
+
+use crate::audio::delay::DelayLine;
+use crate::audio::signal::Signal;
+use crate::core::Hertz;
+
+/// The samples of delay taken off the period for the filters in the loop.
+const LOOP_DELAY: f32 = 4.0;
+
+/// The time for the bow to speed up from rest to full speed, or to stop, in seconds.
+const BOW_RAMP: f32 = 0.05;
+
+/// Below this level a released string counts as silent.
+const SILENCE: f32 = 1e-4;
+
+/// A bowed string, with a period of at most `2 * N` samples.
+#[derive(Debug, Clone)]
+pub struct BowedString<const N: usize> {
+    sample_rate: f32,
+
+    /// The string between the bow and the nut.
+    neck: DelayLine<N>,
+    /// The string between the bow and the bridge.
+    bridge: DelayLine<N>,
+
+    frequency: Hertz,
+    /// Where the bow sits along the string, from 0 at the bridge to 1 at the nut.
+    position: f32,
+    /// How hard the bow presses on the string, from 0 to 1.
+    pressure: f32,
+
+    neck_length: f32,
+    bridge_length: f32,
+    /// The pole of the loss filter at the bridge.
+    pole: f32,
+    /// The steepness of the friction curve, set by the bow pressure.
+    slope: f32,
+
+    /// The speed of the bow.
+    velocity: f32,
+    /// The speed the bow is moving towards.
+    target: f32,
+    /// How far the bow speed moves each sample.
+    step: f32,
+
+    neck_out: f32,
+    bridge_out: f32,
+    filter_y1: f32,
+}
+
+impl<const N: usize> BowedString<N> {
+    /// Builds a string at rest.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut string = Self {
+            sample_rate,
+            neck: DelayLine::new(),
+            bridge: DelayLine::new(),
+
+            frequency: Hertz(220.0),
+            position: 0.127,
+            pressure: 0.5,
+
+            neck_length: 1.0,
+            bridge_length: 1.0,
+            pole: 0.0,
+            slope: 0.0,
+
+            velocity: 0.0,
+            target: 0.0,
+            step: 0.0,
+
+            neck_out: 0.0,
+            bridge_out: 0.0,
+            filter_y1: 0.0,
+        };
+        string.update();
+        string
+    }
+
+    #[inline]
+    pub fn frequency(&self) -> Hertz {
+        self.frequency
+    }
+
+    /// Tunes the string, taking effect immediately for vibrato and slides.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        self.frequency = frequency;
+        self.update();
+    }
+
+    #[inline]
+    pub fn bow_position(&self) -> f32 {
+        self.position
+    }
+
+    /// Moves the bow along the string, from 0 at the bridge to 1 at the nut.
+    ///
+    /// Bowing closer to the bridge gives a brighter, harsher tone.
+    pub fn set_bow_position(&mut self, position: f32) {
+        self.position = position.clamp(0.01, 0.99);
+        self.update();
+    }
+
+    #[inline]
+    pub fn bow_pressure(&self) -> f32 {
+        self.pressure
+    }
+
+    /// Sets how hard the bow presses on the string, from 0 to 1.
+    pub fn set_bow_pressure(&mut self, pressure: f32) {
+        self.pressure = pressure.clamp(0.0, 1.0);
+        self.slope = 5.0 - 4.0 * self.pressure;
+    }
+
+    /// Changes the sample rate, retuning the string.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    /// Recalculates the delay lengths and filters for the current settings.
+    fn update(&mut self) {
+        let period = (self.sample_rate / self.frequency.hertz() - LOOP_DELAY).max(2.0);
+
+        self.bridge_length = (period * self.position).clamp(1.0, N as f32);
+        self.neck_length = (period - self.bridge_length).clamp(1.0, N as f32);
+        self.pole = 0.75 - 0.2 * 22_050.0 / self.sample_rate;
+        self.slope = 5.0 - 4.0 * self.pressure;
+        self.step = 1.0 / (BOW_RAMP * self.sample_rate);
+    }
+
+    /// Starts bowing, at a velocity from 0 to 1.
+    pub fn bow(&mut self, velocity: f32) {
+        self.target = 0.03 + 0.2 * velocity.clamp(0.0, 1.0);
+    }
+
+    /// Lifts the bow, letting the string ring out.
+    pub fn release(&mut self) {
+        self.target = 0.0;
+    }
+
+    /// Returns whether the string is being bowed or is still ringing.
+    pub fn is_active(&self) -> bool {
+        self.target > 0.0 || self.velocity > 0.0 || self.bridge_out.abs() > SILENCE
+    }
+
+    /// Stops the bow and silences the string immediately.
+    pub fn mute(&mut self) {
+        self.neck.clear();
+        self.bridge.clear();
+        self.velocity = 0.0;
+        self.target = 0.0;
+        self.neck_out = 0.0;
+        self.bridge_out = 0.0;
+        self.filter_y1 = 0.0;
+    }
+
+    /// The friction of the bow on the string for a difference in velocity.
+    #[inline]
+    fn friction(&self, velocity: f32) -> f32 {
+        let x = libm::fabsf(velocity * self.slope) + 0.75;
+        let x2 = x * x;
+        (1.0 / (x2 * x2)).min(1.0)
+    }
+}
+
+impl<const N: usize> Signal for BowedString<N> {
+    type Frame = f32;
+
+    #[inline]
+    fn next(&mut self) -> f32 {
+        if self.velocity < self.target {
+            self.velocity = (self.velocity + self.step).min(self.target);
+        } else if self.velocity > self.target {
+            self.velocity = (self.velocity - self.step).max(self.target);
+        }
+
+        // Both ends invert the wave as it reflects, the bridge also loses energy.
+        self.filter_y1 = 0.95 * (1.0 - self.pole) * self.bridge_out + self.pole * self.filter_y1;
+        let bridge_reflection = -self.filter_y1;
+        let nut_reflection = -self.neck_out;
+
+        let string_velocity = bridge_reflection + nut_reflection;
+        let difference = self.velocity - string_velocity;
+        let bowed = difference * self.friction(difference);
+
+        self.neck.push(bridge_reflection + bowed);
+        self.bridge.push(nut_reflection + bowed);
+        self.neck_out = self.neck.tap_linear(self.neck_length);
+        self.bridge_out = self.bridge.tap_linear(self.bridge_length);
+
+        self.bridge_out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::{PitchDetector, Yin};
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_sustains_in_tune() {
+        let mut yin = Yin::<2048>::new(48_000);
+        let mut string = BowedString::<512>::new(48_000.0);
+        string.set_frequency(Hertz(196.0));
+        string.bow(0.8);
+
+        let mut buffer = [0.0; 2048];
+        for _ in 0..10 {
+            buffer.iter_mut().for_each(|x| *x = string.next());
+        }
+        let estimate = yin.detect(&buffer).unwrap();
+        assert_float_eq!(estimate.frequency.hertz(), 196.0, rmax <= 0.01);
+
+        string.release();
+        for _ in 0..48_000 {
+            string.next();
+        }
+        assert!(!string.is_active());
+    }
+}
@@ -0,0 +1,92 @@
+//! Pitch detection for tuners and pitch-following effects.
+//!
+//! Both detectors work on blocks of mono samples and return a
+//! [`PitchEstimate`] with the fundamental frequency and a confidence value
+//! between 0 and 1, so callers can decide when a reading is stable enough to
+//! display or follow:
+//!
+//! - [`Yin`] is the classic YIN algorithm. It copes well with the strong
+//!   harmonics of plucked strings, making it a good fit for instrument tuners.
+//! - [`Mpm`] is the McLeod pitch method. It needs fewer periods of the signal
+//!   to lock on, so it reacts faster for pitch-following effects.
+//!
+//! See [`crate::music::tuner`] for mapping the estimate to the nearest note.
+
+use crate::core::Hertz;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod mpm;
+pub use mpm::Mpm;
+
+pub mod yin;
+pub use yin::Yin;
+
+/// The lowest frequency the detectors search for by default, just under a
+/// 5 string bass's low B.
+pub const DEFAULT_MIN_FREQUENCY: Hertz = Hertz(30.0);
+
+/// The highest frequency the detectors search for by default.
+pub const DEFAULT_MAX_FREQUENCY: Hertz = Hertz(2_000.0);
+
+/// Blocks quieter than this RMS level are treated as silence.
+const SILENCE_RMS: f32 = 1e-4;
+
+/// The fundamental frequency found in a block of samples.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PitchEstimate {
+    /// The estimated fundamental frequency.
+    pub frequency: Hertz,
+    /// How periodic the signal is at that frequency, from 0 to 1.
+    pub confidence: f32,
+}
+
+/// Types that can estimate the pitch of a block of mono samples.
+pub trait PitchDetector {
+    /// Estimates the pitch of the block of samples.
+    ///
+    /// Returns `None` for silent blocks, or blocks too short to hold two
+    /// periods of the lowest frequency being searched for.
+    fn detect(&mut self, samples: &[f32]) -> Option<PitchEstimate>;
+}
+
+/// Returns `true` if the block is too quiet to hold a pitch.
+fn is_silent(samples: &[f32]) -> bool {
+    let energy: f32 = samples.iter().map(|x| x * x).sum();
+    energy < SILENCE_RMS * SILENCE_RMS * samples.len() as f32
+}
+
+/// Refines a peak or dip at `index` by fitting a parabola through it and its
+/// neighbours, returning the fractional position and the value at the vertex.
+fn parabolic(values: &[f32], index: usize) -> (f32, f32) {
+    if index == 0 || index + 1 >= values.len() {
+        return (index as f32, values[index]);
+    }
+
+    let (a, b, c) = (values[index - 1], values[index], values[index + 1]);
+    let denominator = a - 2.0 * b + c;
+    if denominator.abs() < f32::EPSILON {
+        return (index as f32, b);
+    }
+
+    let offset = 0.5 * (a - c) / denominator;
+    (index as f32 + offset, b - 0.25 * (a - c) * offset)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::prelude::*;
+
+    /// Fills the buffer with a sawtooth-ish tone with strong harmonics.
+    pub(crate) fn harmonic_tone(buffer: &mut [f32], frequency: f32, sample_rate: f32) {
+        for (i, x) in buffer.iter_mut().enumerate() {
+            let phase = 2.0 * PI * frequency * i as f32 / sample_rate;
+            *x = 0.5 * libm::sinf(phase)
+                + 0.35 * libm::sinf(2.0 * phase)
+                + 0.25 * libm::sinf(3.0 * phase);
+        }
+    }
+}
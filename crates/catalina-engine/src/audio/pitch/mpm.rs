@@ -0,0 +1,162 @@
+//! The McLeod pitch method.
+//!
+//! From McLeod and Wyvill's
+//! [A Smarter Way to Find Pitch](https://www.cs.otago.ac.nz/research/publications/oucs-2008-03.pdf).
+
+use super::{
+    DEFAULT_MAX_FREQUENCY, DEFAULT_MIN_FREQUENCY, PitchDetector, PitchEstimate, is_silent,
+    parabolic,
+};
+use crate::core::Hertz;
+
+/// The default fraction of the highest peak a key maximum must reach, from the paper.
+pub const DEFAULT_CUTOFF: f32 = 0.93;
+
+/// Estimates pitch using the McLeod pitch method.
+///
+/// Blocks can be up to `N` samples long, and need to hold at least two
+/// periods of the lowest frequency being searched for.
+#[derive(Debug, Clone)]
+pub struct Mpm<const N: usize> {
+    sample_rate: f32,
+    cutoff: f32,
+    min_frequency: Hertz,
+    max_frequency: Hertz,
+    /// The normalized square difference for each lag.
+    nsdf: [f32; N],
+}
+
+impl<const N: usize> Mpm<N> {
+    /// Builds a detector for audio at the given sample rate.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            cutoff: DEFAULT_CUTOFF,
+            min_frequency: DEFAULT_MIN_FREQUENCY,
+            max_frequency: DEFAULT_MAX_FREQUENCY,
+            nsdf: [0.0; N],
+        }
+    }
+
+    /// Sets the range of frequencies to search for.
+    pub fn set_range(&mut self, min: Hertz, max: Hertz) {
+        self.min_frequency = min;
+        self.max_frequency = max;
+    }
+
+    /// Sets the fraction of the highest peak that the chosen peak must reach.
+    ///
+    /// Higher values favour the strongest period, lower values favour the
+    /// shortest period, which avoids picking an octave below.
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = cutoff;
+    }
+}
+
+impl<const N: usize> PitchDetector for Mpm<N> {
+    /// **Panics** if the block is longer than `N` samples.
+    fn detect(&mut self, samples: &[f32]) -> Option<PitchEstimate> {
+        assert!(samples.len() <= N, "block is larger than the detector");
+
+        let min_lag = ((self.sample_rate / self.max_frequency.hertz()) as usize).max(1);
+        let max_lag = (libm::ceilf(self.sample_rate / self.min_frequency.hertz()) as usize)
+            .min((samples.len() / 2).saturating_sub(1));
+
+        if min_lag >= max_lag || is_silent(samples) {
+            return None;
+        }
+
+        let nsdf = &mut self.nsdf[..=max_lag + 1];
+        for (lag, value) in nsdf.iter_mut().enumerate() {
+            let mut correlation = 0.0;
+            let mut energy = 0.0;
+            for j in 0..samples.len() - lag {
+                correlation += samples[j] * samples[j + lag];
+                energy += samples[j] * samples[j] + samples[j + lag] * samples[j + lag];
+            }
+
+            *value = if energy > 0.0 {
+                2.0 * correlation / energy
+            } else {
+                0.0
+            };
+        }
+
+        // Find the highest point between each pair of positive going zero
+        // crossings, skipping the lobe around lag 0.
+        let mut key_maxima = [0usize; 64];
+        let mut count = 0;
+        let mut highest = 0.0f32;
+
+        let mut lag = 1;
+        while lag <= max_lag && nsdf[lag] > 0.0 {
+            lag += 1;
+        }
+
+        let mut peak: Option<usize> = None;
+        while lag <= max_lag {
+            if nsdf[lag] > 0.0 {
+                if lag >= min_lag && peak.is_none_or(|p| nsdf[lag] > nsdf[p]) {
+                    peak = Some(lag);
+                }
+            } else if let Some(p) = peak.take() {
+                if count < key_maxima.len() {
+                    key_maxima[count] = p;
+                    count += 1;
+                }
+                highest = highest.max(nsdf[p]);
+            }
+            lag += 1;
+        }
+        // A peak still rising at the end of the search range is only kept if
+        // it turned over, otherwise it's a partial lobe.
+        if let Some(p) = peak
+            && p < max_lag
+            && count < key_maxima.len()
+        {
+            key_maxima[count] = p;
+            count += 1;
+            highest = highest.max(nsdf[p]);
+        }
+
+        let threshold = self.cutoff * highest;
+        let best = *key_maxima[..count]
+            .iter()
+            .find(|&&lag| nsdf[lag] >= threshold)?;
+
+        let (period, clarity) = parabolic(nsdf, best);
+
+        Some(PitchEstimate {
+            frequency: Hertz(self.sample_rate / period),
+            confidence: clarity.clamp(0.0, 1.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::tests::harmonic_tone;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_detects_tone() {
+        let mut mpm = Mpm::<2048>::new(48_000);
+        let mut buffer = [0.0; 2048];
+
+        for frequency in [82.41, 110.0, 196.0, 440.0, 1318.5] {
+            harmonic_tone(&mut buffer, frequency, 48_000.0);
+            let estimate = mpm.detect(&buffer).unwrap();
+
+            assert_float_eq!(estimate.frequency.hertz(), frequency, rmax <= 0.002);
+            assert!(estimate.confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut mpm = Mpm::<1024>::new(48_000);
+        assert_eq!(mpm.detect(&[0.0; 1024]), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! The YIN pitch detector.
+//!
+//! From de Cheveigné and Kawahara's
+//! [YIN, a fundamental frequency estimator for speech and music](http://audition.ens.fr/adc/pdf/2002_JASA_YIN.pdf).
+
+use super::{
+    DEFAULT_MAX_FREQUENCY, DEFAULT_MIN_FREQUENCY, PitchDetector, PitchEstimate, is_silent,
+    parabolic,
+};
+use crate::core::Hertz;
+
+/// The default dip threshold, from the paper.
+pub const DEFAULT_THRESHOLD: f32 = 0.15;
+
+/// Estimates pitch using the YIN algorithm.
+///
+/// Blocks can be up to `N` samples long. Half of the block is compared
+/// against lagged copies of itself, so a block needs to hold at least two
+/// periods of the lowest frequency being searched for.
+#[derive(Debug, Clone)]
+pub struct Yin<const N: usize> {
+    sample_rate: f32,
+    threshold: f32,
+    min_frequency: Hertz,
+    max_frequency: Hertz,
+    /// The cumulative mean normalized difference for each lag.
+    difference: [f32; N],
+}
+
+impl<const N: usize> Yin<N> {
+    /// Builds a detector for audio at the given sample rate.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            threshold: DEFAULT_THRESHOLD,
+            min_frequency: DEFAULT_MIN_FREQUENCY,
+            max_frequency: DEFAULT_MAX_FREQUENCY,
+            difference: [0.0; N],
+        }
+    }
+
+    /// Sets the range of frequencies to search for.
+    pub fn set_range(&mut self, min: Hertz, max: Hertz) {
+        self.min_frequency = min;
+        self.max_frequency = max;
+    }
+
+    /// Sets the threshold for accepting a dip in the difference function.
+    ///
+    /// Lower values are stricter, favouring octave-correct results over
+    /// finding a result at all.
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
+    }
+}
+
+impl<const N: usize> PitchDetector for Yin<N> {
+    /// **Panics** if the block is longer than `N` samples.
+    fn detect(&mut self, samples: &[f32]) -> Option<PitchEstimate> {
+        assert!(samples.len() <= N, "block is larger than the detector");
+
+        let window = samples.len() / 2;
+        let min_lag = ((self.sample_rate / self.max_frequency.hertz()) as usize).max(2);
+        let max_lag = (libm::ceilf(self.sample_rate / self.min_frequency.hertz()) as usize)
+            .min(window.saturating_sub(1));
+
+        if min_lag >= max_lag || is_silent(samples) {
+            return None;
+        }
+
+        // Difference function, normalized by its running mean as it goes.
+        let difference = &mut self.difference[..=max_lag];
+        difference[0] = 1.0;
+        let mut running_sum = 0.0;
+        for lag in 1..=max_lag {
+            let mut sum = 0.0;
+            for j in 0..window {
+                let delta = samples[j] - samples[j + lag];
+                sum += delta * delta;
+            }
+
+            running_sum += sum;
+            difference[lag] = if running_sum > 0.0 {
+                sum * lag as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        // Take the first dip under the threshold, following it to the bottom.
+        // If there isn't one, fall back to the deepest dip found.
+        let mut best = min_lag;
+        let mut lag = min_lag;
+        while lag <= max_lag {
+            if difference[lag] < self.threshold {
+                while lag < max_lag && difference[lag + 1] < difference[lag] {
+                    lag += 1;
+                }
+                best = lag;
+                break;
+            }
+
+            if difference[lag] < difference[best] {
+                best = lag;
+            }
+            lag += 1;
+        }
+
+        let (period, dip) = parabolic(difference, best);
+
+        Some(PitchEstimate {
+            frequency: Hertz(self.sample_rate / period),
+            confidence: (1.0 - dip).clamp(0.0, 1.0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::tests::harmonic_tone;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_detects_tone() {
+        let mut yin = Yin::<2048>::new(48_000);
+        let mut buffer = [0.0; 2048];
+
+        for frequency in [82.41, 110.0, 196.0, 440.0, 1318.5] {
+            harmonic_tone(&mut buffer, frequency, 48_000.0);
+            let estimate = yin.detect(&buffer).unwrap();
+
+            assert_float_eq!(estimate.frequency.hertz(), frequency, rmax <= 0.002);
+            assert!(estimate.confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut yin = Yin::<1024>::new(48_000);
+        assert_eq!(yin.detect(&[0.0; 1024]), None);
+    }
+}
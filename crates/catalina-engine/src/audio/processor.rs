@@ -0,0 +1,321 @@
+//! Block based audio effects that transform an input into an output.
+//!
+//! Where an [`AudioSource`](super::AudioSource) only produces audio and a
+//! [`Signal`] pulls one frame at a time, an [`AudioProcessor`] is handed a
+//! whole block of input, like an effect pedal sitting between an instrument
+//! and an amp. The host calls [`AudioProcessor::prepare`] before audio starts,
+//! then processes block after block, either from an input buffer into an
+//! output buffer or in place.
+//!
+//! [`FnProcessor`] and [`SignalProcessor`] adapt per-frame transforms, either a
+//! plain closure or a chain of **Signal** adaptors, so they can run block-wise.
+
+use core::cell::Cell;
+
+use crate::audio::frame::Frame;
+use crate::audio::signal::Signal;
+
+/// An effect that processes blocks of frames.
+pub trait AudioProcessor {
+    type Frame: Frame;
+
+    /// Prepares the processor to run at a sample rate, with blocks of at most
+    /// `max_block` frames.
+    ///
+    /// Called before processing starts and whenever the audio settings change,
+    /// never while audio is running, so this is where to allocate or redesign filters.
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        let _ = (sample_rate, max_block);
+    }
+
+    /// Clears any internal state, such as filter memories or delay lines,
+    /// without changing the settings.
+    fn reset(&mut self) {}
+
+    /// Processes a block of frames in place.
+    fn process_in_place(&mut self, buffer: &mut [Self::Frame]);
+
+    /// Processes a block of input frames into the output.
+    ///
+    /// The default implementation copies the input to the output and processes it in place.
+    ///
+    /// **Panics** if the buffers have different lengths.
+    fn process(&mut self, input: &[Self::Frame], output: &mut [Self::Frame]) {
+        assert_eq!(input.len(), output.len());
+
+        output.copy_from_slice(input);
+        self.process_in_place(output);
+    }
+
+    /// Returns the delay the processor adds to the audio, in frames.
+    fn latency(&self) -> usize {
+        0
+    }
+}
+
+impl<P> AudioProcessor for &mut P
+where
+    P: AudioProcessor + ?Sized,
+{
+    type Frame = P::Frame;
+
+    #[inline]
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        (**self).prepare(sample_rate, max_block)
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [Self::Frame]) {
+        (**self).process_in_place(buffer)
+    }
+
+    #[inline]
+    fn process(&mut self, input: &[Self::Frame], output: &mut [Self::Frame]) {
+        (**self).process(input, output)
+    }
+
+    #[inline]
+    fn latency(&self) -> usize {
+        (**self).latency()
+    }
+}
+
+/// Runs a per-frame closure as an [`AudioProcessor`].
+///
+/// # Example
+///
+/// ```
+/// use catalina_engine::audio::processor::{AudioProcessor, FnProcessor};
+///
+/// let mut gain = FnProcessor::new(|frame: [f32; 2]| [frame[0] * 0.5, frame[1] * 0.5]);
+/// let mut buffer = [[1.0, -1.0]; 4];
+/// gain.process_in_place(&mut buffer);
+/// assert_eq!(buffer, [[0.5, -0.5]; 4]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FnProcessor<F, M> {
+    map: M,
+    frame: core::marker::PhantomData<F>,
+}
+
+impl<F, M> FnProcessor<F, M>
+where
+    F: Frame,
+    M: FnMut(F) -> F,
+{
+    pub fn new(map: M) -> Self {
+        Self {
+            map,
+            frame: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<F, M> AudioProcessor for FnProcessor<F, M>
+where
+    F: Frame,
+    M: FnMut(F) -> F,
+{
+    type Frame = F;
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [F]) {
+        for frame in buffer.iter_mut() {
+            *frame = (self.map)(*frame);
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, input: &[F], output: &mut [F]) {
+        assert_eq!(input.len(), output.len());
+
+        for (input, output) in input.iter().zip(output.iter_mut()) {
+            *output = (self.map)(*input);
+        }
+    }
+}
+
+/// A **Signal** that yields the frame currently being processed by a [`SignalProcessor`].
+pub struct Input<'a, F> {
+    slot: &'a Cell<F>,
+}
+
+impl<F> Signal for Input<'_, F>
+where
+    F: Frame,
+{
+    type Frame = F;
+
+    #[inline]
+    fn next(&mut self) -> F {
+        self.slot.get()
+    }
+}
+
+/// Runs a chain of **Signal** adaptors as an [`AudioProcessor`].
+///
+/// The chain is built on an [`Input`] signal, which yields each input frame in
+/// turn as the processor pulls one output frame per input frame. The frame
+/// being processed is passed through a `Cell` owned by the caller, so the
+/// processor works without allocating.
+///
+/// Only adaptors that pull exactly one input frame per output frame, such as
+/// gain, offsets, mixing with another signal or envelope followers, keep the
+/// input and output in step.
+///
+/// # Example
+///
+/// ```
+/// use core::cell::Cell;
+/// use catalina_engine::audio::processor::{AudioProcessor, SignalProcessor};
+/// use catalina_engine::audio::signal::Signal;
+///
+/// let slot = Cell::new(0.0f32);
+/// let mut processor = SignalProcessor::new(&slot, |input| input.scale_amp(0.5).offset_amp(0.25));
+///
+/// let mut buffer = [1.0, -1.0];
+/// processor.process_in_place(&mut buffer);
+/// assert_eq!(buffer, [0.75, -0.25]);
+/// ```
+pub struct SignalProcessor<'a, F, S> {
+    slot: &'a Cell<F>,
+    signal: S,
+}
+
+impl<'a, F, S> SignalProcessor<'a, F, S>
+where
+    F: Frame,
+    S: Signal<Frame = F>,
+{
+    /// Builds the processor from a slot for the current frame and a function
+    /// that builds the chain on top of the input.
+    pub fn new<B>(slot: &'a Cell<F>, build: B) -> Self
+    where
+        B: FnOnce(Input<'a, F>) -> S,
+    {
+        Self {
+            slot,
+            signal: build(Input { slot }),
+        }
+    }
+
+    /// Borrows the chain of adaptors.
+    pub fn signal_mut(&mut self) -> &mut S {
+        &mut self.signal
+    }
+}
+
+impl<F, S> AudioProcessor for SignalProcessor<'_, F, S>
+where
+    F: Frame,
+    S: Signal<Frame = F>,
+{
+    type Frame = F;
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [F]) {
+        for frame in buffer.iter_mut() {
+            self.slot.set(*frame);
+            *frame = self.signal.next();
+        }
+    }
+}
+
+/// Processes blocks through two processors in series.
+#[derive(Debug, Clone)]
+pub struct Chain<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A, B> Chain<A, B> {
+    pub const fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A, B> AudioProcessor for Chain<A, B>
+where
+    A: AudioProcessor,
+    B: AudioProcessor<Frame = A::Frame>,
+{
+    type Frame = A::Frame;
+
+    fn prepare(&mut self, sample_rate: f32, max_block: usize) {
+        self.first.prepare(sample_rate, max_block);
+        self.second.prepare(sample_rate, max_block);
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+    }
+
+    #[inline]
+    fn process_in_place(&mut self, buffer: &mut [Self::Frame]) {
+        self.first.process_in_place(buffer);
+        self.second.process_in_place(buffer);
+    }
+
+    #[inline]
+    fn process(&mut self, input: &[Self::Frame], output: &mut [Self::Frame]) {
+        self.first.process(input, output);
+        self.second.process_in_place(output);
+    }
+
+    fn latency(&self) -> usize {
+        self.first.latency() + self.second.latency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::filter::{Biquad, Coefficients};
+    use crate::core::Hertz;
+
+    #[test]
+    fn test_process_matches_in_place() {
+        let coefficients = Coefficients::low_pass(48_000.0, Hertz(1_000.0), 0.707);
+        let mut a = Biquad::new(48_000.0, coefficients);
+        let mut b = a.clone();
+
+        let input: [f32; 64] = core::array::from_fn(|i| if i % 8 == 0 { 1.0 } else { 0.0 });
+        let mut output = [0.0; 64];
+        AudioProcessor::process(&mut a, &input, &mut output);
+
+        let mut buffer = input;
+        AudioProcessor::process_in_place(&mut b, &mut buffer);
+        assert_eq!(output, buffer);
+    }
+
+    #[test]
+    fn test_signal_processor() {
+        let slot = Cell::new([0.0f32; 2]);
+        let mut processor = SignalProcessor::new(&slot, |input| input.scale_amp(2.0));
+
+        let input = [[0.25, -0.5], [0.5, 0.125]];
+        let mut output = [[0.0; 2]; 2];
+        processor.process(&input, &mut output);
+        assert_eq!(output, [[0.5, -1.0], [1.0, 0.25]]);
+    }
+
+    #[test]
+    fn test_chain() {
+        let mut chain = Chain::new(
+            FnProcessor::new(|x: f32| x + 1.0),
+            FnProcessor::new(|x: f32| x * 3.0),
+        );
+        chain.prepare(48_000.0, 16);
+
+        let mut buffer = [0.0, 1.0];
+        chain.process_in_place(&mut buffer);
+        assert_eq!(buffer, [3.0, 6.0]);
+        assert_eq!(chain.latency(), 0);
+    }
+}
@@ -0,0 +1,312 @@
+//! Fractional fixed-point **Sample** types.
+//!
+//! [`Q15`] and [`Q31`] represent values in the range `-1.0 <= v < 1.0` with 15 and 31
+//! fractional bits, the formats used by CMSIS-DSP and by most codecs. They let the engine
+//! run on parts without an FPU, such as the Cortex-M0+, and take advantage of the DSP
+//! extensions on parts like the Cortex-M4.
+//!
+//! Unlike the integer sample types, arithmetic on the fixed-point types saturates instead of
+//! wrapping, so a signal that overshoots clips like an analog circuit rather than flipping
+//! polarity. Multiplication is fractional, `Q15::HALF * Q15::HALF == Q15::QUARTER`, and
+//! rounds the same way as the matching CMSIS-DSP functions.
+//!
+//! Both types are bit-compatible with the integer type they wrap, and convert to and from
+//! every other sample type the same way it does.
+
+use crate::audio::sample::conv::FromSample;
+use crate::audio::sample::types::{I24, I48, U24, U48};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+macro_rules! fixed_sample_type {
+    ($T:ident: $Rep:ident, $bits:expr, $doc:expr) => {
+        #[doc = $doc]
+        #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        #[repr(transparent)]
+        pub struct $T($Rep);
+
+        impl $T {
+            /// The smallest value, -1.0.
+            pub const MIN: $T = $T($Rep::MIN);
+            /// The largest value, just under 1.0.
+            pub const MAX: $T = $T($Rep::MAX);
+            /// 0.0.
+            pub const ZERO: $T = $T(0);
+            /// 0.5.
+            pub const HALF: $T = $T(1 << ($bits - 1));
+            /// 0.25.
+            pub const QUARTER: $T = $T(1 << ($bits - 2));
+
+            /// Builds a sample from its raw bits.
+            #[inline]
+            pub const fn from_bits(bits: $Rep) -> Self {
+                $T(bits)
+            }
+
+            /// Returns the raw bits of the sample.
+            #[inline]
+            pub const fn to_bits(self) -> $Rep {
+                self.0
+            }
+
+            /// Converts to a float.
+            #[inline]
+            pub fn to_f32(self) -> f32 {
+                (self.0 as f64 / (1u64 << $bits) as f64) as f32
+            }
+
+            /// Returns the absolute value, saturating -1.0 to [`Self::MAX`].
+            #[inline]
+            pub const fn abs(self) -> Self {
+                $T(self.0.saturating_abs())
+            }
+        }
+
+        impl ::core::ops::Add for $T {
+            type Output = $T;
+            #[inline]
+            fn add(self, other: Self) -> Self {
+                $T(self.0.saturating_add(other.0))
+            }
+        }
+
+        impl ::core::ops::Sub for $T {
+            type Output = $T;
+            #[inline]
+            fn sub(self, other: Self) -> Self {
+                $T(self.0.saturating_sub(other.0))
+            }
+        }
+
+        impl ::core::ops::Neg for $T {
+            type Output = $T;
+            #[inline]
+            fn neg(self) -> Self {
+                $T(self.0.saturating_neg())
+            }
+        }
+
+        impl ::core::ops::AddAssign for $T {
+            #[inline]
+            fn add_assign(&mut self, other: Self) {
+                *self = *self + other;
+            }
+        }
+
+        impl ::core::ops::SubAssign for $T {
+            #[inline]
+            fn sub_assign(&mut self, other: Self) {
+                *self = *self - other;
+            }
+        }
+
+        impl ::core::ops::MulAssign for $T {
+            #[inline]
+            fn mul_assign(&mut self, other: Self) {
+                *self = *self * other;
+            }
+        }
+    };
+}
+
+fixed_sample_type!(
+    Q15: i16,
+    15,
+    "A fractional sample with 15 fractional bits, stored in an `i16`."
+);
+
+fixed_sample_type!(
+    Q31: i32,
+    31,
+    "A fractional sample with 31 fractional bits, stored in an `i32`."
+);
+
+impl Q15 {
+    /// Converts from a float, rounding halves away from zero like CMSIS-DSP's
+    /// `arm_float_to_q15`, and saturating values outside of `-1.0 <= v < 1.0`.
+    ///
+    /// This is the same conversion as [`f32_to_i16`](crate::audio::slice::kernels::f32_to_i16).
+    #[inline]
+    pub fn from_f32(value: f32) -> Self {
+        // Adding half with the value's sign and truncating rounds without
+        // branches, and float to integer casts saturate.
+        let scaled = value * 32_768.0;
+        Q15((scaled + 0.5f32.copysign(scaled)) as i16)
+    }
+
+    /// Builds a sample from a wider intermediate, clamping it into range.
+    #[inline]
+    const fn saturate(value: i32) -> Self {
+        if value > i16::MAX as i32 {
+            Self::MAX
+        } else if value < i16::MIN as i32 {
+            Self::MIN
+        } else {
+            Q15(value as i16)
+        }
+    }
+}
+
+impl Q31 {
+    /// Converts from a float, rounding halves away from zero like CMSIS-DSP's
+    /// `arm_float_to_q31`, and saturating values outside of `-1.0 <= v < 1.0`.
+    #[inline]
+    pub fn from_f32(value: f32) -> Self {
+        // The sum is exact in an f64, where an f32 would round the larger values.
+        let scaled = value as f64 * 2_147_483_648.0;
+        Q31((scaled + 0.5f64.copysign(scaled)) as i32)
+    }
+}
+
+/// Fractional multiply, matching CMSIS-DSP's `arm_mult_q15`.
+impl ::core::ops::Mul for Q15 {
+    type Output = Q15;
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        Q15::saturate((self.0 as i32 * other.0 as i32) >> 15)
+    }
+}
+
+/// Fractional multiply, matching CMSIS-DSP's `arm_mult_q31`.
+impl ::core::ops::Mul for Q31 {
+    type Output = Q31;
+    #[inline]
+    fn mul(self, other: Self) -> Self {
+        // Like CMSIS-DSP, the product is saturated to 31 bits before it's
+        // shifted back up, so the lowest bit is always clear and -1 * -1 is
+        // one step below `Q31::MAX`.
+        let product = (self.0 as i64 * other.0 as i64) >> 32;
+        Q31((product.clamp(-(1 << 30), (1 << 30) - 1) << 1) as i32)
+    }
+}
+
+impl From<Q15> for Q31 {
+    #[inline]
+    fn from(value: Q15) -> Self {
+        Q31((value.0 as i32) << 16)
+    }
+}
+
+impl FromSample<Q15> for Q31 {
+    #[inline]
+    fn from_sample_(s: Q15) -> Self {
+        s.into()
+    }
+}
+
+impl FromSample<Q31> for Q15 {
+    #[inline]
+    fn from_sample_(s: Q31) -> Self {
+        Q15((s.0 >> 16) as i16)
+    }
+}
+
+/// Converts through the integer type a fixed-point type is bit-compatible with.
+macro_rules! impl_fixed_conversions {
+    ($T:ident: $Rep:ident from $($U:ty)*) => {
+        $(
+            impl FromSample<$U> for $T {
+                #[inline]
+                fn from_sample_(s: $U) -> Self {
+                    $T(<$Rep as FromSample<$U>>::from_sample_(s))
+                }
+            }
+
+            impl FromSample<$T> for $U {
+                #[inline]
+                fn from_sample_(s: $T) -> Self {
+                    <$U as FromSample<$Rep>>::from_sample_(s.0)
+                }
+            }
+        )*
+    };
+}
+
+impl_fixed_conversions!(Q15: i16 from i8 i16 I24 i32 I48 i64 u8 u16 U24 u32 U48 u64 f32 f64);
+impl_fixed_conversions!(Q31: i32 from i8 i16 I24 i32 I48 i64 u8 u16 U24 u32 U48 u64 f32 f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::sample::Sample;
+
+    #[test]
+    fn test_saturating_ops() {
+        assert_eq!(Q15::MAX + Q15::HALF, Q15::MAX);
+        assert_eq!(Q15::MIN - Q15::HALF, Q15::MIN);
+        assert_eq!(-Q15::MIN, Q15::MAX);
+        assert_eq!(Q15::HALF + Q15::QUARTER - Q15::HALF, Q15::QUARTER);
+
+        assert_eq!(Q31::MAX + Q31::HALF, Q31::MAX);
+        assert_eq!(Q31::MIN - Q31::HALF, Q31::MIN);
+        assert_eq!(-Q31::MIN, Q31::MAX);
+    }
+
+    #[test]
+    fn test_fractional_multiply() {
+        assert_eq!(Q15::HALF * Q15::HALF, Q15::QUARTER);
+        assert_eq!(Q15::HALF * -Q15::HALF, -Q15::QUARTER);
+        assert_eq!(Q15::MIN * Q15::MIN, Q15::MAX);
+        assert_eq!(Q15::from_bits(3) * Q15::HALF, Q15::from_bits(1));
+
+        assert_eq!(Q31::HALF * Q31::HALF, Q31::QUARTER);
+        assert_eq!(Q31::HALF * -Q31::HALF, -Q31::QUARTER);
+        assert_eq!(Q31::MIN * Q31::MIN, Q31::from_bits(0x7FFF_FFFE));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(Q15::from_sample(0.5f32), Q15::HALF);
+        assert_eq!(Q15::from_sample(-1.0f32), Q15::MIN);
+        assert_eq!(Q15::from_f32(2.0), Q15::MAX);
+        assert_eq!(Q15::from_f32(0.5 / 32_768.0), Q15::from_bits(1));
+        assert_eq!(Q15::from_f32(-1.5 / 32_768.0), Q15::from_bits(-2));
+        assert_eq!(Q31::from_f32(-2.0), Q31::MIN);
+        assert_eq!(Q15::HALF.to_sample::<f32>(), 0.5);
+        assert_eq!(Q15::HALF.to_sample::<i16>(), 16_384);
+        assert_eq!(Q15::ZERO.to_sample::<u8>(), 128);
+        assert_eq!(Q15::from_sample(64i8), Q15::HALF);
+
+        assert_eq!(Q31::from_sample(Q15::HALF), Q31::HALF);
+        assert_eq!(Q15::from_sample(Q31::QUARTER), Q15::QUARTER);
+        assert_eq!(Q31::from_sample(-0.25f64), -Q31::QUARTER);
+        assert_eq!(Q31::MIN.to_f32(), -1.0);
+        assert_eq!(Q31::HALF.to_sample::<I24>(), I24::new(4_194_304).unwrap());
+    }
+
+    #[test]
+    fn test_sample_ops() {
+        assert_eq!(Q15::HALF.mul_amp(0.5), Q15::QUARTER);
+        assert_eq!(Q15::QUARTER.add_amp(Q15::QUARTER), Q15::HALF);
+        assert_eq!(Q31::HALF.mul_amp(-1.0), -Q31::HALF);
+        assert_eq!(Q15::EQUILIBRIUM, Q15::ZERO);
+    }
+
+    #[test]
+    fn test_core_blocks() {
+        use crate::audio::envelope::detect::Detector;
+        use crate::audio::oscillator::{Oscillator, OscillatorType, RuntimeOscillator};
+        use crate::audio::signal::{self, Signal};
+        use crate::core::Hertz;
+
+        let mut oscillator = RuntimeOscillator::new(OscillatorType::Sine, 48_000.0, Hertz(1_000.0));
+        let mut buffer = [Q15::ZERO; 48];
+        oscillator.render(&mut buffer);
+        assert_eq!(buffer[0], Q15::ZERO);
+        assert!(buffer[12] > Q15::from_f32(0.99));
+
+        let mut scaled = signal::from_iter(buffer.iter().copied()).scale_amp(0.5);
+        let scaled: [Q15; 13] = core::array::from_fn(|_| scaled.next());
+        assert_eq!(scaled[12], buffer[12].mul_amp(0.5));
+
+        let mut detector = Detector::peak(0.0, 4_800.0);
+        let mut envelope = Q15::ZERO;
+        for sample in buffer {
+            envelope = detector.next(sample);
+        }
+        assert!(envelope > Q15::from_f32(0.99));
+    }
+}
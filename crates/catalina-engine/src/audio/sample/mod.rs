@@ -9,9 +9,11 @@
 extern crate alloc;
 
 pub use conv::{Duplex, FromSample, ToSample};
+pub use fixed::{Q15, Q31};
 pub use types::{I24, I48, U24, U48};
 
 pub mod conv;
+pub mod fixed;
 mod ops;
 pub mod types;
 
@@ -270,7 +272,9 @@ impl_sample! {
     U48: Signed: i64, Float: f64, EQUILIBRIUM: types::u48::EQUILIBRIUM,
     u64: Signed: i64, Float: f64, EQUILIBRIUM: 9_223_372_036_854_775_808,
     f32: Signed: f32, Float: f32, EQUILIBRIUM: 0.0,
-    f64: Signed: f64, Float: f64, EQUILIBRIUM: 0.0
+    f64: Signed: f64, Float: f64, EQUILIBRIUM: 0.0,
+    Q15: Signed: Q15, Float: f32, EQUILIBRIUM: Q15::ZERO,
+    Q31: Signed: Q31, Float: f32, EQUILIBRIUM: Q31::ZERO
 }
 
 /// Integral and floating-point **Sample** format types whose equilibrium is at 0.
@@ -285,7 +289,7 @@ pub trait SignedSample:
 {
 }
 macro_rules! impl_signed_sample { ($($T:ty)*) => { $( impl SignedSample for $T {} )* } }
-impl_signed_sample!(i8 i16 I24 i32 I48 i64 f32 f64);
+impl_signed_sample!(i8 i16 I24 i32 I48 i64 f32 f64 Q15 Q31);
 
 /// Sample format types represented as floating point numbers.
 ///
@@ -0,0 +1,100 @@
+//! An extension to the **Signal** trait that dithers a signal down to a lower resolution
+//! sample type.
+
+use super::Signal;
+use crate::audio::dither::{Dither, sample_bits};
+use crate::audio::frame::Frame;
+use crate::audio::sample::{FromSample, ToSample};
+
+/// An extension to the **Signal** trait that dithers a signal down to a lower resolution
+/// sample type.
+pub trait SignalDither: Signal {
+    /// An adaptor that dithers each frame of the signal into a frame of type `F`.
+    ///
+    /// **Panics** if the frames have more than `CHANNELS` channels.
+    fn dither<F, const CHANNELS: usize>(
+        self,
+        dither: Dither<CHANNELS>,
+    ) -> Dithered<Self, F, CHANNELS>
+    where
+        Self: Sized,
+        <Self::Frame as Frame>::Sample: ToSample<f32>,
+        F: Frame<NumChannels = <Self::Frame as Frame>::NumChannels>,
+        F::Sample: FromSample<f32> + ToSample<f32>,
+    {
+        assert!(Self::Frame::CHANNELS <= CHANNELS);
+
+        Dithered {
+            signal: self,
+            dither,
+            bits: sample_bits::<F::Sample>(),
+            frame: core::marker::PhantomData,
+        }
+    }
+}
+
+/// An adaptor that dithers each frame of a signal into a lower resolution frame type.
+#[derive(Clone)]
+pub struct Dithered<S, F, const CHANNELS: usize> {
+    signal: S,
+    dither: Dither<CHANNELS>,
+    /// The resolution of the output sample type.
+    bits: u32,
+    frame: core::marker::PhantomData<F>,
+}
+
+impl<S, F, const CHANNELS: usize> Dithered<S, F, CHANNELS> {
+    /// Borrows the ditherer, to change its settings.
+    pub fn dither_mut(&mut self) -> &mut Dither<CHANNELS> {
+        &mut self.dither
+    }
+
+    /// Consumes the adaptor, returning the wrapped signal and the ditherer.
+    pub fn into_parts(self) -> (S, Dither<CHANNELS>) {
+        (self.signal, self.dither)
+    }
+}
+
+impl<S, F, const CHANNELS: usize> Signal for Dithered<S, F, CHANNELS>
+where
+    S: Signal,
+    <S::Frame as Frame>::Sample: ToSample<f32>,
+    F: Frame<NumChannels = <S::Frame as Frame>::NumChannels>,
+    F::Sample: FromSample<f32>,
+{
+    type Frame = F;
+
+    #[inline]
+    fn next(&mut self) -> Self::Frame {
+        let frame = self.signal.next();
+        self.dither.frame(frame, self.bits)
+    }
+
+    #[inline]
+    fn is_exhausted(&self) -> bool {
+        self.signal.is_exhausted()
+    }
+}
+
+impl<T> SignalDither for T where T: Signal {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::dither::{DitherKind, NoiseShaping, dither_slice};
+    use crate::audio::signal;
+
+    #[test]
+    fn test_matches_slice() {
+        let input: [[f32; 2]; 32] = core::array::from_fn(|i| [i as f32 / 40.0, -(i as f32) / 80.0]);
+        let dither = Dither::<2>::new(DitherKind::Triangular, NoiseShaping::FirstOrder, 9);
+
+        let mut expected = [[0i16; 2]; 32];
+        dither_slice(&mut dither.clone(), &input, &mut expected);
+
+        let mut dithered = signal::from_iter(input.iter().copied()).dither::<[i16; 2], 2>(dither);
+        for frame in expected {
+            assert_eq!(dithered.next(), frame);
+        }
+    }
+}
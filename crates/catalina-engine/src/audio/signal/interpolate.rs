@@ -93,12 +93,28 @@ where
         self.set_playback_hz_scale(1.0 / scale);
     }
 
+    /// Resets the interpolator and the position between source frames.
+    ///
+    /// Call this after the source jumps, such as when restarting playback, so
+    /// frames from before the jump aren't blended into the new ones.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.interpolator.reset();
+        self.interpolation_value = 0.0;
+    }
+
     /// Borrow the `source_frames` Interpolator from the `Converter`.
     #[inline]
     pub fn source(&self) -> &S {
         &self.source
     }
 
+    /// Borrow the `Interpolator` from the `Converter`.
+    #[inline]
+    pub fn interpolator(&self) -> &I {
+        &self.interpolator
+    }
+
     /// Mutably borrow the `source_frames` Iterator from the `Converter`.
     #[inline]
     pub fn source_mut(&mut self) -> &mut S {
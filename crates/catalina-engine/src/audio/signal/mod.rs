@@ -43,6 +43,7 @@ mod ops;
 mod boxed;
 #[cfg(feature = "alloc")]
 pub mod bus;
+pub mod dither;
 pub mod envelope;
 pub mod rms;
 pub mod window;
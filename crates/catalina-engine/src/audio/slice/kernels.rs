@@ -0,0 +1,459 @@
+//! Block kernels for the hottest buffer operations.
+//!
+//! The generic slice functions work on any frame type, one frame at a time,
+//! which is flexible but leaves the compiler little to optimise. These kernels
+//! work on flat `f32` and `i16` buffers instead, like the CMSIS-DSP basic math
+//! functions, and are written so LLVM can vectorise them: fixed length inner
+//! loops over `chunks_exact`, no bounds checks in the loop body, and no
+//! branches other than min/max.
+//!
+//! On targets with SIMD, such as Helium on the Cortex-M55 or the packed
+//! 16-bit DSP instructions on the Cortex-M4 and M7, the `i16` kernels compile
+//! to saturating SIMD instructions. Everywhere else the same code runs as a
+//! plain scalar loop, so there's no separate fallback to keep in sync.
+//!
+//! The `i16` kernels treat samples as [`Q15`] and saturate rather than wrap.
+//!
+//! Kernels taking two buffers **panic** if their lengths differ.
+
+use crate::audio::sample::Q15;
+
+/// How many samples the kernels process per unrolled loop iteration.
+const LANES: usize = 8;
+
+/// Adds `src` onto `dst`.
+#[inline]
+pub fn mix_in_place_f32(dst: &mut [f32], src: &[f32]) {
+    assert_eq!(dst.len(), src.len());
+
+    let mut dst_chunks = dst.chunks_exact_mut(LANES);
+    let mut src_chunks = src.chunks_exact(LANES);
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        for i in 0..LANES {
+            d[i] += s[i];
+        }
+    }
+    for (d, s) in dst_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(src_chunks.remainder())
+    {
+        *d += s;
+    }
+}
+
+/// Adds `src` scaled by `gain` onto `dst`, such as for a send or a mixer channel.
+#[inline]
+pub fn mix_in_place_with_gain_f32(dst: &mut [f32], src: &[f32], gain: f32) {
+    assert_eq!(dst.len(), src.len());
+
+    let mut dst_chunks = dst.chunks_exact_mut(LANES);
+    let mut src_chunks = src.chunks_exact(LANES);
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        for i in 0..LANES {
+            d[i] += s[i] * gain;
+        }
+    }
+    for (d, s) in dst_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(src_chunks.remainder())
+    {
+        *d += s * gain;
+    }
+}
+
+/// Adds `src` onto `dst`, saturating.
+#[inline]
+pub fn mix_in_place_i16(dst: &mut [i16], src: &[i16]) {
+    assert_eq!(dst.len(), src.len());
+
+    let mut dst_chunks = dst.chunks_exact_mut(LANES);
+    let mut src_chunks = src.chunks_exact(LANES);
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        for i in 0..LANES {
+            d[i] = d[i].saturating_add(s[i]);
+        }
+    }
+    for (d, s) in dst_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(src_chunks.remainder())
+    {
+        *d = d.saturating_add(*s);
+    }
+}
+
+/// Multiplies every sample by a constant gain.
+#[inline]
+pub fn gain_in_place_f32(buffer: &mut [f32], gain: f32) {
+    let mut chunks = buffer.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for sample in chunk.iter_mut() {
+            *sample *= gain;
+        }
+    }
+    for sample in chunks.into_remainder() {
+        *sample *= gain;
+    }
+}
+
+/// Ramps the gain linearly from `start` to `end` across the buffer.
+///
+/// The first sample is scaled by `start`, and the ramp would reach `end` on
+/// the sample after the buffer, so consecutive blocks join without a step.
+#[inline]
+pub fn gain_ramp_in_place_f32(buffer: &mut [f32], start: f32, end: f32) {
+    if start == end {
+        gain_in_place_f32(buffer, start);
+        return;
+    }
+
+    let step = (end - start) / buffer.len() as f32;
+
+    // Computing each gain from its index rather than accumulating the step
+    // keeps the lanes independent and avoids rounding drift.
+    let mut chunks = buffer.chunks_exact_mut(LANES);
+    let mut base = 0;
+    for chunk in &mut chunks {
+        for (i, sample) in chunk.iter_mut().enumerate() {
+            *sample *= start + step * (base + i) as f32;
+        }
+        base += LANES;
+    }
+    for (i, sample) in chunks.into_remainder().iter_mut().enumerate() {
+        *sample *= start + step * (base + i) as f32;
+    }
+}
+
+/// Multiplies every sample by a constant fractional gain.
+#[inline]
+pub fn gain_in_place_i16(buffer: &mut [i16], gain: Q15) {
+    let gain = gain.to_bits() as i32;
+
+    let mut chunks = buffer.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for sample in chunk.iter_mut() {
+            *sample = mul_q15(*sample as i32, gain);
+        }
+    }
+    for sample in chunks.into_remainder() {
+        *sample = mul_q15(*sample as i32, gain);
+    }
+}
+
+/// Ramps a fractional gain linearly from `start` to `end` across the buffer.
+///
+/// Like [`gain_ramp_in_place_f32`], the ramp would reach `end` on the sample after the buffer.
+#[inline]
+pub fn gain_ramp_in_place_i16(buffer: &mut [i16], start: Q15, end: Q15) {
+    if buffer.is_empty() {
+        return;
+    }
+
+    // The gain is tracked with 16 extra fractional bits so slow ramps over
+    // long buffers still move.
+    let start = (start.to_bits() as i64) << 16;
+    let step = (((end.to_bits() as i64) << 16) - start) / buffer.len() as i64;
+
+    for (i, sample) in buffer.iter_mut().enumerate() {
+        let gain = ((start + step * i as i64) >> 16) as i32;
+        *sample = mul_q15(*sample as i32, gain);
+    }
+}
+
+/// Multiplies `dst` by `src` sample by sample, such as to apply an envelope or ring modulate.
+#[inline]
+pub fn multiply_in_place_f32(dst: &mut [f32], src: &[f32]) {
+    assert_eq!(dst.len(), src.len());
+
+    let mut dst_chunks = dst.chunks_exact_mut(LANES);
+    let mut src_chunks = src.chunks_exact(LANES);
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        for i in 0..LANES {
+            d[i] *= s[i];
+        }
+    }
+    for (d, s) in dst_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(src_chunks.remainder())
+    {
+        *d *= s;
+    }
+}
+
+/// Multiplies `dst` by `src` sample by sample as fractions, saturating.
+#[inline]
+pub fn multiply_in_place_i16(dst: &mut [i16], src: &[i16]) {
+    assert_eq!(dst.len(), src.len());
+
+    let mut dst_chunks = dst.chunks_exact_mut(LANES);
+    let mut src_chunks = src.chunks_exact(LANES);
+    for (d, s) in (&mut dst_chunks).zip(&mut src_chunks) {
+        for i in 0..LANES {
+            d[i] = mul_q15(d[i] as i32, s[i] as i32);
+        }
+    }
+    for (d, s) in dst_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(src_chunks.remainder())
+    {
+        *d = mul_q15(*d as i32, *s as i32);
+    }
+}
+
+/// Hard clips every sample into `min..=max`.
+#[inline]
+pub fn clip_in_place_f32(buffer: &mut [f32], min: f32, max: f32) {
+    let mut chunks = buffer.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for sample in chunk.iter_mut() {
+            *sample = sample.max(min).min(max);
+        }
+    }
+    for sample in chunks.into_remainder() {
+        *sample = sample.max(min).min(max);
+    }
+}
+
+/// Hard clips every sample into `min..=max`.
+#[inline]
+pub fn clip_in_place_i16(buffer: &mut [i16], min: i16, max: i16) {
+    let mut chunks = buffer.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        for sample in chunk.iter_mut() {
+            *sample = (*sample).max(min).min(max);
+        }
+    }
+    for sample in chunks.into_remainder() {
+        *sample = (*sample).max(min).min(max);
+    }
+}
+
+/// Interleaves `CHANNELS` separate channel buffers into one buffer of frames.
+///
+/// **Panics** if the channels differ in length, or `output` isn't `CHANNELS` times as long.
+#[inline]
+pub fn interleave<T, const CHANNELS: usize>(channels: [&[T]; CHANNELS], output: &mut [T])
+where
+    T: Copy,
+{
+    let frames = channels.first().map_or(0, |channel| channel.len());
+    assert!(channels.iter().all(|channel| channel.len() == frames));
+    assert_eq!(output.len(), frames * CHANNELS);
+
+    for (channel, samples) in channels.iter().enumerate() {
+        for (frame, sample) in output.chunks_exact_mut(CHANNELS).zip(samples.iter()) {
+            frame[channel] = *sample;
+        }
+    }
+}
+
+/// Splits a buffer of interleaved frames into `CHANNELS` separate channel buffers.
+///
+/// **Panics** if the channels differ in length, or `input` isn't `CHANNELS` times as long.
+#[inline]
+pub fn deinterleave<T, const CHANNELS: usize>(input: &[T], channels: [&mut [T]; CHANNELS])
+where
+    T: Copy,
+{
+    let frames = channels.first().map_or(0, |channel| channel.len());
+    assert!(channels.iter().all(|channel| channel.len() == frames));
+    assert_eq!(input.len(), frames * CHANNELS);
+
+    for (channel, samples) in channels.into_iter().enumerate() {
+        for (frame, sample) in input.chunks_exact(CHANNELS).zip(samples.iter_mut()) {
+            *sample = frame[channel];
+        }
+    }
+}
+
+/// Converts float samples to 16-bit, rounding to nearest and saturating.
+///
+/// Unlike [`FromSample`](crate::audio::sample::FromSample), which truncates,
+/// this rounds, so silence and small signals stay centered. Use
+/// [`dither`](crate::audio::dither) when the output is going to a DAC.
+#[inline]
+pub fn f32_to_i16(input: &[f32], output: &mut [i16]) {
+    assert_eq!(input.len(), output.len());
+
+    let mut out_chunks = output.chunks_exact_mut(LANES);
+    let mut in_chunks = input.chunks_exact(LANES);
+    for (o, i) in (&mut out_chunks).zip(&mut in_chunks) {
+        for lane in 0..LANES {
+            o[lane] = f32_to_q15(i[lane]);
+        }
+    }
+    for (o, i) in out_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(in_chunks.remainder())
+    {
+        *o = f32_to_q15(*i);
+    }
+}
+
+/// Converts 16-bit samples to floats in `-1.0..1.0`.
+#[inline]
+pub fn i16_to_f32(input: &[i16], output: &mut [f32]) {
+    assert_eq!(input.len(), output.len());
+
+    const SCALE: f32 = 1.0 / 32_768.0;
+
+    let mut out_chunks = output.chunks_exact_mut(LANES);
+    let mut in_chunks = input.chunks_exact(LANES);
+    for (o, i) in (&mut out_chunks).zip(&mut in_chunks) {
+        for lane in 0..LANES {
+            o[lane] = i[lane] as f32 * SCALE;
+        }
+    }
+    for (o, i) in out_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(in_chunks.remainder())
+    {
+        *o = *i as f32 * SCALE;
+    }
+}
+
+/// A Q15 multiply of two sign extended samples, saturating the result.
+#[inline(always)]
+fn mul_q15(a: i32, b: i32) -> i16 {
+    ((a * b) >> 15).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+#[inline(always)]
+fn f32_to_q15(sample: f32) -> i16 {
+    Q15::from_f32(sample).to_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Odd lengths so both the unrolled loop and the remainder run.
+    const LENGTH: usize = 19;
+
+    fn ramp() -> [f32; LENGTH] {
+        core::array::from_fn(|i| i as f32 / LENGTH as f32 - 0.5)
+    }
+
+    #[test]
+    fn test_mix_and_gain_f32() {
+        let mut dst = ramp();
+        mix_in_place_f32(&mut dst, &ramp());
+        gain_in_place_f32(&mut dst, 0.5);
+        assert_eq!(dst, ramp());
+
+        mix_in_place_with_gain_f32(&mut dst, &ramp(), -1.0);
+        assert_eq!(dst, [0.0; LENGTH]);
+
+        let mut dst = ramp();
+        multiply_in_place_f32(&mut dst, &[2.0; LENGTH]);
+        clip_in_place_f32(&mut dst, -0.5, 0.5);
+        for (x, y) in dst.iter().zip(ramp()) {
+            assert_eq!(*x, (y * 2.0).clamp(-0.5, 0.5));
+        }
+    }
+
+    #[test]
+    fn test_gain_ramp() {
+        let mut buffer = [1.0f32; 10];
+        gain_ramp_in_place_f32(&mut buffer, 0.0, 1.0);
+        for (i, sample) in buffer.iter().enumerate() {
+            assert!((sample - i as f32 / 10.0).abs() < 1e-6);
+        }
+
+        let mut buffer = [i16::MAX; 10];
+        gain_ramp_in_place_i16(&mut buffer, Q15::ZERO, Q15::HALF);
+        assert_eq!(buffer[0], 0);
+        for (i, sample) in buffer.iter().enumerate() {
+            let expected = i as f32 / 20.0 * 32_767.0;
+            assert!((*sample as f32 - expected).abs() <= 2.0, "{i} {sample}");
+        }
+    }
+
+    #[test]
+    fn test_i16_saturates() {
+        let mut dst = [30_000i16; LENGTH];
+        mix_in_place_i16(&mut dst, &[10_000; LENGTH]);
+        assert_eq!(dst, [i16::MAX; LENGTH]);
+
+        let mut dst = [i16::MIN; LENGTH];
+        multiply_in_place_i16(&mut dst, &[i16::MIN; LENGTH]);
+        assert_eq!(dst, [i16::MAX; LENGTH]);
+
+        let mut dst = [16_384i16; LENGTH];
+        gain_in_place_i16(&mut dst, Q15::HALF);
+        assert_eq!(dst, [8_192; LENGTH]);
+
+        clip_in_place_i16(&mut dst, -100, 100);
+        assert_eq!(dst, [100; LENGTH]);
+    }
+
+    #[test]
+    fn test_interleave() {
+        let left = [1, 2, 3];
+        let right = [4, 5, 6];
+        let mut frames = [0; 6];
+        interleave([&left[..], &right[..]], &mut frames);
+        assert_eq!(frames, [1, 4, 2, 5, 3, 6]);
+
+        let mut left = [0; 3];
+        let mut right = [0; 3];
+        deinterleave(&frames, [&mut left[..], &mut right[..]]);
+        assert_eq!(left, [1, 2, 3]);
+        assert_eq!(right, [4, 5, 6]);
+    }
+
+    #[test]
+    fn test_conversion() {
+        let input = [
+            0.0,
+            0.5,
+            -0.5,
+            1.0,
+            -1.0,
+            2.0,
+            0.4 / 32_768.0,
+            0.6 / 32_768.0,
+            -1.0e-6,
+            0.5 / 32_768.0,
+            -0.5 / 32_768.0,
+            -1.5 / 32_768.0,
+        ];
+        let mut output = [0i16; 12];
+        f32_to_i16(&input, &mut output);
+
+        // Halves round away from zero, like CMSIS-DSP.
+        assert_eq!(
+            output,
+            [
+                0,
+                16_384,
+                -16_384,
+                i16::MAX,
+                i16::MIN,
+                i16::MAX,
+                0,
+                1,
+                0,
+                1,
+                -1,
+                -2
+            ]
+        );
+
+        // Converting a single sample matches the block conversion.
+        for (sample, converted) in input.iter().zip(output) {
+            assert_eq!(Q15::from_f32(*sample).to_bits(), converted);
+        }
+
+        let mut back = [0.0f32; 12];
+        i16_to_f32(&output, &mut back);
+        assert_eq!(back[1], 0.5);
+        assert_eq!(back[4], -1.0);
+    }
+}
@@ -27,6 +27,14 @@ pub mod boxed;
 
 mod frame;
 
+pub mod kernels;
+pub use kernels::{
+    clip_in_place_f32, clip_in_place_i16, deinterleave, f32_to_i16, gain_in_place_f32,
+    gain_in_place_i16, gain_ramp_in_place_f32, gain_ramp_in_place_i16, i16_to_f32, interleave,
+    mix_in_place_f32, mix_in_place_i16, mix_in_place_with_gain_f32, multiply_in_place_f32,
+    multiply_in_place_i16,
+};
+
 // Slice Conversion Traits
 // ----------------------------------------------------------------------------
 
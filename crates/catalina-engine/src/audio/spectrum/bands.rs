@@ -0,0 +1,235 @@
+//! Maps the linearly spaced bins of an FFT onto perceptual bands.
+//!
+//! FFT bins are evenly spaced in hertz, which packs most of them into the top
+//! few octaves where the ear is least discerning. A [`BandMap`] folds them
+//! onto bands spaced on the mel scale, for analysis, or logarithmically, for
+//! spectrum displays where each octave should take the same width.
+
+use crate::{
+    core::Hertz,
+    music::helpers::{inv_mel, mel},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How the band centers are spaced between the minimum and maximum frequency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum BandScale {
+    /// Evenly spaced on the mel scale.
+    Mel,
+    /// Evenly spaced in octaves.
+    Log,
+}
+
+impl BandScale {
+    fn forward(self, frequency: f32) -> f32 {
+        match self {
+            BandScale::Mel => mel(frequency),
+            BandScale::Log => libm::log2f(frequency),
+        }
+    }
+
+    fn inverse(self, value: f32) -> f32 {
+        match self {
+            BandScale::Mel => inv_mel(value),
+            BandScale::Log => libm::exp2f(value),
+        }
+    }
+}
+
+/// Folds FFT magnitudes onto `BANDS` overlapping triangular bands.
+///
+/// Each band rises from the center of the band below it to its own center,
+/// then falls to the center of the band above it, the same shape used for a
+/// mel filter bank. The output of each band is the weighted average of the
+/// bins under it, so a flat spectrum stays flat regardless of band width.
+#[derive(Debug, Clone)]
+pub struct BandMap<const BANDS: usize> {
+    scale: BandScale,
+    /// Hertz per FFT bin.
+    bin_width: f32,
+    /// Band centers, as fractional FFT bins.
+    centers: [f32; BANDS],
+    /// Lower edge of the first band, as a fractional FFT bin.
+    lower: f32,
+    /// Upper edge of the last band, as a fractional FFT bin.
+    upper: f32,
+}
+
+impl<const BANDS: usize> BandMap<BANDS> {
+    /// Builds a map for the bins of an `fft_size` point transform, with
+    /// bands covering `min` to `max`.
+    ///
+    /// **Panics** if there are no bands, or if the range is empty or
+    /// starts at 0Hz when using [`BandScale::Log`].
+    pub fn new(
+        scale: BandScale,
+        sample_rate: f32,
+        fft_size: usize,
+        min: Hertz,
+        max: Hertz,
+    ) -> Self {
+        assert!(BANDS > 0, "a band map needs at least one band");
+        assert!(min.hertz() < max.hertz(), "band range must not be empty");
+        assert!(
+            scale != BandScale::Log || min.hertz() > 0.0,
+            "log bands can't start at 0Hz"
+        );
+
+        let bin_width = sample_rate / fft_size as f32;
+        let low = scale.forward(min.hertz());
+        let high = scale.forward(max.hertz().min(sample_rate / 2.0));
+        // BANDS centers plus the two outer edges, evenly spaced.
+        let step = (high - low) / (BANDS + 1) as f32;
+        let edge = |i: usize| scale.inverse(low + step * i as f32) / bin_width;
+
+        let mut centers = [0.0; BANDS];
+        for (i, center) in centers.iter_mut().enumerate() {
+            *center = edge(i + 1);
+        }
+
+        Self {
+            scale,
+            bin_width,
+            centers,
+            lower: edge(0),
+            upper: edge(BANDS + 1),
+        }
+    }
+
+    /// Returns how the bands are spaced.
+    pub fn scale(&self) -> BandScale {
+        self.scale
+    }
+
+    /// Returns the number of bands.
+    pub const fn len(&self) -> usize {
+        BANDS
+    }
+
+    /// Returns `false`, a map always has at least one band.
+    pub const fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the center frequency of a band.
+    pub fn center(&self, band: usize) -> Hertz {
+        Hertz(self.centers[band] * self.bin_width)
+    }
+
+    /// Folds the magnitude of each FFT bin onto the bands.
+    ///
+    /// The magnitudes can be linear or in decibels, see
+    /// [`magnitudes`](super::magnitudes) and [`magnitudes_db`](super::magnitudes_db).
+    pub fn map(&self, magnitudes: &[f32], output: &mut [f32; BANDS]) {
+        for (band, value) in output.iter_mut().enumerate() {
+            let lower = if band == 0 {
+                self.lower
+            } else {
+                self.centers[band - 1]
+            };
+            let center = self.centers[band];
+            let upper = if band + 1 == BANDS {
+                self.upper
+            } else {
+                self.centers[band + 1]
+            };
+
+            let first = libm::ceilf(lower) as usize;
+            let last = (libm::floorf(upper) as usize).min(magnitudes.len().saturating_sub(1));
+
+            let mut sum = 0.0;
+            let mut weights = 0.0;
+            for (k, magnitude) in magnitudes.iter().enumerate().take(last + 1).skip(first) {
+                let k = k as f32;
+                let weight = if k <= center {
+                    (k - lower) / (center - lower)
+                } else {
+                    (upper - k) / (upper - center)
+                };
+
+                if weight > 0.0 {
+                    sum += weight * magnitude;
+                    weights += weight;
+                }
+            }
+
+            *value = if weights > 0.0 {
+                sum / weights
+            } else {
+                // Narrow low bands can fall between two bins, so interpolate instead.
+                Self::interpolate(magnitudes, center)
+            };
+        }
+    }
+
+    fn interpolate(magnitudes: &[f32], position: f32) -> f32 {
+        if magnitudes.is_empty() {
+            return 0.0;
+        }
+
+        let last = magnitudes.len() - 1;
+        let index = (libm::floorf(position) as usize).min(last);
+        let next = (index + 1).min(last);
+        let fraction = position - index as f32;
+
+        magnitudes[index] + (magnitudes[next] - magnitudes[index]) * fraction.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_centers_are_spaced() {
+        let bands = BandMap::<8>::new(BandScale::Log, 48_000.0, 1024, Hertz(50.0), Hertz(12_800.0));
+
+        // 8 octaves split into 9 steps.
+        let ratio = bands.center(1).hertz() / bands.center(0).hertz();
+        assert_float_eq!(ratio, libm::exp2f(8.0 / 9.0), rmax <= 1e-4);
+
+        let bands = BandMap::<4>::new(BandScale::Mel, 16_000.0, 512, Hertz(0.0), Hertz(8000.0));
+        let step = mel(bands.center(1).hertz()) - mel(bands.center(0).hertz());
+        let next = mel(bands.center(2).hertz()) - mel(bands.center(1).hertz());
+        assert_float_eq!(step, next, rmax <= 1e-3);
+    }
+
+    #[test]
+    fn test_flat_spectrum_stays_flat() {
+        let bands = BandMap::<16>::new(BandScale::Mel, 48_000.0, 256, Hertz(20.0), Hertz(20_000.0));
+
+        let magnitudes = [2.0; 129];
+        let mut output = [0.0; 16];
+        bands.map(&magnitudes, &mut output);
+
+        for value in output {
+            assert_float_eq!(value, 2.0, abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_peak_lands_in_band() {
+        let bands =
+            BandMap::<10>::new(BandScale::Log, 48_000.0, 1024, Hertz(50.0), Hertz(20_000.0));
+
+        let mut magnitudes = [0.0; 513];
+        // A peak at the center of band 6.
+        let bin = libm::roundf(bands.center(6).hertz() / (48_000.0 / 1024.0)) as usize;
+        magnitudes[bin] = 1.0;
+
+        let mut output = [0.0; 10];
+        bands.map(&magnitudes, &mut output);
+
+        let loudest = output
+            .iter()
+            .enumerate()
+            .fold(0, |best, (i, v)| if *v > output[best] { i } else { best });
+        assert_eq!(loudest, 6);
+    }
+}
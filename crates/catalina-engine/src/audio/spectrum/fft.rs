@@ -0,0 +1,280 @@
+//! An in-place radix-2 FFT for real signals.
+//!
+//! A real signal of `N` samples is treated as `N / 2` complex values, with
+//! the even samples as the real parts and the odd samples as the imaginary
+//! parts. That half-size complex transform is run in-place and then split
+//! back apart into the spectrum of the real signal, halving both the work
+//! and the memory of a full complex transform.
+
+use super::Complex;
+
+/// A real-valued FFT of `N` samples.
+///
+/// `N` must be a power of two and at least 4. The struct only holds the
+/// twiddle factors, so a single instance can be shared between any number
+/// of buffers of the same size.
+#[derive(Debug, Clone)]
+pub struct RealFft<const N: usize> {
+    /// `exp(-2 PI i k / N)` for `k` in `0..N / 2`, interleaved as `re, im` pairs.
+    twiddles: [f32; N],
+}
+
+impl<const N: usize> RealFft<N> {
+    /// Precomputes the twiddle factors for the transform size.
+    ///
+    /// **Panics** if `N` is not a power of two of at least 4.
+    pub fn new() -> Self {
+        assert!(
+            N >= 4 && N.is_power_of_two(),
+            "FFT size must be a power of two of at least 4"
+        );
+
+        let mut twiddles = [0.0; N];
+        for k in 0..N / 2 {
+            // Calculated in f64 so the larger tables don't accumulate error.
+            let angle = 2.0 * core::f64::consts::PI * k as f64 / N as f64;
+            twiddles[2 * k] = libm::cos(angle) as f32;
+            twiddles[2 * k + 1] = -libm::sin(angle) as f32;
+        }
+
+        Self { twiddles }
+    }
+
+    /// Returns the number of samples the transform operates on.
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns `false`, a transform always has samples.
+    pub const fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Transforms `N` real samples into a packed spectrum in-place.
+    ///
+    /// The output is unnormalized, a full scale sine lands in its bin with a
+    /// magnitude of `N / 2`. See the [module](super) docs for the layout.
+    pub fn forward(&self, buffer: &mut [f32; N]) {
+        let half = N / 2;
+
+        self.complex_fft(buffer);
+
+        // Bins 0 and N / 2 are purely real, and are packed into the first slot.
+        let (re, im) = (buffer[0], buffer[1]);
+        buffer[0] = re + im;
+        buffer[1] = re - im;
+
+        // Each remaining pair of mirrored bins is unpacked together so the
+        // transform can stay in-place.
+        for k in 1..=half / 2 {
+            let mirror = half - k;
+
+            let a = self.load(buffer, k);
+            let b = self.load(buffer, mirror).conj();
+
+            let even = (a + b).scale(0.5);
+            let odd = Self::rotate_neg_i(a - b).scale(0.5) * self.twiddle(k);
+
+            self.store(buffer, k, even + odd);
+            if mirror != k {
+                self.store(buffer, mirror, (even - odd).conj());
+            }
+        }
+    }
+
+    /// Transforms a packed spectrum back into `N` real samples in-place.
+    ///
+    /// The output is normalized so `inverse` exactly undoes [`RealFft::forward`].
+    pub fn inverse(&self, buffer: &mut [f32; N]) {
+        let half = N / 2;
+
+        let (dc, nyquist) = (buffer[0], buffer[1]);
+        buffer[0] = 0.5 * (dc + nyquist);
+        buffer[1] = 0.5 * (dc - nyquist);
+
+        for k in 1..=half / 2 {
+            let mirror = half - k;
+
+            let a = self.load(buffer, k);
+            let b = self.load(buffer, mirror).conj();
+
+            let even = (a + b).scale(0.5);
+            let odd = (a - b).scale(0.5) * self.twiddle(k).conj();
+
+            // Multiplying by i re-packs the odd samples as imaginary parts.
+            let odd = Self::rotate_i(odd);
+
+            self.store(buffer, k, even + odd);
+            if mirror != k {
+                self.store(buffer, mirror, (even - odd).conj());
+            }
+        }
+
+        // The inverse of a complex FFT is the forward transform of the conjugate.
+        for im in buffer.iter_mut().skip(1).step_by(2) {
+            *im = -*im;
+        }
+        self.complex_fft(buffer);
+
+        let scale = 1.0 / half as f32;
+        for (i, sample) in buffer.iter_mut().enumerate() {
+            *sample *= if i % 2 == 0 { scale } else { -scale };
+        }
+    }
+
+    /// Runs an in-place forward complex FFT over the `N / 2` interleaved values.
+    fn complex_fft(&self, buffer: &mut [f32; N]) {
+        let half = N / 2;
+
+        // Reorder into bit-reversed index order.
+        let bits = half.trailing_zeros();
+        for i in 0..half {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if j > i {
+                buffer.swap(2 * i, 2 * j);
+                buffer.swap(2 * i + 1, 2 * j + 1);
+            }
+        }
+
+        // Iterative butterflies, doubling the sub-transform size each pass.
+        let mut size = 2;
+        while size <= half {
+            let span = size / 2;
+            // The twiddle table is for N points, so sub-transforms step through it.
+            let stride = N / size;
+
+            for start in (0..half).step_by(size) {
+                for j in 0..span {
+                    let top = start + j;
+                    let bottom = top + span;
+
+                    let u = self.load(buffer, top);
+                    let v = self.load(buffer, bottom) * self.twiddle(j * stride);
+
+                    self.store(buffer, top, u + v);
+                    self.store(buffer, bottom, u - v);
+                }
+            }
+
+            size *= 2;
+        }
+    }
+
+    #[inline(always)]
+    fn twiddle(&self, k: usize) -> Complex {
+        Complex::new(self.twiddles[2 * k], self.twiddles[2 * k + 1])
+    }
+
+    #[inline(always)]
+    fn load(&self, buffer: &[f32; N], index: usize) -> Complex {
+        Complex::new(buffer[2 * index], buffer[2 * index + 1])
+    }
+
+    #[inline(always)]
+    fn store(&self, buffer: &mut [f32; N], index: usize, value: Complex) {
+        buffer[2 * index] = value.re;
+        buffer[2 * index + 1] = value.im;
+    }
+
+    /// Multiplies by `-i`.
+    #[inline(always)]
+    fn rotate_neg_i(value: Complex) -> Complex {
+        Complex::new(value.im, -value.re)
+    }
+
+    /// Multiplies by `i`.
+    #[inline(always)]
+    fn rotate_i(value: Complex) -> Complex {
+        Complex::new(-value.im, value.re)
+    }
+}
+
+impl<const N: usize> Default for RealFft<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::spectrum::bin;
+    use crate::prelude::*;
+
+    use float_eq::assert_float_eq;
+
+    /// Direct O(n^2) DFT to check the fast transform against.
+    fn dft(input: &[f32], k: usize) -> Complex {
+        let n = input.len();
+        let mut sum = Complex::ZERO;
+        for (i, x) in input.iter().enumerate() {
+            let angle = -2.0 * core::f64::consts::PI * (k * i) as f64 / n as f64;
+            sum = sum
+                + Complex::new(
+                    (*x as f64 * libm::cos(angle)) as f32,
+                    (*x as f64 * libm::sin(angle)) as f32,
+                );
+        }
+        sum
+    }
+
+    fn test_signal<const N: usize>() -> [f32; N] {
+        let mut signal = [0.0; N];
+        for (i, x) in signal.iter_mut().enumerate() {
+            let t = i as f32 / N as f32;
+            *x = 0.3 + libm::sinf(2.0 * PI * 3.0 * t) + 0.5 * libm::cosf(2.0 * PI * 7.0 * t)
+                - 0.25 * ((i % 5) as f32);
+        }
+        signal
+    }
+
+    fn check_against_dft<const N: usize>() {
+        let fft = RealFft::<N>::new();
+        let input = test_signal::<N>();
+        let mut buffer = input;
+        fft.forward(&mut buffer);
+
+        for k in 0..=N / 2 {
+            let expected = dft(&input, k);
+            let found = bin(&buffer, k);
+            assert_float_eq!(found.re, expected.re, abs <= 1e-3, "bin {} re", k);
+            assert_float_eq!(found.im, expected.im, abs <= 1e-3, "bin {} im", k);
+        }
+    }
+
+    #[test]
+    fn test_forward_matches_dft() {
+        check_against_dft::<4>();
+        check_against_dft::<8>();
+        check_against_dft::<64>();
+        check_against_dft::<256>();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fft = RealFft::<128>::new();
+        let input = test_signal::<128>();
+        let mut buffer = input;
+
+        fft.forward(&mut buffer);
+        fft.inverse(&mut buffer);
+
+        for (found, expected) in buffer.iter().zip(input.iter()) {
+            assert_float_eq!(*found, *expected, abs <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_sine_bin() {
+        const N: usize = 64;
+        let fft = RealFft::<N>::new();
+        let mut buffer = [0.0; N];
+        for (i, x) in buffer.iter_mut().enumerate() {
+            *x = libm::sinf(2.0 * PI * 5.0 * i as f32 / N as f32);
+        }
+        fft.forward(&mut buffer);
+
+        assert_float_eq!(bin(&buffer, 5).norm(), N as f32 / 2.0, abs <= 1e-3);
+        assert_float_eq!(bin(&buffer, 4).norm(), 0.0, abs <= 1e-3);
+    }
+}
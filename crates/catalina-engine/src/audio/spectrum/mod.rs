@@ -0,0 +1,206 @@
+//! Spectral analysis built around a `no_std` real FFT.
+//!
+//! [`RealFft`] transforms a block of `N` real samples in-place into a packed
+//! spectrum of `N / 2 + 1` bins. The helpers in this module read magnitudes
+//! and phases out of that packed layout, [`Stft`] runs the transform over
+//! the windowed chunks yielded by a [`Windower`](crate::audio::signal::window::Windower),
+//! and [`BandMap`] folds the linear FFT bins onto mel or logarithmic bands for
+//! spectrum displays and analysis.
+//!
+//! ## Packed layout
+//!
+//! The spectrum uses the same layout as CMSIS-DSP's `arm_rfft_fast_f32`, so
+//! buffers can be handed to either implementation:
+//!
+//! | index             | value                   |
+//! |-------------------|-------------------------|
+//! | `0`               | DC bin (real)           |
+//! | `1`               | Nyquist bin (real)      |
+//! | `2k`, `2k + 1`    | bin `k` real, imaginary |
+
+use crate::{core::Hertz, prelude::*};
+
+use core::ops::{Mul, Neg};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub mod bands;
+pub use bands::{BandMap, BandScale};
+
+pub mod fft;
+pub use fft::RealFft;
+
+pub mod stft;
+pub use stft::Stft;
+
+/// A complex number used for FFT bins.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+
+    pub const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    /// Returns the complex conjugate.
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    /// Returns the magnitude, or absolute value.
+    pub fn norm(self) -> f32 {
+        libm::hypotf(self.re, self.im)
+    }
+
+    /// Returns the squared magnitude, avoiding the square root.
+    pub fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// Returns the phase angle in radians, in the range `-PI..=PI`.
+    pub fn arg(self) -> f32 {
+        libm::atan2f(self.im, self.re)
+    }
+
+    /// Scales both parts by a real factor.
+    pub fn scale(self, factor: f32) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Self::Output {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+/// Returns the number of bins in the spectrum of an `N` point transform.
+pub const fn bin_count(fft_size: usize) -> usize {
+    fft_size / 2 + 1
+}
+
+/// Returns the center frequency of bin `k` of an `fft_size` point transform.
+pub fn bin_frequency(k: usize, sample_rate: f32, fft_size: usize) -> Hertz {
+    Hertz(k as f32 * sample_rate / fft_size as f32)
+}
+
+/// Reads bin `k` out of a packed spectrum.
+///
+/// **Panics** if `k` is above the Nyquist bin.
+pub fn bin(spectrum: &[f32], k: usize) -> Complex {
+    let half = spectrum.len() / 2;
+    assert!(k <= half);
+
+    match k {
+        0 => Complex::new(spectrum[0], 0.0),
+        k if k == half => Complex::new(spectrum[1], 0.0),
+        k => Complex::new(spectrum[2 * k], spectrum[2 * k + 1]),
+    }
+}
+
+/// Writes the magnitude of each bin of a packed spectrum to `output`.
+///
+/// **Panics** if `output` is not [`bin_count`] long.
+pub fn magnitudes(spectrum: &[f32], output: &mut [f32]) {
+    assert_eq!(output.len(), bin_count(spectrum.len()));
+
+    for (k, magnitude) in output.iter_mut().enumerate() {
+        *magnitude = bin(spectrum, k).norm();
+    }
+}
+
+/// Writes the magnitude of each bin of a packed spectrum to `output` in decibels,
+/// relative to a full scale sine wave of an `N` point transform.
+///
+/// Magnitudes are floored at -120dB so silence doesn't produce infinities.
+///
+/// **Panics** if `output` is not [`bin_count`] long.
+pub fn magnitudes_db(spectrum: &[f32], output: &mut [f32]) {
+    assert_eq!(output.len(), bin_count(spectrum.len()));
+
+    // A full scale sine concentrates N / 2 into its bin.
+    let reference = 2.0 / spectrum.len() as f32;
+
+    for (k, db) in output.iter_mut().enumerate() {
+        let magnitude = bin(spectrum, k).norm() * reference;
+        *db = 20.0 * libm::log10f(magnitude.max(1e-6));
+    }
+}
+
+/// Writes the phase of each bin of a packed spectrum to `output` in radians.
+///
+/// **Panics** if `output` is not [`bin_count`] long.
+pub fn phases(spectrum: &[f32], output: &mut [f32]) {
+    assert_eq!(output.len(), bin_count(spectrum.len()));
+
+    for (k, phase) in output.iter_mut().enumerate() {
+        *phase = bin(spectrum, k).arg();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_complex_ops() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+
+        assert_eq!(a + b, Complex::new(4.0, 1.0));
+        assert_eq!(a - b, Complex::new(-2.0, 3.0));
+        assert_eq!(a * b, Complex::new(5.0, 5.0));
+        assert_eq!(a.conj(), Complex::new(1.0, -2.0));
+        assert_float_eq!(Complex::new(3.0, 4.0).norm(), 5.0, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_packed_bins() {
+        let spectrum = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+
+        assert_eq!(bin(&spectrum, 0), Complex::new(1.0, 0.0));
+        assert_eq!(bin(&spectrum, 1), Complex::new(3.0, 4.0));
+        assert_eq!(bin(&spectrum, 3), Complex::new(7.0, 8.0));
+        assert_eq!(bin(&spectrum, 4), Complex::new(2.0, 0.0));
+        assert_eq!(bin_frequency(4, 48_000.0, 8), Hertz(24_000.0));
+    }
+}
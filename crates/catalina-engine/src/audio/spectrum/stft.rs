@@ -0,0 +1,113 @@
+//! Short-time Fourier transform over windowed chunks of a signal.
+
+use super::RealFft;
+use crate::audio::{
+    signal::window::Windower,
+    window::{Hann, Window as WindowType},
+};
+
+/// Yields the packed spectrum of each windowed chunk of a slice of samples.
+///
+/// The framing is done by a [`Windower`], so the chunk size is the
+/// transform size `N` and consecutive spectra start `hop` samples apart.
+/// Spectra are in the packed layout described in the [module](super) docs,
+/// use [`magnitudes`](super::magnitudes) and friends to read them.
+pub struct Stft<'a, 'f, const N: usize, W = Hann>
+where
+    W: WindowType<f64, Output = f64>,
+{
+    fft: &'f RealFft<N>,
+    windower: Windower<'a, f32, W>,
+}
+
+impl<'a, 'f, const N: usize> Stft<'a, 'f, N, Hann> {
+    /// Builds a short-time transform that uses a Hann window.
+    pub fn hann(fft: &'f RealFft<N>, samples: &'a [f32], hop: usize) -> Self {
+        Self::new(fft, Windower::hann(samples, N, hop))
+    }
+}
+
+impl<'a, 'f, const N: usize, W> Stft<'a, 'f, N, W>
+where
+    W: WindowType<f64, Output = f64>,
+{
+    /// Builds a short-time transform from an existing windower.
+    ///
+    /// **Panics** if the windower's bin size is not the transform size.
+    pub fn new(fft: &'f RealFft<N>, windower: Windower<'a, f32, W>) -> Self {
+        assert_eq!(windower.bin, N, "window size must match the FFT size");
+
+        Self { fft, windower }
+    }
+
+    /// Returns the number of samples between the start of each spectrum.
+    pub fn hop(&self) -> usize {
+        self.windower.hop
+    }
+}
+
+impl<'a, 'f, const N: usize, W> Iterator for Stft<'a, 'f, N, W>
+where
+    W: WindowType<f64, Output = f64>,
+{
+    type Item = [f32; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let windowed = self.windower.next()?;
+
+        let mut buffer = [0.0; N];
+        for (sample, windowed) in buffer.iter_mut().zip(windowed) {
+            *sample = windowed;
+        }
+
+        self.fft.forward(&mut buffer);
+
+        Some(buffer)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.windower.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::spectrum::{bin, bin_count, magnitudes};
+    use crate::prelude::*;
+
+    #[test]
+    fn test_stft_tracks_frequency_change() {
+        const N: usize = 64;
+        let fft = RealFft::<N>::new();
+
+        // A tone at bin 4 for the first half, then bin 12.
+        let mut samples = [0.0f32; 4 * N];
+        for (i, x) in samples.iter_mut().enumerate() {
+            let cycles = if i < 2 * N { 4.0 } else { 12.0 };
+            *x = libm::sinf(2.0 * PI * cycles * i as f32 / N as f32);
+        }
+
+        let mut peaks = [0usize; 16];
+        let mut count = 0;
+        for spectrum in Stft::hann(&fft, &samples, N / 2) {
+            let mut mags = [0.0; bin_count(N)];
+            magnitudes(&spectrum, &mut mags);
+
+            let (peak, _) =
+                mags.iter().enumerate().fold(
+                    (0, 0.0),
+                    |best, (k, m)| {
+                        if *m > best.1 { (k, *m) } else { best }
+                    },
+                );
+            peaks[count] = peak;
+            count += 1;
+        }
+
+        assert_eq!(count, 7);
+        assert_eq!(peaks[0], 4);
+        assert_eq!(peaks[count - 1], 12);
+        assert!(bin(&Stft::hann(&fft, &samples, N).next().unwrap(), 0).norm() < 1.0);
+    }
+}
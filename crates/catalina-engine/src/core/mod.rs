@@ -10,6 +10,8 @@ use float_eq::float_eq;
 use serde::{Deserialize, Serialize};
 
 pub mod ring_buffer;
+pub mod rng;
+pub mod spsc;
 
 /// Frequency in hertz, wraps an f32 with sufficiant 0.0001 precision for musical use.
 ///
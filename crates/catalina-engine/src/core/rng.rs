@@ -0,0 +1,106 @@
+//! A small seedable random number generator for DSP.
+//!
+//! Audio code needs plenty of cheap randomness for dither, noise sources and
+//! humanization, but not cryptographic quality. [`Rng`] is a PCG32 generator,
+//! which is fast on 32-bit parts, works without `std`, and always produces
+//! the same sequence for the same seed so tests and renders are repeatable.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+/// A PCG32 pseudo-random number generator.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Builds a generator from a seed, equal seeds give equal sequences.
+    pub const fn new(seed: u64) -> Self {
+        // Seeding as in the reference implementation, so that
+        // similar seeds don't produce similar sequences.
+        let state = INCREMENT.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let state = state.wrapping_add(seed);
+        Self {
+            state: state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT),
+        }
+    }
+
+    /// Returns the next random `u32`.
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Returns a random value in `0.0..1.0`.
+    #[inline]
+    pub fn next_f32(&mut self) -> f32 {
+        // The top 24 bits fill the mantissa of an f32 exactly.
+        (self.next_u32() >> 8) as f32 * (1.0 / 16_777_216.0)
+    }
+
+    /// Returns a random value in `-1.0..1.0`.
+    #[inline]
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_f32() * 2.0 - 1.0
+    }
+
+    /// Returns a random value in `0..bound`, or 0 when `bound` is 0.
+    #[inline]
+    pub fn next_below(&mut self, bound: u32) -> u32 {
+        ((self.next_u32() as u64 * bound as u64) >> 32) as u32
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+
+        let mut differs = false;
+        for _ in 0..100 {
+            let x = a.next_u32();
+            assert_eq!(x, b.next_u32());
+            differs |= x != c.next_u32();
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Rng::new(7);
+        let mut sum = 0.0;
+        for _ in 0..10_000 {
+            let x = rng.next_f32();
+            assert!((0.0..1.0).contains(&x));
+            sum += x;
+
+            let y = rng.next_bipolar();
+            assert!((-1.0..1.0).contains(&y));
+
+            assert!(rng.next_below(10) < 10);
+        }
+        assert!((sum / 10_000.0 - 0.5).abs() < 0.01);
+        assert_eq!(rng.next_below(0), 0);
+    }
+}
@@ -0,0 +1,453 @@
+//! A lock-free single-producer, single-consumer ring buffer.
+//!
+//! The [`ring_buffer`](super::ring_buffer) types need `&mut` access, so they
+//! can't be shared between contexts. [`Spsc`] can: after splitting it, the
+//! [`Producer`] and [`Consumer`] halves can live in different contexts, such
+//! as an I2S DMA interrupt and the main loop, or a UI thread and an audio
+//! callback, and push and pop concurrently without locks or allocation.
+//!
+//! Only atomic loads and stores are used, never compare-and-swap, so the
+//! buffer works on cores like the Cortex-M0+. On targets without atomics at
+//! all, enable the `critical-section` feature to implement them with
+//! critical sections instead.
+//!
+//! # Example
+//!
+//! ```
+//! use catalina_engine::core::spsc::Spsc;
+//!
+//! let mut ring = Spsc::<f32, 64>::new();
+//! let (mut producer, mut consumer) = ring.split();
+//!
+//! // In the interrupt.
+//! assert_eq!(producer.write(&[0.1, 0.2, 0.3]), 3);
+//!
+//! // In the main loop.
+//! let mut block = [0.0; 4];
+//! assert_eq!(consumer.read(&mut block), 3);
+//! assert_eq!(block[..3], [0.1, 0.2, 0.3]);
+//! ```
+//!
+//! To share the halves with an interrupt handler, the ring buffer needs to
+//! live for `'static`, such as in a `static` cell that hands out a `&'static mut`.
+
+use core::mem::MaybeUninit;
+
+#[cfg(loom)]
+use loom::cell::UnsafeCell;
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(all(not(loom), feature = "critical-section"))]
+use portable_atomic::{AtomicUsize, Ordering};
+
+#[cfg(all(not(loom), not(feature = "critical-section")))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(loom))]
+use cell::UnsafeCell;
+
+/// An `UnsafeCell` with the same interface as loom's, so the buffer can be model checked.
+#[cfg(not(loom))]
+mod cell {
+    #[derive(Debug)]
+    pub(super) struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        pub(super) const fn new(value: T) -> Self {
+            Self(core::cell::UnsafeCell::new(value))
+        }
+
+        #[inline(always)]
+        pub(super) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+            f(self.0.get())
+        }
+
+        #[inline(always)]
+        pub(super) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            f(self.0.get())
+        }
+    }
+}
+
+/// A fixed-capacity ring buffer holding up to `N` items, shared between one
+/// producer and one consumer.
+///
+/// The read and write positions count up to `2 * N` before wrapping, which
+/// tells a full buffer apart from an empty one without wasting a slot.
+pub struct Spsc<T, const N: usize> {
+    buffer: [UnsafeCell<MaybeUninit<T>>; N],
+    /// The position of the next item to read, only written by the consumer.
+    head: AtomicUsize,
+    /// The position of the next item to write, only written by the producer.
+    tail: AtomicUsize,
+}
+
+// SAFETY: the producer only writes slots the consumer isn't reading and the
+// other way around, ordered through the acquire and release of the positions.
+unsafe impl<T: Send, const N: usize> Sync for Spsc<T, N> {}
+
+impl<T, const N: usize> Spsc<T, N>
+where
+    T: Copy,
+{
+    /// Builds an empty ring buffer, usable in a `static`.
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        core::assert!(N > 0 && N <= usize::MAX / 2);
+
+        Self {
+            buffer: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Builds an empty ring buffer.
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        Self {
+            buffer: core::array::from_fn(|_| UnsafeCell::new(MaybeUninit::uninit())),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the most items the buffer can hold.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns how many items are waiting to be read.
+    #[inline]
+    pub fn len(&self) -> usize {
+        distance::<N>(
+            self.head.load(Ordering::Acquire),
+            self.tail.load(Ordering::Acquire),
+        )
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits the buffer into its producer and consumer halves.
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        (Producer { ring: self }, Consumer { ring: self })
+    }
+}
+
+impl<T, const N: usize> Default for Spsc<T, N>
+where
+    T: Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns how many items are between the read and write positions.
+#[inline(always)]
+const fn distance<const N: usize>(head: usize, tail: usize) -> usize {
+    if tail >= head {
+        tail - head
+    } else {
+        tail + 2 * N - head
+    }
+}
+
+/// Moves a position forward, wrapping at `2 * N`.
+#[inline(always)]
+const fn advance<const N: usize>(position: usize, count: usize) -> usize {
+    let position = position + count;
+    if position >= 2 * N {
+        position - 2 * N
+    } else {
+        position
+    }
+}
+
+/// The writing half of a [`Spsc`] ring buffer.
+pub struct Producer<'a, T, const N: usize> {
+    ring: &'a Spsc<T, N>,
+}
+
+// SAFETY: there's only ever one producer, which can be moved to another context.
+unsafe impl<T: Send, const N: usize> Send for Producer<'_, T, N> {}
+
+impl<T, const N: usize> Producer<'_, T, N>
+where
+    T: Copy,
+{
+    /// Returns how many items can be written before the buffer is full.
+    #[inline]
+    pub fn free(&self) -> usize {
+        let head = self.ring.head.load(Ordering::Acquire);
+        let tail = self.ring.tail.load(Ordering::Relaxed);
+
+        N - distance::<N>(head, tail)
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.free() == 0
+    }
+
+    /// Writes an item, returning it back if the buffer is full.
+    #[inline]
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        match self.write(&[item]) {
+            1 => Ok(()),
+            _ => Err(item),
+        }
+    }
+
+    /// Writes as many items as fit, returning how many were written.
+    pub fn write(&mut self, items: &[T]) -> usize {
+        let tail = self.ring.tail.load(Ordering::Relaxed);
+        let count = items.len().min(self.free());
+
+        let mut position = tail;
+        for item in &items[..count] {
+            self.ring.buffer[position % N].with_mut(|slot| {
+                // SAFETY: the slot is free, the consumer won't read it until
+                // the tail is published below.
+                unsafe { (*slot).write(*item) };
+            });
+            position = advance::<N>(position, 1);
+        }
+
+        self.ring.tail.store(position, Ordering::Release);
+        count
+    }
+}
+
+/// The reading half of a [`Spsc`] ring buffer.
+pub struct Consumer<'a, T, const N: usize> {
+    ring: &'a Spsc<T, N>,
+}
+
+// SAFETY: there's only ever one consumer, which can be moved to another context.
+unsafe impl<T: Send, const N: usize> Send for Consumer<'_, T, N> {}
+
+impl<T, const N: usize> Consumer<'_, T, N>
+where
+    T: Copy,
+{
+    /// Returns how many items are waiting to be read.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let tail = self.ring.tail.load(Ordering::Acquire);
+
+        distance::<N>(head, tail)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the next item without removing it.
+    pub fn peek(&self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let head = self.ring.head.load(Ordering::Relaxed);
+        // SAFETY: the slot was published by the producer and isn't freed until the head moves.
+        Some(self.ring.buffer[head % N].with(|slot| unsafe { (*slot).assume_init() }))
+    }
+
+    /// Reads the next item, if there is one.
+    #[inline]
+    pub fn pop(&mut self) -> Option<T> {
+        let mut item = [MaybeUninit::uninit()];
+        match self.read_uninit(&mut item) {
+            // SAFETY: the item was written by the read.
+            1 => Some(unsafe { item[0].assume_init() }),
+            _ => None,
+        }
+    }
+
+    /// Reads as many items as are available into `items`, returning how many were read.
+    pub fn read(&mut self, items: &mut [T]) -> usize {
+        // SAFETY: `MaybeUninit<T>` has the same layout as `T`, and only
+        // initialized values are written.
+        let items = unsafe {
+            core::slice::from_raw_parts_mut(items.as_mut_ptr() as *mut MaybeUninit<T>, items.len())
+        };
+        self.read_uninit(items)
+    }
+
+    fn read_uninit(&mut self, items: &mut [MaybeUninit<T>]) -> usize {
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let count = items.len().min(self.len());
+
+        let mut position = head;
+        for item in &mut items[..count] {
+            self.ring.buffer[position % N].with(|slot| {
+                // SAFETY: the slot was published by the producer, which won't
+                // reuse it until the head is published below.
+                *item = unsafe { *slot };
+            });
+            position = advance::<N>(position, 1);
+        }
+
+        self.ring.head.store(position, Ordering::Release);
+        count
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let mut ring = Spsc::<u32, 4>::new();
+        let (mut producer, mut consumer) = ring.split();
+
+        assert_eq!(consumer.pop(), None);
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        assert!(producer.is_full());
+        assert_eq!(producer.push(4), Err(4));
+
+        assert_eq!(consumer.peek(), Some(0));
+        assert_eq!(consumer.pop(), Some(0));
+        producer.push(4).unwrap();
+        for i in 1..5 {
+            assert_eq!(consumer.pop(), Some(i));
+        }
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_bulk_wraps() {
+        let mut ring = Spsc::<i16, 5>::new();
+        let (mut producer, mut consumer) = ring.split();
+
+        let mut block = [0i16; 5];
+        let mut next = 0i16;
+        let mut expected = 0i16;
+        for _ in 0..20 {
+            let items = [next, next + 1, next + 2];
+            let written = producer.write(&items);
+            next += written as i16;
+
+            let read = consumer.read(&mut block[..2]);
+            for item in &block[..read] {
+                assert_eq!(*item, expected);
+                expected += 1;
+            }
+        }
+
+        // Three in and two out each time settles at three waiting.
+        assert_eq!(consumer.len(), 3);
+        assert_eq!(producer.free(), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_threads() {
+        extern crate std;
+
+        static mut RING: Spsc<u64, 64> = Spsc::new();
+
+        // SAFETY: the ring is only split once, here.
+        #[allow(static_mut_refs)]
+        let (mut producer, mut consumer) = unsafe { RING.split() };
+
+        let writer = std::thread::spawn(move || {
+            let mut next = 0;
+            while next < 20_000 {
+                let items: [u64; 7] = core::array::from_fn(|i| next + i as u64);
+                let count = producer.write(&items[..(20_000 - next).min(7) as usize]);
+                next += count as u64;
+            }
+        });
+
+        let mut expected = 0;
+        let mut block = [0u64; 16];
+        while expected < 20_000 {
+            let count = consumer.read(&mut block);
+            for item in &block[..count] {
+                assert_eq!(*item, expected);
+                expected += 1;
+            }
+        }
+
+        writer.join().unwrap();
+    }
+}
+
+/// Model checks the ring buffer with loom, run with:
+///
+/// ```sh
+/// RUSTFLAGS="--cfg loom" cargo test -p catalina-engine --features std --lib spsc
+/// ```
+#[cfg(all(test, loom))]
+mod tests {
+    use super::*;
+
+    use loom::sync::Arc;
+
+    /// A buffer to share between loom threads, since `split` borrows.
+    struct Shared(UnsafeCell<Spsc<u32, 2>>);
+
+    unsafe impl Sync for Shared {}
+
+    #[test]
+    fn test_loom_ordering() {
+        loom::model(|| {
+            let shared = Arc::new(Shared(UnsafeCell::new(Spsc::new())));
+
+            // SAFETY: the ring is split once and outlives both threads.
+            let ring: &'static mut Spsc<u32, 2> = shared.0.with_mut(|ring| unsafe { &mut *ring });
+            let (mut producer, mut consumer) = ring.split();
+
+            let keep = shared.clone();
+            let writer = loom::thread::spawn(move || {
+                let _keep = keep;
+                assert_eq!(producer.write(&[1, 2]), 2);
+            });
+
+            // Whatever the interleaving, the consumer sees a prefix of the writes, in order.
+            let mut block = [0; 2];
+            let count = consumer.read(&mut block);
+            assert_eq!(block[..count], [1, 2][..count]);
+
+            writer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_loom_reuse() {
+        loom::model(|| {
+            let shared = Arc::new(Shared(UnsafeCell::new(Spsc::new())));
+
+            // SAFETY: the ring is split once and outlives both threads.
+            let ring: &'static mut Spsc<u32, 2> = shared.0.with_mut(|ring| unsafe { &mut *ring });
+            let (mut producer, mut consumer) = ring.split();
+            assert_eq!(producer.write(&[0, 1]), 2);
+
+            // The third push only fits once the consumer has freed a slot.
+            let keep = shared.clone();
+            let writer = loom::thread::spawn(move || {
+                let _keep = keep;
+                producer.push(2).is_ok()
+            });
+
+            assert_eq!(consumer.pop(), Some(0));
+            let pushed = writer.join().unwrap();
+
+            assert_eq!(consumer.pop(), Some(1));
+            assert_eq!(consumer.pop(), pushed.then_some(2));
+            assert_eq!(consumer.pop(), None);
+        });
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The position of a pitch bend wheel, and how far it bends the pitch.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PitchBend {
+    /// How far a full bend moves the pitch, in semitones.
+    range: f32,
+    /// The position of the wheel, from -1 to 1.
+    value: f32,
+}
+
+impl Default for PitchBend {
+    /// A centered wheel with the General MIDI range of 2 semitones.
+    fn default() -> Self {
+        Self::new(2.0)
+    }
+}
+
+impl PitchBend {
+    /// Construct a centered pitch bend with the range in semitones.
+    pub const fn new(range: f32) -> Self {
+        Self { range, value: 0.0 }
+    }
+
+    /// Sets how far a full bend moves the pitch, in semitones.
+    pub fn set_range(&mut self, semitones: f32) {
+        self.range = semitones.abs();
+    }
+
+    #[inline]
+    pub const fn range(&self) -> f32 {
+        self.range
+    }
+
+    /// Sets the position of the wheel, from -1 to 1.
+    pub fn set(&mut self, value: f32) {
+        self.value = value.clamp(-1.0, 1.0);
+    }
+
+    /// Sets the position of the wheel from a 14-bit MIDI pitch bend, centered at 8192.
+    pub fn set_midi(&mut self, value: u16) {
+        self.set((value.min(16_383) as f32 - 8_192.0) / 8_192.0);
+    }
+
+    #[inline]
+    pub const fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Returns how far the pitch is bent, in semitones.
+    #[inline]
+    pub fn semitones(&self) -> f32 {
+        self.value * self.range
+    }
+
+    /// Returns the ratio the bend multiplies frequencies by.
+    #[inline]
+    pub fn ratio(&self) -> f32 {
+        libm::exp2f(self.semitones() / 12.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_bend() {
+        let mut bend = PitchBend::default();
+        assert_eq!(bend.ratio(), 1.0);
+
+        bend.set_range(12.0);
+        bend.set(1.0);
+        assert_float_eq!(bend.ratio(), 2.0, abs <= 1e-6);
+
+        bend.set_midi(0);
+        assert_float_eq!(bend.ratio(), 0.5, abs <= 1e-6);
+        bend.set_midi(8_192);
+        assert_eq!(bend.semitones(), 0.0);
+    }
+}
@@ -0,0 +1,234 @@
+//! Sample-accurate instrument events.
+//!
+//! Events from a sequencer or a MIDI input rarely line up with the start of an
+//! audio block. An [`EventQueue`] holds each event with the frame it should
+//! land on, and splits the rendered block there so the instrument hears it on
+//! that exact frame.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use heapless::Vec;
+
+use super::Instrument;
+use crate::{audio::AudioSource, music::note::Note};
+
+/// A message to an [`Instrument`], following MIDI's channel voice messages.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InstrumentEvent {
+    NoteOn {
+        note: Note,
+        velocity: u8,
+    },
+    NoteOff {
+        note: Note,
+    },
+    /// A pitch bend, from -1 to 1.
+    PitchBend {
+        channel: u8,
+        bend: f32,
+    },
+    /// A raw MIDI control change.
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    /// A change to one of the instrument's own parameters.
+    Parameter {
+        id: u16,
+        value: f32,
+    },
+}
+
+/// An event scheduled a number of frames into the audio stream.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TimedEvent {
+    /// The frame the event lands on, counted from the start of the next
+    /// rendered block.
+    pub offset: usize,
+    pub event: InstrumentEvent,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueueError {
+    /// The queue has no room for another event.
+    Full,
+}
+
+/// A queue of events to play on an instrument at exact frames.
+///
+/// Events are pushed with the frame offset they should land on, and
+/// [`EventQueue::render`] splits the rendered block at each event so it's
+/// applied on that frame rather than at the start of the block. Events past
+/// the end of the block stay queued for the blocks after it.
+#[derive(Debug, Clone, Default)]
+pub struct EventQueue<const EVENTS: usize> {
+    /// The queued events, in the order they land.
+    events: Vec<TimedEvent, EVENTS>,
+}
+
+impl<const EVENTS: usize> EventQueue<EVENTS> {
+    pub const fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+
+    /// Queues an event to land a number of frames into the next rendered block.
+    ///
+    /// Events on the same frame are applied in the order they're pushed.
+    pub fn push(&mut self, offset: usize, event: InstrumentEvent) -> Result<(), QueueError> {
+        let index = self
+            .events
+            .iter()
+            .position(|queued| queued.offset > offset)
+            .unwrap_or(self.events.len());
+
+        self.events
+            .insert(index, TimedEvent { offset, event })
+            .map_err(|_| QueueError::Full)
+    }
+
+    /// Returns the queued events, in the order they land.
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Renders a block from the instrument, applying each event that lands
+    /// in the block on its frame.
+    ///
+    /// The offsets of the events left over are moved back by the length of
+    /// the block, so they stay relative to the start of the next one.
+    pub fn render<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        buffer: &mut [<I as AudioSource>::Frame],
+    ) {
+        let frames = buffer.len();
+        let mut start = 0;
+
+        for timed in self.events.iter().take_while(|timed| timed.offset < frames) {
+            if timed.offset > start {
+                instrument.render(&mut buffer[start..timed.offset]);
+                start = timed.offset;
+            }
+            instrument.handle_event(&timed.event);
+        }
+
+        if start < frames {
+            instrument.render(&mut buffer[start..]);
+        }
+
+        self.events.retain(|timed| timed.offset >= frames);
+        for timed in self.events.iter_mut() {
+            timed.offset -= frames;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{audio::signal::Signal, instrument::NoteError, music::note::CFour};
+
+    /// An instrument that outputs 1 while a note is held.
+    #[derive(Default)]
+    struct Gate {
+        held: bool,
+        parameter: Option<(u16, f32)>,
+    }
+
+    impl Instrument for Gate {
+        fn init(&mut self) {}
+
+        fn note_on(&mut self, _note: Note, _velocity: u8) -> Result<(), NoteError> {
+            self.held = true;
+            Ok(())
+        }
+
+        fn note_off(&mut self, _note: Note) {
+            self.held = false;
+        }
+
+        fn set_parameter(&mut self, id: u16, value: f32) {
+            self.parameter = Some((id, value));
+        }
+    }
+
+    impl Signal for Gate {
+        type Frame = f32;
+
+        fn next(&mut self) -> Self::Frame {
+            if self.held { 1.0 } else { 0.0 }
+        }
+    }
+
+    impl AudioSource for Gate {
+        type Frame = f32;
+
+        fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+            for frame in buffer.iter_mut() {
+                *frame = self.next();
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_splitting() {
+        let mut queue = EventQueue::<4>::new();
+        let mut gate = Gate::default();
+
+        // Pushed out of order, and with the release landing in the next block.
+        queue
+            .push(13, InstrumentEvent::NoteOff { note: CFour })
+            .unwrap();
+        queue
+            .push(
+                3,
+                InstrumentEvent::NoteOn {
+                    note: CFour,
+                    velocity: 100,
+                },
+            )
+            .unwrap();
+        queue
+            .push(3, InstrumentEvent::Parameter { id: 7, value: 0.5 })
+            .unwrap();
+        assert_eq!(queue.events()[0].offset, 3);
+
+        let mut buffer = [0.0; 8];
+        queue.render(&mut gate, &mut buffer);
+        assert_eq!(buffer, [0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(gate.parameter, Some((7, 0.5)));
+        assert_eq!(queue.events()[0].offset, 5);
+
+        queue.render(&mut gate, &mut buffer);
+        assert_eq!(buffer, [1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_full() {
+        let mut queue = EventQueue::<1>::new();
+        let event = InstrumentEvent::NoteOff { note: CFour };
+
+        assert_eq!(queue.push(0, event), Ok(()));
+        assert_eq!(queue.push(0, event), Err(QueueError::Full));
+    }
+}
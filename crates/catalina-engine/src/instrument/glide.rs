@@ -0,0 +1,193 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::Hertz;
+
+/// How the glide time is measured.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum GlideMode {
+    /// Every glide takes the glide time, however far apart the notes are.
+    #[default]
+    ConstantTime,
+    /// The glide moves at a fixed speed, with the glide time being how long
+    /// it takes to cover an octave.
+    ConstantRate,
+}
+
+/// Portamento for a voice, sliding its pitch from one note to the next.
+///
+/// The pitch moves in a straight line in octaves rather than hertz, so a
+/// glide sounds even across the keyboard. A voice starts each note with
+/// [`Glide::start`] and reads its frequency every sample with
+/// [`Glide::advance`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Glide {
+    sample_rate: f32,
+
+    mode: GlideMode,
+    /// The glide time in seconds, with 0 turning glide off.
+    time: f32,
+    /// Only glides between overlapping notes when set.
+    legato: bool,
+
+    /// The current pitch in octaves above 1Hz, or None before the first note.
+    current: Option<f32>,
+    /// The pitch being glided to, in octaves above 1Hz.
+    target: f32,
+    /// How far the pitch moves each sample, in octaves.
+    step: f32,
+}
+
+impl Glide {
+    /// Constructs a glide that's turned off.
+    pub const fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            mode: GlideMode::ConstantTime,
+            time: 0.0,
+            legato: false,
+            current: None,
+            target: 0.0,
+            step: 0.0,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+    }
+
+    pub fn set_mode(&mut self, mode: GlideMode) {
+        self.mode = mode;
+    }
+
+    #[inline]
+    pub const fn mode(&self) -> GlideMode {
+        self.mode
+    }
+
+    /// Sets the glide time in seconds, with 0 turning glide off.
+    pub fn set_time(&mut self, seconds: f32) {
+        self.time = seconds.max(0.0);
+    }
+
+    #[inline]
+    pub const fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Sets whether to only glide between overlapping notes, like a 303
+    /// slide, jumping straight to notes played detached.
+    pub fn set_legato(&mut self, legato: bool) {
+        self.legato = legato;
+    }
+
+    #[inline]
+    pub const fn legato(&self) -> bool {
+        self.legato
+    }
+
+    /// Starts gliding to a frequency from wherever the pitch is now.
+    ///
+    /// `overlapping` is whether the last note was still held when this one
+    /// was played. The first note played always starts at its own pitch.
+    pub fn start(&mut self, frequency: Hertz, overlapping: bool) {
+        let target = libm::log2f(frequency.0.max(f32::MIN_POSITIVE));
+
+        let Some(current) = self.current else {
+            return self.jump(frequency);
+        };
+        if self.time <= 0.0 || (self.legato && !overlapping) {
+            return self.jump(frequency);
+        }
+
+        let samples = self.time * self.sample_rate;
+        self.target = target;
+        self.step = match self.mode {
+            GlideMode::ConstantTime => (target - current).abs() / samples,
+            GlideMode::ConstantRate => 1.0 / samples,
+        };
+    }
+
+    /// Moves the pitch straight to a frequency without gliding.
+    pub fn jump(&mut self, frequency: Hertz) {
+        let pitch = libm::log2f(frequency.0.max(f32::MIN_POSITIVE));
+        self.current = Some(pitch);
+        self.target = pitch;
+        self.step = 0.0;
+    }
+
+    /// Returns true while the pitch is still moving to the last note.
+    pub fn is_gliding(&self) -> bool {
+        self.current.is_some_and(|current| current != self.target)
+    }
+
+    /// Returns the current frequency without advancing the glide.
+    pub fn frequency(&self) -> Hertz {
+        Hertz(libm::exp2f(self.current.unwrap_or(self.target)))
+    }
+
+    /// Advances the glide by a sample, returning the frequency to play.
+    #[inline]
+    pub fn advance(&mut self) -> Hertz {
+        if let Some(current) = &mut self.current {
+            let distance = self.target - *current;
+            if distance.abs() <= self.step {
+                *current = self.target;
+            } else {
+                *current += self.step.copysign(distance);
+            }
+        }
+
+        self.frequency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    fn glide_for(glide: &mut Glide, samples: usize) -> f32 {
+        (0..samples).fold(0.0, |_, _| glide.advance().0)
+    }
+
+    #[test]
+    fn test_constant_time() {
+        let mut glide = Glide::new(1_000.0);
+        glide.set_time(0.1);
+
+        // The first note starts at its own pitch.
+        glide.start(Hertz(110.0), false);
+        assert_eq!(glide.advance(), Hertz(110.0));
+
+        // Halfway through the glide up two octaves, the pitch is up an octave.
+        glide.start(Hertz(440.0), true);
+        assert_float_eq!(glide_for(&mut glide, 50), 220.0, rmax <= 1e-3);
+        assert!(glide.is_gliding());
+        assert_float_eq!(glide_for(&mut glide, 50), 440.0, rmax <= 1e-3);
+        assert!(!glide.is_gliding());
+    }
+
+    #[test]
+    fn test_constant_rate_and_legato() {
+        let mut glide = Glide::new(1_000.0);
+        glide.set_time(0.1);
+        glide.set_mode(GlideMode::ConstantRate);
+        glide.set_legato(true);
+        glide.jump(Hertz(110.0));
+
+        // Two octaves takes twice the glide time.
+        glide.start(Hertz(440.0), true);
+        assert_float_eq!(glide_for(&mut glide, 100), 220.0, rmax <= 1e-3);
+        assert_float_eq!(glide_for(&mut glide, 100), 440.0, rmax <= 1e-3);
+
+        // Detached notes jump when only gliding legato.
+        glide.start(Hertz(110.0), false);
+        assert_eq!(glide.advance(), Hertz(110.0));
+    }
+}
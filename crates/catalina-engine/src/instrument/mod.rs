@@ -3,11 +3,41 @@ use crate::{
     music::note::Note,
 };
 
+// Pitch bend amounts and ranges.
+pub mod bend;
+pub use bend::PitchBend;
+
+// Sustain and sostenuto pedal tracking.
+pub mod pedal;
+pub use pedal::Pedals;
+
+// Portamento between notes.
+pub mod glide;
+pub use glide::{Glide, GlideMode};
+
+// Detuned unison stacking.
+pub mod unison;
+pub use unison::Unison;
+
+// Timestamped events applied within rendered blocks.
+pub mod event;
+pub use event::{EventQueue, InstrumentEvent, QueueError, TimedEvent};
+
+// MPE zones and routing to per-note expression.
+pub mod mpe;
+pub use mpe::{MpeRouter, MpeZone, ZoneConfig};
+
 #[derive(Debug)]
 pub enum NoteError {
     NoVoices,
 }
 
+/// An instrument that can be played from a keyboard, sequencer or other controller.
+///
+/// Only the notes are required. The controller entry points follow MIDI's
+/// channel voice messages, but take normalized values, and default to doing
+/// nothing so instruments only implement the controls they respond to.
+/// Channels are numbered from 0 to 15.
 pub trait Instrument: AudioSource + Signal {
     /// Initializes the instrument for use.
     fn init(&mut self);
@@ -19,4 +49,64 @@ pub trait Instrument: AudioSource + Signal {
 
     /// Signals to the instrument that a note has been released.
     fn note_off(&mut self, note: Note);
+
+    /// Bends the pitch of the notes on a channel, from -1 for the bottom of
+    /// the bend range to 1 for the top.
+    fn pitch_bend(&mut self, _channel: u8, _bend: f32) {}
+
+    /// Sets how far a full pitch bend on a channel moves the pitch, in semitones.
+    fn set_pitch_bend_range(&mut self, _channel: u8, _semitones: f32) {}
+
+    /// Sets the pressure held on every key of a channel, from 0 to 1.
+    fn channel_pressure(&mut self, _channel: u8, _pressure: f32) {}
+
+    /// Sets the pressure held on a single note, from 0 to 1.
+    fn poly_pressure(&mut self, _note: Note, _pressure: f32) {}
+
+    /// Bends the pitch of a single note, in semitones, as a member channel
+    /// of an MPE zone does.
+    fn note_pitch_bend(&mut self, _note: Note, _semitones: f32) {}
+
+    /// Sets the timbre of a single note, from 0 to 1, as the CC74 slide of an
+    /// MPE member channel does.
+    fn note_timbre(&mut self, _note: Note, _timbre: f32) {}
+
+    /// Sets the mod wheel of a channel, from 0 to 1.
+    fn mod_wheel(&mut self, _channel: u8, _value: f32) {}
+
+    /// Presses or releases the sustain pedal, which holds every note
+    /// released while it's down until it's lifted.
+    fn sustain_pedal(&mut self, _channel: u8, _down: bool) {}
+
+    /// Presses or releases the sostenuto pedal, which holds only the notes
+    /// that were down when it was pressed until it's lifted.
+    fn sostenuto_pedal(&mut self, _channel: u8, _down: bool) {}
+
+    /// Sets one of the instrument's own parameters.
+    fn set_parameter(&mut self, _id: u16, _value: f32) {}
+
+    /// Applies an event to the instrument.
+    ///
+    /// Control changes are mapped to the mod wheel and pedals, and notes
+    /// that can't find a voice are dropped.
+    fn handle_event(&mut self, event: &InstrumentEvent) {
+        match *event {
+            InstrumentEvent::NoteOn { note, velocity } => {
+                let _ = self.note_on(note, velocity);
+            }
+            InstrumentEvent::NoteOff { note } => self.note_off(note),
+            InstrumentEvent::PitchBend { channel, bend } => self.pitch_bend(channel, bend),
+            InstrumentEvent::ControlChange {
+                channel,
+                controller,
+                value,
+            } => match controller {
+                1 => self.mod_wheel(channel, value.min(127) as f32 / 127.0),
+                64 => self.sustain_pedal(channel, value >= 64),
+                66 => self.sostenuto_pedal(channel, value >= 64),
+                _ => {}
+            },
+            InstrumentEvent::Parameter { id, value } => self.set_parameter(id, value),
+        }
+    }
 }
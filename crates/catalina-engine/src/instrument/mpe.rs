@@ -0,0 +1,523 @@
+//! MPE (MIDI Polyphonic Expression) routing.
+//!
+//! MPE gives each sounding note a MIDI channel of its own, so a controller can
+//! bend, press and slide every note separately with the ordinary channel
+//! messages. The channels are split into up to two zones:
+//!
+//! - the lower zone, with its master channel on channel 0 and its member
+//!   channels counting up from channel 1,
+//! - and the upper zone, with its master channel on channel 15 and its member
+//!   channels counting down from channel 14.
+//!
+//! Messages on a member channel apply to the note playing on it, while
+//! messages on a master channel apply to every note in the zone. Channels
+//! outside of any zone behave as plain MIDI channels.
+//!
+//! The [`MpeRouter`] sits between the MIDI input and an [`Instrument`],
+//! turning member channel messages into per-note expression and keeping the
+//! zones up to date from the MPE Configuration Messages it's sent.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{Instrument, NoteError};
+use crate::music::note::Note;
+
+/// The number of MIDI channels.
+const CHANNELS: usize = 16;
+
+/// The controller carrying the timbre of an MPE note.
+const TIMBRE: u8 = 74;
+
+// The controllers carrying registered parameter numbers.
+const DATA_ENTRY: u8 = 6;
+const RPN_LSB: u8 = 100;
+const RPN_MSB: u8 = 101;
+
+// The registered parameters the router responds to.
+const RPN_PITCH_BEND_SENSITIVITY: (u8, u8) = (0, 0);
+const RPN_MPE_CONFIGURATION: (u8, u8) = (0, 6);
+const RPN_NULL: (u8, u8) = (127, 127);
+
+/// One of the two MPE zones.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MpeZone {
+    /// The zone mastered from channel 0, with members counting up.
+    Lower,
+    /// The zone mastered from channel 15, with members counting down.
+    Upper,
+}
+
+impl MpeZone {
+    /// Returns the master channel of the zone.
+    pub const fn master_channel(&self) -> u8 {
+        match self {
+            MpeZone::Lower => 0,
+            MpeZone::Upper => 15,
+        }
+    }
+
+    /// Returns the member channel a number of channels in from the master.
+    const fn member_channel(&self, offset: u8) -> u8 {
+        match self {
+            MpeZone::Lower => offset,
+            MpeZone::Upper => 15 - offset,
+        }
+    }
+
+    const fn other(&self) -> Self {
+        match self {
+            MpeZone::Lower => MpeZone::Upper,
+            MpeZone::Upper => MpeZone::Lower,
+        }
+    }
+}
+
+/// The layout and pitch bend ranges of an active MPE zone.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ZoneConfig {
+    /// The number of member channels, from 1 to 15.
+    pub members: u8,
+    /// How far a full bend on a member channel moves its note, in semitones.
+    pub member_bend_range: f32,
+    /// How far a full bend on the master channel moves the zone, in semitones.
+    pub master_bend_range: f32,
+}
+
+impl ZoneConfig {
+    /// Constructs a zone with the MPE default bend ranges of 48 semitones
+    /// for the members and 2 for the master.
+    pub const fn new(members: u8) -> Self {
+        Self {
+            members,
+            member_bend_range: 48.0,
+            master_bend_range: 2.0,
+        }
+    }
+}
+
+/// What a channel is in the current MPE layout.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Role {
+    Master(MpeZone),
+    Member(MpeZone),
+    /// A channel outside of both zones.
+    Plain,
+}
+
+/// The expression last sent on a member channel, kept so it can be applied
+/// to the next note started on it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Expression {
+    /// The bend, from -1 to 1.
+    bend: f32,
+    pressure: f32,
+    timbre: f32,
+}
+
+impl Expression {
+    const DEFAULT: Self = Self {
+        bend: 0.0,
+        pressure: 0.0,
+        timbre: 0.5,
+    };
+}
+
+/// Routes MIDI channel messages to an [`Instrument`] following the MPE zones.
+///
+/// Member channel pitch bend, pressure and timbre (CC74) are sent to the
+/// note playing on the channel through [`Instrument::note_pitch_bend`],
+/// [`Instrument::poly_pressure`] and [`Instrument::note_timbre`]. Master
+/// channel and plain channel messages go to the channel-wide controls.
+///
+/// Notes are addressed by their [`Note`], so two member channels playing the
+/// same note share their expression.
+#[derive(Debug, Clone)]
+pub struct MpeRouter {
+    lower: Option<ZoneConfig>,
+    upper: Option<ZoneConfig>,
+
+    /// The note playing on each channel.
+    notes: [Option<Note>; CHANNELS],
+    /// The expression last sent on each channel.
+    expression: [Expression; CHANNELS],
+    /// The registered parameter selected on each channel.
+    parameters: [(u8, u8); CHANNELS],
+}
+
+impl Default for MpeRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MpeRouter {
+    /// Constructs a router with no zones, so every channel is a plain channel
+    /// until a zone is configured.
+    pub const fn new() -> Self {
+        Self {
+            lower: None,
+            upper: None,
+            notes: [None; CHANNELS],
+            expression: [Expression::DEFAULT; CHANNELS],
+            parameters: [RPN_NULL; CHANNELS],
+        }
+    }
+
+    /// Returns the configuration of a zone, or None if it's inactive.
+    pub const fn zone(&self, zone: MpeZone) -> Option<ZoneConfig> {
+        match zone {
+            MpeZone::Lower => self.lower,
+            MpeZone::Upper => self.upper,
+        }
+    }
+
+    /// Sets up a zone with a number of member channels, as an MPE
+    /// Configuration Message would, with 0 members turning it off.
+    ///
+    /// The zones can share at most 14 member channels, so the other zone
+    /// shrinks to make room, and is turned off if there's none left.
+    pub fn set_zone(&mut self, zone: MpeZone, members: u8) {
+        let members = members.min(15);
+        *self.zone_mut(zone) = (members > 0).then(|| ZoneConfig::new(members));
+
+        let room = 14u8.saturating_sub(members);
+        let other = self.zone_mut(zone.other());
+        if let Some(config) = other {
+            if config.members > room {
+                config.members = room;
+            }
+            if config.members == 0 {
+                *other = None;
+            }
+        }
+    }
+
+    /// Sets the member channel bend range of a zone, in semitones.
+    pub fn set_member_bend_range(&mut self, zone: MpeZone, semitones: f32) {
+        if let Some(config) = self.zone_mut(zone) {
+            config.member_bend_range = semitones.abs();
+        }
+    }
+
+    /// Sets the master channel bend range of a zone, in semitones.
+    pub fn set_master_bend_range(&mut self, zone: MpeZone, semitones: f32) {
+        if let Some(config) = self.zone_mut(zone) {
+            config.master_bend_range = semitones.abs();
+        }
+    }
+
+    /// Starts a note, applying the expression already sent on its member channel.
+    pub fn note_on<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        channel: u8,
+        note: Note,
+        velocity: u8,
+    ) -> Result<(), NoteError> {
+        let channel = channel as usize % CHANNELS;
+        instrument.note_on(note, velocity)?;
+
+        if let Role::Member(zone) = self.role(channel as u8) {
+            let expression = self.expression[channel];
+            let range = self.member_bend_range(zone);
+
+            instrument.note_pitch_bend(note, expression.bend * range);
+            instrument.poly_pressure(note, expression.pressure);
+            instrument.note_timbre(note, expression.timbre);
+            self.notes[channel] = Some(note);
+        }
+
+        Ok(())
+    }
+
+    /// Releases a note.
+    pub fn note_off<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        channel: u8,
+        note: Note,
+    ) {
+        let channel = channel as usize % CHANNELS;
+        if self.notes[channel] == Some(note) {
+            self.notes[channel] = None;
+        }
+
+        instrument.note_off(note);
+    }
+
+    /// Handles a 14-bit pitch bend, centered at 8192.
+    pub fn pitch_bend<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        channel: u8,
+        value: u16,
+    ) {
+        let bend = (value.min(16_383) as f32 - 8_192.0) / 8_192.0;
+
+        match self.role(channel) {
+            Role::Member(zone) => {
+                let channel = channel as usize % CHANNELS;
+                self.expression[channel].bend = bend;
+                if let Some(note) = self.notes[channel] {
+                    instrument.note_pitch_bend(note, bend * self.member_bend_range(zone));
+                }
+            }
+            Role::Master(_) | Role::Plain => instrument.pitch_bend(channel, bend),
+        }
+    }
+
+    /// Handles channel pressure, from 0 to 127.
+    pub fn channel_pressure<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        channel: u8,
+        value: u8,
+    ) {
+        let pressure = normalize(value);
+
+        match self.role(channel) {
+            Role::Member(_) => {
+                let channel = channel as usize % CHANNELS;
+                self.expression[channel].pressure = pressure;
+                if let Some(note) = self.notes[channel] {
+                    instrument.poly_pressure(note, pressure);
+                }
+            }
+            Role::Master(_) | Role::Plain => instrument.channel_pressure(channel, pressure),
+        }
+    }
+
+    /// Handles a control change, picking out the timbre, pedals, mod wheel
+    /// and the registered parameters that configure MPE.
+    pub fn control_change<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        channel: u8,
+        controller: u8,
+        value: u8,
+    ) {
+        let role = self.role(channel);
+        let index = channel as usize % CHANNELS;
+
+        match controller {
+            RPN_MSB => self.parameters[index].0 = value,
+            RPN_LSB => self.parameters[index].1 = value,
+            DATA_ENTRY => self.registered_parameter(instrument, channel, value),
+            TIMBRE if matches!(role, Role::Member(_)) => {
+                let timbre = normalize(value);
+                self.expression[index].timbre = timbre;
+                if let Some(note) = self.notes[index] {
+                    instrument.note_timbre(note, timbre);
+                }
+            }
+            1 => instrument.mod_wheel(channel, normalize(value)),
+            64 => instrument.sustain_pedal(channel, value >= 64),
+            66 => instrument.sostenuto_pedal(channel, value >= 64),
+            _ => {}
+        }
+    }
+
+    /// Applies a data entry to the registered parameter selected on a channel.
+    fn registered_parameter<I: Instrument + ?Sized>(
+        &mut self,
+        instrument: &mut I,
+        channel: u8,
+        value: u8,
+    ) {
+        let role = self.role(channel);
+
+        match self.parameters[channel as usize % CHANNELS] {
+            // The configuration message is only valid on a master channel.
+            RPN_MPE_CONFIGURATION => {
+                let zone = match channel {
+                    0 => MpeZone::Lower,
+                    15 => MpeZone::Upper,
+                    _ => return,
+                };
+                self.set_zone(zone, value);
+                if self.zone(zone).is_some() {
+                    instrument
+                        .set_pitch_bend_range(channel, ZoneConfig::new(value).master_bend_range);
+                }
+            }
+            RPN_PITCH_BEND_SENSITIVITY => match role {
+                // A member channel sets the range of every member in its zone.
+                Role::Member(zone) => self.set_member_bend_range(zone, value as f32),
+                Role::Master(zone) => {
+                    self.set_master_bend_range(zone, value as f32);
+                    instrument.set_pitch_bend_range(channel, value as f32);
+                }
+                Role::Plain => instrument.set_pitch_bend_range(channel, value as f32),
+            },
+            _ => {}
+        }
+    }
+
+    /// Works out what a channel is in the current layout.
+    fn role(&self, channel: u8) -> Role {
+        for zone in [MpeZone::Lower, MpeZone::Upper] {
+            let Some(config) = self.zone(zone) else {
+                continue;
+            };
+
+            if channel == zone.master_channel() {
+                return Role::Master(zone);
+            }
+            if (1..=config.members).any(|offset| zone.member_channel(offset) == channel) {
+                return Role::Member(zone);
+            }
+        }
+
+        Role::Plain
+    }
+
+    fn member_bend_range(&self, zone: MpeZone) -> f32 {
+        self.zone(zone)
+            .map_or(0.0, |config| config.member_bend_range)
+    }
+
+    fn zone_mut(&mut self, zone: MpeZone) -> &mut Option<ZoneConfig> {
+        match zone {
+            MpeZone::Lower => &mut self.lower,
+            MpeZone::Upper => &mut self.upper,
+        }
+    }
+}
+
+/// Normalizes a 7-bit MIDI value to the range 0 to 1.
+fn normalize(value: u8) -> f32 {
+    value.min(127) as f32 / 127.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::audio::{AudioSource, signal::Signal};
+
+    use float_eq::assert_float_eq;
+
+    /// An instrument that keeps the last expression it was sent.
+    #[derive(Default)]
+    struct Recorder {
+        note_bend: Option<(Note, f32)>,
+        pressure: Option<(Note, f32)>,
+        timbre: Option<(Note, f32)>,
+        channel_bend: Option<(u8, f32)>,
+        bend_range: Option<(u8, f32)>,
+    }
+
+    impl Instrument for Recorder {
+        fn init(&mut self) {}
+
+        fn note_on(&mut self, _note: Note, _velocity: u8) -> Result<(), NoteError> {
+            Ok(())
+        }
+
+        fn note_off(&mut self, _note: Note) {}
+
+        fn pitch_bend(&mut self, channel: u8, bend: f32) {
+            self.channel_bend = Some((channel, bend));
+        }
+
+        fn set_pitch_bend_range(&mut self, channel: u8, semitones: f32) {
+            self.bend_range = Some((channel, semitones));
+        }
+
+        fn poly_pressure(&mut self, note: Note, pressure: f32) {
+            self.pressure = Some((note, pressure));
+        }
+
+        fn note_pitch_bend(&mut self, note: Note, semitones: f32) {
+            self.note_bend = Some((note, semitones));
+        }
+
+        fn note_timbre(&mut self, note: Note, timbre: f32) {
+            self.timbre = Some((note, timbre));
+        }
+    }
+
+    impl Signal for Recorder {
+        type Frame = f32;
+
+        fn next(&mut self) -> Self::Frame {
+            0.0
+        }
+    }
+
+    impl AudioSource for Recorder {
+        type Frame = f32;
+
+        fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+            buffer.fill(0.0);
+        }
+    }
+
+    fn note(midi: u8) -> Note {
+        Note::from_midi(midi).unwrap()
+    }
+
+    fn configure(router: &mut MpeRouter, instrument: &mut Recorder, channel: u8, members: u8) {
+        router.control_change(instrument, channel, RPN_MSB, 0);
+        router.control_change(instrument, channel, RPN_LSB, 6);
+        router.control_change(instrument, channel, DATA_ENTRY, members);
+    }
+
+    #[test]
+    fn test_configuration() {
+        let mut router = MpeRouter::new();
+        let mut instrument = Recorder::default();
+
+        configure(&mut router, &mut instrument, 0, 10);
+        assert_eq!(router.zone(MpeZone::Lower), Some(ZoneConfig::new(10)));
+        assert_eq!(instrument.bend_range, Some((0, 2.0)));
+
+        // The upper zone takes the channels it asks for, shrinking the lower zone.
+        configure(&mut router, &mut instrument, 15, 6);
+        assert_eq!(router.zone(MpeZone::Upper).unwrap().members, 6);
+        assert_eq!(router.zone(MpeZone::Lower).unwrap().members, 8);
+
+        // Only master channels can configure a zone.
+        configure(&mut router, &mut instrument, 3, 1);
+        assert_eq!(router.zone(MpeZone::Lower).unwrap().members, 8);
+
+        configure(&mut router, &mut instrument, 0, 0);
+        assert_eq!(router.zone(MpeZone::Lower), None);
+    }
+
+    #[test]
+    fn test_member_expression() {
+        let mut router = MpeRouter::new();
+        let mut instrument = Recorder::default();
+        configure(&mut router, &mut instrument, 0, 15);
+
+        // Expression sent before the note is applied when it starts.
+        router.control_change(&mut instrument, 2, TIMBRE, 127);
+        router.note_on(&mut instrument, 2, note(60), 100).unwrap();
+        assert_eq!(instrument.timbre, Some((note(60), 1.0)));
+        assert_eq!(instrument.note_bend, Some((note(60), 0.0)));
+
+        router.pitch_bend(&mut instrument, 2, 16_383);
+        let (bent, semitones) = instrument.note_bend.unwrap();
+        assert_eq!(bent, note(60));
+        assert_float_eq!(semitones, 48.0, abs <= 0.01);
+
+        router.channel_pressure(&mut instrument, 2, 127);
+        assert_eq!(instrument.pressure, Some((note(60), 1.0)));
+
+        // The master channel bends the whole zone.
+        router.pitch_bend(&mut instrument, 0, 0);
+        assert_eq!(instrument.channel_bend, Some((0, -1.0)));
+
+        // Bends after the note is released aren't routed to it.
+        router.note_off(&mut instrument, 2, note(60));
+        instrument.note_bend = None;
+        router.pitch_bend(&mut instrument, 2, 8_192);
+        assert_eq!(instrument.note_bend, None);
+    }
+}
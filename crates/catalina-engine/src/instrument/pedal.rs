@@ -0,0 +1,173 @@
+use heapless::Vec;
+
+use crate::music::note::Note;
+
+/// Tracks the sustain and sostenuto pedals, deciding when released notes
+/// should actually stop.
+///
+/// The instrument passes its notes through the pedals, and only releases a
+/// note when [`Pedals::note_off`] says it should, or when lifting a pedal
+/// hands back the notes it was holding. Up to `NOTES` notes are tracked, and
+/// notes past that are released straight away rather than being held.
+#[derive(Debug, Clone, Default)]
+pub struct Pedals<const NOTES: usize> {
+    sustain: bool,
+    sostenuto: bool,
+
+    /// The keys that are down.
+    held: Vec<Note, NOTES>,
+    /// The keys that were down when the sostenuto pedal was pressed.
+    sostenuto_notes: Vec<Note, NOTES>,
+    /// The notes that were released while a pedal held them.
+    deferred: Vec<Note, NOTES>,
+}
+
+impl<const NOTES: usize> Pedals<NOTES> {
+    pub const fn new() -> Self {
+        Self {
+            sustain: false,
+            sostenuto: false,
+            held: Vec::new(),
+            sostenuto_notes: Vec::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub const fn sustain(&self) -> bool {
+        self.sustain
+    }
+
+    #[inline]
+    pub const fn sostenuto(&self) -> bool {
+        self.sostenuto
+    }
+
+    /// Returns true if the note is sounding only because a pedal holds it.
+    pub fn is_deferred(&self, note: Note) -> bool {
+        self.deferred.contains(&note)
+    }
+
+    /// Records a key being pressed.
+    pub fn note_on(&mut self, note: Note) {
+        remove(&mut self.deferred, note);
+        if !self.held.contains(&note) {
+            let _ = self.held.push(note);
+        }
+    }
+
+    /// Records a key being released, returning true if the note should stop
+    /// now, or false if a pedal is holding it.
+    pub fn note_off(&mut self, note: Note) -> bool {
+        remove(&mut self.held, note);
+
+        let held = self.sustain || (self.sostenuto && self.sostenuto_notes.contains(&note));
+        if held && !self.deferred.contains(&note) {
+            return self.deferred.push(note).is_err();
+        }
+
+        !held
+    }
+
+    /// Presses or lifts the sustain pedal, calling `release` with each note
+    /// that should stop now that the pedal is lifted.
+    pub fn set_sustain(&mut self, down: bool, release: impl FnMut(Note)) {
+        self.sustain = down;
+        if !down {
+            self.release_deferred(release);
+        }
+    }
+
+    /// Presses or lifts the sostenuto pedal, calling `release` with each note
+    /// that should stop now that the pedal is lifted.
+    ///
+    /// Pressing the pedal captures the keys that are down, and only those
+    /// notes are held by it.
+    pub fn set_sostenuto(&mut self, down: bool, release: impl FnMut(Note)) {
+        if down && !self.sostenuto {
+            self.sostenuto_notes.clone_from(&self.held);
+        }
+
+        self.sostenuto = down;
+        if !down {
+            self.sostenuto_notes.clear();
+            self.release_deferred(release);
+        }
+    }
+
+    /// Releases every deferred note that no pedal is holding any more.
+    fn release_deferred(&mut self, mut release: impl FnMut(Note)) {
+        let (sustain, sostenuto) = (self.sustain, self.sostenuto);
+        let sostenuto_notes = &self.sostenuto_notes;
+
+        self.deferred.retain(|note| {
+            let held = sustain || (sostenuto && sostenuto_notes.contains(note));
+            if !held {
+                release(*note);
+            }
+            held
+        });
+    }
+}
+
+fn remove<const NOTES: usize>(notes: &mut Vec<Note, NOTES>, note: Note) {
+    if let Some(index) = notes.iter().position(|n| *n == note) {
+        notes.swap_remove(index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(midi: u8) -> Note {
+        Note::from_midi(midi).unwrap()
+    }
+
+    #[test]
+    fn test_sustain() {
+        let mut pedals = Pedals::<4>::new();
+
+        pedals.note_on(note(60));
+        assert!(pedals.note_off(note(60)));
+
+        pedals.note_on(note(60));
+        pedals.set_sustain(true, |_| panic!("nothing to release"));
+        assert!(!pedals.note_off(note(60)));
+        assert!(pedals.is_deferred(note(60)));
+
+        // Playing the note again while it's sustained takes it back from the pedal.
+        pedals.note_on(note(60));
+        assert!(!pedals.is_deferred(note(60)));
+        assert!(!pedals.note_off(note(60)));
+
+        let mut released = Vec::<Note, 4>::new();
+        pedals.set_sustain(false, |note| released.push(note).unwrap());
+        assert_eq!(released, [note(60)]);
+    }
+
+    #[test]
+    fn test_sostenuto() {
+        let mut pedals = Pedals::<4>::new();
+
+        // Only the notes down when the pedal is pressed are held.
+        pedals.note_on(note(48));
+        pedals.set_sostenuto(true, |_| {});
+        pedals.note_on(note(64));
+        assert!(!pedals.note_off(note(48)));
+        assert!(pedals.note_off(note(64)));
+
+        // A sustained note captured by the sostenuto outlasts the sustain pedal.
+        pedals.set_sustain(true, |_| {});
+        pedals.note_on(note(67));
+        assert!(!pedals.note_off(note(67)));
+
+        let mut released = Vec::<Note, 4>::new();
+        pedals.set_sustain(false, |note| released.push(note).unwrap());
+        assert_eq!(released, [note(67)]);
+
+        released.clear();
+        pedals.set_sostenuto(false, |note| released.push(note).unwrap());
+        assert_eq!(released, [note(48)]);
+    }
+}
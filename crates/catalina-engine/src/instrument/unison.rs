@@ -0,0 +1,181 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::rng::Rng;
+
+/// Unison stacking, playing each note as several detuned copies spread
+/// across the stereo field, for supersaw-style sounds.
+///
+/// Up to `VOICES` copies can be stacked. The copies are spaced evenly
+/// between the outermost detune either side of the note, and panned in the
+/// same order, so the most detuned copies sit at the edges.
+///
+/// The instrument renders each copy at [`Unison::ratio`] times the note's
+/// frequency and mixes it with [`Unison::gains`], starting each note's
+/// copies at [`Unison::phases`]. The phases are all 0 until some phase
+/// randomness is set, so the copies don't all start in phase.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone)]
+pub struct Unison<const VOICES: usize> {
+    /// How many copies are played, from 1 to `VOICES`.
+    count: usize,
+    /// How far the outermost copies are detuned, in cents.
+    detune: f32,
+    /// How far the outermost copies are panned, from 0 to 1.
+    spread: f32,
+    /// How much the starting phases are randomised, from 0 to 1.
+    randomness: f32,
+
+    /// Generates the random starting phases.
+    rng: Rng,
+}
+
+impl<const VOICES: usize> Default for Unison<VOICES> {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl<const VOICES: usize> Unison<VOICES> {
+    /// Constructs a unison playing a single copy with no phase randomness,
+    /// with the seed for the random phases.
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            count: 1,
+            detune: 0.0,
+            spread: 0.0,
+            randomness: 0.0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Sets how many copies are played, limited to `VOICES`.
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count.clamp(1, VOICES.max(1));
+    }
+
+    #[inline]
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Sets how far the outermost copies are detuned, in cents.
+    pub fn set_detune(&mut self, cents: f32) {
+        self.detune = cents.abs();
+    }
+
+    #[inline]
+    pub const fn detune(&self) -> f32 {
+        self.detune
+    }
+
+    /// Sets how far the outermost copies are panned, from 0 for mono to 1
+    /// for hard left and right.
+    pub fn set_spread(&mut self, spread: f32) {
+        self.spread = spread.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub const fn spread(&self) -> f32 {
+        self.spread
+    }
+
+    /// Sets how much the starting phases are randomised, from 0 for every
+    /// copy starting at phase 0 to 1 for fully random phases.
+    pub fn set_phase_randomness(&mut self, randomness: f32) {
+        self.randomness = randomness.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub const fn phase_randomness(&self) -> f32 {
+        self.randomness
+    }
+
+    /// Returns where a copy sits in the stack, from -1 to 1.
+    fn position(&self, copy: usize) -> f32 {
+        if self.count <= 1 {
+            return 0.0;
+        }
+
+        copy as f32 / (self.count - 1) as f32 * 2.0 - 1.0
+    }
+
+    /// Returns the ratio a copy's frequency is detuned by.
+    #[inline]
+    pub fn ratio(&self, copy: usize) -> f32 {
+        libm::exp2f(self.position(copy) * self.detune / 1_200.0)
+    }
+
+    /// Returns the pan of a copy, from -1 for left to 1 for right.
+    #[inline]
+    pub fn pan(&self, copy: usize) -> f32 {
+        self.position(copy) * self.spread
+    }
+
+    /// Returns the left and right gains of a copy.
+    ///
+    /// The copies are panned with equal power and scaled so the stack is
+    /// about as loud as a single copy.
+    pub fn gains(&self, copy: usize) -> [f32; 2] {
+        let angle = (self.pan(copy) + 1.0) * core::f32::consts::FRAC_PI_4;
+        let level = libm::sqrtf(2.0 / self.count as f32);
+
+        [libm::cosf(angle) * level, libm::sinf(angle) * level]
+    }
+
+    /// Returns the gain of a copy when mixed down to mono.
+    #[inline]
+    pub fn mono_gain(&self) -> f32 {
+        1.0 / libm::sqrtf(self.count as f32)
+    }
+
+    /// Returns starting phases for a new note's copies, from 0 to 1.
+    pub fn phases(&mut self) -> [f32; VOICES] {
+        let randomness = self.randomness;
+        core::array::from_fn(|_| self.rng.next_f32() * randomness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_stack() {
+        let mut unison = Unison::<8>::new(1);
+        assert_eq!(unison.ratio(0), 1.0);
+        assert_eq!(unison.phases(), [0.0; 8]);
+        assert_float_eq!(unison.gains(0), [1.0; 2], abs_all <= 1e-6);
+
+        unison.set_count(3);
+        unison.set_detune(1_200.0);
+        unison.set_spread(1.0);
+
+        // The copies are spread evenly either side of the note.
+        assert_float_eq!(unison.ratio(0), 0.5, abs <= 1e-6);
+        assert_eq!(unison.ratio(1), 1.0);
+        assert_float_eq!(unison.ratio(2), 2.0, abs <= 1e-6);
+
+        // The outer copies are hard left and right, and the center copy is in both.
+        assert_float_eq!(unison.gains(0)[1], 0.0, abs <= 1e-6);
+        assert_float_eq!(unison.gains(2)[0], 0.0, abs <= 1e-6);
+        let [left, right] = unison.gains(1);
+        assert_float_eq!(left, right, abs <= 1e-6);
+
+        // The counts are limited to the stack size.
+        unison.set_count(20);
+        assert_eq!(unison.count(), 8);
+
+        unison.set_phase_randomness(0.0);
+        assert_eq!(unison.phases(), [0.0; 8]);
+        unison.set_phase_randomness(1.0);
+        assert!(
+            unison
+                .phases()
+                .iter()
+                .all(|phase| (0.0..1.0).contains(phase))
+        );
+    }
+}
@@ -0,0 +1,536 @@
+//! A module for working with chords.
+
+use crate::prelude::*;
+
+use core::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use heapless::Vec;
+
+use super::{
+    interval::{Interval, Interval as I},
+    named_pitch::NamedPitch,
+    note::Note,
+    octave::Octave,
+};
+
+/// The most notes a chord can have.
+pub const MAX_CHORD_NOTES: usize = 6;
+
+/// The quality of a chord, which sets the intervals stacked on its root.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    /// A root and fifth, without a third.
+    Power,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+    Augmented7,
+    Major6,
+    Minor6,
+    Dominant9,
+    Major9,
+    Minor9,
+    Add9,
+}
+
+/// The chord symbols each quality is parsed from, with the symbol it's
+/// written with first.
+const SYMBOLS: [(ChordQuality, &[&str]); 20] = [
+    (ChordQuality::Major, &["", "M", "maj"]),
+    (ChordQuality::Minor, &["m", "min", "-"]),
+    (ChordQuality::Diminished, &["dim", "°", "o"]),
+    (ChordQuality::Augmented, &["aug", "+"]),
+    (ChordQuality::Sus2, &["sus2"]),
+    (ChordQuality::Sus4, &["sus4", "sus"]),
+    (ChordQuality::Power, &["5"]),
+    (ChordQuality::Dominant7, &["7", "dom7"]),
+    (ChordQuality::Major7, &["maj7", "M7", "Δ", "Δ7"]),
+    (ChordQuality::Minor7, &["m7", "min7", "-7"]),
+    (
+        ChordQuality::MinorMajor7,
+        &["mMaj7", "mM7", "m(maj7)", "-Δ7"],
+    ),
+    (
+        ChordQuality::HalfDiminished7,
+        &["m7b5", "m7♭5", "ø", "ø7", "-7b5"],
+    ),
+    (ChordQuality::Diminished7, &["dim7", "°7", "o7"]),
+    (ChordQuality::Augmented7, &["aug7", "+7", "7#5", "7♯5"]),
+    (ChordQuality::Major6, &["6", "M6", "maj6"]),
+    (ChordQuality::Minor6, &["m6", "min6", "-6"]),
+    (ChordQuality::Dominant9, &["9"]),
+    (ChordQuality::Major9, &["maj9", "M9", "Δ9"]),
+    (ChordQuality::Minor9, &["m9", "min9", "-9"]),
+    (ChordQuality::Add9, &["add9"]),
+];
+
+impl ChordQuality {
+    /// Returns the intervals of the chord's notes up from its root.
+    pub const fn intervals(&self) -> &'static [Interval] {
+        match self {
+            ChordQuality::Major => &[I::UNISON, I::MAJOR_THIRD, I::PERFECT_FIFTH],
+            ChordQuality::Minor => &[I::UNISON, I::MINOR_THIRD, I::PERFECT_FIFTH],
+            ChordQuality::Diminished => &[I::UNISON, I::MINOR_THIRD, I::DIMINISHED_FIFTH],
+            ChordQuality::Augmented => &[I::UNISON, I::MAJOR_THIRD, I::AUGMENTED_FIFTH],
+            ChordQuality::Sus2 => &[I::UNISON, I::MAJOR_SECOND, I::PERFECT_FIFTH],
+            ChordQuality::Sus4 => &[I::UNISON, I::PERFECT_FOURTH, I::PERFECT_FIFTH],
+            ChordQuality::Power => &[I::UNISON, I::PERFECT_FIFTH],
+            ChordQuality::Dominant7 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordQuality::Major7 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ChordQuality::Minor7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordQuality::MinorMajor7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ChordQuality::HalfDiminished7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::DIMINISHED_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordQuality::Diminished7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::DIMINISHED_FIFTH,
+                I::DIMINISHED_SEVENTH,
+            ],
+            ChordQuality::Augmented7 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::AUGMENTED_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordQuality::Major6 => &[I::UNISON, I::MAJOR_THIRD, I::PERFECT_FIFTH, I::MAJOR_SIXTH],
+            ChordQuality::Minor6 => &[I::UNISON, I::MINOR_THIRD, I::PERFECT_FIFTH, I::MAJOR_SIXTH],
+            ChordQuality::Dominant9 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+                I::MAJOR_NINTH,
+            ],
+            ChordQuality::Major9 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SEVENTH,
+                I::MAJOR_NINTH,
+            ],
+            ChordQuality::Minor9 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+                I::MAJOR_NINTH,
+            ],
+            ChordQuality::Add9 => &[I::UNISON, I::MAJOR_THIRD, I::PERFECT_FIFTH, I::MAJOR_NINTH],
+        }
+    }
+
+    /// Returns the symbol the quality is written with after the root, such
+    /// as `m7b5` for a half-diminished seventh.
+    pub fn symbol(&self) -> &'static str {
+        SYMBOLS
+            .iter()
+            .find(|(quality, _)| quality == self)
+            .map_or("", |(_, symbols)| symbols[0])
+    }
+
+    /// Returns the quality written with a symbol, accepting the common
+    /// alternatives such as `-7` or `ø`.
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        SYMBOLS
+            .iter()
+            .find(|(_, symbols)| symbols.contains(&symbol))
+            .map(|(quality, _)| *quality)
+    }
+}
+
+/// How a chord's notes are spread out when it's played.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug, Default)]
+pub enum Voicing {
+    /// The notes are stacked as closely as they go above the bass.
+    #[default]
+    Close,
+    /// The second highest note is dropped an octave.
+    Drop2,
+    /// The third highest note is dropped an octave.
+    Drop3,
+    /// Every other note above the bass is raised an octave.
+    Spread,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChordParseError {
+    /// There's no chord in the string.
+    Empty,
+    /// The root isn't a letter from A to G followed by accidentals.
+    InvalidRoot,
+    /// The chord symbol after the root isn't one that's known.
+    UnknownQuality,
+    /// The note after the slash isn't one of the chord's notes.
+    InvalidBass,
+}
+
+/// A chord, as a quality built on a root, such as C minor seventh.
+///
+/// The inversion picks which of the chord's notes is in the bass, counting
+/// from 0 for the root. Chords can be parsed from chord names, such as
+/// `Cm7b5` or `F♯7/A♯`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+pub struct Chord {
+    root: NamedPitch,
+    quality: ChordQuality,
+    inversion: u8,
+}
+
+impl Chord {
+    /// Constructs a chord in root position.
+    pub const fn new(root: NamedPitch, quality: ChordQuality) -> Self {
+        Self {
+            root,
+            quality,
+            inversion: 0,
+        }
+    }
+
+    /// Returns the chord with one of its notes in the bass, wrapping around
+    /// past the number of notes.
+    pub fn with_inversion(self, inversion: u8) -> Self {
+        Self {
+            inversion: inversion % self.len() as u8,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub const fn root(&self) -> NamedPitch {
+        self.root
+    }
+
+    #[inline]
+    pub const fn quality(&self) -> ChordQuality {
+        self.quality
+    }
+
+    #[inline]
+    pub const fn inversion(&self) -> u8 {
+        self.inversion
+    }
+
+    /// Returns the number of notes in the chord.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.quality.intervals().len()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.quality.intervals().is_empty()
+    }
+
+    /// Returns the named pitches of the chord in root position, skipping any
+    /// that would need more than three accidentals.
+    pub fn pitches(&self) -> Vec<NamedPitch, MAX_CHORD_NOTES> {
+        self.quality
+            .intervals()
+            .iter()
+            .filter_map(|interval| self.root.transpose(*interval))
+            .collect()
+    }
+
+    /// Returns the named pitch in the bass.
+    ///
+    /// Returns `None` if the inversion is past the chord's notes, which only
+    /// a deserialized chord can have.
+    pub fn bass(&self) -> Option<NamedPitch> {
+        let inversion = self.quality.intervals().get(self.inversion as usize)?;
+        self.root.transpose(*inversion)
+    }
+
+    /// Returns the notes of the chord from the bass up, with the bass note
+    /// in the given octave.
+    ///
+    /// Notes that would be outside the octave range are left out.
+    pub fn notes(&self, octave: Octave, voicing: Voicing) -> Vec<Note, MAX_CHORD_NOTES> {
+        let intervals = self.quality.intervals();
+        let Some(&inversion) = intervals.get(self.inversion as usize) else {
+            return Vec::new();
+        };
+
+        // Build the chord up from the root under the bass, then raise the
+        // notes below the bass over it.
+        let bass = self.bass().map(|bass| Note::new(bass, octave));
+        let Some((bass, root)) =
+            bass.and_then(|bass| Some((bass, bass.transpose_down(inversion)?)))
+        else {
+            return Vec::new();
+        };
+
+        let mut notes: Vec<Note, MAX_CHORD_NOTES> = intervals
+            .iter()
+            .filter_map(|interval| root.transpose(*interval))
+            .collect();
+        for note in notes.iter_mut() {
+            while note.semitone() < bass.semitone() {
+                match note.transpose(Interval::OCTAVE) {
+                    Some(raised) => *note = raised,
+                    None => break,
+                }
+            }
+        }
+        notes.sort_unstable_by_key(Note::semitone);
+
+        // Drop voicings need enough notes for the dropped note to be above
+        // the bass, so smaller chords are left close.
+        let count = notes.len();
+        let dropped = match voicing {
+            Voicing::Drop2 if count >= 3 => Some(count - 2),
+            Voicing::Drop3 if count >= 4 => Some(count - 3),
+            _ => None,
+        };
+        if let Some(index) = dropped
+            && let Some(note) = notes[index].transpose_down(Interval::OCTAVE)
+        {
+            notes[index] = note;
+        }
+        if voicing == Voicing::Spread {
+            for note in notes.iter_mut().skip(1).step_by(2) {
+                if let Some(raised) = note.transpose(Interval::OCTAVE) {
+                    *note = raised;
+                }
+            }
+        }
+        notes.sort_unstable_by_key(Note::semitone);
+
+        notes
+    }
+
+    /// Parses a chord name, such as `C`, `Ebmaj7`, `F#m7b5` or `G7/B`.
+    ///
+    /// A note after a slash puts that note of the chord in the bass, so it
+    /// must be one of the chord's notes.
+    pub fn parse(name: &str) -> Result<Self, ChordParseError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ChordParseError::Empty);
+        }
+
+        let (root, rest) = parse_pitch(name).ok_or(ChordParseError::InvalidRoot)?;
+        let (symbol, bass) = match rest.split_once('/') {
+            Some((symbol, bass)) => (symbol, Some(bass)),
+            None => (rest, None),
+        };
+
+        let quality = ChordQuality::from_symbol(symbol).ok_or(ChordParseError::UnknownQuality)?;
+        let chord = Self::new(root, quality);
+
+        let Some(bass) = bass else {
+            return Ok(chord);
+        };
+        let bass = match parse_pitch(bass) {
+            Some((bass, "")) => bass,
+            _ => return Err(ChordParseError::InvalidBass),
+        };
+        let inversion = chord
+            .pitches()
+            .iter()
+            .position(|pitch| *pitch == bass)
+            .ok_or(ChordParseError::InvalidBass)?;
+
+        Ok(chord.with_inversion(inversion as u8))
+    }
+}
+
+/// Parses a letter and its accidentals from the start of a string,
+/// returning the named pitch and the rest of the string.
+fn parse_pitch(name: &str) -> Option<(NamedPitch, &str)> {
+    let mut chars = name.char_indices();
+    let fifths: i8 = match chars.next()?.1 {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => -1,
+        'G' => 1,
+        'A' => 3,
+        'B' => 5,
+        _ => return None,
+    };
+
+    let mut accidentals: i8 = 0;
+    let mut rest = &name[1..];
+    for (index, char) in chars {
+        accidentals += match char {
+            '#' | '♯' => 1,
+            'b' | '♭' => -1,
+            '𝄪' => 2,
+            '𝄫' => -2,
+            _ => break,
+        };
+        rest = &name[index + char.len_utf8()..];
+
+        // Named pitches go up to triple sharps and flats.
+        if accidentals.abs() > 3 {
+            return None;
+        }
+    }
+
+    Some((NamedPitch::from_fifths(fifths + accidentals * 7)?, rest))
+}
+
+impl FromStr for Chord {
+    type Err = ChordParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Chord::parse(name)
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.quality.symbol())?;
+
+        match self.bass() {
+            Some(bass) if self.inversion > 0 => write!(f, "/{}", bass),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::{
+        named_pitch::NamedPitch::*,
+        note::{
+            AFour, BFive, BFlatFour, BFour, CFive, CFour, DFour, EFive, EFour, EThree, GFour,
+            GThree,
+        },
+    };
+
+    #[test]
+    fn test_parse() {
+        let chord = Chord::parse("Cm7b5").unwrap();
+        assert_eq!(chord.quality(), ChordQuality::HalfDiminished7);
+        assert_eq!(chord.pitches(), [C, EFlat, GFlat, BFlat]);
+
+        assert_eq!(
+            "F#maj7".parse(),
+            Ok(Chord::new(FSharp, ChordQuality::Major7))
+        );
+        assert_eq!("B♭-7".parse(), Ok(Chord::new(BFlat, ChordQuality::Minor7)));
+        assert_eq!("Ab".parse(), Ok(Chord::new(AFlat, ChordQuality::Major)));
+        assert_eq!(
+            "G7/B".parse(),
+            Ok(Chord::new(G, ChordQuality::Dominant7).with_inversion(1))
+        );
+
+        assert_eq!(Chord::parse(" "), Err(ChordParseError::Empty));
+        assert_eq!(Chord::parse("H7"), Err(ChordParseError::InvalidRoot));
+        assert_eq!(
+            Chord::parse("C###################"),
+            Err(ChordParseError::InvalidRoot)
+        );
+        assert_eq!(Chord::parse("Cblah"), Err(ChordParseError::UnknownQuality));
+        assert_eq!(Chord::parse("C/D"), Err(ChordParseError::InvalidBass));
+
+        let chord = Chord::new(DFlat, ChordQuality::HalfDiminished7).with_inversion(3);
+        assert_eq!(chord.to_string(), "D♭m7b5/C♭");
+        assert_eq!(chord.to_string().parse(), Ok(chord));
+    }
+
+    #[test]
+    fn test_inversions() {
+        let c_major = Chord::new(C, ChordQuality::Major);
+        assert_eq!(
+            c_major.notes(Octave::Four, Voicing::Close),
+            [CFour, EFour, GFour]
+        );
+        assert_eq!(
+            c_major
+                .with_inversion(1)
+                .notes(Octave::Four, Voicing::Close),
+            [EFour, GFour, CFive]
+        );
+        assert_eq!(
+            c_major
+                .with_inversion(2)
+                .notes(Octave::Four, Voicing::Close),
+            [GFour, CFive, EFive]
+        );
+        assert_eq!(c_major.with_inversion(3), c_major);
+
+        // An inversion past the notes, as a deserialized chord could have, has no notes.
+        let invalid = Chord {
+            inversion: 3,
+            ..c_major
+        };
+        assert_eq!(invalid.bass(), None);
+        assert!(invalid.notes(Octave::Four, Voicing::Close).is_empty());
+
+        // The bass lands in the octave, even when the letters wrap past B.
+        let a_minor = Chord::new(A, ChordQuality::Minor).with_inversion(1);
+        assert_eq!(a_minor.bass(), Some(C));
+        assert_eq!(
+            a_minor.notes(Octave::Four, Voicing::Close),
+            [CFour, EFour, AFour]
+        );
+
+        let c9 = Chord::new(C, ChordQuality::Dominant9).with_inversion(4);
+        assert_eq!(
+            c9.notes(Octave::Four, Voicing::Close),
+            [DFour, EFour, GFour, BFlatFour, CFive]
+        );
+    }
+
+    #[test]
+    fn test_voicings() {
+        let cmaj7 = Chord::new(C, ChordQuality::Major7);
+        assert_eq!(
+            cmaj7.notes(Octave::Four, Voicing::Drop2),
+            [GThree, CFour, EFour, BFour]
+        );
+        assert_eq!(
+            cmaj7.notes(Octave::Four, Voicing::Drop3),
+            [EThree, CFour, GFour, BFour]
+        );
+        assert_eq!(
+            cmaj7.notes(Octave::Four, Voicing::Spread),
+            [CFour, GFour, EFive, BFive]
+        );
+    }
+}
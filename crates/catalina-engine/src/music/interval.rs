@@ -0,0 +1,352 @@
+//! A module for working with intervals between named pitches.
+
+use crate::prelude::*;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{named_pitch::NamedPitch, note::Note, octave::Octave};
+
+/// The quality of an interval, such as the major in a major third.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug, Ord, PartialOrd)]
+pub enum IntervalQuality {
+    Diminished,
+    Minor,
+    Perfect,
+    Major,
+    Augmented,
+}
+
+/// An interval between two named pitches, such as a major third or a
+/// perfect fifth.
+///
+/// An interval counts letters as well as semitones, so C to E is a major
+/// third while C to F♭ is a diminished fourth, even though both are four
+/// semitones. Transposing by an interval keeps that spelling, so E up a
+/// major third is G♯ rather than A♭.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+pub struct Interval {
+    quality: IntervalQuality,
+    /// The number of letters the interval spans, counting both ends, so a
+    /// unison is 1 and an octave is 8.
+    number: u8,
+}
+
+/// The semitones of the major and perfect intervals in an octave, from the unison.
+const SEMITONES: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// The fifths of the major and perfect intervals in an octave, from the unison.
+const FIFTHS: [i8; 7] = [0, 2, 4, -1, 1, 3, 5];
+
+impl Interval {
+    pub const UNISON: Self = Self::new_unchecked(IntervalQuality::Perfect, 1);
+    pub const AUGMENTED_UNISON: Self = Self::new_unchecked(IntervalQuality::Augmented, 1);
+    pub const MINOR_SECOND: Self = Self::new_unchecked(IntervalQuality::Minor, 2);
+    pub const MAJOR_SECOND: Self = Self::new_unchecked(IntervalQuality::Major, 2);
+    pub const AUGMENTED_SECOND: Self = Self::new_unchecked(IntervalQuality::Augmented, 2);
+    pub const MINOR_THIRD: Self = Self::new_unchecked(IntervalQuality::Minor, 3);
+    pub const MAJOR_THIRD: Self = Self::new_unchecked(IntervalQuality::Major, 3);
+    pub const PERFECT_FOURTH: Self = Self::new_unchecked(IntervalQuality::Perfect, 4);
+    pub const AUGMENTED_FOURTH: Self = Self::new_unchecked(IntervalQuality::Augmented, 4);
+    pub const DIMINISHED_FIFTH: Self = Self::new_unchecked(IntervalQuality::Diminished, 5);
+    pub const PERFECT_FIFTH: Self = Self::new_unchecked(IntervalQuality::Perfect, 5);
+    pub const AUGMENTED_FIFTH: Self = Self::new_unchecked(IntervalQuality::Augmented, 5);
+    pub const MINOR_SIXTH: Self = Self::new_unchecked(IntervalQuality::Minor, 6);
+    pub const MAJOR_SIXTH: Self = Self::new_unchecked(IntervalQuality::Major, 6);
+    pub const AUGMENTED_SIXTH: Self = Self::new_unchecked(IntervalQuality::Augmented, 6);
+    pub const DIMINISHED_SEVENTH: Self = Self::new_unchecked(IntervalQuality::Diminished, 7);
+    pub const MINOR_SEVENTH: Self = Self::new_unchecked(IntervalQuality::Minor, 7);
+    pub const MAJOR_SEVENTH: Self = Self::new_unchecked(IntervalQuality::Major, 7);
+    pub const OCTAVE: Self = Self::new_unchecked(IntervalQuality::Perfect, 8);
+    pub const MINOR_NINTH: Self = Self::new_unchecked(IntervalQuality::Minor, 9);
+    pub const MAJOR_NINTH: Self = Self::new_unchecked(IntervalQuality::Major, 9);
+    pub const AUGMENTED_NINTH: Self = Self::new_unchecked(IntervalQuality::Augmented, 9);
+    pub const PERFECT_ELEVENTH: Self = Self::new_unchecked(IntervalQuality::Perfect, 11);
+    pub const AUGMENTED_ELEVENTH: Self = Self::new_unchecked(IntervalQuality::Augmented, 11);
+    pub const MINOR_THIRTEENTH: Self = Self::new_unchecked(IntervalQuality::Minor, 13);
+    pub const MAJOR_THIRTEENTH: Self = Self::new_unchecked(IntervalQuality::Major, 13);
+
+    const fn new_unchecked(quality: IntervalQuality, number: u8) -> Self {
+        Self { quality, number }
+    }
+
+    /// The widest interval number, spanning from the lowest octave to the
+    /// highest.
+    pub const MAX_NUMBER: u8 = 112;
+
+    /// Constructs an interval, if the quality fits the number.
+    ///
+    /// Unisons, fourths and fifths (and their compounds) are perfect, while
+    /// the others are major or minor. Either can be augmented or diminished,
+    /// except for the unison, which can't be diminished. Numbers past
+    /// [`Interval::MAX_NUMBER`] aren't accepted.
+    pub const fn new(quality: IntervalQuality, number: u8) -> Option<Self> {
+        if number == 0 || number > Self::MAX_NUMBER {
+            return None;
+        }
+
+        let valid = match quality {
+            IntervalQuality::Perfect => Self::is_perfect_number(number),
+            IntervalQuality::Major | IntervalQuality::Minor => !Self::is_perfect_number(number),
+            IntervalQuality::Augmented => true,
+            IntervalQuality::Diminished => number != 1,
+        };
+
+        if valid {
+            Some(Self::new_unchecked(quality, number))
+        } else {
+            None
+        }
+    }
+
+    const fn is_perfect_number(number: u8) -> bool {
+        matches!(number.saturating_sub(1) % 7, 0 | 3 | 4)
+    }
+
+    #[inline]
+    pub const fn quality(&self) -> IntervalQuality {
+        self.quality
+    }
+
+    /// Returns the number of letters the interval spans, counting both ends.
+    #[inline]
+    pub const fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Returns the number of letters the interval moves a pitch by, so a
+    /// third is 2 steps.
+    #[inline]
+    pub const fn steps(&self) -> u8 {
+        self.number.saturating_sub(1)
+    }
+
+    /// Returns true if the interval is wider than an octave.
+    #[inline]
+    pub const fn is_compound(&self) -> bool {
+        self.number > 8
+    }
+
+    /// Returns the interval reduced to within an octave, so a major ninth
+    /// becomes a major second. Octaves stay octaves.
+    pub const fn simple(&self) -> Self {
+        if self.number <= 8 {
+            *self
+        } else {
+            Self::new_unchecked(self.quality, (self.number - 2) % 7 + 2)
+        }
+    }
+
+    /// Returns how far the quality moves the interval from the major or
+    /// perfect interval of the same number, in semitones.
+    const fn alteration(&self) -> i8 {
+        let perfect = Self::is_perfect_number(self.number);
+
+        match self.quality {
+            IntervalQuality::Perfect | IntervalQuality::Major => 0,
+            IntervalQuality::Minor => -1,
+            IntervalQuality::Augmented => 1,
+            IntervalQuality::Diminished if perfect => -1,
+            IntervalQuality::Diminished => -2,
+        }
+    }
+
+    /// Returns the size of the interval in semitones.
+    pub const fn semitones(&self) -> u8 {
+        let steps = self.steps() as i16;
+        let semitones = SEMITONES[(steps % 7) as usize] as i16 + steps / 7 * 12;
+        let semitones = semitones + self.alteration() as i16;
+
+        // Only intervals past `MAX_NUMBER` reach the limit, which `new`
+        // doesn't construct.
+        if semitones > u8::MAX as i16 {
+            u8::MAX
+        } else {
+            semitones as u8
+        }
+    }
+
+    /// Returns how many perfect fifths the interval moves a pitch along the
+    /// line of fifths, ignoring which octave it lands in.
+    pub const fn fifths(&self) -> i8 {
+        FIFTHS[(self.steps() % 7) as usize] + self.alteration() * 7
+    }
+
+    /// Returns the simple interval up from one named pitch to the next,
+    /// if it can be named.
+    ///
+    /// The interval is within an octave, so `B` to `C` is a minor second.
+    /// Returns `None` for intervals past augmented or diminished.
+    pub fn between(from: NamedPitch, to: NamedPitch) -> Option<Self> {
+        let steps = (to.letter_index() + 7 - from.letter_index()) % 7;
+        Self::spanning(from, to, steps)
+    }
+
+    /// Returns the interval up from one note to another, including any
+    /// octaves between them, or `None` if the second note is lower.
+    pub fn between_notes(from: Note, to: Note) -> Option<Self> {
+        let letters =
+            |note: Note| note.octave() as i16 * 7 + note.named_pitch().letter_index() as i16;
+        let steps = u8::try_from(letters(to) - letters(from)).ok()?;
+
+        Self::spanning(from.named_pitch(), to.named_pitch(), steps)
+    }
+
+    /// Returns the interval between two named pitches a number of letters apart.
+    fn spanning(from: NamedPitch, to: NamedPitch, steps: u8) -> Option<Self> {
+        let number = steps.checked_add(1)?;
+        let fifths = to.fifths() - from.fifths() - FIFTHS[(steps % 7) as usize];
+
+        let quality = match (fifths, Self::is_perfect_number(number)) {
+            (0, true) => IntervalQuality::Perfect,
+            (0, false) => IntervalQuality::Major,
+            (-7, false) => IntervalQuality::Minor,
+            (7, _) => IntervalQuality::Augmented,
+            (-7, true) | (-14, false) => IntervalQuality::Diminished,
+            _ => return None,
+        };
+
+        Self::new(quality, number)
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quality = match self.quality {
+            IntervalQuality::Diminished => "d",
+            IntervalQuality::Minor => "m",
+            IntervalQuality::Perfect => "P",
+            IntervalQuality::Major => "M",
+            IntervalQuality::Augmented => "A",
+        };
+
+        write!(f, "{}{}", quality, self.number)
+    }
+}
+
+impl NamedPitch {
+    /// Returns the named pitch an interval above this one, spelled by the
+    /// interval, or `None` if it would need more than three accidentals.
+    pub fn transpose(&self, interval: Interval) -> Option<Self> {
+        NamedPitch::from_fifths(self.fifths().checked_add(interval.fifths())?)
+    }
+
+    /// Returns the named pitch an interval below this one, spelled by the
+    /// interval, or `None` if it would need more than three accidentals.
+    pub fn transpose_down(&self, interval: Interval) -> Option<Self> {
+        NamedPitch::from_fifths(self.fifths().checked_sub(interval.fifths())?)
+    }
+}
+
+impl Note {
+    /// Returns the note an interval above this one, spelled by the interval.
+    ///
+    /// Returns `None` if the note would be past the highest octave or need
+    /// more than three accidentals.
+    pub fn transpose(&self, interval: Interval) -> Option<Self> {
+        let named_pitch = self.named_pitch().transpose(interval)?;
+        self.with_letter_steps(named_pitch, interval.steps() as i16)
+    }
+
+    /// Returns the note an interval below this one, spelled by the interval.
+    ///
+    /// Returns `None` if the note would be below the lowest octave or need
+    /// more than three accidentals.
+    pub fn transpose_down(&self, interval: Interval) -> Option<Self> {
+        let named_pitch = self.named_pitch().transpose_down(interval)?;
+        self.with_letter_steps(named_pitch, -(interval.steps() as i16))
+    }
+
+    /// Returns the note with a named pitch a number of letters away,
+    /// moving to the next octave whenever the letters pass from B to C.
+    fn with_letter_steps(&self, named_pitch: NamedPitch, steps: i16) -> Option<Self> {
+        let letters = self.octave() as i16 * 7 + self.named_pitch().letter_index() as i16 + steps;
+        let octave = Octave::try_from(u8::try_from(letters.div_euclid(7)).ok()?).ok()?;
+
+        Some(Note::new(named_pitch, octave))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::{
+        named_pitch::NamedPitch::*,
+        note::{BFour, CFive, CFour, EFour},
+    };
+
+    #[test]
+    fn test_qualities() {
+        assert_eq!(Interval::new(IntervalQuality::Major, 5), None);
+        assert_eq!(Interval::new(IntervalQuality::Perfect, 3), None);
+        assert_eq!(Interval::new(IntervalQuality::Diminished, 1), None);
+        assert_eq!(Interval::new(IntervalQuality::Augmented, 255), None);
+
+        let widest = Interval::new(IntervalQuality::Augmented, Interval::MAX_NUMBER).unwrap();
+        assert_eq!(widest.semitones(), 15 * 12 + 12);
+
+        assert_eq!(Interval::MAJOR_THIRD.semitones(), 4);
+        assert_eq!(Interval::DIMINISHED_FIFTH.semitones(), 6);
+        assert_eq!(Interval::DIMINISHED_SEVENTH.semitones(), 9);
+        assert_eq!(Interval::MAJOR_NINTH.semitones(), 14);
+        assert_eq!(Interval::MAJOR_NINTH.simple(), Interval::MAJOR_SECOND);
+        assert_eq!(Interval::OCTAVE.simple(), Interval::OCTAVE);
+
+        assert_eq!(Interval::MINOR_SEVENTH.to_string(), "m7");
+        assert_eq!(Interval::AUGMENTED_FOURTH.to_string(), "A4");
+    }
+
+    #[test]
+    fn test_spelling() {
+        // The same number of semitones can be spelled as different intervals.
+        assert_eq!(Interval::between(C, E), Some(Interval::MAJOR_THIRD));
+        assert_eq!(
+            Interval::between(C, FFlat),
+            Interval::new(IntervalQuality::Diminished, 4)
+        );
+        assert_eq!(Interval::between(B, C), Some(Interval::MINOR_SECOND));
+        assert_eq!(Interval::between(D, C), Some(Interval::MINOR_SEVENTH));
+        assert_eq!(
+            Interval::between(FSharp, C),
+            Some(Interval::DIMINISHED_FIFTH)
+        );
+        assert_eq!(Interval::between(CDoubleFlat, FSharp), None);
+
+        assert_eq!(
+            Interval::between_notes(CFour, Note::new(D, Octave::Five)),
+            Some(Interval::MAJOR_NINTH)
+        );
+        assert_eq!(
+            Interval::between_notes(CFour, Note::new(CFlat, Octave::Five)),
+            Interval::new(IntervalQuality::Diminished, 8)
+        );
+        assert_eq!(Interval::between_notes(CFive, CFour), None);
+    }
+
+    #[test]
+    fn test_transpose() {
+        assert_eq!(E.transpose(Interval::MAJOR_THIRD), Some(GSharp));
+        assert_eq!(AFlat.transpose(Interval::MAJOR_THIRD), Some(C));
+        assert_eq!(BFlat.transpose(Interval::AUGMENTED_FOURTH), Some(E));
+        assert_eq!(C.transpose_down(Interval::MINOR_THIRD), Some(A));
+        assert_eq!(BTripleSharp.transpose(Interval::AUGMENTED_FIFTH), None);
+
+        assert_eq!(BFour.transpose(Interval::MINOR_SECOND), Some(CFive));
+        assert_eq!(
+            EFour.transpose(Interval::MAJOR_THIRTEENTH),
+            Some(Note::new(CSharp, Octave::Six))
+        );
+        assert_eq!(
+            CFour.transpose_down(Interval::MINOR_SECOND),
+            Some(Note::new(B, Octave::Three))
+        );
+        assert_eq!(
+            Note::new(C, Octave::Zero).transpose_down(Interval::MINOR_SECOND),
+            None
+        );
+    }
+}
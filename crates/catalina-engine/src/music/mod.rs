@@ -1,5 +1,10 @@
+pub mod chord;
 pub mod helpers;
+pub mod interval;
 pub mod named_pitch;
 pub mod note;
 pub mod octave;
 pub mod pitch;
+pub mod quantizer;
+pub mod scale;
+pub mod tuner;
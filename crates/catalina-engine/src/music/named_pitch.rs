@@ -233,6 +233,29 @@ impl HasLetter for NamedPitch {
 }
 
 impl NamedPitch {
+    /// Returns how many perfect fifths the pitch is above C, or below it
+    /// when negative, following the order the pitches are declared in.
+    ///
+    /// Moving along the line of fifths keeps the spelling of a pitch, so
+    /// intervals are applied as a number of fifths.
+    pub const fn fifths(&self) -> i8 {
+        *self as i8 - NamedPitch::C as i8
+    }
+
+    /// Returns the pitch a number of perfect fifths above C, if it can be
+    /// spelled with at most three sharps or flats.
+    pub fn from_fifths(fifths: i8) -> Option<Self> {
+        let index = fifths.checked_add(NamedPitch::C as i8)?;
+        ALL_PITCHES.get(usize::try_from(index).ok()?).copied()
+    }
+
+    /// Returns the position of the pitch's letter from C, so C is 0 and B is 6.
+    pub const fn letter_index(&self) -> u8 {
+        // The letters repeat every seven fifths, starting from C: C G D A E B F.
+        const LETTERS: [u8; 7] = [0, 4, 1, 5, 2, 6, 3];
+        LETTERS[self.fifths().rem_euclid(7) as usize]
+    }
+
     fn static_name(&self) -> &'static str {
         match self {
             NamedPitch::FTripleFlat => "F♭𝄫",
@@ -378,6 +401,12 @@ impl Sub<i8> for NamedPitch {
     }
 }
 
+impl Display for NamedPitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.static_name())
+    }
+}
+
 impl From<Pitch> for NamedPitch {
     fn from(pitch: Pitch) -> Self {
         NamedPitch::from(&pitch)
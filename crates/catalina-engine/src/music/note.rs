@@ -52,6 +52,44 @@ pub struct Note {
 }
 
 impl Note {
+    /// Builds a note from a named pitch and an octave.
+    pub const fn new(named_pitch: NamedPitch, octave: Octave) -> Self {
+        Self {
+            named_pitch,
+            octave,
+        }
+    }
+
+    /// Returns the note for a MIDI note number, where 60 is C4.
+    ///
+    /// Black keys are spelled as flats. Returns `None` for notes below C0,
+    /// which is MIDI note 12.
+    pub fn from_midi(number: u8) -> Option<Self> {
+        let octave = Octave::try_from((number / 12).checked_sub(1)?).ok()?;
+        let pitch = Pitch::try_from(number % 12).ok()?;
+
+        Some(Self::new(pitch.into(), octave))
+    }
+
+    /// Returns the MIDI note number of the note, where 60 is C4.
+    ///
+    /// Returns `None` if the note is outside the MIDI range.
+    pub fn midi(&self) -> Option<u8> {
+        u8::try_from(self.semitone()).ok().filter(|n| *n <= 127)
+    }
+
+    /// Returns how many semitones the note is above C-1, like a MIDI note
+    /// number without the limits of its range.
+    pub(crate) fn semitone(&self) -> i16 {
+        let octave = self.octave as i16 + self.octave_offset() as i16;
+        (octave + 1) * 12 + self.pitch() as i16
+    }
+
+    /// Returns the named pitch of the note.
+    pub const fn named_pitch(&self) -> NamedPitch {
+        self.named_pitch
+    }
+
     /// Returns the octave of the note.
     pub const fn octave(&self) -> Octave {
         self.octave
@@ -67,24 +105,26 @@ impl Note {
         let mut octave = self.octave();
         let base_frequency = self.pitch().base_frequency();
 
+        octave += self.octave_offset();
+
+        // Not sure why we need the +1.0 on the end, but without it all the tuning was 1 octave off.
+        base_frequency * libm::powf(2.0_f32, octave as u8 as f32)
+    }
+
+    /// Returns how many octaves the note sounds away from its written octave,
+    /// for notes spelled across the B/C boundary.
+    fn octave_offset(&self) -> i8 {
         match self.named_pitch {
             NamedPitch::ATripleSharp
             | NamedPitch::BTripleSharp
             | NamedPitch::BDoubleSharp
-            | NamedPitch::BSharp => {
-                octave += 1;
-            }
+            | NamedPitch::BSharp => 1,
             NamedPitch::DTripleFlat
             | NamedPitch::CTripleFlat
             | NamedPitch::CDoubleFlat
-            | NamedPitch::CFlat => {
-                octave -= 1;
-            }
-            _ => {}
+            | NamedPitch::CFlat => -1,
+            _ => 0,
         }
-
-        // Not sure why we need the +1.0 on the end, but without it all the tuning was 1 octave off.
-        base_frequency * libm::powf(2.0_f32, octave as u8 as f32)
     }
 }
 
@@ -0,0 +1,389 @@
+//! A quantizer that snaps pitches to the notes of a scale.
+//!
+//! Pitches are quantized as semitones, numbered like MIDI notes but
+//! continuous, so notes, MIDI note numbers, V/oct control voltages and
+//! frequencies all snap the same way. Continuous inputs hold their last
+//! note with hysteresis, so a CV sitting between two notes doesn't chatter.
+
+use crate::{
+    core::Hertz,
+    music::{
+        named_pitch::NamedPitch,
+        note::{CFour, Note},
+        octave::Octave,
+        pitch::HasPitch,
+        scale::Scale,
+        tuner::{A4_MIDI, CONCERT_A},
+    },
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Which way a pitch between two scale notes is snapped.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Rounding {
+    /// Snaps to the closest note, or the lower one when it's halfway.
+    #[default]
+    Nearest,
+    /// Snaps to the closest note at or above the pitch.
+    Up,
+    /// Snaps to the closest note at or below the pitch.
+    Down,
+}
+
+/// Snaps pitches to the degrees of a scale.
+///
+/// Degrees can be masked off to quantize to part of the scale, such as
+/// just the chord tones. Quantized notes are spelled from the scale, so a
+/// pitch snapped to F major's fourth is a B♭ rather than an A♯.
+#[derive(Debug, Clone)]
+pub struct Quantizer {
+    scale: Scale,
+    /// A bit for each degree of the scale that's snapped to, from the root.
+    mask: u16,
+    /// The spelling of each pitch class that's snapped to, from C.
+    allowed: [Option<NamedPitch>; 12],
+
+    rounding: Rounding,
+    /// How much closer another note must be before a continuous input
+    /// leaves its last note, in semitones.
+    hysteresis: f32,
+    /// The last note a continuous input was snapped to, in semitones.
+    last: Option<i16>,
+
+    /// The note at 0V for V/oct inputs, in semitones.
+    zero_volts: f32,
+    /// The frequency of A4 for frequency inputs.
+    reference: Hertz,
+}
+
+impl Quantizer {
+    /// Constructs a quantizer snapping to every degree of a scale.
+    pub fn new(scale: Scale) -> Self {
+        let mut quantizer = Self {
+            scale,
+            mask: 0,
+            allowed: [None; 12],
+            rounding: Rounding::Nearest,
+            hysteresis: 0.0,
+            last: None,
+            zero_volts: CFour.midi().unwrap_or(60) as f32,
+            reference: CONCERT_A,
+        };
+        quantizer.set_mask(u16::MAX);
+
+        quantizer
+    }
+
+    #[inline]
+    pub fn scale(&self) -> &Scale {
+        &self.scale
+    }
+
+    /// Sets the scale, snapping to all of its degrees.
+    pub fn set_scale(&mut self, scale: Scale) {
+        self.scale = scale;
+        self.set_mask(u16::MAX);
+    }
+
+    /// Returns the mask of degrees that are snapped to, with bit 0 for the root.
+    #[inline]
+    pub const fn mask(&self) -> u16 {
+        self.mask
+    }
+
+    /// Sets which degrees are snapped to, with bit 0 for the root.
+    ///
+    /// Bits past the number of degrees in the scale are ignored. With no
+    /// degrees set, nothing is quantized.
+    pub fn set_mask(&mut self, mask: u16) {
+        let degrees = self.scale.len().min(u16::BITS as usize);
+        self.mask = mask
+            & u16::MAX
+                .checked_shr(u16::BITS - degrees as u32)
+                .unwrap_or(0);
+
+        self.allowed = [None; 12];
+        for (degree, interval) in self.scale.intervals().iter().enumerate() {
+            if self.mask & (1 << degree) == 0 {
+                continue;
+            }
+            if let Some(named_pitch) = self.scale.root().transpose(*interval) {
+                self.allowed[named_pitch.pitch() as usize] = Some(named_pitch);
+            }
+        }
+    }
+
+    /// Sets whether one degree of the scale is snapped to.
+    pub fn set_degree(&mut self, degree: usize, active: bool) {
+        if degree >= u16::BITS as usize {
+            return;
+        }
+
+        if active {
+            self.set_mask(self.mask | 1 << degree);
+        } else {
+            self.set_mask(self.mask & !(1 << degree));
+        }
+    }
+
+    #[inline]
+    pub const fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding;
+    }
+
+    #[inline]
+    pub const fn hysteresis(&self) -> f32 {
+        self.hysteresis
+    }
+
+    /// Sets how much closer another note must be, in semitones, before a
+    /// continuous input leaves the note it was last snapped to.
+    pub fn set_hysteresis(&mut self, semitones: f32) {
+        self.hysteresis = semitones.max(0.0);
+    }
+
+    /// Sets the note at 0V for V/oct inputs, which is C4 by default.
+    pub fn set_zero_volts(&mut self, note: Note) {
+        self.zero_volts = note.semitone() as f32;
+    }
+
+    /// Sets the frequency of A4 for frequency inputs.
+    pub fn set_reference(&mut self, reference: Hertz) {
+        self.reference = reference;
+    }
+
+    /// Forgets the last note continuous inputs were snapped to.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+
+    /// Returns true if the pitch class of a note in semitones is snapped to.
+    fn is_allowed(&self, semitones: i16) -> bool {
+        self.allowed[semitones.rem_euclid(12) as usize].is_some()
+    }
+
+    /// Snaps a pitch in semitones to a note in the scale, without hysteresis.
+    fn snap(&self, semitones: f32) -> Option<i16> {
+        if !semitones.is_finite() || self.mask == 0 {
+            return None;
+        }
+
+        // Pitches far outside the range of notes are kept clear of the
+        // limits of an i16, and every pitch class comes round within an
+        // octave either side.
+        let semitones = semitones.clamp(-128.0, 256.0);
+        let below = libm::floorf(semitones) as i16;
+        let mut candidates = (below - 12..=below + 13).filter(|note| self.is_allowed(*note));
+
+        match self.rounding {
+            Rounding::Nearest => candidates.min_by(|a, b| {
+                let a = (*a as f32 - semitones).abs();
+                let b = (*b as f32 - semitones).abs();
+                a.total_cmp(&b)
+            }),
+            Rounding::Up => candidates.find(|note| *note as f32 >= semitones),
+            Rounding::Down => candidates.rfind(|note| *note as f32 <= semitones),
+        }
+    }
+
+    /// Quantizes a note, spelling it from the scale.
+    ///
+    /// Returns `None` if no degrees are active or the note would be outside
+    /// the octave range.
+    pub fn quantize_note(&self, note: Note) -> Option<Note> {
+        let semitones = self.snap(note.semitone() as f32)?;
+        let named_pitch = self.allowed[semitones.rem_euclid(12) as usize]?;
+
+        // Notes like B♯ and C♭ sit an octave off from their pitch, so the
+        // octave is found from where the spelled note lands.
+        let guess = Note::new(named_pitch, Octave::Zero);
+        let octave = (semitones - guess.semitone()).div_euclid(12);
+        let octave = Octave::try_from(u8::try_from(octave).ok()?).ok()?;
+
+        Some(Note::new(named_pitch, octave))
+    }
+
+    /// Quantizes a MIDI note number.
+    ///
+    /// Returns `None` if no degrees are active or the note would be outside
+    /// the MIDI range.
+    pub fn quantize_midi(&self, note: u8) -> Option<u8> {
+        let semitones = self.snap(note as f32)?;
+        u8::try_from(semitones).ok().filter(|n| *n <= 127)
+    }
+
+    /// Quantizes a continuous pitch in semitones, numbered like MIDI notes,
+    /// holding the last note with the hysteresis.
+    ///
+    /// Returns `None` if no degrees are active or the pitch snaps outside
+    /// the MIDI range.
+    pub fn quantize(&mut self, semitones: f32) -> Option<f32> {
+        let mut note = self
+            .snap(semitones)
+            .filter(|note| (0..=127).contains(note))?;
+
+        // The last note is only held while it's still on the side of the
+        // input the rounding snaps to.
+        let holds = |last: i16| match self.rounding {
+            Rounding::Nearest => true,
+            Rounding::Up => last as f32 >= semitones,
+            Rounding::Down => last as f32 <= semitones,
+        };
+        if let Some(last) = self.last
+            && last != note
+            && self.is_allowed(last)
+            && holds(last)
+        {
+            let closer = (semitones - last as f32).abs() - (semitones - note as f32).abs();
+            if closer < self.hysteresis {
+                note = last;
+            }
+        }
+        self.last = Some(note);
+
+        Some(note as f32)
+    }
+
+    /// Quantizes a V/oct control voltage, holding the last note with the
+    /// hysteresis.
+    ///
+    /// Returns `None` if no degrees are active or the voltage snaps outside
+    /// the MIDI range.
+    pub fn quantize_volts(&mut self, volts: f32) -> Option<f32> {
+        let semitones = self.quantize(self.zero_volts + volts * 12.0)?;
+        Some((semitones - self.zero_volts) / 12.0)
+    }
+
+    /// Quantizes a frequency in equal temperament from the reference,
+    /// holding the last note with the hysteresis.
+    ///
+    /// Returns `None` if no degrees are active, the frequency isn't positive
+    /// or it snaps outside the MIDI range.
+    pub fn quantize_hertz(&mut self, frequency: Hertz) -> Option<Hertz> {
+        if frequency.hertz() <= 0.0 {
+            return None;
+        }
+
+        let semitones = A4_MIDI + 12.0 * libm::log2f(frequency.hertz() / self.reference.hertz());
+        let semitones = self.quantize(semitones)?;
+
+        Some(Hertz(
+            self.reference.hertz() * libm::exp2f((semitones - A4_MIDI) / 12.0),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::{
+        named_pitch::NamedPitch::*,
+        note::{ASharpFour, BFlatFour, BFour, CFive},
+        scale::ScaleKind,
+    };
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_rounding() {
+        let mut quantizer = Quantizer::new(Scale::new(C, ScaleKind::Major));
+
+        assert_eq!(quantizer.quantize_midi(61), Some(60));
+        quantizer.set_rounding(Rounding::Up);
+        assert_eq!(quantizer.quantize_midi(61), Some(62));
+        quantizer.set_rounding(Rounding::Down);
+        assert_eq!(quantizer.quantize_midi(66), Some(65));
+
+        // Notes are spelled from the scale.
+        let quantizer = Quantizer::new(Scale::new(F, ScaleKind::Major));
+        assert_eq!(quantizer.quantize_note(ASharpFour), Some(BFlatFour));
+        assert_eq!(quantizer.quantize_note(BFour), Some(BFlatFour));
+        assert_eq!(quantizer.quantize_note(CFive), Some(CFive));
+
+        let quantizer = Quantizer::new(Scale::new(CSharp, ScaleKind::Major));
+        assert_eq!(
+            quantizer.quantize_note(CFive),
+            Some(Note::new(BSharp, Octave::Four))
+        );
+    }
+
+    #[test]
+    fn test_hysteresis_rounding() {
+        let mut quantizer = Quantizer::new(Scale::new(C, ScaleKind::Major));
+        quantizer.set_hysteresis(0.5);
+
+        // Rounding down never holds a note above the input.
+        quantizer.set_rounding(Rounding::Down);
+        assert_eq!(quantizer.quantize(65.0), Some(65.0));
+        assert_eq!(quantizer.quantize(64.9), Some(64.0));
+
+        // Rounding up never holds a note below the input.
+        quantizer.set_rounding(Rounding::Up);
+        assert_eq!(quantizer.quantize(64.0), Some(64.0));
+        assert_eq!(quantizer.quantize(64.1), Some(65.0));
+
+        // Within the rounding, the last note is still held.
+        quantizer.set_hysteresis(1.5);
+        assert_eq!(quantizer.quantize(63.9), Some(65.0));
+    }
+
+    #[test]
+    fn test_mask() {
+        let mut quantizer = Quantizer::new(Scale::new(C, ScaleKind::Major));
+
+        // Only the triad's root, third and fifth.
+        quantizer.set_mask(0b10101);
+        assert_eq!(quantizer.quantize_midi(65), Some(64));
+        assert_eq!(quantizer.quantize_midi(66), Some(67));
+        assert_eq!(quantizer.quantize_midi(70), Some(72));
+
+        quantizer.set_degree(6, true);
+        assert_eq!(quantizer.quantize_midi(70), Some(71));
+
+        quantizer.set_mask(0);
+        assert_eq!(quantizer.quantize_midi(60), None);
+        quantizer.set_mask(u16::MAX);
+        assert_eq!(quantizer.mask(), 0b111_1111);
+    }
+
+    #[test]
+    fn test_continuous() {
+        let mut quantizer = Quantizer::new(Scale::new(C, ScaleKind::Major));
+        quantizer.set_hysteresis(0.3);
+
+        // E and F are a semitone apart, so the input holds E past the halfway point.
+        assert_eq!(quantizer.quantize(64.0), Some(64.0));
+        assert_eq!(quantizer.quantize(64.6), Some(64.0));
+        assert_eq!(quantizer.quantize(64.7), Some(65.0));
+        assert_eq!(quantizer.quantize(64.4), Some(65.0));
+        assert_eq!(quantizer.quantize(64.3), Some(64.0));
+
+        quantizer.reset();
+        quantizer.set_hysteresis(0.0);
+        assert_eq!(quantizer.quantize_volts(0.04), Some(0.0));
+        assert_float_eq!(
+            quantizer.quantize_volts(1.1 / 12.0).unwrap(),
+            2.0 / 12.0,
+            abs <= 1e-6
+        );
+
+        // Pitches that snap outside the MIDI range aren't quantized.
+        assert_eq!(quantizer.quantize(127.0), Some(127.0));
+        assert_eq!(quantizer.quantize(1e6), None);
+        assert_eq!(quantizer.quantize_volts(-1e9), None);
+        assert_eq!(quantizer.quantize_hertz(Hertz(1e9)), None);
+        assert_eq!(quantizer.quantize_hertz(Hertz(f32::INFINITY)), None);
+
+        let frequency = quantizer.quantize_hertz(Hertz(445.0)).unwrap();
+        assert_float_eq!(frequency.hertz(), 440.0, abs <= 1e-3);
+        assert_eq!(quantizer.quantize_hertz(Hertz(0.0)), None);
+    }
+}
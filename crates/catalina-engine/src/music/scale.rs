@@ -0,0 +1,397 @@
+//! A module for working with scales and modes.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use heapless::Vec;
+
+use super::{
+    interval::{Interval, Interval as I},
+    named_pitch::NamedPitch,
+    note::Note,
+    octave::Octave,
+    pitch::{HasPitch, Pitch},
+};
+
+/// The most degrees a scale can have.
+pub const MAX_DEGREES: usize = 12;
+
+/// The seven modes of the major scale.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+pub enum Mode {
+    Ionian,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Aeolian,
+    Locrian,
+}
+
+/// The scales with intervals built in.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+pub enum ScaleKind {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    /// The ascending melodic minor, with a raised sixth and seventh.
+    MelodicMinor,
+    MajorPentatonic,
+    MinorPentatonic,
+    /// The minor pentatonic with an added flat fifth.
+    Blues,
+    WholeTone,
+    /// The chromatic scale, spelled with sharps.
+    Chromatic,
+    Mode(Mode),
+}
+
+const IONIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MAJOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MAJOR_SIXTH,
+    I::MAJOR_SEVENTH,
+];
+const DORIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MAJOR_SIXTH,
+    I::MINOR_SEVENTH,
+];
+const PHRYGIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MINOR_SECOND,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MINOR_SIXTH,
+    I::MINOR_SEVENTH,
+];
+const LYDIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MAJOR_THIRD,
+    I::AUGMENTED_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MAJOR_SIXTH,
+    I::MAJOR_SEVENTH,
+];
+const MIXOLYDIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MAJOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MAJOR_SIXTH,
+    I::MINOR_SEVENTH,
+];
+const AEOLIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MINOR_SIXTH,
+    I::MINOR_SEVENTH,
+];
+const LOCRIAN: [Interval; 7] = [
+    I::UNISON,
+    I::MINOR_SECOND,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::DIMINISHED_FIFTH,
+    I::MINOR_SIXTH,
+    I::MINOR_SEVENTH,
+];
+const HARMONIC_MINOR: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MINOR_SIXTH,
+    I::MAJOR_SEVENTH,
+];
+const MELODIC_MINOR: [Interval; 7] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MAJOR_SIXTH,
+    I::MAJOR_SEVENTH,
+];
+const MAJOR_PENTATONIC: [Interval; 5] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MAJOR_THIRD,
+    I::PERFECT_FIFTH,
+    I::MAJOR_SIXTH,
+];
+const MINOR_PENTATONIC: [Interval; 5] = [
+    I::UNISON,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::PERFECT_FIFTH,
+    I::MINOR_SEVENTH,
+];
+const BLUES: [Interval; 6] = [
+    I::UNISON,
+    I::MINOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::DIMINISHED_FIFTH,
+    I::PERFECT_FIFTH,
+    I::MINOR_SEVENTH,
+];
+const WHOLE_TONE: [Interval; 6] = [
+    I::UNISON,
+    I::MAJOR_SECOND,
+    I::MAJOR_THIRD,
+    I::AUGMENTED_FOURTH,
+    I::AUGMENTED_FIFTH,
+    I::MINOR_SEVENTH,
+];
+const CHROMATIC: [Interval; 12] = [
+    I::UNISON,
+    I::AUGMENTED_UNISON,
+    I::MAJOR_SECOND,
+    I::AUGMENTED_SECOND,
+    I::MAJOR_THIRD,
+    I::PERFECT_FOURTH,
+    I::AUGMENTED_FOURTH,
+    I::PERFECT_FIFTH,
+    I::AUGMENTED_FIFTH,
+    I::MAJOR_SIXTH,
+    I::AUGMENTED_SIXTH,
+    I::MAJOR_SEVENTH,
+];
+
+impl Mode {
+    /// Returns the intervals of the mode from its root.
+    pub const fn intervals(&self) -> &'static [Interval] {
+        match self {
+            Mode::Ionian => &IONIAN,
+            Mode::Dorian => &DORIAN,
+            Mode::Phrygian => &PHRYGIAN,
+            Mode::Lydian => &LYDIAN,
+            Mode::Mixolydian => &MIXOLYDIAN,
+            Mode::Aeolian => &AEOLIAN,
+            Mode::Locrian => &LOCRIAN,
+        }
+    }
+}
+
+impl ScaleKind {
+    /// Returns the intervals of the scale from its root.
+    pub const fn intervals(&self) -> &'static [Interval] {
+        match self {
+            ScaleKind::Major => &IONIAN,
+            ScaleKind::NaturalMinor => &AEOLIAN,
+            ScaleKind::HarmonicMinor => &HARMONIC_MINOR,
+            ScaleKind::MelodicMinor => &MELODIC_MINOR,
+            ScaleKind::MajorPentatonic => &MAJOR_PENTATONIC,
+            ScaleKind::MinorPentatonic => &MINOR_PENTATONIC,
+            ScaleKind::Blues => &BLUES,
+            ScaleKind::WholeTone => &WHOLE_TONE,
+            ScaleKind::Chromatic => &CHROMATIC,
+            ScaleKind::Mode(mode) => mode.intervals(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScaleError {
+    /// The scale has no degrees, or more than [`MAX_DEGREES`].
+    InvalidLength,
+    /// The intervals don't start at the unison and rise within an octave.
+    InvalidIntervals,
+}
+
+/// A scale, as the intervals of each of its degrees up from a root.
+///
+/// The degrees are spelled from the root's name, so F major has a B♭
+/// rather than an A♯.
+#[derive(PartialEq, Eq, Clone, Hash, Debug)]
+pub struct Scale {
+    root: NamedPitch,
+    intervals: Vec<Interval, MAX_DEGREES>,
+}
+
+impl Scale {
+    /// Constructs one of the built in scales.
+    pub fn new(root: NamedPitch, kind: ScaleKind) -> Self {
+        Self {
+            root,
+            intervals: Vec::from_slice(kind.intervals()).unwrap(),
+        }
+    }
+
+    /// Constructs a scale from its own intervals.
+    ///
+    /// The intervals must start at the unison and rise in semitones,
+    /// staying within the octave.
+    pub fn custom(root: NamedPitch, intervals: &[Interval]) -> Result<Self, ScaleError> {
+        let intervals = Vec::from_slice(intervals).map_err(|_| ScaleError::InvalidLength)?;
+
+        if intervals.is_empty() {
+            return Err(ScaleError::InvalidLength);
+        }
+        if intervals[0] != Interval::UNISON {
+            return Err(ScaleError::InvalidIntervals);
+        }
+        let rising = intervals
+            .windows(2)
+            .all(|pair| pair[0].semitones() < pair[1].semitones());
+        if !rising || intervals.iter().any(|interval| interval.semitones() >= 12) {
+            return Err(ScaleError::InvalidIntervals);
+        }
+
+        Ok(Self { root, intervals })
+    }
+
+    #[inline]
+    pub const fn root(&self) -> NamedPitch {
+        self.root
+    }
+
+    /// Returns the intervals of each degree up from the root.
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    /// Returns the number of degrees in the scale.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Returns the named pitch of a degree, counted from 0 for the root and
+    /// wrapping around past the top of the scale.
+    pub fn degree(&self, degree: usize) -> Option<NamedPitch> {
+        self.root
+            .transpose(self.intervals[degree % self.intervals.len()])
+    }
+
+    /// Returns the named pitches of the scale, from the root up.
+    pub fn pitches(&self) -> impl Iterator<Item = NamedPitch> + '_ {
+        self.intervals
+            .iter()
+            .filter_map(|interval| self.root.transpose(*interval))
+    }
+
+    /// Returns true if the pitch is in the scale, however it's spelled.
+    pub fn contains(&self, pitch: Pitch) -> bool {
+        self.pitches().any(|named| named.pitch() == pitch)
+    }
+
+    /// Returns the note of a degree, counted from the root in an octave.
+    ///
+    /// Degrees past the top of the scale carry on into the octaves above,
+    /// and negative degrees count down into the octaves below. Returns
+    /// `None` if the note would be outside the octave range.
+    pub fn note(&self, octave: Octave, degree: i32) -> Option<Note> {
+        let len = self.intervals.len() as i32;
+        let octave = octave as i32 + degree.div_euclid(len);
+        let octave = Octave::try_from(u8::try_from(octave).ok()?).ok()?;
+
+        Note::new(self.root, octave).transpose(self.intervals[degree.rem_euclid(len) as usize])
+    }
+
+    /// Returns the mode of the scale starting on one of its degrees, such
+    /// as D dorian for degree 1 of C major.
+    ///
+    /// Returns `None` if a degree can't be spelled from the new root.
+    pub fn mode(&self, degree: usize) -> Option<Self> {
+        let root = self.degree(degree)?;
+
+        let mut intervals = Vec::new();
+        for index in 0..self.len() {
+            let pitch = self.degree(degree + index)?;
+            intervals.push(Interval::between(root, pitch)?).ok()?;
+        }
+
+        Some(Self { root, intervals })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::{
+        named_pitch::NamedPitch::*,
+        note::{DFive, DFour, GThree},
+    };
+
+    #[test]
+    fn test_spelling() {
+        let f_major = Scale::new(F, ScaleKind::Major);
+        assert!(f_major.pitches().eq([F, G, A, BFlat, C, D, E]));
+        assert!(f_major.contains(Pitch::BFlat));
+        assert!(!f_major.contains(Pitch::B));
+
+        let e_harmonic = Scale::new(E, ScaleKind::HarmonicMinor);
+        assert!(e_harmonic.pitches().eq([E, FSharp, G, A, B, C, DSharp]));
+
+        let c_dorian = Scale::new(C, ScaleKind::Mode(Mode::Dorian));
+        assert_eq!(c_dorian.degree(2), Some(EFlat));
+        assert_eq!(c_dorian.degree(9), Some(EFlat));
+    }
+
+    #[test]
+    fn test_notes_and_modes() {
+        let c_major = Scale::new(C, ScaleKind::Major);
+        assert_eq!(c_major.note(Octave::Four, 1), Some(DFour));
+        assert_eq!(c_major.note(Octave::Four, 8), Some(DFive));
+        assert_eq!(c_major.note(Octave::Four, -3), Some(GThree));
+
+        let d_dorian = c_major.mode(1).unwrap();
+        assert_eq!(d_dorian, Scale::new(D, ScaleKind::Mode(Mode::Dorian)));
+
+        let pentatonic = Scale::new(A, ScaleKind::MinorPentatonic);
+        assert_eq!(
+            pentatonic.mode(1),
+            Some(Scale::new(C, ScaleKind::MajorPentatonic))
+        );
+    }
+
+    #[test]
+    fn test_custom() {
+        let hirajoshi = [
+            Interval::UNISON,
+            Interval::MAJOR_SECOND,
+            Interval::MINOR_THIRD,
+            Interval::PERFECT_FIFTH,
+            Interval::MINOR_SIXTH,
+        ];
+        let scale = Scale::custom(A, &hirajoshi).unwrap();
+        assert!(scale.pitches().eq([A, B, C, E, F]));
+
+        assert_eq!(Scale::custom(A, &[]), Err(ScaleError::InvalidLength));
+        assert_eq!(
+            Scale::custom(
+                A,
+                &[
+                    Interval::UNISON,
+                    Interval::MAJOR_THIRD,
+                    Interval::MAJOR_SECOND
+                ]
+            ),
+            Err(ScaleError::InvalidIntervals)
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! A chromatic tuner that maps frequencies to the nearest note.
+//!
+//! The tuner works in twelve-tone equal temperament from a reference pitch
+//! for A4, so the cents offset is measured against the exact equal
+//! tempered frequency rather than the rounded table in [`Pitch`](crate::music::pitch::Pitch).
+//!
+//! Pair it with one of the detectors in [`crate::audio::pitch`] to build an
+//! instrument tuner.
+
+use crate::{audio::pitch::PitchEstimate, core::Hertz, music::note::Note};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The standard A4 concert pitch.
+pub const CONCERT_A: Hertz = Hertz(440.0);
+
+/// The MIDI note number of A4.
+pub(crate) const A4_MIDI: f32 = 69.0;
+
+/// The nearest note to a frequency and how far off it is.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TunerReading {
+    /// The closest note in equal temperament.
+    pub note: Note,
+    /// How far the frequency is from the note, from -50 to 50 cents.
+    pub cents: f32,
+    /// The frequency that was measured.
+    pub frequency: Hertz,
+}
+
+impl TunerReading {
+    /// Returns `true` if the reading is within `tolerance` cents of the note.
+    pub fn in_tune(&self, tolerance: f32) -> bool {
+        self.cents.abs() <= tolerance
+    }
+}
+
+/// Maps frequencies to the nearest chromatic note.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tuner {
+    reference: Hertz,
+    min_confidence: f32,
+}
+
+impl Tuner {
+    /// Builds a tuner with A4 tuned to the reference frequency.
+    pub fn new(reference: Hertz) -> Self {
+        Self {
+            reference,
+            min_confidence: 0.8,
+        }
+    }
+
+    /// Returns the reference frequency of A4.
+    pub fn reference(&self) -> Hertz {
+        self.reference
+    }
+
+    /// Sets the reference frequency of A4, for example 432Hz or 442Hz.
+    pub fn set_reference(&mut self, reference: Hertz) {
+        self.reference = reference;
+    }
+
+    /// Sets the lowest pitch estimate confidence that [`Tuner::tune_estimate`] accepts.
+    pub fn set_min_confidence(&mut self, confidence: f32) {
+        self.min_confidence = confidence;
+    }
+
+    /// Returns the equal tempered frequency of a note.
+    ///
+    /// Returns `None` if the note is outside the MIDI range.
+    pub fn frequency(&self, note: Note) -> Option<Hertz> {
+        let semitones = note.midi()? as f32 - A4_MIDI;
+        Some(Hertz(
+            self.reference.hertz() * libm::exp2f(semitones / 12.0),
+        ))
+    }
+
+    /// Finds the nearest note to a frequency.
+    ///
+    /// Returns `None` if the frequency is not positive, or is outside of the
+    /// notes the tuner can name, C0 up to G9.
+    pub fn tune(&self, frequency: Hertz) -> Option<TunerReading> {
+        if frequency.hertz() <= 0.0 {
+            return None;
+        }
+
+        let semitones = 12.0 * libm::log2f(frequency.hertz() / self.reference.hertz());
+        let nearest = libm::roundf(semitones);
+        let number = A4_MIDI + nearest;
+        if !(0.0..=127.0).contains(&number) {
+            return None;
+        }
+
+        Some(TunerReading {
+            note: Note::from_midi(number as u8)?,
+            cents: 100.0 * (semitones - nearest),
+            frequency,
+        })
+    }
+
+    /// Finds the nearest note to a pitch estimate, ignoring estimates below
+    /// the minimum confidence.
+    pub fn tune_estimate(&self, estimate: PitchEstimate) -> Option<TunerReading> {
+        if estimate.confidence < self.min_confidence {
+            return None;
+        }
+
+        self.tune(estimate.frequency)
+    }
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self::new(CONCERT_A)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::music::note::{AFour, CFour, EFlatFour, ETwo};
+
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_midi_round_trip() {
+        assert_eq!(Note::from_midi(60), Some(CFour));
+        assert_eq!(Note::from_midi(69), Some(AFour));
+        assert_eq!(Note::from_midi(63), Some(EFlatFour));
+        assert_eq!(Note::from_midi(11), None);
+
+        for number in 12..=127 {
+            assert_eq!(Note::from_midi(number).unwrap().midi(), Some(number));
+        }
+    }
+
+    #[test]
+    fn test_tune() {
+        let tuner = Tuner::default();
+
+        let reading = tuner.tune(Hertz(440.0)).unwrap();
+        assert_eq!(reading.note, AFour);
+        assert_float_eq!(reading.cents, 0.0, abs <= 0.01);
+
+        // Low E string, slightly sharp.
+        let reading = tuner
+            .tune(Hertz(82.41 * libm::exp2f(10.0 / 1200.0)))
+            .unwrap();
+        assert_eq!(reading.note, ETwo);
+        assert_float_eq!(reading.cents, 10.0, abs <= 0.1);
+        assert!(reading.in_tune(15.0));
+        assert!(!reading.in_tune(5.0));
+
+        // A quarter tone flat of C4 still names C4.
+        let reading = tuner
+            .tune(Hertz(261.63 * libm::exp2f(-45.0 / 1200.0)))
+            .unwrap();
+        assert_eq!(reading.note, CFour);
+        assert_float_eq!(reading.cents, -45.0, abs <= 0.1);
+
+        assert_eq!(tuner.tune(Hertz(0.0)), None);
+    }
+
+    #[test]
+    fn test_reference() {
+        let tuner = Tuner::new(Hertz(432.0));
+
+        let reading = tuner.tune(Hertz(440.0)).unwrap();
+        assert_eq!(reading.note, AFour);
+        assert_float_eq!(reading.cents, 31.77, abs <= 0.05);
+        assert_eq!(tuner.frequency(AFour), Some(Hertz(432.0)));
+    }
+
+    #[test]
+    fn test_tune_estimate() {
+        let tuner = Tuner::default();
+        let estimate = PitchEstimate {
+            frequency: Hertz(440.0),
+            confidence: 0.5,
+        };
+
+        assert_eq!(tuner.tune_estimate(estimate), None);
+        assert!(
+            tuner
+                .tune_estimate(PitchEstimate {
+                    confidence: 0.95,
+                    ..estimate
+                })
+                .is_some()
+        );
+    }
+}
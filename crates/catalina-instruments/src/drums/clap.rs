@@ -0,0 +1,175 @@
+use catalina_engine::{
+    audio::{
+        filter::biquad::{Biquad, Coefficients, FilterKind},
+        signal::Signal,
+    },
+    core::{Hertz, rng::Rng},
+};
+
+use super::Decay;
+
+/// How many quick bursts make up the start of a clap, like several hands
+/// clapping not quite together.
+const BURSTS: u8 = 4;
+
+/// The time between bursts with the snap all the way up, in seconds.
+const TIGHT_SPACING: f32 = 0.004;
+
+/// How much further apart the bursts spread with no snap, in seconds.
+const LOOSE_SPREAD: f32 = 0.012;
+
+/// An analogue style hand clap.
+///
+/// Bandpassed noise is retriggered in a few quick bursts, then rings out
+/// in a longer tail like the room the clap was recorded in.
+pub struct Clap {
+    sample_rate: f32,
+
+    burst: Decay,
+    tail: Decay,
+    noise: Rng,
+    filter: Biquad,
+
+    /// How many bursts are left to play, including the tail.
+    bursts: u8,
+    /// How many samples until the next burst.
+    countdown: u32,
+    level: f32,
+
+    decay: f32,
+    tone: f32,
+    snap: f32,
+}
+
+impl Clap {
+    /// Construct a new clap.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut clap = Self {
+            sample_rate,
+
+            burst: Decay::new(0.01, sample_rate),
+            tail: Decay::new(0.3, sample_rate),
+            noise: Rng::new(39),
+            filter: Biquad::new(sample_rate, Coefficients::IDENTITY),
+
+            bursts: 0,
+            countdown: 0,
+            level: 0.0,
+
+            decay: 0.3,
+            tone: 0.5,
+            snap: 0.5,
+        };
+        clap.filter.set_smoothing_time(0.0);
+        clap.set_tone(clap.tone);
+        clap
+    }
+
+    /// Sets how long the tail rings for, in seconds.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds;
+        self.tail.set_time(seconds, self.sample_rate);
+    }
+
+    /// Sets the brightness of the clap, from 0 to 1.
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone = tone.clamp(0.0, 1.0);
+
+        let center = (700.0 + self.tone * 1800.0).min(self.sample_rate * 0.45);
+        self.filter.set_coefficients(Coefficients::new(
+            FilterKind::BandPass,
+            self.sample_rate,
+            Hertz(center),
+            2.0,
+            0.0,
+        ));
+    }
+
+    /// Sets how tightly the bursts are bunched, from 0 for a loose crowd to 1 for a single slap.
+    pub fn set_snap(&mut self, snap: f32) {
+        self.snap = snap.clamp(0.0, 1.0);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.burst.set_time(0.01, sample_rate);
+        self.tail.set_time(self.decay, sample_rate);
+        self.filter.set_sample_rate(sample_rate);
+        self.set_tone(self.tone);
+    }
+
+    /// Claps, with the velocity from 0 to 1.
+    pub fn trigger(&mut self, velocity: f32) {
+        self.level = velocity;
+        self.bursts = BURSTS;
+        self.countdown = 0;
+        self.tail.trigger(0.0);
+    }
+
+    /// Returns true while the clap is still sounding.
+    pub fn is_active(&self) -> bool {
+        self.bursts > 0 || self.burst.is_active() || self.tail.is_active()
+    }
+
+    /// The number of samples between bursts.
+    fn spacing(&self) -> u32 {
+        ((TIGHT_SPACING + (1.0 - self.snap) * LOOSE_SPREAD) * self.sample_rate) as u32
+    }
+}
+
+impl Signal for Clap {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if !self.is_active() {
+            return 0.0;
+        }
+
+        if self.bursts > 0 {
+            if self.countdown == 0 {
+                self.bursts -= 1;
+                if self.bursts > 0 {
+                    self.burst.trigger(self.level);
+                    self.countdown = self.spacing();
+                } else {
+                    self.tail.trigger(self.level * 0.6);
+                }
+            } else {
+                self.countdown -= 1;
+            }
+        }
+
+        let level = self.burst.next() + self.tail.next();
+        self.filter.process(self.noise.next_bipolar()) * level * 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clap_bursts() {
+        let mut clap = Clap::new(48_000.0);
+        clap.set_snap(1.0);
+        clap.trigger(1.0);
+
+        // Each burst waits out the spacing and starts on the sample after,
+        // louder than the end of the last.
+        let spacing = (TIGHT_SPACING * 48_000.0) as usize + 1;
+        let frames: [f32; 960] = core::array::from_fn(|_| clap.next());
+        let peak = |from: usize| {
+            frames[from..from + 20]
+                .iter()
+                .fold(0.0f32, |peak, frame| peak.max(frame.abs()))
+        };
+        for burst in 1..BURSTS as usize - 1 {
+            assert!(peak(burst * spacing) > peak(burst * spacing - 20));
+        }
+
+        for _ in 0..48_000 {
+            clap.next();
+        }
+        assert!(!clap.is_active());
+    }
+}
@@ -0,0 +1,210 @@
+use catalina_engine::{
+    audio::{
+        filter::biquad::{Biquad, Coefficients, FilterKind},
+        signal::Signal,
+    },
+    core::{Hertz, rng::Rng},
+};
+
+use super::Decay;
+
+/// The frequencies of the six square waves in the 808's metallic cluster,
+/// relative to the lowest.
+const RATIOS: [f32; 6] = [1.0, 1.304, 1.466, 1.787, 1.932, 2.536];
+
+/// How quickly a choked hat is silenced, in seconds.
+const CHOKE_TIME: f32 = 0.01;
+
+/// An analogue style hi-hat or cymbal.
+///
+/// Six detuned square waves beat against each other into a metallic cluster,
+/// which is mixed with noise and then bandpassed and highpassed down to the
+/// sizzle. A short decay gives a closed hat and a long one an open hat.
+pub struct HiHat {
+    sample_rate: f32,
+
+    frequency: f32,
+    phases: [f32; 6],
+    amp: Decay,
+    noise: Rng,
+    band_pass: Biquad,
+    high_pass: Biquad,
+
+    decay: f32,
+    tone: f32,
+    snap: f32,
+}
+
+impl HiHat {
+    /// Construct a new hi-hat, with the decay in seconds.
+    pub fn new(sample_rate: f32, decay: f32) -> Self {
+        let mut hat = Self {
+            sample_rate,
+
+            frequency: 400.0,
+            phases: [0.0; 6],
+            amp: Decay::new(decay, sample_rate),
+            noise: Rng::new(42),
+            band_pass: Biquad::new(sample_rate, Coefficients::IDENTITY),
+            high_pass: Biquad::new(sample_rate, Coefficients::IDENTITY),
+
+            decay,
+            tone: 0.5,
+            snap: 0.3,
+        };
+        hat.band_pass.set_smoothing_time(0.0);
+        hat.high_pass.set_smoothing_time(0.0);
+        hat.set_tone(hat.tone);
+        hat
+    }
+
+    /// Sets the frequency of the lowest square wave in the cluster.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        self.frequency = frequency.0;
+    }
+
+    /// Sets how long the hat rings for, in seconds.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds;
+        self.amp.set_time(seconds, self.sample_rate);
+    }
+
+    /// Sets the brightness of the hat, from 0 to 1.
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone = tone.clamp(0.0, 1.0);
+
+        let nyquist = self.sample_rate * 0.45;
+        let center = (6000.0 + self.tone * 6000.0).min(nyquist);
+        let cutoff = (4000.0 + self.tone * 4000.0).min(nyquist);
+        self.band_pass.set_coefficients(Coefficients::new(
+            FilterKind::BandPass,
+            self.sample_rate,
+            Hertz(center),
+            1.5,
+            0.0,
+        ));
+        self.high_pass.set_coefficients(Coefficients::high_pass(
+            self.sample_rate,
+            Hertz(cutoff),
+            0.707,
+        ));
+    }
+
+    /// Sets how much noise is mixed into the metallic cluster, from 0 to 1.
+    pub fn set_snap(&mut self, snap: f32) {
+        self.snap = snap.clamp(0.0, 1.0);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.amp.set_time(self.decay, sample_rate);
+        self.band_pass.set_sample_rate(sample_rate);
+        self.high_pass.set_sample_rate(sample_rate);
+        self.set_tone(self.tone);
+    }
+
+    /// Hits the hat, with the velocity from 0 to 1.
+    pub fn trigger(&mut self, velocity: f32) {
+        self.amp.set_time(self.decay, self.sample_rate);
+        self.amp.trigger(velocity);
+    }
+
+    /// Quickly silences the hat, such as an open hat cut off by a closed one.
+    pub fn choke(&mut self) {
+        self.amp.set_time(CHOKE_TIME, self.sample_rate);
+    }
+
+    /// Returns true while the hat is still sounding.
+    pub fn is_active(&self) -> bool {
+        self.amp.is_active()
+    }
+}
+
+impl Signal for HiHat {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if !self.is_active() {
+            return 0.0;
+        }
+
+        let mut metal = 0.0;
+        for (phase, ratio) in self.phases.iter_mut().zip(RATIOS) {
+            *phase += self.frequency * ratio / self.sample_rate;
+            if *phase >= 1.0 {
+                *phase -= 1.0;
+            }
+            metal += if *phase < 0.5 { 1.0 } else { -1.0 };
+        }
+        metal /= RATIOS.len() as f32;
+
+        let noise = self.noise.next_bipolar();
+        let mix = metal * (1.0 - self.snap) + noise * self.snap;
+
+        let filtered = self.high_pass.process(self.band_pass.process(mix));
+        filtered * self.amp.next() * 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the hat for a number of samples, returning whether it's still sounding.
+    fn run(hat: &mut HiHat, samples: usize) -> bool {
+        for _ in 0..samples {
+            hat.next();
+        }
+        hat.is_active()
+    }
+
+    #[test]
+    fn test_hihat_decay() {
+        // A closed hat is gone well before an open one.
+        let mut closed = HiHat::new(48_000.0, 0.05);
+        let mut open = HiHat::new(48_000.0, 0.5);
+        closed.trigger(1.0);
+        open.trigger(1.0);
+
+        assert!(!run(&mut closed, 4_800));
+        assert!(run(&mut open, 4_800));
+    }
+
+    #[test]
+    fn test_hihat_choke() {
+        let mut hat = HiHat::new(48_000.0, 0.5);
+        hat.trigger(1.0);
+        assert!(run(&mut hat, 480));
+
+        // Choking silences the open hat 80dB down, a third again past
+        // the 60dB choke time.
+        hat.choke();
+        assert!(!run(
+            &mut hat,
+            (CHOKE_TIME * 48_000.0 * 4.0 / 3.0) as usize + 1
+        ));
+
+        // The next hit rings for the full decay again.
+        hat.trigger(1.0);
+        assert!(run(&mut hat, 4_800));
+    }
+
+    #[test]
+    fn test_hihat_is_bright() {
+        let mut hat = HiHat::new(48_000.0, 0.5);
+        hat.trigger(1.0);
+
+        // The cluster is highpassed down to the sizzle, well above the
+        // frequency of its lowest square wave.
+        let mut last = hat.next();
+        let crossings = (0..960)
+            .filter(|_| {
+                let frame = hat.next();
+                let crossed = (last < 0.0) != (frame < 0.0);
+                last = frame;
+                crossed
+            })
+            .count();
+        assert!(crossings > 100, "{crossings}");
+    }
+}
@@ -0,0 +1,134 @@
+use catalina_engine::{audio::signal::Signal, core::Hertz, core::rng::Rng};
+
+use super::{Body, Decay};
+
+/// An analogue style kick drum.
+///
+/// A sine body sweeps down from several times its frequency to give the
+/// punch, with a short burst of noise on top for the beater click.
+pub struct Kick {
+    sample_rate: f32,
+
+    body: Body,
+    amp: Decay,
+    click: Decay,
+    noise: Rng,
+
+    decay: f32,
+    tone: f32,
+    snap: f32,
+}
+
+impl Kick {
+    /// Construct a new kick drum.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut kick = Self {
+            sample_rate,
+
+            body: Body::new(50.0, 0.0, 0.04, sample_rate),
+            amp: Decay::new(0.5, sample_rate),
+            click: Decay::new(0.003, sample_rate),
+            noise: Rng::new(36),
+
+            decay: 0.5,
+            tone: 0.3,
+            snap: 0.5,
+        };
+        kick.set_snap(kick.snap);
+        kick
+    }
+
+    /// Sets the frequency the body settles to.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        self.body.frequency = frequency.0;
+    }
+
+    /// Sets how long the kick rings for, in seconds.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds;
+        self.amp.set_time(seconds, self.sample_rate);
+    }
+
+    /// Sets the level of the beater click and how hard the body is driven, from 0 to 1.
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone = tone.clamp(0.0, 1.0);
+    }
+
+    /// Sets how far the pitch sweeps down from, from 0 for a flat thud to 1 for a laser zap.
+    pub fn set_snap(&mut self, snap: f32) {
+        self.snap = snap.clamp(0.0, 1.0);
+        self.body.depth = self.snap * 8.0;
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.body.set_sweep_time(0.04, sample_rate);
+        self.amp.set_time(self.decay, sample_rate);
+        self.click.set_time(0.003, sample_rate);
+    }
+
+    /// Hits the kick, with the velocity from 0 to 1.
+    pub fn trigger(&mut self, velocity: f32) {
+        self.body.trigger();
+        self.amp.trigger(velocity);
+        self.click.trigger(velocity * self.tone);
+    }
+
+    /// Returns true while the kick is still sounding.
+    pub fn is_active(&self) -> bool {
+        self.amp.is_active()
+    }
+}
+
+impl Signal for Kick {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if !self.is_active() {
+            return 0.0;
+        }
+
+        let body = self.body.next(self.sample_rate) * self.amp.next();
+        let click = self.noise.next_bipolar() * self.click.next();
+
+        // Driving the body into a soft clip fattens it like an overdriven VCA.
+        let drive = 1.0 + self.tone * 3.0;
+        libm::tanhf((body + click) * drive) / libm::tanhf(drive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the zero crossings in a block of the signal.
+    fn crossings(kick: &mut Kick, count: usize) -> usize {
+        let mut last = kick.next();
+        (0..count)
+            .filter(|_| {
+                let frame = kick.next();
+                let crossed = (last < 0.0) != (frame < 0.0);
+                last = frame;
+                crossed
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_kick_pitch_sweep() {
+        let mut kick = Kick::new(48_000.0);
+        kick.set_tone(0.0);
+        kick.set_snap(1.0);
+        kick.trigger(1.0);
+
+        // The body starts well above its frequency and settles down to it.
+        let start = crossings(&mut kick, 960);
+        for _ in 0..9_600 {
+            kick.next();
+        }
+        let settled = crossings(&mut kick, 960);
+
+        assert!(start >= settled * 3, "{start} against {settled}");
+        assert!((1..=3).contains(&settled));
+    }
+}
@@ -0,0 +1,165 @@
+use catalina_engine::{
+    audio::{AudioSource, signal::Signal},
+    core::Hertz,
+    instrument::{Instrument, NoteError},
+    music::note::Note,
+};
+
+use super::{Clap, HiHat, Kick, Snare, Tom};
+use crate::synths::normalize_velocity;
+
+/// A kit of drum voices played on the General MIDI drum notes.
+///
+/// | Voice        | MIDI notes |
+/// |--------------|------------|
+/// | `kick`       | 35, 36     |
+/// | `snare`      | 38, 40     |
+/// | `clap`       | 39         |
+/// | `closed_hat` | 42, 44     |
+/// | `open_hat`   | 46         |
+/// | `low_tom`    | 41, 43     |
+/// | `mid_tom`    | 45, 47     |
+/// | `high_tom`   | 48, 50     |
+///
+/// Drums are one-shots, so they ring out for their decay whatever the note
+/// length, and a closed hat chokes the open hat like on a real hi-hat stand.
+/// The voices are public to set up the kit's sound.
+pub struct DrumKit {
+    pub kick: Kick,
+    pub snare: Snare,
+    pub clap: Clap,
+    pub closed_hat: HiHat,
+    pub open_hat: HiHat,
+    pub low_tom: Tom,
+    pub mid_tom: Tom,
+    pub high_tom: Tom,
+}
+
+impl DrumKit {
+    /// Construct a new drum kit.
+    pub fn new(sample_rate: usize) -> Self {
+        let sample_rate = sample_rate as f32;
+
+        Self {
+            kick: Kick::new(sample_rate),
+            snare: Snare::new(sample_rate),
+            clap: Clap::new(sample_rate),
+            closed_hat: HiHat::new(sample_rate, 0.06),
+            open_hat: HiHat::new(sample_rate, 0.5),
+            low_tom: Tom::new(sample_rate, Hertz(90.0)),
+            mid_tom: Tom::new(sample_rate, Hertz(130.0)),
+            high_tom: Tom::new(sample_rate, Hertz(180.0)),
+        }
+    }
+
+    /// Changes the sample rate of every voice in the kit.
+    pub fn set_sample_rate(&mut self, sample_rate: usize) {
+        let sample_rate = sample_rate as f32;
+
+        self.kick.set_sample_rate(sample_rate);
+        self.snare.set_sample_rate(sample_rate);
+        self.clap.set_sample_rate(sample_rate);
+        self.closed_hat.set_sample_rate(sample_rate);
+        self.open_hat.set_sample_rate(sample_rate);
+        self.low_tom.set_sample_rate(sample_rate);
+        self.mid_tom.set_sample_rate(sample_rate);
+        self.high_tom.set_sample_rate(sample_rate);
+    }
+}
+
+/// The interfaces for controlling the instrument from the framework.
+impl Instrument for DrumKit {
+    fn init(&mut self) {}
+
+    /// Hits the drum mapped to the note, ignoring notes without a drum.
+    fn note_on(&mut self, note: Note, velocity: u8) -> Result<(), NoteError> {
+        let velocity = normalize_velocity(velocity);
+
+        match note.midi() {
+            Some(35 | 36) => self.kick.trigger(velocity),
+            Some(38 | 40) => self.snare.trigger(velocity),
+            Some(39) => self.clap.trigger(velocity),
+            Some(42 | 44) => {
+                self.open_hat.choke();
+                self.closed_hat.trigger(velocity);
+            }
+            Some(46) => self.open_hat.trigger(velocity),
+            Some(41 | 43) => self.low_tom.trigger(velocity),
+            Some(45 | 47) => self.mid_tom.trigger(velocity),
+            Some(48 | 50) => self.high_tom.trigger(velocity),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Drums ring out on their own, so releasing a note does nothing.
+    fn note_off(&mut self, _note: Note) {}
+}
+
+impl Signal for DrumKit {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        self.kick.next()
+            + self.snare.next()
+            + self.clap.next()
+            + self.closed_hat.next()
+            + self.open_hat.next()
+            + self.low_tom.next()
+            + self.mid_tom.next()
+            + self.high_tom.next()
+    }
+}
+
+impl AudioSource for DrumKit {
+    type Frame = f32;
+
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn energy(kit: &mut DrumKit, count: usize) -> f32 {
+        (0..count)
+            .map(|_| {
+                let frame = kit.next();
+                frame * frame
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_kit_notes() {
+        let mut kit = DrumKit::new(48_000);
+
+        // Notes without a drum stay silent.
+        kit.note_on(Note::from_midi(60).unwrap(), 127).unwrap();
+        assert_eq!(energy(&mut kit, 480), 0.0);
+
+        for midi in [36, 38, 39, 42, 46, 41, 45, 48] {
+            kit.note_on(Note::from_midi(midi).unwrap(), 100).unwrap();
+            assert!(energy(&mut kit, 480) > 0.1, "note {midi} is silent");
+        }
+
+        // A closed hat chokes the ringing open hat.
+        kit.note_on(Note::from_midi(46).unwrap(), 127).unwrap();
+        kit.note_on(Note::from_midi(42).unwrap(), 127).unwrap();
+        for _ in 0..4_800 {
+            kit.next();
+        }
+        assert!(!kit.open_hat.is_active());
+
+        // Every voice rings out on its own.
+        for _ in 0..96_000 {
+            kit.next();
+        }
+        assert_eq!(energy(&mut kit, 480), 0.0);
+    }
+}
@@ -0,0 +1,124 @@
+//! Synthesized drum voices modelled on analogue drum machines like the 808
+//! and 909, and on the drum models of Mutable Instruments' Plaits.
+//!
+//! - [`Kick`] is a sine body swept down in pitch, with a noise click.
+//! - [`Snare`] is a pair of tuned bodies under a burst of filtered noise.
+//! - [`HiHat`] is a bank of six detuned square waves, the metallic cluster
+//!   from the 808, mixed with noise and filtered.
+//! - [`Clap`] is filtered noise retriggered in a few quick bursts.
+//! - [`Tom`] is a tuned body with a gentle pitch sweep.
+//!
+//! Every voice is triggered with a velocity from 0 to 1 and has decay, tone
+//! and snap controls, where the tone and snap shape whatever suits the voice.
+//! [`DrumKit`] gathers them into an [`Instrument`] played on General MIDI drum
+//! notes.
+//!
+//! [`Instrument`]: catalina_engine::instrument::Instrument
+
+use catalina_engine::audio::oscillator;
+
+pub mod kick;
+pub use kick::Kick;
+
+pub mod snare;
+pub use snare::Snare;
+
+pub mod hihat;
+pub use hihat::HiHat;
+
+pub mod clap;
+pub use clap::Clap;
+
+pub mod tom;
+pub use tom::Tom;
+
+pub mod kit;
+pub use kit::DrumKit;
+
+/// The level below which an envelope counts as silent.
+const SILENCE: f32 = 0.0001;
+
+/// An exponential decay, the envelope behind every part of a drum hit.
+#[derive(Clone)]
+pub(crate) struct Decay {
+    level: f32,
+    gain: f32,
+}
+
+impl Decay {
+    pub(crate) fn new(seconds: f32, sample_rate: f32) -> Self {
+        let mut decay = Self {
+            level: 0.0,
+            gain: 0.0,
+        };
+        decay.set_time(seconds, sample_rate);
+        decay
+    }
+
+    /// Sets how long the envelope takes to fall by 60dB.
+    pub(crate) fn set_time(&mut self, seconds: f32, sample_rate: f32) {
+        self.gain = if seconds * sample_rate > 1.0 {
+            libm::powf(0.001, 1.0 / (seconds * sample_rate))
+        } else {
+            0.0
+        };
+    }
+
+    pub(crate) fn trigger(&mut self, level: f32) {
+        self.level = level;
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.level > SILENCE
+    }
+
+    #[inline]
+    pub(crate) fn next(&mut self) -> f32 {
+        let level = self.level;
+        self.level *= self.gain;
+        level
+    }
+}
+
+/// A sine oscillator that starts sharp and sweeps down to its frequency,
+/// like the resonant body of an analogue drum.
+#[derive(Clone)]
+pub(crate) struct Body {
+    pub(crate) frequency: f32,
+    /// How far above the frequency the sweep starts, as a multiple of it.
+    pub(crate) depth: f32,
+    sweep: Decay,
+    phase: f32,
+}
+
+impl Body {
+    pub(crate) fn new(frequency: f32, depth: f32, sweep: f32, sample_rate: f32) -> Self {
+        Self {
+            frequency,
+            depth,
+            sweep: Decay::new(sweep, sample_rate),
+            phase: 0.0,
+        }
+    }
+
+    pub(crate) fn set_sweep_time(&mut self, seconds: f32, sample_rate: f32) {
+        self.sweep.set_time(seconds, sample_rate);
+    }
+
+    pub(crate) fn trigger(&mut self) {
+        self.phase = 0.0;
+        self.sweep.trigger(1.0);
+    }
+
+    #[inline]
+    pub(crate) fn next(&mut self, sample_rate: f32) -> f32 {
+        let frequency = self.frequency * (1.0 + self.depth * self.sweep.next());
+
+        self.phase += frequency / sample_rate;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        }
+
+        oscillator::sine::<f32>(self.phase)
+    }
+}
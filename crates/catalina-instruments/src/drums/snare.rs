@@ -0,0 +1,180 @@
+use catalina_engine::{
+    audio::{
+        filter::biquad::{Biquad, Coefficients},
+        signal::Signal,
+    },
+    core::{Hertz, rng::Rng},
+};
+
+use super::{Body, Decay};
+
+/// The ratio of the upper body mode to the lower, as on the 909.
+const OVERTONE: f32 = 1.83;
+
+/// An analogue style snare drum.
+///
+/// Two tuned bodies give the drum's shell, and a burst of highpassed noise
+/// gives the rattle of the snares.
+pub struct Snare {
+    sample_rate: f32,
+
+    bodies: [Body; 2],
+    body_amp: Decay,
+    noise_amp: Decay,
+    noise: Rng,
+    filter: Biquad,
+
+    decay: f32,
+    tone: f32,
+    snap: f32,
+}
+
+impl Snare {
+    /// Construct a new snare drum.
+    pub fn new(sample_rate: f32) -> Self {
+        let mut snare = Self {
+            sample_rate,
+
+            bodies: [
+                Body::new(180.0, 0.5, 0.01, sample_rate),
+                Body::new(180.0 * OVERTONE, 0.5, 0.01, sample_rate),
+            ],
+            body_amp: Decay::new(0.1, sample_rate),
+            noise_amp: Decay::new(0.25, sample_rate),
+            noise: Rng::new(38),
+            filter: Biquad::new(sample_rate, Coefficients::IDENTITY),
+
+            decay: 0.25,
+            tone: 0.5,
+            snap: 0.6,
+        };
+        snare.filter.set_smoothing_time(0.0);
+        snare.set_tone(snare.tone);
+        snare
+    }
+
+    /// Sets the frequency of the lower body mode.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        self.bodies[0].frequency = frequency.0;
+        self.bodies[1].frequency = frequency.0 * OVERTONE;
+    }
+
+    /// Sets how long the snares rattle for, in seconds.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds;
+        self.noise_amp.set_time(seconds, self.sample_rate);
+    }
+
+    /// Sets the brightness of the snares, from 0 to 1.
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone = tone.clamp(0.0, 1.0);
+
+        let cutoff = (1000.0 + self.tone * 7000.0).min(self.sample_rate * 0.45);
+        self.filter.set_coefficients(Coefficients::high_pass(
+            self.sample_rate,
+            Hertz(cutoff),
+            0.707,
+        ));
+    }
+
+    /// Sets the level of the snares against the body, from 0 to 1.
+    pub fn set_snap(&mut self, snap: f32) {
+        self.snap = snap.clamp(0.0, 1.0);
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        for body in &mut self.bodies {
+            body.set_sweep_time(0.01, sample_rate);
+        }
+        self.body_amp.set_time(0.1, sample_rate);
+        self.noise_amp.set_time(self.decay, sample_rate);
+        self.filter.set_sample_rate(sample_rate);
+        self.set_tone(self.tone);
+    }
+
+    /// Hits the snare, with the velocity from 0 to 1.
+    pub fn trigger(&mut self, velocity: f32) {
+        for body in &mut self.bodies {
+            body.trigger();
+        }
+        self.body_amp.trigger(velocity);
+        self.noise_amp.trigger(velocity);
+    }
+
+    /// Returns true while the snare is still sounding.
+    pub fn is_active(&self) -> bool {
+        self.body_amp.is_active() || self.noise_amp.is_active()
+    }
+}
+
+impl Signal for Snare {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if !self.is_active() {
+            return 0.0;
+        }
+
+        let body = (self.bodies[0].next(self.sample_rate) * 0.6
+            + self.bodies[1].next(self.sample_rate) * 0.4)
+            * self.body_amp.next();
+        let snares = self.filter.process(self.noise.next_bipolar()) * self.noise_amp.next();
+
+        body * (1.0 - self.snap * 0.5) + snares * self.snap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the zero crossings in a block of the signal.
+    fn crossings(snare: &mut Snare, count: usize) -> usize {
+        let mut last = snare.next();
+        (0..count)
+            .filter(|_| {
+                let frame = snare.next();
+                let crossed = (last < 0.0) != (frame < 0.0);
+                last = frame;
+                crossed
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_snare_snap() {
+        // Without the snares, only the tuned bodies sound.
+        let mut snare = Snare::new(48_000.0);
+        snare.set_snap(0.0);
+        snare.trigger(1.0);
+        let body = crossings(&mut snare, 960);
+
+        // The highpassed noise of the snares crosses far more often.
+        let mut snare = Snare::new(48_000.0);
+        snare.set_snap(1.0);
+        snare.trigger(1.0);
+        let snares = crossings(&mut snare, 960);
+
+        assert!(body < 30, "{body}");
+        assert!(snares > body * 5, "{snares} against {body}");
+    }
+
+    #[test]
+    fn test_snare_decay() {
+        let mut snare = Snare::new(48_000.0);
+        snare.set_decay(0.1);
+        snare.trigger(1.0);
+
+        // Both the body and the snares fall silent within their decay times.
+        for _ in 0..4_800 {
+            snare.next();
+        }
+        assert!(snare.is_active());
+        for _ in 0..2_400 {
+            snare.next();
+        }
+        assert!(!snare.is_active());
+        assert_eq!(snare.next(), 0.0);
+    }
+}
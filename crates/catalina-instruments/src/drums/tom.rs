@@ -0,0 +1,148 @@
+use catalina_engine::{
+    audio::signal::Signal,
+    core::{Hertz, rng::Rng},
+};
+
+use super::{Body, Decay};
+
+/// An analogue style tom or conga.
+///
+/// A tuned body with a gentler pitch sweep than the kick, and a touch of
+/// noise for the stick hitting the head.
+pub struct Tom {
+    sample_rate: f32,
+
+    body: Body,
+    amp: Decay,
+    stick: Decay,
+    noise: Rng,
+
+    decay: f32,
+    tone: f32,
+    snap: f32,
+}
+
+impl Tom {
+    /// Construct a new tom tuned to the frequency.
+    pub fn new(sample_rate: f32, frequency: Hertz) -> Self {
+        let mut tom = Self {
+            sample_rate,
+
+            body: Body::new(frequency.0, 0.0, 0.05, sample_rate),
+            amp: Decay::new(0.4, sample_rate),
+            stick: Decay::new(0.008, sample_rate),
+            noise: Rng::new(45),
+
+            decay: 0.4,
+            tone: 0.2,
+            snap: 0.3,
+        };
+        tom.set_snap(tom.snap);
+        tom
+    }
+
+    /// Sets the frequency the body settles to.
+    pub fn set_frequency(&mut self, frequency: Hertz) {
+        self.body.frequency = frequency.0;
+    }
+
+    /// Sets how long the tom rings for, in seconds.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds;
+        self.amp.set_time(seconds, self.sample_rate);
+    }
+
+    /// Sets the level of the stick noise, from 0 to 1.
+    pub fn set_tone(&mut self, tone: f32) {
+        self.tone = tone.clamp(0.0, 1.0);
+    }
+
+    /// Sets how far the pitch sweeps down from, from 0 to 1.
+    pub fn set_snap(&mut self, snap: f32) {
+        self.snap = snap.clamp(0.0, 1.0);
+        self.body.depth = self.snap * 2.0;
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.body.set_sweep_time(0.05, sample_rate);
+        self.amp.set_time(self.decay, sample_rate);
+        self.stick.set_time(0.008, sample_rate);
+    }
+
+    /// Hits the tom, with the velocity from 0 to 1.
+    pub fn trigger(&mut self, velocity: f32) {
+        self.body.trigger();
+        self.amp.trigger(velocity);
+        self.stick.trigger(velocity * self.tone);
+    }
+
+    /// Returns true while the tom is still sounding.
+    pub fn is_active(&self) -> bool {
+        self.amp.is_active()
+    }
+}
+
+impl Signal for Tom {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if !self.is_active() {
+            return 0.0;
+        }
+
+        self.body.next(self.sample_rate) * self.amp.next()
+            + self.noise.next_bipolar() * self.stick.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the zero crossings in a block of the signal.
+    fn crossings(tom: &mut Tom, count: usize) -> usize {
+        let mut last = tom.next();
+        (0..count)
+            .filter(|_| {
+                let frame = tom.next();
+                let crossed = (last < 0.0) != (frame < 0.0);
+                last = frame;
+                crossed
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_tom_tuning() {
+        let mut tom = Tom::new(48_000.0, Hertz(200.0));
+        tom.set_snap(1.0);
+        tom.trigger(1.0);
+
+        // The body starts sharp and settles on its tuning, 200Hz crossing
+        // zero 20 times in each 50ms.
+        let start = crossings(&mut tom, 2_400);
+        for _ in 0..9_600 {
+            tom.next();
+        }
+        let settled = crossings(&mut tom, 2_400);
+
+        assert!(start > settled, "{start} against {settled}");
+        assert!(settled.abs_diff(20) <= 1, "{settled}");
+    }
+
+    #[test]
+    fn test_tom_decay() {
+        let mut tom = Tom::new(48_000.0, Hertz(120.0));
+        tom.set_decay(0.2);
+        tom.trigger(1.0);
+
+        // The hit counts as silent 80dB down, a third again past its 60dB decay time.
+        let mut samples = 0usize;
+        while tom.is_active() {
+            tom.next();
+            samples += 1;
+        }
+        assert!(samples.abs_diff(12_800) <= 1, "{samples}");
+    }
+}
@@ -4,3 +4,5 @@
 extern crate alloc;
 
 pub mod synths;
+
+pub mod drums;
@@ -1,67 +1,367 @@
 use heapless::index_map::FnvIndexMap;
 
 use catalina_engine::{
-    audio::{AudioSource, signal::Signal},
-    instrument::{Instrument, NoteError},
-    music::note::{self, Note},
+    audio::{AudioSource, oscillator, signal::Signal},
+    core::Hertz,
+    instrument::{Glide, Instrument, NoteError, Pedals, PitchBend, Unison},
+    music::note::Note,
 };
 
-pub mod oscillator;
-pub(crate) use oscillator::AdditiveOscillator;
+pub mod partial;
+pub use partial::{Partial, SpectralMacros};
 
 pub mod voice;
 pub(crate) use voice::Voice;
 
-/// A type of synthesizer that adds multiple oscillators together, typically sine
-/// waves, at different frequencies, amplitudes and phases to build harmonics.
-pub struct AdditiveSynth {
+/// A type of synthesizer that adds multiple sine waves together at different
+/// frequencies, amplitudes and phases to build up a spectrum of `PARTIALS`
+/// partials.
+///
+/// Each [`Partial`] plays at a ratio of the played note's frequency, so
+/// whole number ratios build up the harmonic series. The [`SpectralMacros`]
+/// then reshape all of the partials at once, and a spectrum analysed with
+/// the engine's FFT can be resynthesized with
+/// [`AdditiveSynth::set_partials_from_spectrum`].
+///
+/// The synth responds to the sustain and sostenuto pedals on every channel,
+/// and to pitch bend on every channel with its own range, the bends of the
+/// channels adding up.
+///
+/// Each note can glide in from the last note played, and can be stacked
+/// into as many as `UNISON` detuned copies, which [`AdditiveSynth::next_stereo`]
+/// spreads across the stereo field.
+pub struct AdditiveSynth<const PARTIALS: usize = 4, const UNISON: usize = 1> {
     sample_rate: usize,
 
-    /// We have a bank of 4 optional oscillators that are added for each voice.
+    /// The partials as they've been set.
+    partials: [Partial; PARTIALS],
+    /// The macros applied over the partials.
+    macros: SpectralMacros,
+    /// The partials after the macros, which is what's actually played.
     ///
-    /// At least the first oscillator needs to be enabled, the rest are optional.
-    oscillators: [AdditiveOscillator; 4],
+    /// This is recalculated whenever the partials or macros change so the
+    /// macros don't need evaluating every sample.
+    shaped: [Partial; PARTIALS],
 
     /// Configure the instrument with 8-voice polyphony.
     ///
     /// Each voice pair tracks the phase data for that note.
-    voices: FnvIndexMap<Note, Voice, 8>,
+    voices: FnvIndexMap<Note, Voice<PARTIALS, UNISON>, 8>,
+
+    /// The glide settings each new voice starts from.
+    glide: Glide,
+    /// The frequency of the last note played, which new notes glide from.
+    last_frequency: Option<Hertz>,
+
+    unison: Unison<UNISON>,
+    /// The frequency ratio of each unison copy.
+    ///
+    /// Like the shaped partials, these and the gains are cached so they're
+    /// only recalculated when the unison changes.
+    ratios: [f32; UNISON],
+    /// The left and right gains of each unison copy.
+    gains: [[f32; 2]; UNISON],
+
+    /// The pitch bend of each channel, applied to every voice.
+    bends: [PitchBend; 16],
+    /// Holds notes while the sustain or sostenuto pedals are down.
+    pedals: Pedals<8>,
 }
 
-impl AdditiveSynth {
+impl<const PARTIALS: usize, const UNISON: usize> AdditiveSynth<PARTIALS, UNISON> {
     /// Construct a new instance of the additive synth.
+    ///
+    /// The partials are tuned to the harmonic series, with only the
+    /// fundamental sounding.
     pub fn new(sample_rate: usize) -> Self {
-        Self {
+        let partials =
+            core::array::from_fn(|i| Partial::harmonic(i + 1, if i == 0 { 1.0 } else { 0.0 }));
+
+        let mut synth = Self {
             sample_rate,
 
-            // By default we're only populating the first oscillator.
-            oscillators: [
-                AdditiveOscillator::new(true, note::CFour.frequency()),
-                AdditiveOscillator::new(false, note::CFour.frequency()),
-                AdditiveOscillator::new(false, note::CFour.frequency()),
-                AdditiveOscillator::new(false, note::CFour.frequency()),
-            ],
+            partials,
+            macros: SpectralMacros::default(),
+            shaped: partials,
 
             voices: FnvIndexMap::new(),
+
+            glide: Glide::new(sample_rate as f32),
+            last_frequency: None,
+
+            unison: Unison::new(sample_rate as u64),
+            ratios: [1.0; UNISON],
+            gains: [[1.0; 2]; UNISON],
+
+            bends: [PitchBend::default(); 16],
+            pedals: Pedals::new(),
+        };
+        synth.reshape();
+        synth.respread();
+        synth
+    }
+
+    /// Returns the partials, before the macros are applied.
+    pub fn partials(&self) -> &[Partial; PARTIALS] {
+        &self.partials
+    }
+
+    /// Replaces every partial.
+    pub fn set_partials(&mut self, partials: [Partial; PARTIALS]) {
+        self.partials = partials;
+        self.reshape();
+    }
+
+    /// Replaces a single partial.
+    ///
+    /// **Panics** if the index is out of range.
+    pub fn set_partial(&mut self, index: usize, partial: Partial) {
+        self.partials[index] = partial;
+        self.shaped[index] = self.macros.apply(&partial);
+    }
+
+    /// Returns the spectral macros.
+    pub fn macros(&self) -> &SpectralMacros {
+        &self.macros
+    }
+
+    /// Replaces the spectral macros.
+    pub fn set_macros(&mut self, macros: SpectralMacros) {
+        self.macros = macros;
+        self.reshape();
+    }
+
+    /// Tilts the spectrum, from -1 for darker to 1 for brighter.
+    pub fn set_brightness(&mut self, brightness: f32) {
+        self.macros.brightness = brightness.clamp(-1.0, 1.0);
+        self.reshape();
+    }
+
+    /// Balances the odd harmonics against the even, from -1 for only odd to 1 for only even.
+    pub fn set_odd_even(&mut self, balance: f32) {
+        self.macros.odd_even = balance.clamp(-1.0, 1.0);
+        self.reshape();
+    }
+
+    /// Stretches the partials apart, from 0 for a harmonic spectrum.
+    pub fn set_inharmonicity(&mut self, inharmonicity: f32) {
+        self.macros.inharmonicity = inharmonicity.max(0.0);
+        self.reshape();
+    }
+
+    /// Returns the glide settings.
+    pub fn glide(&self) -> &Glide {
+        &self.glide
+    }
+
+    /// Returns the glide settings to change, which apply from the next note.
+    pub fn glide_mut(&mut self) -> &mut Glide {
+        &mut self.glide
+    }
+
+    /// Returns the unison settings.
+    pub fn unison(&self) -> &Unison<UNISON> {
+        &self.unison
+    }
+
+    /// Sets how many unison copies each note plays, up to `UNISON`.
+    pub fn set_unison_count(&mut self, count: usize) {
+        self.unison.set_count(count);
+        self.respread();
+    }
+
+    /// Sets how far the outermost unison copies are detuned, in cents.
+    pub fn set_unison_detune(&mut self, cents: f32) {
+        self.unison.set_detune(cents);
+        self.respread();
+    }
+
+    /// Sets how far the outermost unison copies are panned, from 0 to 1.
+    pub fn set_unison_spread(&mut self, spread: f32) {
+        self.unison.set_spread(spread);
+        self.respread();
+    }
+
+    /// Sets how much the starting phases of the unison copies are randomised,
+    /// from 0 to 1. Randomness is off by default, so the partials start at
+    /// their own phases.
+    pub fn set_unison_phase_randomness(&mut self, randomness: f32) {
+        self.unison.set_phase_randomness(randomness);
+    }
+
+    /// Produces the next stereo frame of audio from the synth, with the
+    /// unison copies spread across it.
+    pub fn next_stereo(&mut self) -> [f32; 2] {
+        let gains = self.gains;
+        let mut frame = [0.0; 2];
+        self.render_copies(|copy, sample| {
+            frame[0] += sample * gains[copy][0];
+            frame[1] += sample * gains[copy][1];
+        });
+
+        frame
+    }
+
+    /// Renders a stereo buffer, like [`AdditiveSynth::next_stereo`].
+    pub fn render_stereo(&mut self, buffer: &'_ mut [[f32; 2]]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next_stereo();
         }
     }
+
+    /// Resynthesizes a sound from the magnitudes of an analysed spectrum.
+    ///
+    /// `magnitudes` holds every bin of an FFT, as written by
+    /// [`magnitudes`](catalina_engine::audio::spectrum::magnitudes), of a sound
+    /// with the given fundamental. Each partial is tuned to the loudest bin
+    /// around its harmonic, so inharmonic sounds keep their stretch, and the
+    /// levels are normalized so the loudest partial is at full level.
+    ///
+    /// The partials are left as they are if the spectrum has fewer than two
+    /// bins, or the fundamental or sample rate aren't positive.
+    pub fn set_partials_from_spectrum(
+        &mut self,
+        magnitudes: &[f32],
+        fundamental: Hertz,
+        sample_rate: f32,
+    ) {
+        if magnitudes.len() < 2 || !(fundamental.0 > 0.0) || !(sample_rate > 0.0) {
+            return;
+        }
+
+        let fft_size = ((magnitudes.len() - 1) * 2) as f32;
+        let bin_width = sample_rate / fft_size;
+        let spacing = fundamental.0 / bin_width;
+
+        let mut loudest: f32 = 0.0;
+        for (i, partial) in self.partials.iter_mut().enumerate() {
+            let center = spacing * (i + 1) as f32;
+            let from = libm::floorf(center - spacing * 0.5).max(1.0) as usize;
+            let to = (libm::ceilf(center + spacing * 0.5) as usize).min(magnitudes.len() - 1);
+
+            let peak = (from..=to).max_by(|a, b| magnitudes[*a].total_cmp(&magnitudes[*b]));
+
+            *partial = match peak {
+                Some(k) => {
+                    let level = magnitudes[k];
+                    loudest = loudest.max(level);
+                    Partial::new(peak_bin(magnitudes, k) / spacing, level, 0.0)
+                }
+                None => Partial::SILENT,
+            };
+        }
+
+        if loudest > 0.0 {
+            for partial in &mut self.partials {
+                partial.level /= loudest;
+            }
+        }
+
+        self.reshape();
+    }
+
+    fn reshape(&mut self) {
+        for (shaped, partial) in self.shaped.iter_mut().zip(self.partials.iter()) {
+            *shaped = self.macros.apply(partial);
+        }
+    }
+
+    fn respread(&mut self) {
+        for copy in 0..UNISON {
+            self.ratios[copy] = self.unison.ratio(copy);
+            self.gains[copy] = self.unison.gains(copy);
+        }
+    }
+
+    /// Renders the next sample of each unison copy of every voice, passing
+    /// each to `mix` along with which copy it is.
+    fn render_copies(&mut self, mut mix: impl FnMut(usize, f32)) {
+        let sample_rate = self.sample_rate as f32;
+        let semitones: f32 = self.bends.iter().map(PitchBend::semitones).sum();
+        let bend = libm::exp2f(semitones / 12.0);
+        let copies = self.unison.count();
+
+        // Loop through each active voice and sum them for the frame.
+        for voice in self.voices.values_mut() {
+            let frequency = voice.glide.advance().0 * bend;
+
+            for (copy, phases) in voice.phases.iter_mut().enumerate().take(copies) {
+                let frequency = frequency * self.ratios[copy];
+                let mut sample = 0.0;
+
+                for (partial, phase) in self.shaped.iter().zip(phases.iter_mut()) {
+                    // Partials above Nyquist would alias, so they're skipped.
+                    let increment = frequency * partial.ratio / sample_rate;
+                    if partial.level == 0.0 || increment >= 0.5 {
+                        continue;
+                    }
+
+                    // Sample each partial and add them together
+                    // (the add in **add**itive synthesis).
+                    sample += oscillator::sine::<f32>(*phase) * partial.level;
+
+                    // Shift the phase of each partial of the voice
+                    // so that the voices oscillate independently.
+                    *phase += increment;
+                    if *phase >= 1.0 {
+                        *phase -= 1.0;
+                    }
+                }
+
+                mix(copy, sample);
+            }
+        }
+    }
+}
+
+/// Refines the position of a peak in a spectrum to a fraction of a bin by
+/// fitting a parabola through it and its neighbours.
+fn peak_bin(magnitudes: &[f32], k: usize) -> f32 {
+    if k == 0 || k + 1 >= magnitudes.len() {
+        return k as f32;
+    }
+
+    let (left, center, right) = (magnitudes[k - 1], magnitudes[k], magnitudes[k + 1]);
+    let denominator = left - 2.0 * center + right;
+    if denominator.abs() < f32::EPSILON {
+        return k as f32;
+    }
+
+    k as f32 + 0.5 * (left - right) / denominator
 }
 
 /// The interfaces for controlling the instrument from the framework.
-impl Instrument for AdditiveSynth {
+impl<const PARTIALS: usize, const UNISON: usize> Instrument for AdditiveSynth<PARTIALS, UNISON> {
     fn init(&mut self) {}
 
     /// Called when a note is pressed.
     fn note_on(&mut self, note: Note, _velocity: u8) -> Result<(), NoteError> {
+        // The new voice glides in from the last note, counting as legato
+        // if any other note is still sounding.
+        let frequency = note.frequency();
+        let mut glide = self.glide;
+        if let Some(last) = self.last_frequency {
+            glide.jump(last);
+        }
+        glide.start(frequency, !self.voices.is_empty());
+
         // Attempt to add a voice.
         //
         // .insert() will return an error if the voices map is full.
         self.voices
             .insert(
-                note,         // This is the note we're adding a voice for
-                Voice::new(), // This holds the data for the voice.
+                note, // This is the note we're adding a voice for
+                // This holds the data for the voice, starting each
+                // partial at its configured phase.
+                Voice::new(
+                    self.partials.map(|partial| partial.phase),
+                    self.unison.phases(),
+                    glide,
+                ),
             )
             .map_err(|_| NoteError::NoVoices)?;
+        self.pedals.note_on(note);
+        self.last_frequency = Some(frequency);
 
         // There should ideally be some logic here to prempt
         // voices, but that's an exercise for later.
@@ -71,92 +371,49 @@ impl Instrument for AdditiveSynth {
 
     /// Called when a note is released.
     fn note_off(&mut self, note: Note) {
-        // Remove the voice for the note when the note is released.
-        self.voices.remove(&note);
+        // Remove the voice for the note when the note is released,
+        // unless one of the pedals is holding it.
+        if self.pedals.note_off(note) {
+            self.voices.remove(&note);
+        }
+    }
+
+    fn pitch_bend(&mut self, channel: u8, bend: f32) {
+        self.bends[channel as usize % 16].set(bend);
+    }
+
+    fn set_pitch_bend_range(&mut self, channel: u8, semitones: f32) {
+        self.bends[channel as usize % 16].set_range(semitones);
+    }
+
+    fn sustain_pedal(&mut self, _channel: u8, down: bool) {
+        let voices = &mut self.voices;
+        self.pedals.set_sustain(down, |note| {
+            voices.remove(&note);
+        });
+    }
+
+    fn sostenuto_pedal(&mut self, _channel: u8, down: bool) {
+        let voices = &mut self.voices;
+        self.pedals.set_sostenuto(down, |note| {
+            voices.remove(&note);
+        });
     }
 }
 
 /// Allows the synth to be used in [`Signal`]` chains.
-impl Signal for AdditiveSynth {
+impl<const PARTIALS: usize, const UNISON: usize> Signal for AdditiveSynth<PARTIALS, UNISON> {
     type Frame = f32;
 
     /// Produces the next frame of audio from the synth.
     fn next(&mut self) -> Self::Frame {
         // The final sample for the frame.
         //
-        // This is the result of all the voices (active notes) summed together.
+        // This is the result of all the voices (active notes) summed
+        // together, with the unison copies mixed down to mono.
+        let gain = self.unison.mono_gain();
         let mut sample = 0.0;
-
-        // Loop through each active voice and sum them for the frame.
-        for (note, voice) in self.voices.iter_mut() {
-            // The sample for this voice.
-            //
-            // This is the result of the oscillators summed
-            // together (the add in **add**itive synthesis).
-            let mut voice_sample = 0.0;
-
-            // Process the first oscillator for the voice, if enabled.
-            if self.oscillators[0].is_enabled() {
-                let osc = &self.oscillators[0];
-                // Sample each configured oscillator and add them together.
-                voice_sample = voice_sample + osc.sample::<f32>(voice.phase_0);
-
-                // Shift the base oscillator phase of the voice
-                // so that the voices oscillate independently.
-                voice.phase_0 =
-                    voice.phase_0 + (osc.note_frequency(note).hertz() / self.sample_rate as f32);
-                if voice.phase_0 >= 1.0 {
-                    voice.phase_0 = 0.0;
-                }
-            }
-
-            // Process the second oscillator for the voice, if enabled.
-            if self.oscillators[1].is_enabled() {
-                let osc = &self.oscillators[1];
-                // Sample each configured oscillator and add them together.
-                voice_sample = voice_sample + osc.sample::<f32>(voice.phase_1);
-
-                // Shift the base oscillator phase of the voice
-                // so that the voices oscillate independently.
-                voice.phase_1 =
-                    voice.phase_1 + (osc.note_frequency(note).hertz() / self.sample_rate as f32);
-                if voice.phase_1 >= 1.0 {
-                    voice.phase_1 = 0.0;
-                }
-            }
-
-            // Process the third oscillator for the voice, if enabled.
-            if self.oscillators[2].is_enabled() {
-                let osc = &self.oscillators[2];
-                // Sample each configured oscillator and add them together.
-                voice_sample = voice_sample + osc.sample::<f32>(voice.phase_2);
-
-                // Shift the base oscillator phase of the voice
-                // so that the voices oscillate independently.
-                voice.phase_2 =
-                    voice.phase_2 + (osc.note_frequency(note).hertz() / self.sample_rate as f32);
-                if voice.phase_2 >= 1.0 {
-                    voice.phase_2 = 0.0;
-                }
-            }
-
-            // Process the fourth oscillator for the voice, if enabled.
-            if self.oscillators[3].is_enabled() {
-                let osc = &self.oscillators[3];
-                // Sample each configured oscillator and add them together.
-                voice_sample = voice_sample + osc.sample::<f32>(voice.phase_3);
-
-                // Shift the base oscillator phase of the voice
-                // so that the voices oscillate independently.
-                voice.phase_3 =
-                    voice.phase_3 + (osc.note_frequency(note).hertz() / self.sample_rate as f32);
-                if voice.phase_3 >= 1.0 {
-                    voice.phase_3 = 0.0;
-                }
-            }
-
-            sample = sample + voice_sample;
-        }
+        self.render_copies(|_, copy| sample += copy * gain);
 
         // Note that the resulting buffer will be clipped on playback
         // depending on the voice count and frequencies.
@@ -168,18 +425,192 @@ impl Signal for AdditiveSynth {
     }
 }
 
-impl AudioSource for AdditiveSynth {
+impl<const PARTIALS: usize, const UNISON: usize> AudioSource for AdditiveSynth<PARTIALS, UNISON> {
     type Frame = f32;
 
     fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
-        for i in 0..buffer.len() {
+        for frame in buffer.iter_mut() {
             // Note that the resulting buffer will be clipped on playback
             // depending on the voice count and frequencies.
             //
             // It's on the receiving end of the rendered buffer to apply
             // amplitude scaling to bring the audio samples down to an
             // acceptable level for playback.
-            buffer[i] = self.next();
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use catalina_engine::{
+        audio::spectrum::{RealFft, magnitudes},
+        music::note::{CFive, CFour},
+    };
+
+    const SAMPLE_RATE: usize = 48_000;
+
+    #[test]
+    fn test_partials_track_the_note() {
+        let mut synth = AdditiveSynth::<2>::new(SAMPLE_RATE);
+        synth.set_partials([Partial::harmonic(1, 0.0), Partial::harmonic(2, 1.0)]);
+        synth.note_on(CFour, 100).unwrap();
+
+        // The second harmonic of C4 crosses zero twice per cycle.
+        let frames: [f32; SAMPLE_RATE] = core::array::from_fn(|_| synth.next());
+        let crossings = frames
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count();
+        let expected = 2.0 * 2.0 * CFour.frequency().hertz();
+        assert!((crossings as f32 - expected).abs() <= 2.0);
+    }
+
+    #[test]
+    fn test_partial_phase() {
+        // Without phase randomness, each partial starts at its own phase.
+        let mut synth = AdditiveSynth::<1>::new(SAMPLE_RATE);
+        synth.set_partials([Partial::new(1.0, 1.0, 0.0)]);
+        synth.note_on(CFour, 100).unwrap();
+        assert!(synth.next().abs() <= 1e-6);
+
+        // A quarter turn in, the partial starts at its peak.
+        let mut synth = AdditiveSynth::<1>::new(SAMPLE_RATE);
+        synth.set_partials([Partial::new(1.0, 1.0, 0.25)]);
+        synth.note_on(CFour, 100).unwrap();
+        let first = synth.next();
+        assert!(first > 0.0);
+        assert!((0..SAMPLE_RATE / 200).all(|_| synth.next() <= first));
+    }
+
+    #[test]
+    fn test_bend_and_sustain() {
+        let mut synth = AdditiveSynth::<1>::new(SAMPLE_RATE);
+        synth.set_pitch_bend_range(0, 12.0);
+        synth.pitch_bend(0, 1.0);
+
+        // Each channel keeps its own range.
+        synth.set_pitch_bend_range(1, 24.0);
+        assert_eq!(synth.bends[0].semitones(), 12.0);
+        synth.note_on(CFour, 100).unwrap();
+
+        // Bending up an octave doubles the zero crossings.
+        let frames: [f32; SAMPLE_RATE] = core::array::from_fn(|_| synth.next());
+        let crossings = frames
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count();
+        let expected = 2.0 * 2.0 * CFour.frequency().hertz();
+        assert!((crossings as f32 - expected).abs() <= 2.0);
+
+        // The sustain pedal keeps the released note playing until it's lifted.
+        synth.sustain_pedal(0, true);
+        synth.note_off(CFour);
+        assert!((0..480).any(|_| synth.next() != 0.0));
+
+        synth.sustain_pedal(0, false);
+        assert!((0..480).all(|_| synth.next() == 0.0));
+    }
+
+    #[test]
+    fn test_glide() {
+        let mut synth = AdditiveSynth::<1>::new(SAMPLE_RATE);
+        synth.glide_mut().set_time(0.1);
+
+        synth.note_on(CFour, 100).unwrap();
+        synth.note_off(CFour);
+
+        // Gliding up an octave, the pitch averages out between the two notes.
+        synth.note_on(CFive, 100).unwrap();
+        let frames: [f32; SAMPLE_RATE / 10] = core::array::from_fn(|_| synth.next());
+        let crossings = frames
+            .windows(2)
+            .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+            .count() as f32;
+        let average =
+            (CFive.frequency().hertz() - CFour.frequency().hertz()) / core::f32::consts::LN_2;
+        assert!(
+            (crossings - 2.0 * average / 10.0).abs() <= 3.0,
+            "{crossings}"
+        );
+    }
+
+    #[test]
+    fn test_unison_spread() {
+        let mut synth = AdditiveSynth::<1, 2>::new(SAMPLE_RATE);
+        synth.set_unison_count(2);
+        synth.set_unison_detune(1_200.0);
+        synth.set_unison_spread(1.0);
+        synth.note_on(CFour, 100).unwrap();
+
+        // The copies are an octave either side, panned hard left and right.
+        let mut crossings = [0; 2];
+        let mut last = [0.0; 2];
+        for _ in 0..SAMPLE_RATE {
+            let frame = synth.next_stereo();
+            for side in 0..2 {
+                if (frame[side] < 0.0) != (last[side] < 0.0) {
+                    crossings[side] += 1;
+                }
+            }
+            last = frame;
+        }
+
+        let expected = 2.0 * CFour.frequency().hertz();
+        assert!((crossings[0] as f32 - expected / 2.0).abs() <= 2.0);
+        assert!((crossings[1] as f32 - expected * 2.0).abs() <= 2.0);
+    }
+
+    #[test]
+    fn test_macros() {
+        let macros = SpectralMacros {
+            brightness: -0.5,
+            odd_even: -1.0,
+            inharmonicity: 0.01,
+        };
+
+        // Darkening falls off by 6dB per octave, and even harmonics are removed.
+        let third = macros.apply(&Partial::harmonic(3, 1.0));
+        assert!((third.level - 1.0 / 3.0).abs() <= 1e-6);
+        assert_eq!(macros.apply(&Partial::harmonic(2, 1.0)).level, 0.0);
+        assert!((macros.apply(&Partial::harmonic(1, 1.0)).level - 1.0).abs() <= 1e-6);
+
+        // The partials are stretched sharp.
+        assert!(third.ratio > 3.1);
+    }
+
+    #[test]
+    fn test_resynthesis() {
+        const N: usize = 1024;
+        let fundamental = 375.0; // Exactly 8 bins at 48kHz.
+
+        let mut buffer: [f32; N] = core::array::from_fn(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            oscillator::sine::<f32>(fundamental * t)
+                + 0.5 * oscillator::sine::<f32>(2.0 * fundamental * t)
+                + 0.25 * oscillator::sine::<f32>(3.0 * fundamental * t)
+        });
+        RealFft::<N>::new().forward(&mut buffer);
+        let mut spectrum = [0.0; N / 2 + 1];
+        magnitudes(&buffer, &mut spectrum);
+
+        let mut synth = AdditiveSynth::<4>::new(SAMPLE_RATE);
+        synth.set_partials_from_spectrum(&spectrum, Hertz(fundamental), SAMPLE_RATE as f32);
+
+        let partials = synth.partials();
+        for (partial, (ratio, level)) in partials.iter().zip([(1.0, 1.0), (2.0, 0.5), (3.0, 0.25)])
+        {
+            assert!((partial.ratio - ratio).abs() <= 0.01);
+            assert!((partial.level - level).abs() <= 0.01);
         }
+        assert!(partials[3].level < 0.01);
+
+        // Without a spectrum or fundamental to work from, the partials are kept.
+        let partials = *synth.partials();
+        synth.set_partials_from_spectrum(&[], Hertz(fundamental), SAMPLE_RATE as f32);
+        synth.set_partials_from_spectrum(&spectrum, Hertz(0.0), SAMPLE_RATE as f32);
+        assert_eq!(*synth.partials(), partials);
     }
 }
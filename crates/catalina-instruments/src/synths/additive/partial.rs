@@ -0,0 +1,68 @@
+/// A sine partial of the additive synth, tuned relative to the played note.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Partial {
+    /// The frequency of the partial as a multiple of the note's, where the
+    /// whole numbers are the harmonic series.
+    pub ratio: f32,
+    /// The amplitude of the partial, from 0 to 1.
+    pub level: f32,
+    /// The phase the partial starts at when a note is played, from 0 to 1.
+    pub phase: f32,
+}
+
+impl Partial {
+    /// A partial that contributes nothing.
+    pub const SILENT: Partial = Partial::new(1.0, 0.0, 0.0);
+
+    pub const fn new(ratio: f32, level: f32, phase: f32) -> Self {
+        Self {
+            ratio,
+            level,
+            phase,
+        }
+    }
+
+    /// Constructs the `number`th harmonic, counting the fundamental as 1.
+    pub const fn harmonic(number: usize, level: f32) -> Self {
+        Self::new(number as f32, level, 0.0)
+    }
+}
+
+/// Macros that reshape every partial at once.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SpectralMacros {
+    /// Tilts the levels of the partials by their ratio, from -1 for -12dB per
+    /// octave to 1 for +12dB per octave.
+    pub brightness: f32,
+    /// Balances the odd harmonics against the even ones, from -1 for only
+    /// odd harmonics, like a square wave, to 1 for only even harmonics. The
+    /// fundamental is left alone.
+    pub odd_even: f32,
+    /// Stretches the partials apart like a stiff piano string or bell, where
+    /// each partial is raised by `sqrt(1 + inharmonicity * ratio²)`.
+    pub inharmonicity: f32,
+}
+
+impl SpectralMacros {
+    /// Returns the partial after applying the macros.
+    pub fn apply(&self, partial: &Partial) -> Partial {
+        let ratio = partial.ratio;
+
+        let tilt = libm::powf(ratio.max(f32::EPSILON), self.brightness * 2.0);
+
+        let harmonic = libm::roundf(ratio) as u32;
+        let balance = match harmonic {
+            0 | 1 => 1.0,
+            n if n % 2 == 0 => (1.0 + self.odd_even).min(1.0),
+            _ => (1.0 - self.odd_even).min(1.0),
+        };
+
+        let stretch = libm::sqrtf(1.0 + self.inharmonicity.max(0.0) * ratio * ratio);
+
+        Partial {
+            ratio: ratio * stretch,
+            level: partial.level * tilt * balance,
+            phase: partial.phase,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use catalina_engine::instrument::Glide;
+
 /// A voice renders the output sound from the synth.
 ///
 /// In a monophonic synth there is a single voice that
@@ -5,31 +7,26 @@
 ///
 /// In polyphonic synths there are several voices that
 /// can play sounds from multiple keys at once.
-pub(crate) struct Voice {
-    /// Phase of the voice to be fed to the oscillators.
+pub(crate) struct Voice<const PARTIALS: usize, const UNISON: usize> {
+    /// Phase of each partial of each unison copy of the voice, from 0 to 1.
     ///
     /// Note that because the speed of the phase change is
     /// relative to the frequency and sample rate, we need
-    /// to maintain a seperate phase for each of the 4
-    /// oscillators because they may each be set to a
-    /// different base frequency.
-    ///
-    /// Increments each sample, and loops back
-    /// to 0 when exceeding the sample rate.
-    pub(crate) phase_0: f32,
-    pub(crate) phase_1: f32,
-    pub(crate) phase_2: f32,
-    pub(crate) phase_3: f32,
+    /// to maintain a seperate phase for each partial
+    /// because they each play at a different frequency.
+    pub(crate) phases: [[f32; PARTIALS]; UNISON],
+
+    /// Slides the voice's pitch in from the last note played.
+    pub(crate) glide: Glide,
 }
 
-impl Voice {
-    /// Constructs a new voice for the additive synth.
-    pub fn new() -> Self {
+impl<const PARTIALS: usize, const UNISON: usize> Voice<PARTIALS, UNISON> {
+    /// Constructs a new voice for the additive synth, starting each partial
+    /// at the given phase, offset by the starting phase of each unison copy.
+    pub fn new(phases: [f32; PARTIALS], offsets: [f32; UNISON], glide: Glide) -> Self {
         Self {
-            phase_0: 0.0,
-            phase_1: 0.0,
-            phase_2: 0.0,
-            phase_3: 0.0,
+            phases: offsets.map(|offset| phases.map(|phase| (phase + offset) % 1.0)),
+            glide,
         }
     }
 }
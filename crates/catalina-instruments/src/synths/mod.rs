@@ -1 +1,104 @@
+use core::cmp::Reverse;
+
+use catalina_engine::music::note::Note;
+
 pub mod additive;
+
+pub mod physical;
+
+pub mod subtractive;
+
+/// Converts a MIDI style velocity into the 0 to 1 range used by the models.
+#[inline]
+pub(crate) fn normalize_velocity(velocity: u8) -> f32 {
+    velocity.min(127) as f32 / 127.0
+}
+
+/// Tracks which note each voice is playing.
+///
+/// Shared by the synths whose voices keep ringing after their note is
+/// released, so voices aren't freed on note off. A new note takes over the
+/// voice already playing it, then the longest released voice, and only
+/// steals a held voice when every voice is held.
+pub(crate) struct Allocator<const VOICES: usize> {
+    /// The note held on each voice.
+    notes: [Option<Note>; VOICES],
+    /// When each voice was last started or released, to find the oldest.
+    ages: [u32; VOICES],
+    clock: u32,
+}
+
+impl<const VOICES: usize> Allocator<VOICES> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            notes: [None; VOICES],
+            ages: [0; VOICES],
+            clock: 0,
+        }
+    }
+
+    /// Picks the voice to play a note on.
+    pub(crate) fn allocate(&mut self, note: Note) -> usize {
+        let voice = self
+            .notes
+            .iter()
+            .position(|held| *held == Some(note))
+            .or_else(|| self.oldest(|held| held.is_none()))
+            .or_else(|| self.oldest(|_| true))
+            .unwrap_or(0);
+
+        self.notes[voice] = Some(note);
+        self.touch(voice);
+        voice
+    }
+
+    /// Releases the voice holding a note, returning it.
+    pub(crate) fn release(&mut self, note: Note) -> Option<usize> {
+        let voice = self.notes.iter().position(|held| *held == Some(note))?;
+
+        self.notes[voice] = None;
+        self.touch(voice);
+        Some(voice)
+    }
+
+    /// Returns the voice holding a note.
+    pub(crate) fn voice(&self, note: Note) -> Option<usize> {
+        self.notes.iter().position(|held| *held == Some(note))
+    }
+
+    /// Returns the oldest voice matching the filter, the first on a tie.
+    fn oldest(&self, filter: impl Fn(&Option<Note>) -> bool) -> Option<usize> {
+        (0..VOICES)
+            .filter(|&voice| filter(&self.notes[voice]))
+            .max_by_key(|&voice| (self.clock.wrapping_sub(self.ages[voice]), Reverse(voice)))
+    }
+
+    fn touch(&mut self, voice: usize) {
+        self.clock = self.clock.wrapping_add(1);
+        self.ages[voice] = self.clock;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocator() {
+        let notes = [60, 62, 64, 65].map(|midi| Note::from_midi(midi).unwrap());
+        let mut allocator = Allocator::<3>::new();
+
+        assert_eq!(allocator.allocate(notes[0]), 0);
+        assert_eq!(allocator.allocate(notes[1]), 1);
+        assert_eq!(allocator.allocate(notes[2]), 2);
+
+        // Every voice is held, so the oldest is stolen.
+        assert_eq!(allocator.allocate(notes[3]), 0);
+
+        // Released voices are reused before held ones, and repeated notes keep their voice.
+        assert_eq!(allocator.release(notes[2]), Some(2));
+        assert_eq!(allocator.release(notes[2]), None);
+        assert_eq!(allocator.allocate(notes[1]), 1);
+        assert_eq!(allocator.allocate(notes[0]), 2);
+    }
+}
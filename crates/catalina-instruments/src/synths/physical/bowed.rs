@@ -0,0 +1,111 @@
+use catalina_engine::{
+    audio::{AudioSource, physical::BowedString, signal::Signal},
+    instrument::{Instrument, NoteError},
+    music::note::Note,
+};
+
+use crate::synths::{Allocator, normalize_velocity};
+
+/// A polyphonic bowed string synth, for sustained violin and cello like tones.
+///
+/// Each voice is a bowed waveguide whose two halves hold up to `LENGTH`
+/// samples each. The string sustains while the note is held and rings out
+/// once the bow is lifted.
+pub struct BowedSynth<const VOICES: usize = 4, const LENGTH: usize = 512> {
+    strings: [BowedString<LENGTH>; VOICES],
+    allocator: Allocator<VOICES>,
+}
+
+impl<const VOICES: usize, const LENGTH: usize> BowedSynth<VOICES, LENGTH> {
+    /// Construct a new instance of the bowed string synth.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            strings: core::array::from_fn(|_| BowedString::new(sample_rate as f32)),
+            allocator: Allocator::new(),
+        }
+    }
+
+    /// Moves the bow along the strings, from 0 at the bridge to 1 at the nut.
+    pub fn set_bow_position(&mut self, position: f32) {
+        for string in &mut self.strings {
+            string.set_bow_position(position);
+        }
+    }
+
+    /// Sets how hard the bow presses on the strings, from 0 to 1.
+    pub fn set_bow_pressure(&mut self, pressure: f32) {
+        for string in &mut self.strings {
+            string.set_bow_pressure(pressure);
+        }
+    }
+}
+
+/// The interfaces for controlling the instrument from the framework.
+impl<const VOICES: usize, const LENGTH: usize> Instrument for BowedSynth<VOICES, LENGTH> {
+    fn init(&mut self) {}
+
+    /// Starts bowing a string, with the velocity setting the bow speed.
+    fn note_on(&mut self, note: Note, velocity: u8) -> Result<(), NoteError> {
+        let string = &mut self.strings[self.allocator.allocate(note)];
+        string.set_frequency(note.frequency());
+        string.bow(normalize_velocity(velocity));
+
+        Ok(())
+    }
+
+    /// Lifts the bow from the string.
+    fn note_off(&mut self, note: Note) {
+        if let Some(voice) = self.allocator.release(note) {
+            self.strings[voice].release();
+        }
+    }
+}
+
+impl<const VOICES: usize, const LENGTH: usize> Signal for BowedSynth<VOICES, LENGTH> {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        self.strings.iter_mut().map(|string| string.next()).sum()
+    }
+}
+
+impl<const VOICES: usize, const LENGTH: usize> AudioSource for BowedSynth<VOICES, LENGTH> {
+    type Frame = f32;
+
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the loudest frame over a number of samples.
+    fn peak(synth: &mut BowedSynth, samples: usize) -> f32 {
+        (0..samples).fold(0.0f32, |peak, _| peak.max(synth.next().abs()))
+    }
+
+    #[test]
+    fn test_bow_sustains() {
+        let note = Note::from_midi(55).unwrap();
+        let mut synth = BowedSynth::<4>::new(48_000);
+        synth.note_on(note, 100).unwrap();
+
+        // The string keeps sounding for as long as it's bowed.
+        peak(&mut synth, 4_800);
+        let early = peak(&mut synth, 4_800);
+        peak(&mut synth, 38_400);
+        let late = peak(&mut synth, 4_800);
+        assert!(early > 0.01, "{early}");
+        assert!(late > early * 0.5, "{late} against {early}");
+
+        // Lifting the bow lets it ring out.
+        synth.note_off(note);
+        peak(&mut synth, 48_000);
+        let lifted = peak(&mut synth, 4_800);
+        assert!(lifted < late * 0.1, "{lifted} against {late}");
+    }
+}
@@ -0,0 +1,11 @@
+//! Synths built on the physical models from the engine, which synthesize
+//! plucked, bowed and struck tones without any stored samples.
+
+pub mod plucked;
+pub use plucked::PluckedSynth;
+
+pub mod bowed;
+pub use bowed::BowedSynth;
+
+pub mod struck;
+pub use struck::StruckSynth;
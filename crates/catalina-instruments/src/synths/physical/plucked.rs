@@ -0,0 +1,130 @@
+use catalina_engine::{
+    audio::{AudioSource, physical::PluckedString, signal::Signal},
+    instrument::{Instrument, NoteError},
+    music::note::Note,
+};
+
+use crate::synths::{Allocator, normalize_velocity};
+
+/// A polyphonic plucked string synth, for guitars, harps and basses.
+///
+/// Each voice is an extended Karplus-Strong string with a period of up to
+/// `LENGTH` samples, which sets the lowest note, such as around 47Hz for the
+/// default 1024 samples at 48kHz.
+pub struct PluckedSynth<const VOICES: usize = 4, const LENGTH: usize = 1024> {
+    strings: [PluckedString<LENGTH>; VOICES],
+    allocator: Allocator<VOICES>,
+
+    /// How long a held string rings for, in seconds.
+    decay: f32,
+    /// How long a released string rings for, in seconds.
+    release: f32,
+}
+
+impl<const VOICES: usize, const LENGTH: usize> PluckedSynth<VOICES, LENGTH> {
+    /// Construct a new instance of the plucked string synth.
+    pub fn new(sample_rate: usize) -> Self {
+        Self {
+            // Seed each string differently so chords don't pluck identical noise.
+            strings: core::array::from_fn(|i| PluckedString::new(sample_rate as f32, i as u64)),
+            allocator: Allocator::new(),
+
+            decay: 4.0,
+            release: 0.3,
+        }
+    }
+
+    /// Sets how much the strings damp high frequencies, from 0 to 1.
+    pub fn set_damping(&mut self, damping: f32) {
+        for string in &mut self.strings {
+            string.set_damping(damping);
+        }
+    }
+
+    /// Sets where the strings are plucked, from 0 at the bridge to 1 at the nut.
+    pub fn set_pick_position(&mut self, position: f32) {
+        for string in &mut self.strings {
+            string.set_pick_position(position);
+        }
+    }
+
+    /// Sets how long a held string rings for, in seconds.
+    pub fn set_decay(&mut self, seconds: f32) {
+        self.decay = seconds;
+    }
+
+    /// Sets how long a string rings for after its note is released, in seconds.
+    pub fn set_release(&mut self, seconds: f32) {
+        self.release = seconds;
+    }
+}
+
+/// The interfaces for controlling the instrument from the framework.
+impl<const VOICES: usize, const LENGTH: usize> Instrument for PluckedSynth<VOICES, LENGTH> {
+    fn init(&mut self) {}
+
+    /// Plucks a string, stealing the oldest voice if they're all busy.
+    fn note_on(&mut self, note: Note, velocity: u8) -> Result<(), NoteError> {
+        let string = &mut self.strings[self.allocator.allocate(note)];
+        string.set_frequency(note.frequency());
+        string.set_decay(self.decay);
+        string.pluck(normalize_velocity(velocity));
+
+        Ok(())
+    }
+
+    /// Mutes the string, letting it ring out for the release time.
+    fn note_off(&mut self, note: Note) {
+        if let Some(voice) = self.allocator.release(note) {
+            self.strings[voice].set_decay(self.release);
+        }
+    }
+}
+
+impl<const VOICES: usize, const LENGTH: usize> Signal for PluckedSynth<VOICES, LENGTH> {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        self.strings.iter_mut().map(|string| string.next()).sum()
+    }
+}
+
+impl<const VOICES: usize, const LENGTH: usize> AudioSource for PluckedSynth<VOICES, LENGTH> {
+    type Frame = f32;
+
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the loudest frame over a number of samples.
+    fn peak(synth: &mut PluckedSynth, samples: usize) -> f32 {
+        (0..samples).fold(0.0f32, |peak, _| peak.max(synth.next().abs()))
+    }
+
+    #[test]
+    fn test_pluck_and_release() {
+        let note = Note::from_midi(57).unwrap();
+        let mut held = PluckedSynth::<4>::new(48_000);
+        let mut released = PluckedSynth::<4>::new(48_000);
+        held.note_on(note, 127).unwrap();
+        released.note_on(note, 127).unwrap();
+        assert!(peak(&mut held, 480) > 0.1);
+        assert!(peak(&mut released, 480) > 0.1);
+
+        // A held string rings on, while a released one is damped to its release time.
+        released.note_off(note);
+        peak(&mut held, 24_000);
+        peak(&mut released, 24_000);
+        let held = peak(&mut held, 4_800);
+        let released = peak(&mut released, 4_800);
+        assert!(held > 0.05, "{held}");
+        assert!(released < held * 0.1, "{released} against {held}");
+    }
+}
@@ -0,0 +1,152 @@
+use catalina_engine::{
+    audio::{
+        AudioSource,
+        physical::{ModalBank, Mode},
+        signal::Signal,
+    },
+    core::Hertz,
+    instrument::{Instrument, NoteError},
+    music::note::Note,
+};
+
+use crate::synths::{Allocator, normalize_velocity};
+
+/// The partials of a marimba bar, with the overtones tuned near two octaves,
+/// and three octaves and a major third, above the fundamental.
+pub const MARIMBA: [Mode; 4] = [
+    Mode::new(1.0, 0.6, 1.2),
+    Mode::new(3.99, 0.25, 0.4),
+    Mode::new(10.65, 0.1, 0.15),
+    Mode::new(20.8, 0.05, 0.08),
+];
+
+/// The partials of a church bell, with the hum below the strike note,
+/// a minor third tierce and the nominal an octave up.
+pub const BELL: [Mode; 4] = [
+    Mode::new(0.5, 0.3, 6.0),
+    Mode::new(1.0, 0.4, 4.0),
+    Mode::new(1.19, 0.25, 3.0),
+    Mode::new(2.0, 0.3, 2.0),
+];
+
+/// A polyphonic struck synth, for mallets, bells and other struck objects.
+///
+/// Each voice is a bank of `MODES` resonators, set from presets like
+/// [`MARIMBA`] and [`BELL`] or from custom modes.
+pub struct StruckSynth<const VOICES: usize = 4, const MODES: usize = 4> {
+    banks: [ModalBank<MODES>; VOICES],
+    allocator: Allocator<VOICES>,
+
+    /// How long a released note rings for, in seconds.
+    release: f32,
+}
+
+impl<const VOICES: usize, const MODES: usize> StruckSynth<VOICES, MODES> {
+    /// Construct a new instance of the struck synth, with the modes each voice rings at.
+    pub fn new(sample_rate: usize, modes: [Mode; MODES]) -> Self {
+        Self {
+            banks: core::array::from_fn(|_| {
+                ModalBank::new(sample_rate as f32, Hertz(440.0), modes)
+            }),
+            allocator: Allocator::new(),
+            release: 0.25,
+        }
+    }
+
+    /// Replaces the modes of every voice, such as when switching presets.
+    pub fn set_modes(&mut self, modes: [Mode; MODES]) {
+        for bank in &mut self.banks {
+            bank.set_modes(modes);
+        }
+    }
+
+    /// Sets how long a note rings for after it's released, in seconds.
+    pub fn set_release(&mut self, seconds: f32) {
+        self.release = seconds;
+    }
+}
+
+/// The interfaces for controlling the instrument from the framework.
+impl<const VOICES: usize, const MODES: usize> Instrument for StruckSynth<VOICES, MODES> {
+    fn init(&mut self) {}
+
+    /// Strikes a voice, with the velocity setting how hard.
+    fn note_on(&mut self, note: Note, velocity: u8) -> Result<(), NoteError> {
+        let bank = &mut self.banks[self.allocator.allocate(note)];
+        bank.set_frequency(note.frequency());
+        bank.strike(normalize_velocity(velocity));
+
+        Ok(())
+    }
+
+    /// Damps the voice, letting it ring out for the release time.
+    fn note_off(&mut self, note: Note) {
+        if let Some(voice) = self.allocator.release(note) {
+            self.banks[voice].damp(self.release);
+        }
+    }
+}
+
+impl<const VOICES: usize, const MODES: usize> Signal for StruckSynth<VOICES, MODES> {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        self.banks.iter_mut().map(|bank| bank.next()).sum()
+    }
+}
+
+impl<const VOICES: usize, const MODES: usize> AudioSource for StruckSynth<VOICES, MODES> {
+    type Frame = f32;
+
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the loudest frame over a number of samples.
+    fn peak(synth: &mut StruckSynth, samples: usize) -> f32 {
+        (0..samples).fold(0.0f32, |peak, _| peak.max(synth.next().abs()))
+    }
+
+    #[test]
+    fn test_strike_velocity() {
+        let note = Note::from_midi(60).unwrap();
+        let mut hard = StruckSynth::<4>::new(48_000, MARIMBA);
+        let mut soft = StruckSynth::<4>::new(48_000, MARIMBA);
+        hard.note_on(note, 127).unwrap();
+        soft.note_on(note, 64).unwrap();
+
+        // Striking half as hard rings half as loud.
+        let hard = peak(&mut hard, 4_800);
+        let soft = peak(&mut soft, 4_800);
+        assert!(hard > 0.1, "{hard}");
+        assert!(
+            (soft / hard - 64.0 / 127.0).abs() < 0.01,
+            "{soft} against {hard}"
+        );
+    }
+
+    #[test]
+    fn test_damp_on_release() {
+        let note = Note::from_midi(60).unwrap();
+        let mut held = StruckSynth::<4>::new(48_000, BELL);
+        let mut released = StruckSynth::<4>::new(48_000, BELL);
+        held.note_on(note, 127).unwrap();
+        released.note_on(note, 127).unwrap();
+
+        // A held bell rings for seconds, while a released one is damped.
+        released.note_off(note);
+        peak(&mut held, 24_000);
+        peak(&mut released, 24_000);
+        let held = peak(&mut held, 4_800);
+        let released = peak(&mut released, 4_800);
+        assert!(held > 0.05, "{held}");
+        assert!(released < held * 0.01, "{released} against {held}");
+    }
+}
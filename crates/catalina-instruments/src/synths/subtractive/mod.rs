@@ -0,0 +1,371 @@
+//! A virtual analogue polysynth, which carves sounds out of harmonically
+//! rich oscillators with a resonant filter.
+//!
+//! Each voice follows the classic subtractive signal path:
+//!
+//! - two variable shape oscillators, morphing from triangle through saw to
+//!   square, with the second detunable and optionally hard synced to the first,
+//! - a square sub oscillator an octave below and a noise source,
+//! - a resonant state variable filter, swept by its own envelope, the voice's
+//!   LFO and the played note,
+//! - and an amp envelope scaled by velocity.
+//!
+//! Each voice also follows its own pitch bend, pressure and timbre, so the
+//! synth can be played from an MPE controller through an
+//! [`MpeRouter`](catalina_engine::instrument::MpeRouter).
+//!
+//! The sound is set by a [`Patch`], shared by every voice.
+
+use catalina_engine::{
+    audio::{AudioSource, filter::SvfMode, signal::Signal},
+    core::Hertz,
+    instrument::{Instrument, NoteError, PitchBend},
+    music::note::Note,
+};
+
+use super::{Allocator, normalize_velocity};
+
+pub mod voice;
+pub(crate) use voice::Voice;
+
+/// The stages of an ADSR envelope, in seconds apart from the sustain level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeSettings {
+    pub attack: f32,
+    pub decay: f32,
+    /// The level held while the note is held, from 0 to 1.
+    pub sustain: f32,
+    pub release: f32,
+}
+
+/// The settings shared by every voice of a [`SubtractiveSynth`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Patch {
+    /// The shape of the first oscillator, from 0 for a triangle through 0.5
+    /// for a saw to 1 for a square.
+    pub osc1_shape: f32,
+    /// The pulse width of the first oscillator, from 0 to 1.
+    pub osc1_pulse_width: f32,
+    pub osc1_level: f32,
+
+    /// The shape of the second oscillator, like [`Patch::osc1_shape`].
+    pub osc2_shape: f32,
+    pub osc2_pulse_width: f32,
+    pub osc2_level: f32,
+    /// How far the second oscillator is tuned from the first, in semitones.
+    pub osc2_semitones: f32,
+    /// A fine detune of the second oscillator, in cents.
+    pub osc2_detune: f32,
+    /// Hard syncs the second oscillator to the first, so tuning it sweeps
+    /// its harmonics instead of its pitch.
+    pub sync: bool,
+
+    /// The level of the square sub oscillator, an octave below the first.
+    pub sub_level: f32,
+    pub noise_level: f32,
+
+    pub filter_mode: SvfMode,
+    pub cutoff: Hertz,
+    /// The filter resonance, from 0 to 1.
+    pub resonance: f32,
+    /// How far the filter envelope sweeps the cutoff, in octaves.
+    pub filter_envelope_amount: f32,
+    /// How much the cutoff follows the played note, from 0 to 1 for fully
+    /// tracking it from C4.
+    pub key_tracking: f32,
+
+    pub amp_envelope: EnvelopeSettings,
+    pub filter_envelope: EnvelopeSettings,
+
+    /// The rate of each voice's triangle LFO.
+    pub lfo_rate: Hertz,
+    /// How far the LFO bends the pitch, in semitones.
+    pub lfo_to_pitch: f32,
+    /// How far the LFO sweeps the cutoff, in octaves.
+    pub lfo_to_cutoff: f32,
+    /// How far the LFO moves the pulse widths, from 0 to 0.5.
+    pub lfo_to_pulse_width: f32,
+
+    /// How far a note's pressure opens the cutoff, in octaves.
+    pub pressure_to_cutoff: f32,
+    /// How far a note's pressure raises its level, from 0 to 1.
+    pub pressure_to_amp: f32,
+    /// How far a note's timbre sweeps the cutoff either side of its center,
+    /// in octaves.
+    pub timbre_to_cutoff: f32,
+}
+
+impl Default for Patch {
+    /// A plain saw lead with a gently enveloped filter.
+    fn default() -> Self {
+        Self {
+            osc1_shape: 0.5,
+            osc1_pulse_width: 0.5,
+            osc1_level: 1.0,
+
+            osc2_shape: 0.5,
+            osc2_pulse_width: 0.5,
+            osc2_level: 0.0,
+            osc2_semitones: 0.0,
+            osc2_detune: 7.0,
+            sync: false,
+
+            sub_level: 0.0,
+            noise_level: 0.0,
+
+            filter_mode: SvfMode::LowPass,
+            cutoff: Hertz(2_000.0),
+            resonance: 0.2,
+            filter_envelope_amount: 2.0,
+            key_tracking: 0.5,
+
+            amp_envelope: EnvelopeSettings {
+                attack: 0.005,
+                decay: 0.3,
+                sustain: 0.7,
+                release: 0.2,
+            },
+            filter_envelope: EnvelopeSettings {
+                attack: 0.005,
+                decay: 0.4,
+                sustain: 0.3,
+                release: 0.3,
+            },
+
+            lfo_rate: Hertz(5.0),
+            lfo_to_pitch: 0.0,
+            lfo_to_cutoff: 0.0,
+            lfo_to_pulse_width: 0.0,
+
+            pressure_to_cutoff: 1.0,
+            pressure_to_amp: 0.0,
+            timbre_to_cutoff: 2.0,
+        }
+    }
+}
+
+/// A polyphonic subtractive synth, with `VOICES` voices.
+///
+/// Released voices keep sounding through their release stage, so a new note
+/// takes over the longest released voice, and only steals a held voice when
+/// every voice is held.
+pub struct SubtractiveSynth<const VOICES: usize = 8> {
+    patch: Patch,
+    voices: [Voice; VOICES],
+    allocator: Allocator<VOICES>,
+    /// The pitch bend applied to every voice, on top of their own.
+    bend: PitchBend,
+}
+
+impl<const VOICES: usize> SubtractiveSynth<VOICES> {
+    /// Construct a new instance of the subtractive synth with the default patch.
+    pub fn new(sample_rate: usize) -> Self {
+        let patch = Patch::default();
+
+        Self {
+            patch,
+            voices: core::array::from_fn(|i| Voice::new(sample_rate, i as u64, &patch)),
+            allocator: Allocator::new(),
+            bend: PitchBend::default(),
+        }
+    }
+
+    /// Returns the current patch.
+    pub fn patch(&self) -> &Patch {
+        &self.patch
+    }
+
+    /// Replaces the patch, which sounding voices pick up straight away.
+    pub fn set_patch(&mut self, patch: Patch) {
+        self.patch = patch;
+        for voice in &mut self.voices {
+            voice.configure(&self.patch);
+        }
+    }
+
+    /// Returns how many voices are sounding, including released voices.
+    pub fn active_voices(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.is_active()).count()
+    }
+}
+
+/// The interfaces for controlling the instrument from the framework.
+impl<const VOICES: usize> Instrument for SubtractiveSynth<VOICES> {
+    fn init(&mut self) {}
+
+    /// Starts a voice, stealing the oldest if they're all busy.
+    fn note_on(&mut self, note: Note, velocity: u8) -> Result<(), NoteError> {
+        let voice = self.allocator.allocate(note);
+        self.voices[voice].start(note, normalize_velocity(velocity));
+
+        Ok(())
+    }
+
+    /// Releases the voice playing the note into its release stage.
+    fn note_off(&mut self, note: Note) {
+        if let Some(voice) = self.allocator.release(note) {
+            self.voices[voice].release();
+        }
+    }
+
+    fn pitch_bend(&mut self, _channel: u8, bend: f32) {
+        self.bend.set(bend);
+    }
+
+    fn set_pitch_bend_range(&mut self, _channel: u8, semitones: f32) {
+        self.bend.set_range(semitones);
+    }
+
+    fn poly_pressure(&mut self, note: Note, pressure: f32) {
+        if let Some(voice) = self.allocator.voice(note) {
+            self.voices[voice].set_pressure(pressure);
+        }
+    }
+
+    fn note_pitch_bend(&mut self, note: Note, semitones: f32) {
+        if let Some(voice) = self.allocator.voice(note) {
+            self.voices[voice].set_bend(semitones);
+        }
+    }
+
+    fn note_timbre(&mut self, note: Note, timbre: f32) {
+        if let Some(voice) = self.allocator.voice(note) {
+            self.voices[voice].set_timbre(timbre);
+        }
+    }
+}
+
+impl<const VOICES: usize> Signal for SubtractiveSynth<VOICES> {
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        let (patch, bend) = (&self.patch, self.bend.semitones());
+        self.voices
+            .iter_mut()
+            .map(|voice| voice.next(patch, bend))
+            .sum()
+    }
+}
+
+impl<const VOICES: usize> AudioSource for SubtractiveSynth<VOICES> {
+    type Frame = f32;
+
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use catalina_engine::music::note::CFour;
+
+    fn level<const VOICES: usize>(synth: &mut SubtractiveSynth<VOICES>, count: usize) -> f32 {
+        (0..count).fold(0.0, |peak: f32, _| peak.max(synth.next().abs()))
+    }
+
+    /// Counts the rising zero crossings, once per cycle of the note.
+    fn crossings<const VOICES: usize>(synth: &mut SubtractiveSynth<VOICES>, count: usize) -> usize {
+        let mut last = synth.next();
+        (0..count)
+            .filter(|_| {
+                let sample = synth.next();
+                let rising = last < 0.0 && sample >= 0.0;
+                last = sample;
+                rising
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_voice_lifecycle() {
+        let mut synth = SubtractiveSynth::<2>::new(48_000);
+        synth.set_patch(Patch {
+            osc2_level: 0.5,
+            sub_level: 0.5,
+            lfo_to_pitch: 0.3,
+            ..Default::default()
+        });
+
+        assert_eq!(level(&mut synth, 480), 0.0);
+
+        synth.note_on(CFour, 127).unwrap();
+        let held = level(&mut synth, 9_600);
+        assert!(held > 0.2 && held < 1.5, "{held}");
+        assert_eq!(synth.active_voices(), 1);
+
+        // The voice keeps sounding through its release, then falls silent.
+        synth.note_off(CFour);
+        assert!(level(&mut synth, 480) > 0.1);
+        level(&mut synth, 96_000);
+        assert_eq!(synth.active_voices(), 0);
+        assert_eq!(level(&mut synth, 480), 0.0);
+    }
+
+    #[test]
+    fn test_no_sustain_frees_voice() {
+        let mut synth = SubtractiveSynth::<1>::new(48_000);
+        synth.set_patch(Patch {
+            amp_envelope: EnvelopeSettings {
+                attack: 0.005,
+                decay: 0.05,
+                sustain: 0.0,
+                release: 0.2,
+            },
+            ..Default::default()
+        });
+
+        // The voice is free once it decays to silence, with the note still held.
+        synth.note_on(CFour, 127).unwrap();
+        level(&mut synth, 48_000);
+        assert_eq!(synth.active_voices(), 0);
+
+        // Playing the note again reuses the voice.
+        synth.note_on(CFour, 127).unwrap();
+        assert!(level(&mut synth, 480) > 0.1);
+    }
+
+    #[test]
+    fn test_note_expression() {
+        // A single voice, so the note played again reuses the bent voice.
+        let mut synth = SubtractiveSynth::<1>::new(48_000);
+        synth.set_patch(Patch {
+            pressure_to_amp: 0.5,
+            ..Default::default()
+        });
+
+        // Let the envelopes settle on their sustain levels.
+        synth.note_on(CFour, 127).unwrap();
+        level(&mut synth, 96_000);
+        let resting = level(&mut synth, 4_800);
+        let pitch = crossings(&mut synth, 48_000);
+        assert!(resting > 0.0);
+
+        // Pressure on another note doesn't reach the held voice.
+        synth.poly_pressure(Note::from_midi(64).unwrap(), 1.0);
+        assert!((level(&mut synth, 4_800) - resting).abs() < 0.05);
+
+        synth.poly_pressure(CFour, 1.0);
+        let pressed = level(&mut synth, 4_800);
+        assert!(pressed > resting * 1.5, "{pressed} {resting}");
+
+        // Bending the note up an octave doubles its pitch.
+        synth.note_pitch_bend(CFour, 12.0);
+        let bent = crossings(&mut synth, 48_000);
+        assert!(
+            (bent as f32 / pitch as f32 - 2.0).abs() < 0.1,
+            "{bent} {pitch}"
+        );
+
+        // A voice started again forgets the expression of its last note.
+        synth.note_off(CFour);
+        level(&mut synth, 96_000);
+        synth.note_on(CFour, 127).unwrap();
+        level(&mut synth, 96_000);
+        assert!((level(&mut synth, 4_800) - resting).abs() < 0.05);
+        assert!(crossings(&mut synth, 48_000).abs_diff(pitch) <= 2);
+    }
+}
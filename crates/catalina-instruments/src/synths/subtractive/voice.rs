@@ -0,0 +1,209 @@
+use catalina_engine::{
+    audio::{
+        envelope::adsr::Envelope,
+        filter::Svf,
+        oscillator::{Oscillator, variable::VariableShapeOscillator},
+    },
+    core::{Hertz, rng::Rng},
+    music::note::Note,
+};
+
+use super::{EnvelopeSettings, Patch};
+
+/// The lowest the filter cutoff is swept to.
+const MIN_CUTOFF: f32 = 20.0;
+
+/// A voice of the subtractive synth, rendering one note through the patch.
+pub(crate) struct Voice {
+    sample_rate: f32,
+
+    // NOTE: the variable shape oscillator plays at its sync frequency, with
+    //  its main frequency only used as the master it's reset by when synced.
+    osc1: VariableShapeOscillator,
+    osc2: VariableShapeOscillator,
+    sub_phase: f32,
+    noise: Rng,
+
+    filter: Svf,
+    amp_envelope: Envelope,
+    filter_envelope: Envelope,
+    lfo_phase: f32,
+
+    /// The frequency of the played note.
+    frequency: f32,
+    /// How many semitones the played note is from C4, for key tracking.
+    semitones: f32,
+    velocity: f32,
+
+    /// The note's own pitch bend, in semitones.
+    bend: f32,
+    /// The note's pressure, from 0 to 1.
+    pressure: f32,
+    /// The note's timbre, from 0 to 1, centered at 0.5.
+    timbre: f32,
+
+    gate: bool,
+    active: bool,
+}
+
+impl Voice {
+    pub(crate) fn new(sample_rate: usize, seed: u64, patch: &Patch) -> Self {
+        let mut voice = Self {
+            sample_rate: sample_rate as f32,
+
+            osc1: VariableShapeOscillator::new(sample_rate),
+            osc2: VariableShapeOscillator::new(sample_rate),
+            sub_phase: 0.0,
+            noise: Rng::new(seed),
+
+            filter: Svf::new(sample_rate as f32),
+            amp_envelope: Envelope::new(sample_rate),
+            filter_envelope: Envelope::new(sample_rate),
+            lfo_phase: 0.0,
+
+            frequency: 440.0,
+            semitones: 0.0,
+            velocity: 0.0,
+
+            bend: 0.0,
+            pressure: 0.0,
+            timbre: 0.5,
+
+            gate: false,
+            active: false,
+        };
+        voice.configure(patch);
+        voice
+    }
+
+    /// Applies the parts of the patch that aren't read every sample.
+    pub(crate) fn configure(&mut self, patch: &Patch) {
+        apply_envelope(&mut self.amp_envelope, &patch.amp_envelope);
+        apply_envelope(&mut self.filter_envelope, &patch.filter_envelope);
+
+        self.osc1.set_waveshape(patch.osc1_shape);
+        self.osc1.set_sync(false);
+        self.osc2.set_waveshape(patch.osc2_shape);
+        self.osc2.set_sync(patch.sync);
+
+        self.filter.set_mode(patch.filter_mode);
+        self.filter.set_resonance(patch.resonance);
+    }
+
+    /// Starts playing a note, with the velocity from 0 to 1.
+    pub(crate) fn start(&mut self, note: Note, velocity: f32) {
+        self.frequency = note.frequency().hertz();
+        self.semitones = note.midi().map_or(0.0, |midi| midi as f32 - 60.0);
+        self.velocity = velocity;
+
+        self.bend = 0.0;
+        self.pressure = 0.0;
+        self.timbre = 0.5;
+
+        // A voice taken over while its gate is still open restarts its envelopes.
+        if self.gate {
+            self.amp_envelope.retrigger();
+            self.filter_envelope.retrigger();
+        } else {
+            self.filter.reset();
+        }
+        self.lfo_phase = 0.0;
+        self.gate = true;
+        self.active = true;
+    }
+
+    /// Releases the note into the release stage of the envelopes.
+    pub(crate) fn release(&mut self) {
+        self.gate = false;
+    }
+
+    /// Bends the note, in semitones.
+    pub(crate) fn set_bend(&mut self, semitones: f32) {
+        self.bend = semitones;
+    }
+
+    /// Sets the pressure on the note, from 0 to 1.
+    pub(crate) fn set_pressure(&mut self, pressure: f32) {
+        self.pressure = pressure.clamp(0.0, 1.0);
+    }
+
+    /// Sets the timbre of the note, from 0 to 1.
+    pub(crate) fn set_timbre(&mut self, timbre: f32) {
+        self.timbre = timbre.clamp(0.0, 1.0);
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Renders the next sample, with the pitch bend shared by every voice in semitones.
+    #[inline]
+    pub(crate) fn next(&mut self, patch: &Patch, bend: f32) -> f32 {
+        if !self.active {
+            return 0.0;
+        }
+
+        // A triangle LFO, from -1 to 1.
+        self.lfo_phase += patch.lfo_rate.0 / self.sample_rate;
+        if self.lfo_phase >= 1.0 {
+            self.lfo_phase -= 1.0;
+        }
+        let lfo = 1.0 - 4.0 * (self.lfo_phase - 0.5).abs();
+
+        let semitones = lfo * patch.lfo_to_pitch + self.bend + bend;
+        let frequency = self.frequency * libm::exp2f(semitones / 12.0);
+        let osc2_frequency =
+            frequency * libm::exp2f((patch.osc2_semitones + patch.osc2_detune / 100.0) / 12.0);
+        let pulse_width = lfo * patch.lfo_to_pulse_width;
+
+        self.osc1.set_sync_frequency(Hertz(frequency));
+        self.osc1
+            .set_pulse_width(patch.osc1_pulse_width + pulse_width);
+        self.osc2.set_frequency(Hertz(frequency));
+        self.osc2.set_sync_frequency(Hertz(osc2_frequency));
+        self.osc2
+            .set_pulse_width(patch.osc2_pulse_width + pulse_width);
+
+        self.sub_phase += frequency * 0.5 / self.sample_rate;
+        if self.sub_phase >= 1.0 {
+            self.sub_phase -= 1.0;
+        }
+        let sub = if self.sub_phase < 0.5 { 1.0 } else { -1.0 };
+
+        let mix = Oscillator::<f32>::sample(&mut self.osc1) * patch.osc1_level
+            + Oscillator::<f32>::sample(&mut self.osc2) * patch.osc2_level
+            + sub * patch.sub_level
+            + self.noise.next_bipolar() * patch.noise_level;
+        let headroom =
+            (patch.osc1_level + patch.osc2_level + patch.sub_level + patch.noise_level).max(1.0);
+
+        let amp = self.amp_envelope.process(self.gate);
+        let sweep = self.filter_envelope.process(self.gate);
+
+        let octaves = sweep * patch.filter_envelope_amount
+            + lfo * patch.lfo_to_cutoff
+            + patch.key_tracking * self.semitones / 12.0
+            + self.pressure * patch.pressure_to_cutoff
+            + (self.timbre - 0.5) * patch.timbre_to_cutoff;
+        self.filter.set_cutoff(Hertz(
+            (patch.cutoff.0 * libm::exp2f(octaves)).max(MIN_CUTOFF),
+        ));
+        let filtered = self.filter.process(mix / headroom);
+
+        // The amp envelope finishes on its release, or on its decay with
+        // no sustain while the note is still held.
+        if !self.amp_envelope.is_active() {
+            self.active = false;
+        }
+
+        let level = self.velocity * (1.0 - patch.pressure_to_amp * (1.0 - self.pressure));
+        filtered * amp * level
+    }
+}
+
+fn apply_envelope(envelope: &mut Envelope, settings: &EnvelopeSettings) {
+    envelope.set_attack_time(settings.attack, 0.0);
+    envelope.set_decay_time(settings.decay);
+    envelope.set_sustain_level(settings.sustain);
+    envelope.set_release_time(settings.release);
+}
@@ -1,2 +1,5 @@
 mod midi;
 pub use midi::*;
+
+mod sampler;
+pub use sampler::*;
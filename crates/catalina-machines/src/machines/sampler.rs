@@ -0,0 +1,590 @@
+use catalina_engine::{
+    audio::{
+        AudioSource,
+        envelope::adsr::Envelope,
+        interpolate::Interpolator,
+        sample::{Sample, ToSample},
+        signal::{Signal, interpolate::Converter},
+    },
+    music::note::Note,
+};
+use catalina_seq::{ParameterID, ParameterValue, TriggerEvent};
+
+use crate::Machine;
+
+/// How the sampler loops once it reaches the loop end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopMode {
+    /// Plays through to the end of the sample once.
+    #[default]
+    Off,
+    /// Jumps back to the loop start.
+    Forward,
+    /// Reverses direction at each loop point.
+    PingPong,
+}
+
+/// Defines the parameters that can be set or locked for a sampler machine.
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplerParameter {
+    Unknown = 0,
+
+    /// Where playback starts, from 0 to 1 through the sample.
+    Start = 1,
+    /// Where playback ends, from 0 to 1 through the sample.
+    End = 2,
+    /// Where the loop starts, from 0 to 1 through the played region.
+    LoopStart = 3,
+    /// Where the loop ends, from 0 to 1 through the played region.
+    LoopEnd = 4,
+    /// The [`LoopMode`], as 0 for off, 1 for forward and 2 for ping-pong.
+    LoopMode = 5,
+    /// Plays the sample backwards.
+    Reverse = 6,
+    /// Tunes the sample in semitones.
+    Tune = 7,
+    /// How many equal slices the played region is divided into.
+    Slices = 8,
+    /// Which slice is played, counting from 0.
+    Slice = 9,
+    /// The amp envelope attack time in seconds.
+    Attack = 10,
+    /// The amp envelope decay time in seconds.
+    Decay = 11,
+    /// The amp envelope sustain level, from 0 to 1.
+    Sustain = 12,
+    /// The amp envelope release time in seconds.
+    Release = 13,
+    /// The output volume, from 0 to 1.
+    Volume = 14,
+}
+
+impl SamplerParameter {
+    /// Looks up the parameter for an ID, returning [`SamplerParameter::Unknown`]
+    /// for IDs the sampler doesn't have.
+    pub fn from_id(id: ParameterID) -> Self {
+        match id {
+            1 => Self::Start,
+            2 => Self::End,
+            3 => Self::LoopStart,
+            4 => Self::LoopEnd,
+            5 => Self::LoopMode,
+            6 => Self::Reverse,
+            7 => Self::Tune,
+            8 => Self::Slices,
+            9 => Self::Slice,
+            10 => Self::Attack,
+            11 => Self::Decay,
+            12 => Self::Sustain,
+            13 => Self::Release,
+            14 => Self::Volume,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// The settings a sampler plays a note with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerSettings {
+    pub start: f32,
+    pub end: f32,
+    pub loop_start: f32,
+    pub loop_end: f32,
+    pub loop_mode: LoopMode,
+    pub reverse: bool,
+    /// The tuning in semitones, applied on top of the note.
+    pub tune: f32,
+    pub slices: u8,
+    pub slice: u8,
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+    pub volume: f32,
+}
+
+impl Default for SamplerSettings {
+    fn default() -> Self {
+        Self {
+            start: 0.0,
+            end: 1.0,
+            loop_start: 0.0,
+            loop_end: 1.0,
+            loop_mode: LoopMode::Off,
+            reverse: false,
+            tune: 0.0,
+            slices: 1,
+            slice: 0,
+            attack: 0.0,
+            decay: 0.0,
+            sustain: 1.0,
+            release: 0.05,
+            volume: 1.0,
+        }
+    }
+}
+
+impl SamplerSettings {
+    /// Sets a parameter, ignoring parameters the sampler doesn't have.
+    pub fn set(&mut self, parameter: ParameterID, value: ParameterValue) {
+        match SamplerParameter::from_id(parameter) {
+            SamplerParameter::Unknown => {}
+            SamplerParameter::Start => self.start = value.as_float().clamp(0.0, 1.0),
+            SamplerParameter::End => self.end = value.as_float().clamp(0.0, 1.0),
+            SamplerParameter::LoopStart => self.loop_start = value.as_float().clamp(0.0, 1.0),
+            SamplerParameter::LoopEnd => self.loop_end = value.as_float().clamp(0.0, 1.0),
+            SamplerParameter::LoopMode => {
+                self.loop_mode = match value.as_int() {
+                    1 => LoopMode::Forward,
+                    2 => LoopMode::PingPong,
+                    _ => LoopMode::Off,
+                }
+            }
+            SamplerParameter::Reverse => self.reverse = value.as_bool(),
+            SamplerParameter::Tune => self.tune = value.as_float(),
+            SamplerParameter::Slices => self.slices = value.as_int().clamp(1, 255) as u8,
+            SamplerParameter::Slice => self.slice = value.as_int().clamp(0, 255) as u8,
+            SamplerParameter::Attack => self.attack = value.as_float().max(0.0),
+            SamplerParameter::Decay => self.decay = value.as_float().max(0.0),
+            SamplerParameter::Sustain => self.sustain = value.as_float().clamp(0.0, 1.0),
+            SamplerParameter::Release => self.release = value.as_float().max(0.0),
+            SamplerParameter::Volume => self.volume = value.as_float().clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Reads through a sample, following the region, loop and direction
+/// of the note being played.
+struct Playhead<'a, S> {
+    sample: &'a [S],
+
+    /// The first and one past the last sample that can be played.
+    region: (isize, isize),
+    /// The first and one past the last sample of the loop.
+    looped: (isize, isize),
+    mode: LoopMode,
+
+    position: isize,
+    direction: isize,
+    finished: bool,
+    /// How many silent frames have been read since playback finished.
+    silence: usize,
+}
+
+impl<'a, S> Playhead<'a, S> {
+    fn new(sample: &'a [S]) -> Self {
+        Self {
+            sample,
+            region: (0, 0),
+            looped: (0, 0),
+            mode: LoopMode::Off,
+            position: 0,
+            direction: 1,
+            finished: true,
+            silence: 0,
+        }
+    }
+
+    /// Restarts playback with the region and loop from the settings.
+    fn restart(&mut self, settings: &SamplerSettings) {
+        let length = self.sample.len() as isize;
+        if length == 0 {
+            self.finished = true;
+            return;
+        }
+
+        let at = |from: isize, to: isize, fraction: f32| {
+            from + ((to - from) as f32 * fraction.clamp(0.0, 1.0) + 0.5) as isize
+        };
+
+        // The region the start and end points select, at least a sample long.
+        let start = at(0, length, settings.start.min(settings.end)).min(length - 1);
+        let end = at(0, length, settings.start.max(settings.end)).max(start + 1);
+
+        // Narrowed to one of the equally sized slices.
+        let slices = settings.slices.max(1) as isize;
+        let slice = (settings.slice as isize).min(slices - 1);
+        let first = start + (end - start) * slice / slices;
+        let last = (start + (end - start) * (slice + 1) / slices).max(first + 1);
+        self.region = (first, last);
+
+        let loop_start = at(first, last, settings.loop_start).min(last - 1);
+        let loop_end = at(first, last, settings.loop_end).max(loop_start + 1);
+        self.looped = (loop_start, loop_end.min(last));
+
+        self.mode = settings.loop_mode;
+        if settings.reverse {
+            self.position = last - 1;
+            self.direction = -1;
+        } else {
+            self.position = first;
+            self.direction = 1;
+        }
+        self.finished = false;
+        self.silence = 0;
+    }
+
+    fn stop(&mut self) {
+        self.finished = true;
+    }
+
+    fn advance(&mut self) {
+        let next = self.position + self.direction;
+        let (loop_start, loop_end) = self.looped;
+
+        match self.mode {
+            LoopMode::Forward if self.direction > 0 && next >= loop_end => {
+                self.position = loop_start;
+            }
+            LoopMode::Forward if self.direction < 0 && next < loop_start => {
+                self.position = loop_end - 1;
+            }
+            // Bounce back without repeating the sample at the loop point.
+            LoopMode::PingPong if self.direction > 0 && next >= loop_end => {
+                self.direction = -1;
+                self.position = (loop_end - 2).max(loop_start);
+            }
+            LoopMode::PingPong if self.direction < 0 && next < loop_start => {
+                self.direction = 1;
+                self.position = (loop_start + 1).min(loop_end - 1);
+            }
+            _ if next < self.region.0 || next >= self.region.1 => self.finished = true,
+            _ => self.position = next,
+        }
+    }
+}
+
+impl<S> Signal for Playhead<'_, S>
+where
+    S: Sample + ToSample<f32>,
+{
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if self.finished {
+            self.silence = self.silence.saturating_add(1);
+            return 0.0;
+        }
+
+        let frame = self.sample[self.position as usize].to_sample::<f32>();
+        self.advance();
+        frame
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.finished
+    }
+}
+
+/// The sampler machine plays a buffer of samples, like the sample
+/// tracks on Elektron machines.
+///
+/// Notes are pitched relative to C4 using the interpolator the machine
+/// is constructed with, such as [`Linear`] for cheap playback or [`Sinc`]
+/// for cleaner repitching. Start, end and loop points, reverse playback
+/// and slicing select which part of the sample plays, and an amp envelope
+/// shapes each note.
+///
+/// Parameter changes received through [`SamplerMachine::handle_event`] are
+/// locked to the next note only, while [`SamplerMachine::set_parameter`]
+/// changes the machine's own settings.
+///
+/// [`Linear`]: catalina_engine::audio::interpolate::linear::Linear
+/// [`Sinc`]: catalina_engine::audio::interpolate::sinc::Sinc
+pub struct SamplerMachine<'a, S, I>
+where
+    S: Sample + ToSample<f32>,
+    I: Interpolator<Frame = f32>,
+{
+    sample_rate: usize,
+    /// The rate the sample was recorded at.
+    sample_hz: f32,
+
+    voice: Converter<Playhead<'a, S>, I>,
+    envelope: Envelope,
+
+    /// The settings notes play with when they aren't locked.
+    settings: SamplerSettings,
+    /// The settings for the next note, when it has parameter locks.
+    locks: Option<SamplerSettings>,
+
+    /// How many samples long a step of a note's length is, or 0 to
+    /// hold notes until they're released.
+    step_length: u32,
+    /// How many samples remain until the playing note is released.
+    gate_remaining: u32,
+    gate: bool,
+    gain: f32,
+    playing: bool,
+}
+
+impl<'a, S, I> SamplerMachine<'a, S, I>
+where
+    S: Sample + ToSample<f32>,
+    I: Interpolator<Frame = f32>,
+{
+    /// Construct a new sampler machine over a sample recorded at the output sample rate.
+    pub fn new(sample_rate: usize, sample: &'a [S], interpolator: I) -> Self {
+        Self {
+            sample_rate,
+            sample_hz: sample_rate as f32,
+
+            voice: Converter::scale_playback_hz(Playhead::new(sample), interpolator, 1.0),
+            envelope: Envelope::new(sample_rate),
+
+            settings: SamplerSettings::default(),
+            locks: None,
+
+            step_length: 0,
+            gate_remaining: 0,
+            gate: false,
+            gain: 0.0,
+            playing: false,
+        }
+    }
+
+    /// Replaces the sample, along with the rate it was recorded at, stopping playback.
+    pub fn set_sample(&mut self, sample: &'a [S], sample_hz: f32) {
+        *self.voice.source_mut() = Playhead::new(sample);
+        self.sample_hz = sample_hz;
+        self.stop();
+    }
+
+    /// Returns the settings notes play with when they aren't locked.
+    pub fn settings(&self) -> &SamplerSettings {
+        &self.settings
+    }
+
+    /// Replaces the settings notes play with.
+    pub fn set_settings(&mut self, settings: SamplerSettings) {
+        self.settings = settings;
+    }
+
+    /// Sets one of the machine's parameters for every following note.
+    pub fn set_parameter(&mut self, parameter: ParameterID, value: ParameterValue) {
+        self.settings.set(parameter, value);
+    }
+
+    /// Sets how many samples long one step of a note's length is, such as
+    /// from the sequencer's tempo. With a length of 0 notes hold until
+    /// they're released.
+    pub fn set_step_length(&mut self, samples: u32) {
+        self.step_length = samples;
+    }
+
+    /// Handles an event from a sequencer trigger.
+    ///
+    /// Parameter changes lock the parameter for the next played note.
+    pub fn handle_event(&mut self, event: &TriggerEvent) {
+        match *event {
+            TriggerEvent::PlayNote {
+                note,
+                velocity,
+                length,
+            } => self.trigger(note, velocity, length),
+            TriggerEvent::ParameterChange { parameter, value } => {
+                self.locks
+                    .get_or_insert(self.settings)
+                    .set(parameter, value);
+            }
+        }
+    }
+
+    /// Plays a note, holding it for `length` steps when a step length is set.
+    pub fn trigger(&mut self, note: Note, velocity: u8, length: u8) {
+        let settings = self.locks.take().unwrap_or(self.settings);
+
+        self.voice.source_mut().restart(&settings);
+        self.voice.reset();
+
+        let semitones = note.midi().unwrap_or(60) as f32 - 60.0 + settings.tune;
+        let scale = self.sample_hz / self.sample_rate as f32 * libm::exp2f(semitones / 12.0);
+        self.voice
+            .set_playback_hz_scale(scale.max(f32::EPSILON) as f64);
+
+        self.envelope.set_attack_time(settings.attack, 0.0);
+        self.envelope.set_decay_time(settings.decay);
+        self.envelope.set_sustain_level(settings.sustain);
+        self.envelope.set_release_time(settings.release);
+        self.envelope.retrigger();
+
+        self.gate = true;
+        self.gate_remaining = self.step_length.saturating_mul(length as u32);
+        self.gain = settings.volume * velocity.min(127) as f32 / 127.0;
+        self.playing = true;
+    }
+
+    /// Releases the playing note, letting the envelope fade it out.
+    pub fn release(&mut self) {
+        self.gate = false;
+        self.gate_remaining = 0;
+    }
+
+    /// Stops playback immediately.
+    pub fn stop(&mut self) {
+        self.voice.source_mut().stop();
+        self.release();
+        self.playing = false;
+    }
+
+    /// Returns true while a note is playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+}
+
+impl<S, I> Machine for SamplerMachine<'_, S, I>
+where
+    S: Sample + ToSample<f32>,
+    I: Interpolator<Frame = f32>,
+{
+}
+
+impl<S, I> Signal for SamplerMachine<'_, S, I>
+where
+    S: Sample + ToSample<f32>,
+    I: Interpolator<Frame = f32>,
+{
+    type Frame = f32;
+
+    fn next(&mut self) -> Self::Frame {
+        if !self.playing {
+            return 0.0;
+        }
+
+        if self.gate_remaining > 0 {
+            self.gate_remaining -= 1;
+            self.gate = self.gate_remaining > 0;
+        }
+
+        let level = self.envelope.process(self.gate);
+        let frame = self.voice.next() * level * self.gain;
+
+        // Keep playing once the sample has ended until the interpolator has
+        // drained the last frames it was given.
+        let drained = self.voice.source().silence >= self.voice.interpolator().buffered_frames();
+        if drained || !self.envelope.is_active() {
+            self.playing = false;
+        }
+
+        frame
+    }
+}
+
+impl<S, I> AudioSource for SamplerMachine<'_, S, I>
+where
+    S: Sample + ToSample<f32>,
+    I: Interpolator<Frame = f32>,
+{
+    type Frame = f32;
+
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        for frame in buffer.iter_mut() {
+            *frame = self.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use catalina_engine::{audio::interpolate::linear::Linear, music::note::CFour};
+
+    const SAMPLE: [f32; 8] = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7];
+
+    /// Plays the playhead directly, skipping the interpolator.
+    fn play(settings: SamplerSettings, count: usize) -> Vec<f32> {
+        let mut playhead = Playhead::new(&SAMPLE);
+        playhead.restart(&settings);
+        (0..count).map(|_| playhead.next()).collect()
+    }
+
+    #[test]
+    fn test_playhead_loops() {
+        let forward = SamplerSettings {
+            loop_mode: LoopMode::Forward,
+            loop_start: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            play(forward, 10),
+            [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.4, 0.5]
+        );
+
+        let ping_pong = SamplerSettings {
+            loop_mode: LoopMode::PingPong,
+            loop_start: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            play(ping_pong, 12),
+            [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.6, 0.5, 0.4, 0.5]
+        );
+    }
+
+    #[test]
+    fn test_playhead_reverse_and_slices() {
+        let reverse = SamplerSettings {
+            reverse: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            play(reverse, 9),
+            [0.7, 0.6, 0.5, 0.4, 0.3, 0.2, 0.1, 0.0, 0.0]
+        );
+
+        let slice = SamplerSettings {
+            slices: 4,
+            slice: 2,
+            ..Default::default()
+        };
+        assert_eq!(play(slice, 3), [0.4, 0.5, 0.0]);
+    }
+
+    #[test]
+    fn test_parameter_lock_applies_once() {
+        let sample = [1.0f32; 64];
+        let mut sampler = SamplerMachine::new(48_000, &sample, Linear::new(0.0, 0.0));
+        let play = TriggerEvent::PlayNote {
+            note: CFour,
+            velocity: 127,
+            length: 1,
+        };
+
+        sampler.handle_event(&TriggerEvent::ParameterChange {
+            parameter: SamplerParameter::Volume as ParameterID,
+            value: ParameterValue::Float(0.5),
+        });
+        sampler.handle_event(&play);
+        let locked: Vec<f32> = (0..8).map(|_| sampler.next()).collect();
+        assert_eq!(locked[4], 0.5);
+
+        // The lock only applied to the note it was programmed with.
+        sampler.handle_event(&play);
+        let unlocked: Vec<f32> = (0..8).map(|_| sampler.next()).collect();
+        assert_eq!(unlocked[4], 1.0);
+        assert_eq!(sampler.settings().volume, 1.0);
+
+        // Playing past the end of a one-shot sample stops the machine.
+        for _ in 0..64 {
+            sampler.next();
+        }
+        assert!(!sampler.is_playing());
+    }
+
+    #[test]
+    fn test_plays_until_interpolator_drains() {
+        let sample = [1.0f32; 4];
+        let mut sampler = SamplerMachine::new(48_000, &sample, Linear::new(0.0, 0.0));
+        sampler.trigger(CFour, 127, 0);
+
+        let mut played = Vec::new();
+        while sampler.is_playing() {
+            played.push(sampler.next());
+        }
+        assert_eq!(played.iter().filter(|&&frame| frame > 0.0).count(), 4);
+        assert_eq!(played.last(), Some(&0.0));
+    }
+}
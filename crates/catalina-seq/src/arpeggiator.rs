@@ -0,0 +1,759 @@
+use heapless::Vec;
+
+use catalina_engine::{
+    audio::{AudioSource, signal::Signal},
+    core::rng::Rng,
+    instrument::{Instrument, NoteError},
+    music::note::Note,
+};
+
+use crate::{SEQUENCER_PPQM, STEP_SUBSTEPS, TickClock, TriggerEvent};
+
+/// The order the arpeggiator plays the held notes in.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ArpMode {
+    /// From the lowest note to the highest.
+    #[default]
+    Up,
+    /// From the highest note to the lowest.
+    Down,
+    /// Up and then back down, without repeating the top and bottom notes.
+    UpDown,
+    /// A random held note each step.
+    Random,
+    /// In the order the notes were played.
+    AsPlayed,
+    /// Every held note at once, retriggered each step.
+    Chord,
+}
+
+/// How often the arpeggiator steps, as a division of the sequencer's beat.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ArpRate {
+    Quarter,
+    Eighth,
+    EighthTriplet,
+    #[default]
+    Sixteenth,
+    SixteenthTriplet,
+    ThirtySecond,
+}
+
+impl ArpRate {
+    /// Returns how many sequencer ticks each step lasts.
+    pub const fn ticks(&self) -> u32 {
+        let quarter = SEQUENCER_PPQM as u32;
+
+        match self {
+            ArpRate::Quarter => quarter,
+            ArpRate::Eighth => quarter / 2,
+            ArpRate::EighthTriplet => quarter / 3,
+            ArpRate::Sixteenth => quarter / 4,
+            ArpRate::SixteenthTriplet => quarter / 6,
+            ArpRate::ThirtySecond => quarter / 8,
+        }
+    }
+}
+
+/// A note the arpeggiator starts or stops.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ArpEvent {
+    NoteOn { note: Note, velocity: u8 },
+    NoteOff { note: Note },
+}
+
+/// An arpeggiator, stepping through the held notes in time with the sequencer.
+///
+/// Notes are held with [`Arpeggiator::note_on`] and [`Arpeggiator::note_off`],
+/// and [`Arpeggiator::tick`] is called on every sequencer tick to emit the
+/// notes to play. Up to `NOTES` notes are held, and notes past that are
+/// ignored, so the arpeggiator never allocates.
+///
+/// On a sequencer track, the track's note triggers can be fed in with
+/// [`Arpeggiator::handle_trigger_event`] so each trigger is arpeggiated for
+/// its length. To play an instrument directly, wrap it in an [`Arpeggiated`].
+#[derive(Debug, Clone)]
+pub struct Arpeggiator<const NOTES: usize = 16> {
+    mode: ArpMode,
+    rate: ArpRate,
+    /// How many octaves the pattern spans, from 1.
+    octaves: u8,
+    /// How long each note is held, as a fraction of its step.
+    gate: f32,
+    /// How far the offbeat steps are pushed back, from 0.5 for straight to 0.75.
+    swing: f32,
+    /// Keeps the last notes playing after their keys are released.
+    latch: bool,
+
+    /// The notes being arpeggiated and their velocities, in the order they
+    /// were played.
+    held: Vec<(Note, u8), NOTES>,
+    /// The keys that are down, which differ from the held notes when latched.
+    pressed: Vec<Note, NOTES>,
+    /// Notes held by sequencer triggers, and how many ticks until they're released.
+    timed: Vec<(Note, u32), NOTES>,
+    /// The notes playing now.
+    sounding: Vec<Note, NOTES>,
+
+    /// How many steps have been played since the pattern started.
+    position: usize,
+    /// The tick within the current pair of steps, which swing is measured over.
+    counter: u32,
+    /// How many ticks until the sounding notes are released.
+    gate_remaining: u32,
+
+    rng: Rng,
+}
+
+impl<const NOTES: usize> Default for Arpeggiator<NOTES> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<const NOTES: usize> Arpeggiator<NOTES> {
+    /// Constructs an arpeggiator playing up a single octave in sixteenths,
+    /// with `seed` picking the notes of the random mode.
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            mode: ArpMode::Up,
+            rate: ArpRate::Sixteenth,
+            octaves: 1,
+            gate: 0.5,
+            swing: 0.5,
+            latch: false,
+
+            held: Vec::new(),
+            pressed: Vec::new(),
+            timed: Vec::new(),
+            sounding: Vec::new(),
+
+            position: 0,
+            counter: 0,
+            gate_remaining: 0,
+
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn set_mode(&mut self, mode: ArpMode) {
+        self.mode = mode;
+    }
+
+    #[inline]
+    pub fn mode(&self) -> ArpMode {
+        self.mode
+    }
+
+    /// Sets the rate, which applies from the next pair of steps.
+    pub fn set_rate(&mut self, rate: ArpRate) {
+        self.rate = rate;
+    }
+
+    #[inline]
+    pub fn rate(&self) -> ArpRate {
+        self.rate
+    }
+
+    /// Sets how many octaves the pattern spans, from 1 to 4.
+    pub fn set_octaves(&mut self, octaves: u8) {
+        self.octaves = octaves.clamp(1, 4);
+    }
+
+    #[inline]
+    pub fn octaves(&self) -> u8 {
+        self.octaves
+    }
+
+    /// Sets how long each note is held, as a fraction of its step.
+    ///
+    /// At 1 each note is held right up to the start of the next one, which is
+    /// released on the same tick the next note starts, so the notes don't overlap.
+    pub fn set_gate(&mut self, gate: f32) {
+        self.gate = gate.clamp(0.0, 1.0);
+    }
+
+    #[inline]
+    pub fn gate(&self) -> f32 {
+        self.gate
+    }
+
+    /// Sets the swing, from 0.5 for straight steps to 0.75 for the offbeat
+    /// steps being pushed three quarters of the way to the next step.
+    pub fn set_swing(&mut self, swing: f32) {
+        self.swing = swing.clamp(0.5, 0.75);
+    }
+
+    #[inline]
+    pub fn swing(&self) -> f32 {
+        self.swing
+    }
+
+    /// Sets whether the notes keep playing after their keys are released,
+    /// until a new chord is played.
+    ///
+    /// Turning the latch off lets go of the notes whose keys aren't down.
+    pub fn set_latch(&mut self, latch: bool) {
+        self.latch = latch;
+
+        if !latch {
+            let (pressed, timed) = (&self.pressed, &self.timed);
+            self.held.retain(|(note, _)| {
+                pressed.contains(note) || timed.iter().any(|(timed, _)| timed == note)
+            });
+        }
+    }
+
+    #[inline]
+    pub fn latch(&self) -> bool {
+        self.latch
+    }
+
+    /// Returns true if there are notes being arpeggiated.
+    pub fn is_active(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    /// Holds a note to be arpeggiated.
+    pub fn note_on(&mut self, note: Note, velocity: u8) {
+        // A new chord played after every key is released replaces the latched notes.
+        if self.latch && self.pressed.is_empty() && self.timed.is_empty() {
+            self.held.clear();
+        }
+
+        // Starting from nothing restarts the pattern on the next tick.
+        if self.held.is_empty() {
+            self.position = 0;
+            self.counter = 0;
+        }
+
+        if !self.pressed.contains(&note) {
+            let _ = self.pressed.push(note);
+        }
+        if !self.held.iter().any(|(held, _)| *held == note) {
+            let _ = self.held.push((note, velocity));
+        }
+    }
+
+    /// Releases a note, which stops being arpeggiated unless it's latched.
+    pub fn note_off(&mut self, note: Note) {
+        remove(&mut self.pressed, |pressed| *pressed == note);
+
+        if !self.latch {
+            remove(&mut self.held, |(held, _)| *held == note);
+        }
+    }
+
+    /// Handles a trigger from a sequencer track, holding the note it plays
+    /// for the trigger's length in steps.
+    pub fn handle_trigger_event(&mut self, event: &TriggerEvent) {
+        if let TriggerEvent::PlayNote {
+            note,
+            velocity,
+            length,
+        } = *event
+        {
+            let ticks = length.max(1) as u32 * STEP_SUBSTEPS as u32;
+
+            self.note_on(note, velocity);
+            remove(&mut self.pressed, |pressed| *pressed == note);
+            remove(&mut self.timed, |(timed, _)| *timed == note);
+            let _ = self.timed.push((note, ticks));
+        }
+    }
+
+    /// Stops every note, emitting the releases for the notes playing.
+    pub fn stop(&mut self, mut emit: impl FnMut(ArpEvent)) {
+        self.held.clear();
+        self.pressed.clear();
+        self.timed.clear();
+        self.release(&mut emit);
+    }
+
+    /// Advances the arpeggiator by a sequencer tick, emitting the notes to
+    /// start and stop on it.
+    pub fn tick(&mut self, mut emit: impl FnMut(ArpEvent)) {
+        self.expire_timed();
+
+        if self.held.is_empty() {
+            self.release(&mut emit);
+            return;
+        }
+
+        // Steps come in pairs so the swing can push the second one back.
+        let pair = self.rate.ticks() * 2;
+        let offbeat = ((pair as f32 * self.swing) as u32).clamp(1, pair - 1);
+
+        let length = match self.counter {
+            0 => Some(offbeat),
+            counter if counter == offbeat => Some(pair - offbeat),
+            _ => None,
+        };
+
+        if let Some(length) = length {
+            self.release(&mut emit);
+            self.play(&mut emit);
+            self.gate_remaining = ((length as f32 * self.gate) as u32).max(1);
+        } else if self.gate_remaining > 0 {
+            self.gate_remaining -= 1;
+            if self.gate_remaining == 0 {
+                self.release(&mut emit);
+            }
+        }
+
+        self.counter = (self.counter + 1) % pair;
+    }
+
+    /// Counts down the notes held by triggers, releasing those that are done.
+    fn expire_timed(&mut self) {
+        let mut index = 0;
+        while index < self.timed.len() {
+            let (note, remaining) = &mut self.timed[index];
+            *remaining = remaining.saturating_sub(1);
+
+            if *remaining == 0 {
+                let note = *note;
+                self.timed.swap_remove(index);
+                if !self.latch && !self.pressed.contains(&note) {
+                    remove(&mut self.held, |(held, _)| *held == note);
+                }
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    /// Starts the notes of the next step.
+    fn play(&mut self, emit: &mut impl FnMut(ArpEvent)) {
+        let mut notes = self.held.clone();
+        if self.mode != ArpMode::AsPlayed {
+            notes.sort_unstable_by_key(|(note, _)| note.midi());
+        }
+        let count = notes.len() * self.octaves as usize;
+
+        if self.mode == ArpMode::Chord {
+            for index in 0..count {
+                self.start(&notes, index, emit);
+            }
+        } else {
+            let step = self.position % count;
+            let index = match self.mode {
+                ArpMode::Down => count - 1 - step,
+                ArpMode::UpDown if count > 1 => {
+                    let step = self.position % (count * 2 - 2);
+                    if step < count {
+                        step
+                    } else {
+                        count * 2 - 2 - step
+                    }
+                }
+                ArpMode::Random => self.rng.next_below(count as u32) as usize,
+                _ => step,
+            };
+            self.start(&notes, index, emit);
+        }
+
+        self.position = self.position.wrapping_add(1);
+    }
+
+    /// Starts the note at a position in the pattern, counting up through
+    /// the notes in the pattern's order and then their octaves.
+    fn start(&mut self, notes: &[(Note, u8)], index: usize, emit: &mut impl FnMut(ArpEvent)) {
+        let (note, velocity) = notes[index % notes.len()];
+        let octave = (index / notes.len()) as u8;
+
+        let Some(note) = note
+            .midi()
+            .and_then(|midi| midi.checked_add(octave * 12))
+            .and_then(Note::from_midi)
+        else {
+            return;
+        };
+
+        if !self.sounding.contains(&note) && self.sounding.push(note).is_ok() {
+            emit(ArpEvent::NoteOn { note, velocity });
+        }
+    }
+
+    /// Stops the notes playing now.
+    fn release(&mut self, emit: &mut impl FnMut(ArpEvent)) {
+        for note in self.sounding.drain(..) {
+            emit(ArpEvent::NoteOff { note });
+        }
+    }
+}
+
+fn remove<T, const N: usize>(items: &mut Vec<T, N>, filter: impl Fn(&T) -> bool) {
+    if let Some(index) = items.iter().position(filter) {
+        items.remove(index);
+    }
+}
+
+/// Wraps an [`Instrument`] so the notes played on it are arpeggiated.
+///
+/// The wrapper keeps its own clock, running the arpeggiator at the tempo it's
+/// set to and starting each note on the exact frame its tick falls on. Every
+/// other control, including the per-note expression, passes straight through
+/// to the instrument.
+pub struct Arpeggiated<I: Instrument, const NOTES: usize = 16> {
+    instrument: I,
+    arpeggiator: Arpeggiator<NOTES>,
+    clock: TickClock,
+}
+
+impl<I: Instrument, const NOTES: usize> Arpeggiated<I, NOTES> {
+    pub fn new(instrument: I, sample_rate: usize, bpm: f32, seed: u64) -> Self {
+        Self {
+            instrument,
+            arpeggiator: Arpeggiator::new(seed),
+            clock: TickClock::new(sample_rate, bpm),
+        }
+    }
+
+    pub fn arpeggiator(&self) -> &Arpeggiator<NOTES> {
+        &self.arpeggiator
+    }
+
+    pub fn arpeggiator_mut(&mut self) -> &mut Arpeggiator<NOTES> {
+        &mut self.arpeggiator
+    }
+
+    pub fn instrument(&self) -> &I {
+        &self.instrument
+    }
+
+    pub fn instrument_mut(&mut self) -> &mut I {
+        &mut self.instrument
+    }
+
+    /// Unwraps the instrument, leaving any arpeggiated notes playing on it.
+    pub fn into_inner(self) -> I {
+        self.instrument
+    }
+
+    /// Sets the tempo the arpeggiator runs at.
+    pub fn set_bpm(&mut self, bpm: f32) {
+        self.clock.set_bpm(bpm);
+    }
+}
+
+/// Plays an arpeggiator event on an instrument.
+fn apply<I: Instrument>(instrument: &mut I, event: ArpEvent) {
+    match event {
+        ArpEvent::NoteOn { note, velocity } => {
+            // A note without a free voice is skipped, like a dropped MIDI note.
+            let _ = instrument.note_on(note, velocity);
+        }
+        ArpEvent::NoteOff { note } => instrument.note_off(note),
+    }
+}
+
+impl<I: Instrument, const NOTES: usize> Instrument for Arpeggiated<I, NOTES> {
+    fn init(&mut self) {
+        self.instrument.init();
+    }
+
+    fn note_on(&mut self, note: Note, velocity: u8) -> Result<(), NoteError> {
+        self.arpeggiator.note_on(note, velocity);
+        Ok(())
+    }
+
+    fn note_off(&mut self, note: Note) {
+        self.arpeggiator.note_off(note);
+    }
+
+    fn pitch_bend(&mut self, channel: u8, bend: f32) {
+        self.instrument.pitch_bend(channel, bend);
+    }
+
+    fn set_pitch_bend_range(&mut self, channel: u8, semitones: f32) {
+        self.instrument.set_pitch_bend_range(channel, semitones);
+    }
+
+    fn channel_pressure(&mut self, channel: u8, pressure: f32) {
+        self.instrument.channel_pressure(channel, pressure);
+    }
+
+    fn poly_pressure(&mut self, note: Note, pressure: f32) {
+        self.instrument.poly_pressure(note, pressure);
+    }
+
+    fn note_pitch_bend(&mut self, note: Note, semitones: f32) {
+        self.instrument.note_pitch_bend(note, semitones);
+    }
+
+    fn note_timbre(&mut self, note: Note, timbre: f32) {
+        self.instrument.note_timbre(note, timbre);
+    }
+
+    fn mod_wheel(&mut self, channel: u8, value: f32) {
+        self.instrument.mod_wheel(channel, value);
+    }
+
+    fn sustain_pedal(&mut self, channel: u8, down: bool) {
+        self.instrument.sustain_pedal(channel, down);
+    }
+
+    fn sostenuto_pedal(&mut self, channel: u8, down: bool) {
+        self.instrument.sostenuto_pedal(channel, down);
+    }
+
+    fn set_parameter(&mut self, id: u16, value: f32) {
+        self.instrument.set_parameter(id, value);
+    }
+}
+
+impl<I: Instrument, const NOTES: usize> Signal for Arpeggiated<I, NOTES> {
+    type Frame = <I as Signal>::Frame;
+
+    fn next(&mut self) -> Self::Frame {
+        let (instrument, arpeggiator) = (&mut self.instrument, &mut self.arpeggiator);
+        self.clock.advance(1, |_| {
+            arpeggiator.tick(|event| apply(instrument, event));
+        });
+
+        self.instrument.next()
+    }
+}
+
+impl<I: Instrument, const NOTES: usize> AudioSource for Arpeggiated<I, NOTES> {
+    type Frame = <I as AudioSource>::Frame;
+
+    /// Renders the instrument, splitting the block at each tick so the
+    /// notes land on the exact frame.
+    fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+        let (instrument, arpeggiator) = (&mut self.instrument, &mut self.arpeggiator);
+        let mut start = 0;
+
+        self.clock.advance(buffer.len(), |offset| {
+            if offset > start {
+                instrument.render(&mut buffer[start..offset]);
+                start = offset;
+            }
+            arpeggiator.tick(|event| apply(instrument, event));
+        });
+
+        if start < buffer.len() {
+            self.instrument.render(&mut buffer[start..]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(midi: u8) -> Note {
+        Note::from_midi(midi).unwrap()
+    }
+
+    /// Ticks the arpeggiator, returning the note started on each tick.
+    fn run<const TICKS: usize>(arp: &mut Arpeggiator) -> [Option<u8>; TICKS] {
+        core::array::from_fn(|_| {
+            let mut started = None;
+            arp.tick(|event| {
+                if let ArpEvent::NoteOn { note, .. } = event {
+                    started = note.midi();
+                }
+            });
+            started
+        })
+    }
+
+    /// Returns the notes started every sixteenth.
+    fn steps<const STEPS: usize>(arp: &mut Arpeggiator) -> [Option<u8>; STEPS] {
+        let ticks = run::<384>(arp);
+        core::array::from_fn(|step| ticks[step * 24])
+    }
+
+    #[test]
+    fn test_modes() {
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.note_on(note(64), 100);
+        arp.note_on(note(60), 100);
+        arp.set_octaves(2);
+        assert_eq!(
+            steps::<5>(&mut arp),
+            [Some(60), Some(64), Some(72), Some(76), Some(60)]
+        );
+
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.set_mode(ArpMode::UpDown);
+        for midi in [67, 60, 64] {
+            arp.note_on(note(midi), 100);
+        }
+        assert_eq!(
+            steps::<5>(&mut arp),
+            [Some(60), Some(64), Some(67), Some(64), Some(60)]
+        );
+
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.set_mode(ArpMode::AsPlayed);
+        for midi in [67, 60, 64] {
+            arp.note_on(note(midi), 100);
+        }
+        assert_eq!(steps::<3>(&mut arp), [Some(67), Some(60), Some(64)]);
+
+        // A chord starts every note together, and releases them together.
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.set_mode(ArpMode::Chord);
+        arp.note_on(note(60), 100);
+        arp.note_on(note(64), 100);
+        let (mut on, mut off) = (0, 0);
+        for _ in 0..48 {
+            arp.tick(|event| match event {
+                ArpEvent::NoteOn { .. } => on += 1,
+                ArpEvent::NoteOff { .. } => off += 1,
+            });
+        }
+        assert_eq!((on, off), (4, 4));
+    }
+
+    #[test]
+    fn test_gate_and_swing() {
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.set_gate(0.25);
+        arp.set_swing(0.75);
+        arp.note_on(note(60), 100);
+
+        let mut events = Vec::<(usize, bool), 8>::new();
+        for tick in 0..48 {
+            arp.tick(|event| {
+                let on = matches!(event, ArpEvent::NoteOn { .. });
+                events.push((tick, on)).unwrap();
+            });
+        }
+
+        // The offbeat is pushed from tick 24 to 36, and each note is held for
+        // a quarter of its swung step.
+        assert_eq!(events, [(0, true), (9, false), (36, true), (39, false)]);
+
+        // A full gate releases each note on the tick the next one starts.
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.set_gate(1.0);
+        arp.note_on(note(60), 100);
+        arp.note_on(note(64), 100);
+
+        let mut events = Vec::<(usize, bool), 8>::new();
+        for tick in 0..48 {
+            arp.tick(|event| {
+                let on = matches!(event, ArpEvent::NoteOn { .. });
+                events.push((tick, on)).unwrap();
+            });
+        }
+        assert_eq!(events, [(0, true), (24, false), (24, true)]);
+    }
+
+    #[test]
+    fn test_latch_and_triggers() {
+        let mut arp = Arpeggiator::<16>::new(0);
+        arp.set_latch(true);
+        arp.note_on(note(60), 100);
+        arp.note_off(note(60));
+        assert!(arp.is_active());
+
+        // A new chord replaces the latched one.
+        arp.note_on(note(62), 100);
+        assert_eq!(steps::<2>(&mut arp), [Some(62), Some(62)]);
+        arp.note_off(note(62));
+        arp.set_latch(false);
+        assert!(!arp.is_active());
+
+        // A trigger holds its note for its length in steps.
+        arp.handle_trigger_event(&TriggerEvent::PlayNote {
+            note: note(65),
+            velocity: 100,
+            length: 2,
+        });
+        assert_eq!(steps::<3>(&mut arp), [Some(65), Some(65), None]);
+        assert!(!arp.is_active());
+    }
+
+    /// An instrument that counts the notes started on it, and the per-note
+    /// expression sent to it.
+    #[derive(Default)]
+    struct Counter {
+        started: usize,
+        expression: usize,
+    }
+
+    impl Instrument for Counter {
+        fn init(&mut self) {}
+
+        fn note_on(&mut self, _note: Note, _velocity: u8) -> Result<(), NoteError> {
+            self.started += 1;
+            Ok(())
+        }
+
+        fn note_off(&mut self, _note: Note) {}
+
+        fn poly_pressure(&mut self, _note: Note, _pressure: f32) {
+            self.expression += 1;
+        }
+
+        fn note_pitch_bend(&mut self, _note: Note, _semitones: f32) {
+            self.expression += 1;
+        }
+
+        fn note_timbre(&mut self, _note: Note, _timbre: f32) {
+            self.expression += 1;
+        }
+    }
+
+    impl Signal for Counter {
+        type Frame = f32;
+
+        fn next(&mut self) -> Self::Frame {
+            self.started as f32
+        }
+    }
+
+    impl AudioSource for Counter {
+        type Frame = f32;
+
+        fn render(&mut self, buffer: &'_ mut [Self::Frame]) {
+            buffer.fill(self.started as f32);
+        }
+    }
+
+    #[test]
+    fn test_wrapped_instrument() {
+        // At 125 BPM and 48kHz, each sixteenth is 24 ticks of 240 frames.
+        let mut arp = Arpeggiated::<_, 16>::new(Counter::default(), 48_000, 125.0, 0);
+        arp.note_on(note(60), 100).unwrap();
+
+        let mut buffer = [0.0; 6_000];
+        arp.render(&mut buffer);
+
+        // The second note lands on the exact frame of its step.
+        assert_eq!(buffer[0], 1.0);
+        assert_eq!(buffer[5_759], 1.0);
+        assert_eq!(buffer[5_760], 2.0);
+
+        // Rendering frame by frame plays the same notes.
+        assert_eq!(arp.next(), 2.0);
+        assert_eq!(arp.instrument().started, 2);
+
+        // Per-note expression reaches the instrument.
+        arp.poly_pressure(note(60), 0.5);
+        arp.note_pitch_bend(note(60), 1.0);
+        arp.note_timbre(note(60), 0.5);
+        assert_eq!(arp.instrument().expression, 3);
+    }
+
+    #[test]
+    fn test_random_seed() {
+        let random = |seed| {
+            let mut arp = Arpeggiator::<16>::new(seed);
+            arp.set_mode(ArpMode::Random);
+            for midi in [60, 62, 64, 65, 67] {
+                arp.note_on(note(midi), 100);
+            }
+            steps::<16>(&mut arp)
+        };
+
+        // The same seed plays the same pattern, and another seed a different one.
+        assert_eq!(random(1), random(1));
+        assert_ne!(random(1), random(2));
+    }
+}
@@ -28,6 +28,9 @@ pub use track::*;
 mod pattern;
 pub use pattern::*;
 
+mod arpeggiator;
+pub use arpeggiator::*;
+
 /// The root type of the sequencer that initiates and
 /// manages the rest of the sequencer components.
 pub struct Sequencer<const MAX_PATTERNS: usize, const MAX_TRACKS: usize, const MAX_STEPS: usize> {
@@ -7,13 +7,19 @@ pub type ParameterID = u16;
 
 /// Indicates the type of a parameter value.
 #[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParameterValueKind {
     Bool,
+    Int,
+    Float,
 }
 
 /// Represents the value of a parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ParameterValue {
     Bool(bool),
+    Int(i32),
+    Float(f32),
 }
 
 impl ParameterValue {
@@ -21,6 +27,35 @@ impl ParameterValue {
     pub fn kind(&self) -> ParameterValueKind {
         match self {
             ParameterValue::Bool(_) => ParameterValueKind::Bool,
+            ParameterValue::Int(_) => ParameterValueKind::Int,
+            ParameterValue::Float(_) => ParameterValueKind::Float,
+        }
+    }
+
+    /// Returns the value as a bool, treating non-zero numbers as true.
+    pub fn as_bool(&self) -> bool {
+        match *self {
+            ParameterValue::Bool(value) => value,
+            ParameterValue::Int(value) => value != 0,
+            ParameterValue::Float(value) => value != 0.0,
+        }
+    }
+
+    /// Returns the value as an integer, truncating floats.
+    pub fn as_int(&self) -> i32 {
+        match *self {
+            ParameterValue::Bool(value) => value as i32,
+            ParameterValue::Int(value) => value,
+            ParameterValue::Float(value) => value as i32,
+        }
+    }
+
+    /// Returns the value as a float.
+    pub fn as_float(&self) -> f32 {
+        match *self {
+            ParameterValue::Bool(value) => value as i32 as f32,
+            ParameterValue::Int(value) => value as f32,
+            ParameterValue::Float(value) => value,
         }
     }
 }
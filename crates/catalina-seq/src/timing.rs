@@ -236,3 +236,107 @@ impl PatternTiming {
         self.tick - 1 == STEP_SUBSTEPS
     }
 }
+
+/// Converts the sequencer's ticks into frame offsets in the audio stream,
+/// so events triggered on a tick can be scheduled on the exact frame it
+/// falls on instead of the start of the rendered block.
+pub struct TickClock {
+    sample_rate: f64,
+
+    /// How many frames pass between ticks at the current tempo.
+    frames_per_tick: f64,
+
+    /// How many frames there are until the next tick, from the start of
+    /// the next block.
+    until_tick: f64,
+}
+
+impl TickClock {
+    /// Constructs a clock with its first tick on the first frame.
+    pub fn new(sample_rate: usize, bpm: f32) -> Self {
+        let mut clock = Self {
+            sample_rate: sample_rate as f64,
+            frames_per_tick: 0.0,
+            until_tick: 0.0,
+        };
+        clock.set_bpm(bpm);
+        clock
+    }
+
+    /// Sets the tempo, which applies from the next tick.
+    ///
+    /// Ticks are kept at least a frame apart, so a zero sample rate or an
+    /// extreme tempo can't stall [`advance`](Self::advance).
+    pub fn set_bpm(&mut self, bpm: f32) {
+        let seconds_per_tick = 60.0 / (bpm.max(1.0) as f64 * SEQUENCER_PPQM as f64);
+        self.frames_per_tick = (seconds_per_tick * self.sample_rate).max(1.0);
+    }
+
+    /// Returns how many frames pass between ticks.
+    pub fn frames_per_tick(&self) -> f64 {
+        self.frames_per_tick
+    }
+
+    /// Restarts the clock, with the next tick on the first frame of the next block.
+    pub fn reset(&mut self) {
+        self.until_tick = 0.0;
+    }
+
+    /// Advances the clock by a block of frames, calling `tick` with the
+    /// offset of each tick that falls within the block.
+    ///
+    /// The fractional position between ticks is carried over between
+    /// blocks, so the ticks don't drift however the blocks are sized.
+    pub fn advance(&mut self, frames: usize, mut tick: impl FnMut(usize)) {
+        let frames = frames as f64;
+
+        while self.until_tick < frames {
+            // Truncating rounds down, keeping the tick inside the block.
+            tick(self.until_tick as usize);
+            self.until_tick += self.frames_per_tick;
+        }
+
+        self.until_tick -= frames;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_clock() {
+        // At 125 BPM and 48kHz, each tick is exactly 240 frames.
+        let mut clock = TickClock::new(48_000, 125.0);
+        assert_eq!(clock.frames_per_tick(), 240.0);
+
+        let mut offsets = [0usize; 4];
+        let mut count = 0;
+        clock.advance(512, |offset| {
+            offsets[count] = offset;
+            count += 1;
+        });
+        assert_eq!(&offsets[..count], &[0, 240, 480]);
+
+        // The next block picks up where the last left off.
+        count = 0;
+        clock.advance(512, |offset| {
+            offsets[count] = offset;
+            count += 1;
+        });
+        assert_eq!(&offsets[..count], &[208, 448]);
+    }
+
+    #[test]
+    fn test_tick_clock_degenerate() {
+        // Without a sample rate or tempo, the clock still moves forward.
+        for (sample_rate, bpm) in [(0, 120.0), (48_000, 0.0), (48_000, f32::INFINITY)] {
+            let mut clock = TickClock::new(sample_rate, bpm);
+            assert!(clock.frames_per_tick() >= 1.0);
+
+            let mut count = 0;
+            clock.advance(64, |_| count += 1);
+            assert!(count <= 64);
+        }
+    }
+}
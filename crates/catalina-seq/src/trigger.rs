@@ -1,6 +1,6 @@
 use catalina_engine::music::note::{CFour, Note};
 
-use crate::{Events, ParameterID, STEP_SUBSTEPS, TICKS_PER_BAR};
+use crate::{Events, ParameterID, ParameterValue, STEP_SUBSTEPS, TICKS_PER_BAR};
 
 /// Specifies a conditional rule used to decide if the trigger should play.
 #[derive(Default, PartialEq)]
@@ -128,9 +128,29 @@ impl TriggerCondition {
 
 /// Specifies a value for a parameter that's
 /// changed by a given step triggering.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ParameterLock {
     /// The parameter that the lock exists for.
     parameter: ParameterID,
+    /// The value the parameter is locked to for the step.
+    value: ParameterValue,
+}
+
+impl ParameterLock {
+    /// Constructs a lock holding the parameter at the value for the step.
+    pub fn new(parameter: ParameterID, value: ParameterValue) -> Self {
+        Self { parameter, value }
+    }
+
+    /// Returns the parameter that the lock exists for.
+    pub fn parameter(&self) -> ParameterID {
+        self.parameter
+    }
+
+    /// Returns the value the parameter is locked to.
+    pub fn value(&self) -> ParameterValue {
+        self.value
+    }
 }
 
 /// An event that can be emitted from a trigger.
@@ -149,7 +169,10 @@ pub enum TriggerEvent {
 
     /// Indicates that the trigger changes a
     /// parameter, and what that change is.
-    ParameterChange {},
+    ParameterChange {
+        parameter: ParameterID,
+        value: ParameterValue,
+    },
 }
 
 /// A trigger placed on a step in a track.
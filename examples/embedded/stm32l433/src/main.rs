@@ -1,12 +1,36 @@
 #![no_std]
 #![no_main]
 
+use catalina::engine::audio::AudioSource;
+use catalina::engine::audio::interface::{AudioInterface, CodecFormat};
+use catalina::engine::audio::oscillator::{Oscillator, OscillatorType, RuntimeOscillator};
+use catalina::engine::core::Hertz as Frequency;
 use embassy_executor::Spawner;
 use embassy_stm32::i2s::{Config, Format, I2S};
 use embassy_stm32::time::Hertz;
 
 use {defmt_rtt as _, panic_probe as _};
 
+const SAMPLE_RATE: u32 = 48_000;
+
+/// The words in each half of the interface buffers, 64 stereo frames
+/// of 16-bit samples.
+const HALF_WORDS: usize = 128;
+
+/// A sine tone on both channels.
+struct Tone(RuntimeOscillator);
+
+impl AudioSource for Tone {
+    type Frame = [f32; 2];
+
+    fn render(&mut self, buffer: &mut [[f32; 2]]) {
+        for frame in buffer {
+            let sample: f32 = self.0.sample();
+            *frame = [sample * 0.5; 2];
+        }
+    }
+}
+
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
     // Initialize the microscontroller (MCU).
@@ -17,6 +41,7 @@ async fn main(_spawner: Spawner) {
     let mut dma_buffer = [0u16; 2400];
     let mut i2s_config = Config::default();
     i2s_config.format = Format::Data16Channel32;
+    i2s_config.frequency = Hertz(SAMPLE_RATE);
     i2s_config.master_clock = false;
     let mut i2s = I2S::new_txonly_nomck(
         p.SPI3,
@@ -27,9 +52,32 @@ async fn main(_spawner: Spawner) {
         &mut dma_buffer,
         i2s_config,
     );
+
+    // The interface converts the rendered frames into the
+    // same format the I2S peripheral is configured for.
+    let mut interface =
+        AudioInterface::<[f32; 2], HALF_WORDS, 64>::new(CodecFormat::Data16Channel32);
+    let mut tone = Tone(RuntimeOscillator::new(
+        OscillatorType::Sine,
+        SAMPLE_RATE as f32,
+        Frequency(440.0),
+    ));
+
+    // Prime both halves before starting the DMA.
+    interface.render(&mut tone);
     i2s.start();
 
     loop {
-        i2s.write(&wavetable).await.ok();
+        // Embassy's driver runs the DMA over its own ring buffer, so the
+        // interface is only used from this task. Each half is released once
+        // it's copied into the ring buffer, and refilled while the other
+        // half is written, keeping the ping-pong going.
+        i2s.write(&interface.tx_buffer()[..HALF_WORDS]).await.ok();
+        interface.on_half_transfer();
+        interface.render(&mut tone);
+
+        i2s.write(&interface.tx_buffer()[HALF_WORDS..]).await.ok();
+        interface.on_transfer_complete();
+        interface.render(&mut tone);
     }
 }
@@ -1,34 +1,51 @@
 use heapless::index_map::FnvIndexMap;
 
 use catalina::engine::{
-    audio::{
-        AudioSource, FromSample, Sample,
-        oscillator::{Oscillator, OscillatorType, RuntimeOscillator},
-        signal::Signal,
-    },
-    instrument::{Instrument, NoteError},
+    audio::{AudioSource, FromSample, Sample, oscillator, signal::Signal},
+    core::Hertz,
+    instrument::{Glide, Instrument, NoteError, Unison},
     music::note::Note,
 };
 
+/// The most unison copies a voice can stack.
+const UNISON: usize = 7;
+
 /// A voice is one of multiple simultaneous sounds in a polyphonic synthesizer.
 ///
 /// When a key/note on the synth is pressed it allocates a "voice" for the sound
-/// that key makes. In this example, the sound of the voice is a sine oscillator.
+/// that key makes. In this example, the sound of the voice is a stack of
+/// detuned sine oscillators that glide in from the last note played.
 struct Voice {
-    /// The sine oscillator used to render the voice.
-    pub osc: RuntimeOscillator,
+    /// The phase of each unison copy's sine oscillator, from 0 to 1.
+    phases: [f32; UNISON],
+
+    /// Slides the voice's pitch from the last note played.
+    glide: Glide,
 }
 
 impl Voice {
-    pub fn new(osc: RuntimeOscillator) -> Self {
-        Self { osc }
+    pub fn new(phases: [f32; UNISON], glide: Glide) -> Self {
+        Self { phases, glide }
     }
 
-    /// Takes the next sample from the oscillator and increments the voice time base.
-    fn next_sample<S: Sample + FromSample<f32>>(&mut self) -> S {
-        let sample = self.osc.sample();
-
-        sample
+    /// Takes the next sample from each of the voice's unison copies, passing
+    /// them to `mix` with the index of the copy.
+    fn next_samples<S: Sample + FromSample<f32>>(
+        &mut self,
+        sample_rate: f32,
+        unison: &Unison<UNISON>,
+        mut mix: impl FnMut(usize, S),
+    ) {
+        let frequency = self.glide.advance();
+
+        for (copy, phase) in self.phases.iter_mut().enumerate().take(unison.count()) {
+            mix(copy, oscillator::sine(*phase));
+
+            *phase += frequency.hertz() * unison.ratio(copy) / sample_rate;
+            if *phase >= 1.0 {
+                *phase -= 1.0;
+            }
+        }
     }
 }
 
@@ -38,9 +55,17 @@ pub struct SineInstrument {
 
     /// Configure the instrument with 8-voice polyphony.
     ///
-    /// Since we're a basic sine synth, we use one
-    /// sine wave oscillator as each synth voice.
+    /// Since we're a basic sine synth, we use a stack
+    /// of sine wave oscillators as each synth voice.
     voices: FnvIndexMap<Note, Voice, 8>,
+
+    /// The glide settings each new voice starts from.
+    glide: Glide,
+    /// The frequency of the last note, which new notes glide from.
+    last_frequency: Option<Hertz>,
+
+    /// How the voices are stacked into detuned copies.
+    unison: Unison<UNISON>,
 }
 
 impl SineInstrument {
@@ -48,8 +73,37 @@ impl SineInstrument {
         Self {
             sample_rate,
             voices: FnvIndexMap::new(),
+            glide: Glide::new(sample_rate),
+            last_frequency: None,
+            unison: Unison::new(0),
         }
     }
+
+    /// Returns the glide settings, which apply from the next note.
+    pub fn glide_mut(&mut self) -> &mut Glide {
+        &mut self.glide
+    }
+
+    /// Returns the unison settings.
+    pub fn unison_mut(&mut self) -> &mut Unison<UNISON> {
+        &mut self.unison
+    }
+
+    /// Renders the next frame with the unison copies spread across the left
+    /// and right channels.
+    pub fn next_stereo(&mut self) -> [f32; 2] {
+        let mut frame = [0.0; 2];
+
+        for (_, voice) in self.voices.iter_mut() {
+            voice.next_samples(self.sample_rate, &self.unison, |copy, sample: f32| {
+                let [left, right] = self.unison.gains(copy);
+                frame[0] += sample * left;
+                frame[1] += sample * right;
+            });
+        }
+
+        frame
+    }
 }
 
 /// AudioSource provides the implementations for rendering
@@ -63,20 +117,13 @@ impl AudioSource for SineInstrument {
     /// Render out to a mono audio buffer.
     fn render(&mut self, buffer: &'_ mut [f32]) {
         for i in 0..buffer.len() {
-            let mut sample = 0.0;
-
-            // Loop through each active voice and sum them for the frame.
-            for (_, voice) in self.voices.iter_mut() {
-                sample = sample + voice.next_sample::<f32>();
-            }
-
             // Note that the resulting buffer will be clipped on playback
             // depending on the voice count and frequencies.
             //
             // It's on the receiving end of the rendered buffer to apply
             // amplitude scaling to bring the audio samples down to an
             // acceptable level for playback.
-            buffer[i] = sample;
+            buffer[i] = self.next();
         }
     }
 }
@@ -86,10 +133,13 @@ impl Signal for SineInstrument {
 
     fn next(&mut self) -> Self::Frame {
         let mut sample = 0.0;
+        let gain = self.unison.mono_gain();
 
         // Loop through each active voice and sum them for the frame.
         for (_, voice) in self.voices.iter_mut() {
-            sample = sample + voice.next_sample::<f32>();
+            voice.next_samples(self.sample_rate, &self.unison, |_, copy: f32| {
+                sample = sample + copy * gain;
+            });
         }
 
         // Note that the resulting buffer will be clipped on playback
@@ -109,8 +159,8 @@ impl Instrument for SineInstrument {
     fn note_on(&mut self, note: Note, _velocity: u8) -> Result<(), NoteError> {
         // Get the frequency of the note in hertz.
         //
-        // We use this as the frequency of our voice oscillator so
-        // that the oscillator plays in-key with the triggered note.
+        // We use this as the frequency of our voice oscillators so
+        // that the oscillators play in-key with the triggered note.
         let freq = note.frequency();
 
         println!(
@@ -118,19 +168,23 @@ impl Instrument for SineInstrument {
             note, freq.0, self.sample_rate
         );
 
+        // Glide in from the last note, as a legato if another note is still held.
+        let mut glide = self.glide;
+        if let Some(last) = self.last_frequency {
+            glide.jump(last);
+        }
+        glide.start(freq, !self.voices.is_empty());
+
         // Attempt to add a voice.
         //
         // .insert() will return an error if the voices map is full.
         self.voices
             .insert(
-                note, // This is the note we're adding a voice for
-                Voice::new(RuntimeOscillator::new(
-                    OscillatorType::Sine,
-                    self.sample_rate,
-                    freq,
-                )), // This is the oscillator for the voice.
+                note,                                    // This is the note we're adding a voice for
+                Voice::new(self.unison.phases(), glide), // These are the oscillators for the voice.
             )
             .map_err(|_| NoteError::NoVoices)?;
+        self.last_frequency = Some(freq);
 
         // There should ideally be some logic here to prempt
         // voices, but that's an exercise for later.
@@ -1,5 +1,5 @@
 use catalina::engine::{
-    audio::{AudioSource, Frame},
+    audio::Frame,
     instrument::Instrument,
     music::note,
 };
@@ -69,6 +69,13 @@ where
     // Create an instance of the example instrument.
     let mut inst = SineInstrument::new(sample_rate as f32);
 
+    // Glide between the notes, and thicken each one into a detuned stack.
+    inst.glide_mut().set_time(0.15);
+    inst.unison_mut().set_count(5);
+    inst.unison_mut().set_detune(15.0);
+    inst.unison_mut().set_spread(0.8);
+    inst.unison_mut().set_phase_randomness(1.0);
+
     let err_fn = |err| eprintln!("an error occurred on stream: {err}");
 
     let time_at_start = std::time::Instant::now();
@@ -109,14 +116,20 @@ where
                 // Note: This isn't terribly efficiant, should
                 //  look at options for passing an entire slice
                 //  in with dasp_slice with slice::to_frame_slice(
-                let mut f: [f32; 1] = [0_f32; 1];
-                inst.render(&mut f);
+                let f = inst.next_stereo();
+
+                // Write the left sample to the first channel, and the right
+                // sample to the rest, or mix them down for a single channel.
+                for (channel, sample) in frame.iter_mut().enumerate() {
+                    let value = match (channels, channel) {
+                        (1, _) => (f[0] + f[1]) * 0.5,
+                        (_, 0) => f[0],
+                        _ => f[1],
+                    };
 
-                // Write the sample to the left, and if present, the right channel.
-                for sample in frame.iter_mut() {
                     // Note that we scale the sample down to avoid
                     // clipping when introducing other voices.
-                    *sample = f[0].scale_amp(0.25).to_sample();
+                    *sample = value.scale_amp(0.25).to_sample();
                 }
             }
         },